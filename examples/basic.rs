@@ -59,7 +59,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )?;
 
         let result = query.execute(&pool).await?;
-        println!("Inserted user '{}': last_insert_id={}", name, result.last_insert_id());
+        println!("Inserted user '{}': last_insert_id={:?}", name, result.last_insert_id());
     }
 
     // Example 2: Query all users with PreparedQueryAs
@@ -106,7 +106,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     let result = update_query.execute(&pool).await?;
-    println!("Updated {} row(s)", result.rows_affected());
+    println!("Updated {} row(s)", result.rows_matched());
 
     // Verify the update
     let mut verify_query = PreparedQueryAs::<User, _>::new(
@@ -134,7 +134,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     let result = delete_query.execute(&pool).await?;
-    println!("Deleted {} row(s)", result.rows_affected());
+    println!("Deleted {} row(s)", result.rows_matched());
 
     // Show final state
     println!("\n--- Final state ---");
@@ -34,7 +34,7 @@ async fn transfer_money(
     )?;
 
     let result = debit.execute(&mut **tx).await?;
-    if result.rows_affected() == 0 {
+    if result.rows_matched() == 0 {
         return Err("Source account not found".into());
     }
 
@@ -63,7 +63,7 @@ async fn transfer_money(
     )?;
 
     let result = credit.execute(&mut **tx).await?;
-    if result.rows_affected() == 0 {
+    if result.rows_matched() == 0 {
         return Err("Destination account not found".into());
     }
 
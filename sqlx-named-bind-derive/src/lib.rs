@@ -0,0 +1,103 @@
+//! `#[derive(NamedParams)]` for [sqlx-named-bind](https://docs.rs/sqlx-named-bind).
+//!
+//! Use it through the `derive` feature of `sqlx-named-bind`, which re-exports the macro
+//! as `sqlx_named_bind::params::NamedParams`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implements `sqlx_named_bind::params::NamedParams` for a struct with named fields.
+///
+/// Each field is bound to the placeholder of the same name (`name` to `:name`) by
+/// cloning its value. Field attributes:
+///
+/// * `#[named_params(rename = "other")]` binds the field to `:other` instead
+/// * `#[named_params(skip)]` leaves the field out
+#[proc_macro_derive(NamedParams, attributes(named_params))]
+pub fn derive_named_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "NamedParams can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "NamedParams can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut arms = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut name = ident.to_string();
+        let mut skip = false;
+        for attr in &field.attrs {
+            if !attr.path().is_ident("named_params") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip` or `rename = \"...\"`"))
+                }
+            })?;
+        }
+        if !skip {
+            let key = format!(":{name}");
+            arms.push(quote! {
+                #key => query.bind(::std::clone::Clone::clone(&self.#ident)),
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sqlx_named_bind::params::NamedParams for #ident #ty_generics
+        #where_clause
+        {
+            fn bind_field<'q>(
+                &self,
+                query: ::sqlx_named_bind::query::Q<'q>,
+                name: &str,
+            ) -> ::sqlx_named_bind::query::Q<'q> {
+                match name {
+                    #(#arms)*
+                    _ => query,
+                }
+            }
+
+            fn bind_field_as<'q, R>(
+                &self,
+                query: ::sqlx_named_bind::query_as::QA<'q, R>,
+                name: &str,
+            ) -> ::sqlx_named_bind::query_as::QA<'q, R> {
+                match name {
+                    #(#arms)*
+                    _ => query,
+                }
+            }
+        }
+    })
+}
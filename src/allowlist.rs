@@ -0,0 +1,209 @@
+//! Hardened mode that only lets known SQL run.
+//!
+//! An [`AllowList`] installed with [`install`] holds the statements the application is
+//! expected to run: every template of a [`QueryRegistry`] and any static templates
+//! added with [`AllowList::allow`]. From then on, creating a
+//! [`PreparedQuery`](crate::PreparedQuery) or [`PreparedQueryAs`](crate::PreparedQueryAs)
+//! for any other SQL, such as a string assembled at runtime, is rejected with
+//! [`Error::NotAllowed`](crate::Error::NotAllowed), or only reported as a `tracing`
+//! warning in [`Mode::Audit`].
+//!
+//! Templates are compared after placeholder conversion, so the same template always
+//! matches regardless of how it is bound. A [`ConditionalTemplate`] renders to a
+//! different statement for each combination of fragments; allow every rendering that
+//! may run.
+//!
+//! The [`batch`](crate::batch) helpers and [`import_ndjson`](crate::json::import_ndjson)
+//! check their templates too; a [`bulk_update`](crate::batch::bulk_update) is allowed
+//! with [`AllowList::allow_bulk_update`], since it has no template.
+//!
+//! Statements the crate sends on its own behalf, such as `GET_LOCK` for a
+//! [`NamedLock`](crate::lock::NamedLock) or the GTID queries of
+//! [`consistency`](crate::consistency), are not checked and need not be listed.
+//!
+//! [`ConditionalTemplate`]: crate::conditional::ConditionalTemplate
+
+use crate::batch::bulk_update_sql;
+use crate::builder::build_query;
+use crate::hint::validate_identifier;
+use crate::registry::QueryRegistry;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+static ALLOW_LIST: RwLock<Option<Arc<AllowList>>> = RwLock::new(None);
+
+/// What happens to SQL that is not on the installed allow-list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Fail with [`Error::NotAllowed`](crate::Error::NotAllowed)
+    #[default]
+    Reject,
+    /// Log a `tracing` warning and let the query run
+    Audit,
+}
+
+/// The statements allowed to run.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::allowlist::{self, AllowList, Mode};
+/// use sqlx_named_bind::registry::QueryRegistry;
+/// use sqlx_named_bind::PreparedQuery;
+///
+/// let mut registry = QueryRegistry::new();
+/// registry.add("find_user", "SELECT id, name FROM users WHERE id = :id")?;
+///
+/// let list = AllowList::new(Mode::Reject)
+///     .allow_registry(&registry)
+///     .allow("DELETE FROM sessions WHERE expires_at < NOW()")?;
+/// assert!(list.contains("SELECT id, name FROM users WHERE id = ?"));
+///
+/// allowlist::install(list);
+/// let adhoc = PreparedQuery::new("DELETE FROM users", |q, _| q);
+/// assert!(matches!(adhoc, Err(sqlx_named_bind::Error::NotAllowed(_))));
+/// # allowlist::uninstall();
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AllowList {
+    mode: Mode,
+    statements: HashSet<String>,
+}
+
+impl AllowList {
+    /// Creates an empty allow-list enforced in `mode`.
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            statements: HashSet::new(),
+        }
+    }
+
+    /// Allows `template`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed.
+    pub fn allow(mut self, template: &str) -> crate::Result<Self> {
+        self.statements.insert(build_query(template)?);
+        Ok(self)
+    }
+
+    /// Allows [`batch::bulk_update`](crate::batch::bulk_update) of `value_column` in
+    /// `table`, keyed by `key_column`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if a name is
+    /// not a plain identifier.
+    pub fn allow_bulk_update(
+        mut self,
+        table: &str,
+        key_column: &str,
+        value_column: &str,
+    ) -> crate::Result<Self> {
+        for name in [table, key_column, value_column] {
+            validate_identifier(name)?;
+        }
+        self.statements
+            .insert(bulk_update_sql(table, key_column, value_column, 1));
+        Ok(self)
+    }
+
+    /// Allows every template of `registry`.
+    pub fn allow_registry(mut self, registry: &QueryRegistry) -> Self {
+        self.statements
+            .extend(registry.entries().map(|entry| entry.sql().to_owned()));
+        self
+    }
+
+    /// Returns the enforcement mode.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns `true` if `sql`, with positional placeholders, is allowed.
+    pub fn contains(&self, sql: &str) -> bool {
+        self.statements.contains(sql)
+    }
+
+    /// Checks `sql` against the list, in its mode.
+    fn check(&self, sql: &str) -> crate::Result<()> {
+        if self.contains(sql) {
+            return Ok(());
+        }
+        match self.mode {
+            Mode::Reject => Err(crate::Error::NotAllowed(sql.to_owned())),
+            Mode::Audit => {
+                tracing::warn!(sql = %sql, "query not on the allow-list");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Installs `list` for every subsequent query created in the process, replacing any
+/// list installed before.
+pub fn install(list: AllowList) {
+    *ALLOW_LIST.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(list));
+}
+
+/// Removes the installed allow-list, allowing every query again.
+pub fn uninstall() {
+    *ALLOW_LIST.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Checks `sql`, with positional placeholders, against the installed allow-list.
+pub(crate) fn check(sql: &str) -> crate::Result<()> {
+    match &*ALLOW_LIST.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(list) => list.check(sql),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_follows_mode() {
+        let mut registry = QueryRegistry::new();
+        registry
+            .add("find", "SELECT * FROM users WHERE id = :id")
+            .unwrap();
+        let list = AllowList::new(Mode::Reject)
+            .allow_registry(&registry)
+            .allow("SELECT 1")
+            .unwrap();
+
+        list.check("SELECT * FROM users WHERE id = ?").unwrap();
+        list.check("SELECT 1").unwrap();
+        assert!(matches!(
+            list.check("SELECT * FROM users"),
+            Err(crate::Error::NotAllowed(sql)) if sql == "SELECT * FROM users"
+        ));
+
+        let audit = AllowList {
+            mode: Mode::Audit,
+            ..list
+        };
+        audit.check("SELECT * FROM users").unwrap();
+    }
+
+    #[test]
+    fn test_allow_bulk_update() {
+        let list = AllowList::new(Mode::Reject)
+            .allow_bulk_update("products", "id", "price")
+            .unwrap();
+        list.check(&bulk_update_sql("products", "id", "price", 1))
+            .unwrap();
+        assert!(list
+            .check(&bulk_update_sql("products", "id", "stock", 1))
+            .is_err());
+        assert!(matches!(
+            AllowList::new(Mode::Reject).allow_bulk_update("products; --", "id", "price"),
+            Err(crate::Error::InvalidIdentifier(_))
+        ));
+    }
+}
@@ -0,0 +1,546 @@
+//! Batch execution of one named-parameter template over many parameter sets.
+//!
+//! Very large batches are committed in chunks, each in its own transaction, so a
+//! backfill of millions of rows never turns into a single enormous transaction.
+//!
+//! [`bulk_update`] covers the common case of setting one column to a different value
+//! per row with a few `CASE WHEN` statements instead of one `UPDATE` per row, and
+//! [`bulk_insert`] inserts many rows with a few multi-row `INSERT` statements.
+//!
+//! Every statement runs the [`invalidation`](crate::invalidation) hooks of the table it
+//! writes to, like [`PreparedQuery::execute`](crate::PreparedQuery::execute).
+//!
+//! Each chunk transaction, and each call of the bulk helpers, registers with the
+//! [`shutdown`] coordinator as one execution, so draining waits for it
+//! instead of stopping it between two statements.
+
+use crate::allowlist;
+use crate::builder::{build_query, placeholder_order};
+use crate::hint::validate_identifier;
+use crate::lexer::tokenize;
+use crate::limits;
+use crate::query::{execute_arguments, execute_bound, placeholder_key, Q};
+use crate::shutdown;
+use crate::tags::Tags;
+use crate::tenancy;
+use sqlx::mysql::MySqlArguments;
+use sqlx::{Arguments, Encode, MySql, MySqlConnection, MySqlPool, Type};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Summary of a successfully completed batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchOutcome {
+    /// Number of parameter sets executed
+    pub processed: usize,
+    /// Number of transactions committed
+    pub chunks: usize,
+    /// Total rows affected over all statements
+    pub rows_affected: u64,
+}
+
+/// Progress of a chunked batch, reported after every committed chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// Number of parameter sets committed so far
+    pub processed: usize,
+    /// Number of the chunk that was just committed, starting at 1
+    pub chunk: usize,
+    /// Time elapsed since the batch started
+    pub elapsed: Duration,
+}
+
+/// Executes `template` once per parameter set, committing every `chunk_size` sets in
+/// their own transaction.
+///
+/// The template is parsed once. For every parameter set, the binder is called for each
+/// placeholder with the placeholder name and a reference to the current parameter set.
+///
+/// If a statement or commit fails, the transaction of the current chunk is rolled back
+/// and every previously committed chunk stays committed. The returned
+/// [`Error::BatchFailed`](crate::Error::BatchFailed) reports how many parameter sets
+/// were committed and the index of the set that failed, so the job can resume from there.
+///
+/// # Arguments
+///
+/// * `pool` - Pool to open one transaction per chunk from
+/// * `template` - SQL template with named placeholders
+/// * `param_sets` - One item per execution of the template
+/// * `chunk_size` - Number of parameter sets per transaction (a value of `0` is treated as `1`)
+/// * `binder` - Binds the value for a placeholder from the current parameter set
+///
+/// # Errors
+///
+/// Returns an error if the template cannot be parsed,
+/// [`Error::NotAllowed`](crate::Error::NotAllowed) if an installed
+/// [`allowlist`] does not list it,
+/// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if it is rejected
+/// by an installed [`tenancy`] policy, or `BatchFailed` if any chunk
+/// fails.
+/// A parameter set the binder leaves a placeholder of unbound fails with
+/// [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) (or
+/// `UnboundPlaceholders`) as the source, before its statement is sent.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::batch::execute_batch_chunked;
+///
+/// struct NewUser {
+///     name: String,
+///     email: String,
+/// }
+///
+/// # async fn example(users: Vec<NewUser>) -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let outcome = execute_batch_chunked(
+///     &pool,
+///     "INSERT INTO users (name, email) VALUES (:name, :email)",
+///     users,
+///     1_000,
+///     |q, key, user| match key {
+///         ":name" => q.bind(user.name.clone()),
+///         ":email" => q.bind(user.email.clone()),
+///         _ => q,
+///     },
+/// )
+/// .await?;
+/// println!("Inserted {} users in {} transactions", outcome.processed, outcome.chunks);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn execute_batch_chunked<P, I, F>(
+    pool: &MySqlPool,
+    template: &str,
+    param_sets: I,
+    chunk_size: usize,
+    binder: F,
+) -> crate::Result<BatchOutcome>
+where
+    I: IntoIterator<Item = P>,
+    F: for<'q> FnMut(Q<'q>, &str, &P) -> Q<'q>,
+{
+    execute_batch_chunked_with_progress(pool, template, param_sets, chunk_size, binder, |_| {})
+        .await
+}
+
+/// Like [`execute_batch_chunked`], but calls `progress` after every committed chunk.
+///
+/// The progress callback is the place to report to operators and to checkpoint the
+/// position of a long-running backfill: `processed` parameter sets are durably committed
+/// when it is called.
+///
+/// # Errors
+///
+/// Returns the errors of [`execute_batch_chunked`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::batch::execute_batch_chunked_with_progress;
+///
+/// # async fn example(ids: Vec<i64>) -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// execute_batch_chunked_with_progress(
+///     &pool,
+///     "UPDATE users SET migrated = 1 WHERE id = :id",
+///     ids,
+///     500,
+///     |q, key, id| match key {
+///         ":id" => q.bind(*id),
+///         _ => q,
+///     },
+///     |progress| {
+///         println!(
+///             "chunk {}: {} rows committed in {:?}",
+///             progress.chunk, progress.processed, progress.elapsed
+///         );
+///     },
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn execute_batch_chunked_with_progress<P, I, F, G>(
+    pool: &MySqlPool,
+    template: &str,
+    param_sets: I,
+    chunk_size: usize,
+    mut binder: F,
+    mut progress: G,
+) -> crate::Result<BatchOutcome>
+where
+    I: IntoIterator<Item = P>,
+    F: for<'q> FnMut(Q<'q>, &str, &P) -> Q<'q>,
+    G: FnMut(&BatchProgress),
+{
+    let started = Instant::now();
+    let mut order: Arc<[String]> = placeholder_order(template)?.into();
+    let mut sql: Arc<str> = build_query(template)?.into();
+    allowlist::check(&sql)?;
+    tenancy::enforce(&mut sql, &mut order)?;
+    let chunk_size = chunk_size.max(1);
+
+    let mut outcome = BatchOutcome::default();
+    let mut param_sets = param_sets.into_iter().peekable();
+    while param_sets.peek().is_some() {
+        let committed = outcome.processed;
+        let failed = |position: usize, source: crate::Error| crate::Error::BatchFailed {
+            committed,
+            failed_at: position,
+            source: Box::new(source),
+        };
+
+        // Registered as one execution, so shutdown waits for the whole chunk.
+        let in_flight = shutdown::enter().map_err(|e| failed(committed, e))?;
+        let chunk = async {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| failed(committed, e.into()))?;
+            let mut executed = 0;
+            let mut rows_affected = 0;
+            for params in param_sets.by_ref().take(chunk_size) {
+                let result = execute_bound(
+                    &sql,
+                    &order,
+                    &mut |q, key| binder(q, key, &params),
+                    &mut *tx,
+                )
+                .await
+                .map_err(|e| failed(committed + executed, e))?;
+                rows_affected += result.rows_matched();
+                executed += 1;
+            }
+            tx.commit().await.map_err(|e| failed(committed, e.into()))?;
+            Ok::<_, crate::Error>((executed, rows_affected))
+        };
+        let (executed, rows_affected) = shutdown::within(in_flight, chunk).await?;
+
+        outcome.processed += executed;
+        outcome.chunks += 1;
+        outcome.rows_affected += rows_affected;
+
+        progress(&BatchProgress {
+            processed: outcome.processed,
+            chunk: outcome.chunks,
+            elapsed: started.elapsed(),
+        });
+    }
+    Ok(outcome)
+}
+
+/// Sets `value_column` to a different value for each key, using one `CASE WHEN`
+/// statement per chunk of `chunk_size` rows.
+///
+/// Each chunk runs as
+/// `UPDATE table SET value_column = CASE key_column WHEN ? THEN ? ... END WHERE key_column IN (?, ...)`,
+/// which is much faster than running one `UPDATE` per row. Chunks larger than MySQL's
+/// placeholder limit allows are reduced automatically.
+///
+/// The statements run on `conn` one after another without an implicit transaction;
+/// pass a transaction (`&mut *tx`) to apply all chunks atomically.
+///
+/// # Arguments
+///
+/// * `conn` - Connection or transaction to run the statements on
+/// * `table` - Table to update
+/// * `key_column` - Column identifying the rows, usually the primary key
+/// * `value_column` - Column to set
+/// * `updates` - Pairs of (key, new value)
+/// * `chunk_size` - Number of rows per statement (a value of `0` is treated as `1`),
+///   lowered to stay within [`limits::max_placeholders`]
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if a table or
+/// column name is not a plain identifier, [`Error::NotAllowed`](crate::Error::NotAllowed)
+/// if an installed [`allowlist`] does not list the update (see
+/// [`AllowList::allow_bulk_update`](crate::allowlist::AllowList::allow_bulk_update)),
+/// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if `table` is a
+/// table of an installed [`tenancy`] policy, since the update has no
+/// tenant filter, or an error if a statement fails. Chunks executed before the failure
+/// are not rolled back unless `conn` is a transaction.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::collections::HashMap;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::batch::bulk_update;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let prices: HashMap<i64, f64> = HashMap::from([(1, 9.99), (2, 19.99), (3, 4.5)]);
+///
+/// let mut tx = pool.begin().await?;
+/// let outcome = bulk_update(&mut *tx, "products", "id", "price", prices, 1_000).await?;
+/// tx.commit().await?;
+/// println!("Updated {} rows", outcome.rows_affected);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn bulk_update<K, V, I>(
+    conn: &mut MySqlConnection,
+    table: &str,
+    key_column: &str,
+    value_column: &str,
+    updates: I,
+    chunk_size: usize,
+) -> crate::Result<BatchOutcome>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send,
+    V: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+{
+    for name in [table, key_column, value_column] {
+        validate_identifier(name)?;
+    }
+    let one_row = bulk_update_sql(table, key_column, value_column, 1);
+    allowlist::check(&one_row)?;
+    tenancy::check(&one_row, &[])?;
+    limits::check_placeholders(3)?;
+    let chunk_size = chunk_size.clamp(1, limits::max_placeholders() / 3);
+
+    // Registered as one execution, so shutdown waits for every chunk.
+    shutdown::scope(async {
+        let mut outcome = BatchOutcome::default();
+        let mut updates = updates.into_iter().peekable();
+        while updates.peek().is_some() {
+            let chunk: Vec<(K, V)> = updates.by_ref().take(chunk_size).collect();
+            let rows = chunk.len();
+            let sql = bulk_update_sql(table, key_column, value_column, rows);
+
+            let mut arguments = MySqlArguments::default();
+            let mut keys = Vec::with_capacity(chunk.len());
+            for (key, value) in chunk {
+                arguments.add(key.clone()).map_err(sqlx::Error::Encode)?;
+                arguments.add(value).map_err(sqlx::Error::Encode)?;
+                keys.push(key);
+            }
+            for key in keys {
+                arguments.add(key).map_err(sqlx::Error::Encode)?;
+            }
+
+            let tags = Tags::new();
+            let query = sqlx::query_with;
+            let result = execute_arguments(&sql, &tags, None, arguments, query, &mut *conn).await?;
+            outcome.processed += rows;
+            outcome.chunks += 1;
+            outcome.rows_affected += result.rows_matched();
+        }
+        Ok(outcome)
+    })
+    .await
+}
+
+/// Builds the `CASE WHEN` update statement for `rows` rows. Names must already be validated.
+pub(crate) fn bulk_update_sql(
+    table: &str,
+    key_column: &str,
+    value_column: &str,
+    rows: usize,
+) -> String {
+    let cases = " WHEN ? THEN ?".repeat(rows);
+    let keys = vec!["?"; rows].join(", ");
+    format!(
+        "UPDATE {table} SET {value_column} = CASE {key_column}{cases} ELSE {value_column} END \
+         WHERE {key_column} IN ({keys})"
+    )
+}
+
+/// Inserts `rows` with one multi-row `INSERT` statement per chunk of `chunk_size` rows.
+///
+/// `template` names its columns and has a single placeholder after `VALUES`, e.g.
+/// `INSERT INTO t (a, b) VALUES :rows`. For each chunk the placeholder is expanded to
+/// `(?, ?), (?, ?), ...`, and the binder is called once per column of every row, with
+/// the column name as the placeholder (`:a`, `:b`) and a reference to the row. Chunks
+/// larger than MySQL's placeholder limit allows are reduced automatically.
+///
+/// The statements run on `conn` one after another without an implicit transaction;
+/// pass a transaction (`&mut *tx`) to insert all rows atomically.
+///
+/// # Arguments
+///
+/// * `conn` - Connection or transaction to run the statements on
+/// * `template` - `INSERT` template with a column list and one placeholder for the rows
+/// * `rows` - One item per row to insert
+/// * `chunk_size` - Number of rows per statement (a value of `0` is treated as `1`),
+///   lowered to stay within [`limits::max_placeholders`]
+/// * `binder` - Binds the value for a column from the current row
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidTemplate`](crate::Error::InvalidTemplate) if the template
+/// does not have the shape above, [`Error::NotAllowed`](crate::Error::NotAllowed) if an
+/// installed [`allowlist`] does not list the template,
+/// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if it inserts into
+/// a table of an installed [`tenancy`] policy without a tenant column,
+/// [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) (or
+/// `UnboundPlaceholders`) if the binder leaves a column of a row unbound, or an error if
+/// a statement fails. Chunks executed before the failure are not rolled back unless
+/// `conn` is a transaction.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::batch::bulk_insert;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let users = vec![("Jane", "jane@example.com"), ("John", "john@example.com")];
+///
+/// let mut tx = pool.begin().await?;
+/// let outcome = bulk_insert(
+///     &mut *tx,
+///     "INSERT INTO users (name, email) VALUES :rows",
+///     users,
+///     1_000,
+///     |q, key, (name, email)| match key {
+///         ":name" => q.bind(*name),
+///         ":email" => q.bind(*email),
+///         _ => q,
+///     },
+/// )
+/// .await?;
+/// tx.commit().await?;
+/// println!("Inserted {} rows", outcome.rows_affected);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn bulk_insert<P, I, F>(
+    conn: &mut MySqlConnection,
+    template: &str,
+    rows: I,
+    chunk_size: usize,
+    mut binder: F,
+) -> crate::Result<BatchOutcome>
+where
+    I: IntoIterator<Item = P>,
+    F: for<'q> FnMut(Q<'q>, &str, &P) -> Q<'q>,
+{
+    let sql = build_query(template)?;
+    allowlist::check(&sql)?;
+    let (at, columns) = insert_columns(&sql)?;
+    tenancy::check(&sql, &columns)?;
+    limits::check_placeholders(columns.len())?;
+    let chunk_size = chunk_size.clamp(1, limits::max_placeholders() / columns.len());
+
+    // Registered as one execution, so shutdown waits for every chunk.
+    shutdown::scope(async {
+        let mut outcome = BatchOutcome::default();
+        let mut rows = rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            let chunk: Vec<P> = rows.by_ref().take(chunk_size).collect();
+            let sql = bulk_insert_sql(&sql, at, columns.len(), chunk.len());
+
+            let order: Vec<String> = chunk.iter().flat_map(|_| columns.iter().cloned()).collect();
+            // The binder is called once per entry of `order`, in order.
+            let mut calls = 0;
+            let result = execute_bound(
+                &sql,
+                &order,
+                &mut |q, key| {
+                    let row = &chunk[calls / columns.len()];
+                    calls += 1;
+                    binder(q, key, row)
+                },
+                &mut *conn,
+            )
+            .await?;
+            outcome.processed += chunk.len();
+            outcome.chunks += 1;
+            outcome.rows_affected += result.rows_matched();
+        }
+        Ok(outcome)
+    })
+    .await
+}
+
+/// Returns the offset of the rows placeholder in converted `sql` and the placeholder
+/// key of each column in its column list.
+fn insert_columns(sql: &str) -> crate::Result<(usize, Vec<String>)> {
+    let invalid = |reason: &str| crate::Error::InvalidTemplate(format!("{reason}: {sql}"));
+    let tokens = tokenize(sql);
+    let mut placeholders = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| token.is_punct(sql, '?'));
+    let (Some((index, placeholder)), None) = (placeholders.next(), placeholders.next()) else {
+        return Err(invalid("bulk insert needs exactly one placeholder"));
+    };
+    let values = index.checked_sub(1).map(|i| tokens[i]);
+    if !values.is_some_and(|t| t.is_keyword(sql, "VALUES") || t.is_keyword(sql, "VALUE")) {
+        return Err(invalid("the bulk insert placeholder must follow VALUES"));
+    }
+    let close = index
+        .checked_sub(2)
+        .filter(|&i| tokens[i].is_punct(sql, ')'))
+        .ok_or_else(|| invalid("bulk insert needs a column list"))?;
+    let open = tokens[..close]
+        .iter()
+        .rposition(|token| token.is_punct(sql, '('))
+        .ok_or_else(|| invalid("bulk insert needs a column list"))?;
+    let columns: Vec<String> = tokens[open + 1..close]
+        .iter()
+        .filter(|token| !token.is_punct(sql, ','))
+        .map(|token| placeholder_key(token.ident(sql)))
+        .collect();
+    if columns.is_empty() {
+        return Err(invalid("bulk insert needs a column list"));
+    }
+    Ok((placeholder.start, columns))
+}
+
+/// Expands the rows placeholder at offset `at` of `sql` to `rows` tuples of `columns`
+/// placeholders.
+fn bulk_insert_sql(sql: &str, at: usize, columns: usize, rows: usize) -> String {
+    let tuple = format!("({})", vec!["?"; columns].join(", "));
+    let values = vec![tuple.as_str(); rows].join(", ");
+    format!("{}{values}{}", &sql[..at], &sql[at + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_update_sql() {
+        assert_eq!(
+            bulk_update_sql("products", "id", "price", 2),
+            "UPDATE products SET price = CASE id WHEN ? THEN ? WHEN ? THEN ? ELSE price END \
+             WHERE id IN (?, ?)"
+        );
+    }
+
+    #[test]
+    fn test_bulk_insert_sql() {
+        let sql = build_query(
+            "INSERT INTO users (name, `e-mail`) VALUES :rows \
+             ON DUPLICATE KEY UPDATE name = VALUES(name)",
+        )
+        .unwrap();
+        let (at, columns) = insert_columns(&sql).unwrap();
+        assert_eq!(columns, [":name", ":e-mail"]);
+        assert_eq!(
+            bulk_insert_sql(&sql, at, columns.len(), 2),
+            "INSERT INTO users (name, `e-mail`) VALUES (?, ?), (?, ?) \
+             ON DUPLICATE KEY UPDATE name = VALUES(name)"
+        );
+
+        for template in [
+            "INSERT INTO users VALUES :rows",
+            "INSERT INTO users (name) VALUES (:name)",
+            "INSERT INTO users (name) VALUES :rows ON DUPLICATE KEY UPDATE at = :at",
+        ] {
+            let sql = build_query(template).unwrap();
+            assert!(matches!(
+                insert_columns(&sql),
+                Err(crate::Error::InvalidTemplate(_))
+            ));
+        }
+    }
+}
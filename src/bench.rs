@@ -0,0 +1,279 @@
+//! Benchmarking registry queries with the application's binding behavior.
+//!
+//! A [`QueryBench`] executes one [`registry`](crate::registry) entry repeatedly against a
+//! target pool, binding each execution from a [`NamedBinds`] parameter set, and reports
+//! latency percentiles and throughput in a [`BenchReport`]. Queries run through
+//! [`RegistryEntry::prepare`] like in the application, so tags, timeouts, budgets and
+//! list expansion apply to the benchmark too, and tuning a query can be scripted against
+//! a staging database.
+//!
+//! Writes change the target database; point it at a disposable one.
+
+use crate::conditional::NamedBinds;
+use crate::kind::StatementKind;
+use crate::registry::RegistryEntry;
+use futures_util::{StreamExt, TryStreamExt};
+use sqlx::MySqlPool;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Repeated executions of one registry query.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{bench::QueryBench, conditional::NamedBinds, registry::QueryRegistry};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let staging = MySqlPool::connect("mysql://staging/app").await?;
+/// let registry = QueryRegistry::load_dir("queries")?;
+/// let entry = registry.get("find_user").expect("query is registered");
+///
+/// let report = QueryBench::new(entry)
+///     .generate(|i| {
+///         let mut binds = NamedBinds::new();
+///         binds.set(":id", (i % 10_000) as i64);
+///         binds
+///     })
+///     .warmup(50)
+///     .iterations(1_000)
+///     .concurrency(8)
+///     .run(&staging)
+///     .await?;
+/// println!("{report}");
+/// # Ok(())
+/// # }
+/// ```
+pub struct QueryBench<'r> {
+    entry: &'r RegistryEntry,
+    params: Params<'r>,
+    iterations: usize,
+    warmup: usize,
+    concurrency: usize,
+}
+
+enum Params<'r> {
+    Fixed(Vec<NamedBinds>),
+    Generated(Box<dyn FnMut(usize) -> NamedBinds + Send + 'r>),
+}
+
+impl<'r> QueryBench<'r> {
+    /// Creates a benchmark of `entry` running 100 executions one at a time, without
+    /// warm-up, bound from an empty parameter set.
+    pub fn new(entry: &'r RegistryEntry) -> Self {
+        Self {
+            entry,
+            params: Params::Fixed(Vec::new()),
+            iterations: 100,
+            warmup: 0,
+            concurrency: 1,
+        }
+    }
+
+    /// Binds the executions from `params`, cycling through them in order.
+    pub fn params(mut self, params: Vec<NamedBinds>) -> Self {
+        self.params = Params::Fixed(params);
+        self
+    }
+
+    /// Binds each execution from the parameter set `generate` returns for its index,
+    /// counting warm-up executions first.
+    pub fn generate<G>(mut self, generate: G) -> Self
+    where
+        G: FnMut(usize) -> NamedBinds + Send + 'r,
+    {
+        self.params = Params::Generated(Box::new(generate));
+        self
+    }
+
+    /// Sets how many executions are measured.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets how many executions run before measuring, to fill caches and prepare the
+    /// statement on the pool's connections.
+    pub fn warmup(mut self, warmup: usize) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Sets how many executions run at once. A `concurrency` of `0` is treated as `1`.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Runs the warm-up and measured executions on `pool`.
+    ///
+    /// A `SELECT` counts the rows it returns; other statements count the rows they
+    /// affected.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first execution that fails.
+    pub async fn run(mut self, pool: &MySqlPool) -> crate::Result<BenchReport> {
+        self.run_batch(pool, 0, self.warmup).await?;
+        let started = Instant::now();
+        let samples = self.run_batch(pool, self.warmup, self.iterations).await?;
+        let elapsed = started.elapsed();
+        Ok(BenchReport::new(self.entry.name(), samples, elapsed))
+    }
+
+    /// Runs `count` executions bound from the parameter sets starting at index `first`,
+    /// returning the latency and row count of each.
+    async fn run_batch(
+        &mut self,
+        pool: &MySqlPool,
+        first: usize,
+        count: usize,
+    ) -> crate::Result<Vec<(Duration, u64)>> {
+        let entry = self.entry;
+        let concurrency = self.concurrency;
+        let params = &mut self.params;
+        futures_util::stream::iter(first..first + count)
+            .map(|i| {
+                let binds = match params {
+                    Params::Fixed(sets) if sets.is_empty() => NamedBinds::new(),
+                    Params::Fixed(sets) => sets[i % sets.len()].clone(),
+                    Params::Generated(generate) => generate(i),
+                };
+                async move { execute_once(entry, &binds, pool).await }
+            })
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await
+    }
+}
+
+async fn execute_once(
+    entry: &RegistryEntry,
+    binds: &NamedBinds,
+    pool: &MySqlPool,
+) -> crate::Result<(Duration, u64)> {
+    let mut query = entry.prepare(binds.binder())?;
+    let started = Instant::now();
+    let rows = match query.kind() {
+        StatementKind::Select => query.fetch_rows(pool).await?.len() as u64,
+        _ => query.execute(pool).await?.rows_matched(),
+    };
+    Ok((started.elapsed(), rows))
+}
+
+/// Latency percentiles and throughput of a [`QueryBench`] run, over the measured
+/// executions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// Name of the benchmarked query
+    pub name: String,
+    /// Number of measured executions
+    pub iterations: usize,
+    /// Rows returned or affected, summed over the measured executions
+    pub rows: u64,
+    /// Wall-clock time of the measured executions
+    pub elapsed: Duration,
+    /// Fastest execution
+    pub min: Duration,
+    /// Median latency
+    pub p50: Duration,
+    /// 90th percentile latency
+    pub p90: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Slowest execution
+    pub max: Duration,
+}
+
+impl BenchReport {
+    fn new(name: &str, samples: Vec<(Duration, u64)>, elapsed: Duration) -> Self {
+        let rows = samples.iter().map(|(_, rows)| rows).sum();
+        let mut latencies: Vec<Duration> = samples.into_iter().map(|(took, _)| took).collect();
+        latencies.sort_unstable();
+        Self {
+            name: name.to_owned(),
+            iterations: latencies.len(),
+            rows,
+            elapsed,
+            min: percentile(&latencies, 0.0),
+            p50: percentile(&latencies, 50.0),
+            p90: percentile(&latencies, 90.0),
+            p99: percentile(&latencies, 99.0),
+            max: percentile(&latencies, 100.0),
+        }
+    }
+
+    /// Returns the measured executions per second of wall-clock time.
+    pub fn executions_per_sec(&self) -> f64 {
+        per_sec(self.iterations as f64, self.elapsed)
+    }
+
+    /// Returns the rows returned or affected per second of wall-clock time.
+    pub fn rows_per_sec(&self) -> f64 {
+        per_sec(self.rows as f64, self.elapsed)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} executions in {:?} ({:.1}/s, {:.1} rows/s); \
+             min {:?}, p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            self.name,
+            self.iterations,
+            self.elapsed,
+            self.executions_per_sec(),
+            self.rows_per_sec(),
+            self.min,
+            self.p50,
+            self.p90,
+            self.p99,
+            self.max
+        )
+    }
+}
+
+/// Returns the nearest-rank `p`th percentile of the sorted `latencies`, or zero if there
+/// are none.
+fn percentile(latencies: &[Duration], p: f64) -> Duration {
+    if latencies.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * latencies.len() as f64).ceil() as usize;
+    latencies[rank.clamp(1, latencies.len()) - 1]
+}
+
+fn per_sec(count: f64, elapsed: Duration) -> f64 {
+    match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => count / secs,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_percentiles() {
+        let samples = (1..=200)
+            .rev()
+            .map(|ms| (Duration::from_millis(ms), 2))
+            .collect();
+        let report = BenchReport::new("q", samples, Duration::from_secs(4));
+        assert_eq!(report.iterations, 200);
+        assert_eq!(report.rows, 400);
+        assert_eq!(report.min, Duration::from_millis(1));
+        assert_eq!(report.p50, Duration::from_millis(100));
+        assert_eq!(report.p90, Duration::from_millis(180));
+        assert_eq!(report.p99, Duration::from_millis(198));
+        assert_eq!(report.max, Duration::from_millis(200));
+        assert_eq!(report.executions_per_sec(), 50.0);
+        assert_eq!(report.rows_per_sec(), 100.0);
+
+        let empty = BenchReport::new("q", Vec::new(), Duration::ZERO);
+        assert_eq!((empty.p99, empty.rows_per_sec()), (Duration::ZERO, 0.0));
+    }
+}
@@ -0,0 +1,155 @@
+//! A blocking facade for code that is not async, such as CLI tools and migration scripts.
+//!
+//! Requires the `blocking` feature. Futures are driven on a single, lazily created
+//! current-thread Tokio runtime shared by the whole process. Connections are bound to
+//! the runtime they were opened on, so open pools with [`connect`] (or inside
+//! [`block_on`]) rather than on another runtime.
+//!
+//! None of these functions may be called from within an async context.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use sqlx::FromRow;
+//! use sqlx_named_bind::{blocking, PreparedQuery, PreparedQueryAs};
+//!
+//! #[derive(FromRow)]
+//! struct User {
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! fn main() -> Result<(), sqlx_named_bind::Error> {
+//!     let pool = blocking::connect("mysql://localhost/test")?;
+//!
+//!     PreparedQuery::new("UPDATE users SET active = 0 WHERE id = :id", |q, key| match key {
+//!         ":id" => q.bind(42),
+//!         _ => q,
+//!     })?
+//!     .execute_blocking(&pool)?;
+//!
+//!     let users: Vec<User> = PreparedQueryAs::new("SELECT id, name FROM users", |q, _| q)?
+//!         .fetch_all_blocking(&pool)?;
+//!     println!("{} users", users.len());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::query::Q;
+use crate::query_as::QA;
+use crate::{ExecuteResult, PreparedQuery, PreparedQueryAs};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Executor, MySql, MySqlPool};
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Builder, Runtime};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn runtime() -> crate::Result<&'static Runtime> {
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+/// Runs `future` to completion on the shared runtime.
+///
+/// # Errors
+///
+/// Returns an error if the runtime cannot be created, or the error of `future`.
+///
+/// # Panics
+///
+/// Panics if called from within an async context.
+pub fn block_on<T, Fut>(future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    runtime()?.block_on(future)
+}
+
+/// Opens a connection pool on the shared runtime.
+///
+/// # Errors
+///
+/// Returns an error if the runtime cannot be created or the connection fails.
+pub fn connect(url: &str) -> crate::Result<MySqlPool> {
+    block_on(async { Ok(MySqlPool::connect(url).await?) })
+}
+
+impl<F> PreparedQuery<F>
+where
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+{
+    /// Blocking version of [`execute`](PreparedQuery::execute).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime cannot be created or the query fails.
+    pub fn execute_blocking<'e, E>(&mut self, executor: E) -> crate::Result<ExecuteResult>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        block_on(self.execute(executor))
+    }
+}
+
+impl<R, F> PreparedQueryAs<R, F>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    /// Blocking version of [`fetch_all`](PreparedQueryAs::fetch_all).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime cannot be created or the query fails.
+    pub fn fetch_all_blocking<'e, E>(&mut self, executor: E) -> crate::Result<Vec<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        block_on(self.fetch_all(executor))
+    }
+
+    /// Blocking version of [`fetch_one`](PreparedQueryAs::fetch_one).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime cannot be created, the query fails or returns
+    /// no rows.
+    pub fn fetch_one_blocking<'e, E>(&mut self, executor: E) -> crate::Result<R>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        block_on(self.fetch_one(executor))
+    }
+
+    /// Blocking version of [`fetch_optional`](PreparedQueryAs::fetch_optional).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime cannot be created or the query fails.
+    pub fn fetch_optional_blocking<'e, E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        block_on(self.fetch_optional(executor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_on_reuses_runtime() {
+        assert_eq!(block_on(async { Ok(1) }).unwrap(), 1);
+        let first = runtime().unwrap() as *const Runtime;
+        assert!(std::ptr::eq(first, runtime().unwrap()));
+
+        let handle = std::thread::spawn(|| block_on(async { Ok(2) }).unwrap());
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+//! Execution budgets selected by query tags.
+//!
+//! Budgets are configured once for the whole process with [`install`], keyed by tag,
+//! e.g. strict limits for `class=interactive` and generous ones for `class=batch`.
+//! Every `execute`/`fetch_*` of a tagged [`PreparedQuery`](crate::PreparedQuery) or
+//! [`PreparedQueryAs`](crate::PreparedQueryAs) is then held to the budgets of its tags,
+//! without any change at the call site. When several rules match, the strictest limit
+//! of each kind applies.
+//!
+//! A duration budget cancels the query once it runs too long; a row budget stops
+//! reading rows as soon as the result set grows past it. Both fail with
+//! [`Error::BudgetExceeded`](crate::Error::BudgetExceeded). Duration budgets use Tokio's
+//! timer, so they need a Tokio runtime.
+
+use crate::tags::Tags;
+use futures_util::{Stream, TryStreamExt};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+static BUDGETS: RwLock<Option<Arc<Budgets>>> = RwLock::new(None);
+
+/// Limits applied to an execution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    max_duration: Option<Duration>,
+    max_rows: Option<u64>,
+}
+
+impl Budget {
+    /// Creates a budget without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits how long the query may run.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Limits how many rows the query may return.
+    pub fn max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Returns the duration limit, if any.
+    pub fn duration_limit(&self) -> Option<Duration> {
+        self.max_duration
+    }
+
+    /// Returns the row limit, if any.
+    pub fn row_limit(&self) -> Option<u64> {
+        self.max_rows
+    }
+
+    /// Returns the budget with its duration limit capped at `timeout`, if any.
+    pub(crate) fn within(self, timeout: Option<Duration>) -> Budget {
+        self.merge(Budget {
+            max_duration: timeout,
+            max_rows: None,
+        })
+    }
+
+    /// Returns the stricter of each limit of `self` and `other`.
+    fn merge(self, other: Budget) -> Budget {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Budget {
+            max_duration: min(self.max_duration, other.max_duration),
+            max_rows: min(self.max_rows, other.max_rows),
+        }
+    }
+}
+
+/// Budgets keyed by tag.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use sqlx_named_bind::budget::{self, Budget, Budgets};
+/// use sqlx_named_bind::tags::Tags;
+///
+/// let budgets = Budgets::new()
+///     .tag("class", "interactive", Budget::new().max_duration(Duration::from_millis(500)).max_rows(1_000))
+///     .tag("class", "batch", Budget::new().max_duration(Duration::from_secs(600)));
+///
+/// let mut tags = Tags::new();
+/// tags.insert("class", "interactive");
+/// assert_eq!(budgets.budget_for(&tags).row_limit(), Some(1_000));
+///
+/// budget::install(budgets);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Budgets {
+    rules: Vec<(String, String, Budget)>,
+}
+
+impl Budgets {
+    /// Creates an empty set of budgets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies `budget` to queries tagged `key=value`.
+    pub fn tag(mut self, key: &str, value: &str, budget: Budget) -> Self {
+        self.rules.push((key.to_owned(), value.to_owned(), budget));
+        self
+    }
+
+    /// Returns the combined budget for a query with `tags`.
+    pub fn budget_for(&self, tags: &Tags) -> Budget {
+        self.rules
+            .iter()
+            .filter(|(key, value, _)| tags.get(key) == Some(value.as_str()))
+            .fold(Budget::new(), |budget, (_, _, rule)| budget.merge(*rule))
+    }
+}
+
+/// Installs `budgets` for every subsequent execution in the process, replacing any
+/// budgets installed before.
+pub fn install(budgets: Budgets) {
+    *BUDGETS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(budgets));
+}
+
+/// Removes the installed budgets.
+pub fn uninstall() {
+    *BUDGETS.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Returns the installed budget for a query with `tags`.
+pub(crate) fn current(tags: &Tags) -> Budget {
+    if tags.is_empty() {
+        return Budget::new();
+    }
+    match &*BUDGETS.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(budgets) => budgets.budget_for(tags),
+        None => Budget::new(),
+    }
+}
+
+/// Runs `future` within the duration limit of `budget`.
+pub(crate) async fn run<T, Fut>(budget: Budget, tags: &Tags, future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let Some(limit) = budget.max_duration else {
+        return future.await;
+    };
+    match tokio::time::timeout(limit, future).await {
+        Ok(result) => result,
+        Err(_) => Err(crate::Error::BudgetExceeded(format!(
+            "query tagged {tags} ran longer than {limit:?}"
+        ))),
+    }
+}
+
+/// Collects `rows`, failing as soon as there are more than the row limit of `budget`.
+pub(crate) async fn collect_rows<R, S>(
+    budget: Budget,
+    tags: &Tags,
+    rows: S,
+) -> crate::Result<Vec<R>>
+where
+    S: Stream<Item = Result<R, sqlx::Error>>,
+{
+    Ok(collect_into(budget, tags, rows).await?.0)
+}
+
+/// Like [`collect_rows`], extending any collection; also returns the number of rows.
+pub(crate) async fn collect_into<R, C, S>(
+    budget: Budget,
+    tags: &Tags,
+    rows: S,
+) -> crate::Result<(C, u64)>
+where
+    C: Default + Extend<R>,
+    S: Stream<Item = Result<R, sqlx::Error>>,
+{
+    let mut rows = std::pin::pin!(rows);
+    let mut collected = C::default();
+    let mut received = 0;
+    while let Some(row) = rows.try_next().await? {
+        check_row(budget, tags, received)?;
+        collected.extend(Some(row));
+        received += 1;
+    }
+    Ok((collected, received))
+}
+
+/// Fails if another row after `received` rows would exceed the row limit of `budget`.
+pub(crate) fn check_row(budget: Budget, tags: &Tags, received: u64) -> crate::Result<()> {
+    if budget.max_rows.is_some_and(|max| received >= max) {
+        return Err(crate::Error::BudgetExceeded(format!(
+            "query tagged {tags} returned more than {received} rows"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_strictest_matching_rules_apply() {
+        let budgets = Budgets::new()
+            .tag("class", "interactive", Budget::new().max_rows(100))
+            .tag(
+                "team",
+                "search",
+                Budget::new()
+                    .max_rows(500)
+                    .max_duration(Duration::from_secs(1)),
+            )
+            .tag("class", "batch", Budget::new().max_rows(1));
+
+        let mut tags = Tags::new();
+        tags.insert("class", "interactive").insert("team", "search");
+        assert_eq!(
+            budgets.budget_for(&tags),
+            Budget::new()
+                .max_rows(100)
+                .max_duration(Duration::from_secs(1))
+        );
+        assert_eq!(budgets.budget_for(&Tags::new()), Budget::new());
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_enforced() {
+        let tags = Tags::new();
+        let rows = futures_util::stream::iter((0..5).map(Ok::<_, sqlx::Error>));
+        assert_eq!(
+            collect_rows(Budget::new().max_rows(5), &tags, rows)
+                .await
+                .unwrap()
+                .len(),
+            5
+        );
+        let rows = futures_util::stream::iter((0..6).map(Ok::<_, sqlx::Error>));
+        assert!(matches!(
+            collect_rows(Budget::new().max_rows(5), &tags, rows).await,
+            Err(crate::Error::BudgetExceeded(_))
+        ));
+
+        let rows = futures_util::stream::iter([3, 1, 3].map(Ok::<_, sqlx::Error>));
+        let (set, received): (BTreeSet<_>, _) =
+            collect_into(Budget::new(), &tags, rows).await.unwrap();
+        assert_eq!(set, BTreeSet::from([1, 3]));
+        assert_eq!(received, 3);
+
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        };
+        let budget = Budget::new().max_duration(Duration::from_millis(10));
+        assert!(matches!(
+            run(budget, &tags, slow).await,
+            Err(crate::Error::BudgetExceeded(_))
+        ));
+    }
+}
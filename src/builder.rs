@@ -1,8 +1,11 @@
-use regex::Regex;
+use crate::lexer::{skip_line, skip_quoted, tokenize};
+use std::ops::Range;
 
 /// Converts named placeholders (`:name`) to positional placeholders (`?`) for MySQL.
 ///
-/// This function is used internally by `PreparedQuery` and `PreparedQueryAs`.
+/// This function is used internally by `PreparedQuery` and `PreparedQueryAs`. Text inside
+/// single- or double-quoted string literals and `--`, `#` and `/* */` comments is left
+/// untouched, so `'10:30'` and `-- see :docs` are not placeholders.
 ///
 /// # Examples
 ///
@@ -11,12 +14,283 @@ use regex::Regex;
 ///
 /// let sql = build_query("SELECT * FROM users WHERE id = :id AND name = :name")?;
 /// assert_eq!(sql, "SELECT * FROM users WHERE id = ? AND name = ?");
+///
+/// let sql = build_query("SELECT * FROM events WHERE note = 'meet at 10:30' AND id = :id")?;
+/// assert_eq!(sql, "SELECT * FROM events WHERE note = 'meet at 10:30' AND id = ?");
 /// # Ok::<(), sqlx_named_bind::Error>(())
 /// ```
 pub fn build_query(template: &str) -> crate::Result<String> {
-    let regex = Regex::new(r":[a-zA-Z0-9_]+")?;
-    let replaced = regex.replace_all(template, "?").into_owned();
-    Ok(replaced)
+    build_query_with_style(template, PlaceholderStyle::Colon)
+}
+
+/// Returns the named placeholders of a template in the order they appear.
+///
+/// Repeated placeholders are returned once per occurrence, matching the order in which
+/// positional binds must be supplied for the SQL returned by [`build_query`].
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::builder::placeholder_order;
+///
+/// let order = placeholder_order("SELECT * FROM users WHERE id = :id OR parent_id = :id")?;
+/// assert_eq!(order, vec![":id", ":id"]);
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn placeholder_order(template: &str) -> crate::Result<Vec<String>> {
+    placeholder_order_with_style(template, PlaceholderStyle::Colon)
+}
+
+/// The syntax of named placeholders in a template.
+///
+/// Whatever the style, placeholders are reported to binders as `:name`, so the same
+/// binder works for templates written in any style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    /// `:name`
+    #[default]
+    Colon,
+    /// `@name`, as in SQL Server and ADO.NET; `@@system_variable` is left untouched
+    At,
+    /// `$name`
+    Dollar,
+    /// `${name}`
+    DollarBrace,
+}
+
+/// Like [`build_query`], for templates whose placeholders are written in `style`.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::builder::{build_query_with_style, PlaceholderStyle};
+///
+/// let sql = build_query_with_style("SELECT * FROM users WHERE id = @id", PlaceholderStyle::At)?;
+/// assert_eq!(sql, "SELECT * FROM users WHERE id = ?");
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn build_query_with_style(template: &str, style: PlaceholderStyle) -> crate::Result<String> {
+    Ok(rewrite_template(template, style).0)
+}
+
+/// Like [`placeholder_order`], for templates whose placeholders are written in `style`.
+///
+/// Names are returned as `:name` whatever the style.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::builder::{placeholder_order_with_style, PlaceholderStyle};
+///
+/// let order = placeholder_order_with_style(
+///     "SELECT * FROM users WHERE id = ${id} AND name = ${name}",
+///     PlaceholderStyle::DollarBrace,
+/// )?;
+/// assert_eq!(order, vec![":id", ":name"]);
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn placeholder_order_with_style(
+    template: &str,
+    style: PlaceholderStyle,
+) -> crate::Result<Vec<String>> {
+    Ok(rewrite_template(template, style).1)
+}
+
+/// A named placeholder found in a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Name as reported to binders, `:name` whatever the style
+    pub name: String,
+    /// Byte range of the placeholder in the template, including its sigil and braces
+    pub span: Range<usize>,
+}
+
+/// A template parsed by [`parse_template`] or [`parse_template_with_style`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedTemplate {
+    /// The SQL with each placeholder replaced by `?`
+    pub sql: String,
+    /// The placeholders in the order they appear, once per occurrence
+    pub placeholders: Vec<Placeholder>,
+}
+
+impl ParsedTemplate {
+    /// Returns the placeholder names in bind order, as [`placeholder_order`] does.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.placeholders.iter().map(|p| p.name.as_str())
+    }
+}
+
+/// Parses a template with the same parser queries use, returning the rewritten SQL and
+/// the name and byte span of every placeholder.
+///
+/// Use it to build tooling such as linters or documentation generators that must agree
+/// with the library on what is a placeholder.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::builder::parse_template;
+///
+/// let template = "SELECT * FROM users WHERE id = :id AND note <> ':x'";
+/// let parsed = parse_template(template);
+/// assert_eq!(parsed.sql, "SELECT * FROM users WHERE id = ? AND note <> ':x'");
+/// assert_eq!(parsed.placeholders[0].name, ":id");
+/// assert_eq!(&template[parsed.placeholders[0].span.clone()], ":id");
+/// ```
+pub fn parse_template(template: &str) -> ParsedTemplate {
+    parse_template_with_style(template, PlaceholderStyle::Colon)
+}
+
+/// Like [`parse_template`], for templates whose placeholders are written in `style`.
+///
+/// Spans cover the placeholder as written, e.g. `${id}`.
+pub fn parse_template_with_style(template: &str, style: PlaceholderStyle) -> ParsedTemplate {
+    let mut sql = String::with_capacity(template.len());
+    let mut placeholders = Vec::new();
+    let mut copied = 0;
+    scan_placeholders(template, style, |span, name| {
+        sql.push_str(&template[copied..span.start]);
+        sql.push('?');
+        copied = span.end;
+        placeholders.push(Placeholder {
+            name: format!(":{name}"),
+            span,
+        });
+    });
+    sql.push_str(&template[copied..]);
+    ParsedTemplate { sql, placeholders }
+}
+
+/// Rewrites each placeholder written in `style` in `template` to `?` and returns the
+/// rewritten SQL with the placeholder names, as `:name`, in one pass.
+pub(crate) fn rewrite_template(template: &str, style: PlaceholderStyle) -> (String, Vec<String>) {
+    let mut sql = String::with_capacity(template.len());
+    let mut order = Vec::new();
+    let mut copied = 0;
+    scan_placeholders(template, style, |range, name| {
+        sql.push_str(&template[copied..range.start]);
+        sql.push('?');
+        copied = range.end;
+        order.push(format!(":{name}"));
+    });
+    sql.push_str(&template[copied..]);
+    (sql, order)
+}
+
+/// Calls `found` with the byte range and bare name of each placeholder written in
+/// `style` in `template`, skipping string literals, quoted identifiers, comments and
+/// `::` casts.
+fn scan_placeholders<'t>(
+    template: &'t str,
+    style: PlaceholderStyle,
+    mut found: impl FnMut(Range<usize>, &'t str),
+) {
+    let bytes = template.as_bytes();
+    let sigil = match style {
+        PlaceholderStyle::Colon => b':',
+        PlaceholderStyle::At => b'@',
+        PlaceholderStyle::Dollar | PlaceholderStyle::DollarBrace => b'$',
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i, quote),
+            b'#' => i = skip_line(bytes, i),
+            b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = match template[i + 2..].find("*/") {
+                    Some(pos) => i + 2 + pos + 2,
+                    None => bytes.len(),
+                };
+            }
+            b'@' if style == PlaceholderStyle::At && bytes.get(i + 1) == Some(&b'@') => {
+                i = name_end(bytes, i + 2);
+            }
+            b':' if style == PlaceholderStyle::Colon && bytes.get(i + 1) == Some(&b':') => {
+                i = name_end(bytes, i + 2);
+            }
+            b if b == sigil => {
+                let braced = style == PlaceholderStyle::DollarBrace;
+                let name_start = if braced { i + 2 } else { i + 1 };
+                if braced && bytes.get(i + 1) != Some(&b'{') {
+                    i += 1;
+                    continue;
+                }
+                let name_end = name_end(bytes, name_start);
+                let end = if braced {
+                    (name_end > name_start && bytes.get(name_end) == Some(&b'}'))
+                        .then_some(name_end + 1)
+                } else {
+                    (name_end > name_start).then_some(name_end)
+                };
+                match end {
+                    Some(end) => {
+                        found(i..end, &template[name_start..name_end]);
+                        i = end;
+                    }
+                    None => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+fn name_end(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < bytes.len() && is_name_byte(bytes[end]) {
+        end += 1;
+    }
+    end
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Splits a multi-statement template on `;`, for migration files and fixture scripts.
+///
+/// Semicolons inside string literals, quoted identifiers and comments do not end a
+/// statement. Each statement is returned trimmed and without its `;`, with its
+/// placeholders untouched; pieces holding only whitespace or comments are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::builder::split_statements;
+///
+/// let statements = split_statements(
+///     "INSERT INTO notes (body) VALUES (:body); -- seed; not a statement\n\
+///      UPDATE notes SET body = 'a;b' WHERE id = :id;\n",
+/// );
+/// assert_eq!(
+///     statements,
+///     vec![
+///         "INSERT INTO notes (body) VALUES (:body)",
+///         "-- seed; not a statement\nUPDATE notes SET body = 'a;b' WHERE id = :id",
+///     ]
+/// );
+/// ```
+pub fn split_statements(template: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_tokens = false;
+    for token in tokenize(template) {
+        if token.is_punct(template, ';') {
+            if has_tokens {
+                statements.push(template[start..token.start].trim());
+            }
+            start = token.end;
+            has_tokens = false;
+        } else {
+            has_tokens = true;
+        }
+    }
+    if has_tokens {
+        statements.push(template[start..].trim());
+    }
+    statements
 }
 
 #[cfg(test)]
@@ -52,4 +326,113 @@ mod tests {
         let result = build_query("SELECT * FROM users WHERE user_id = :user_id").unwrap();
         assert_eq!(result, "SELECT * FROM users WHERE user_id = ?");
     }
+
+    #[test]
+    fn test_build_query_skips_string_literals() {
+        let template = r#"SELECT * FROM t WHERE note = 'meet at 10:30' AND a = 'it''s :x' AND b = 'a\':y' AND c = ":z" AND id = :id"#;
+        assert_eq!(
+            build_query(template).unwrap(),
+            r#"SELECT * FROM t WHERE note = 'meet at 10:30' AND a = 'it''s :x' AND b = 'a\':y' AND c = ":z" AND id = ?"#
+        );
+        assert_eq!(placeholder_order(template).unwrap(), vec![":id"]);
+    }
+
+    #[test]
+    fn test_build_query_skips_comments() {
+        let template = "SELECT * FROM t -- TODO: fix this :later\n\
+                        WHERE a = :a # :hash\n\
+                        AND b = /* :param docs */ :b";
+        assert_eq!(
+            build_query(template).unwrap(),
+            "SELECT * FROM t -- TODO: fix this :later\n\
+             WHERE a = ? # :hash\n\
+             AND b = /* :param docs */ ?"
+        );
+        assert_eq!(placeholder_order(template).unwrap(), vec![":a", ":b"]);
+    }
+
+    #[test]
+    fn test_build_query_skips_identifiers_and_casts() {
+        let template = "SELECT `a:b`, `x``:y` FROM t WHERE c = :c::text AND d::int = :d";
+        assert_eq!(
+            build_query(template).unwrap(),
+            "SELECT `a:b`, `x``:y` FROM t WHERE c = ?::text AND d::int = ?"
+        );
+        assert_eq!(placeholder_order(template).unwrap(), vec![":c", ":d"]);
+    }
+
+    #[test]
+    fn test_placeholder_styles() {
+        let cases = [
+            (
+                PlaceholderStyle::At,
+                "SELECT @@version, '@x' WHERE a = @id AND b = @name_2",
+            ),
+            (
+                PlaceholderStyle::Dollar,
+                "SELECT '$x' WHERE a = $id AND b = $name_2",
+            ),
+            (
+                PlaceholderStyle::DollarBrace,
+                "SELECT $x, ${ WHERE a = ${id} AND b = ${name_2}",
+            ),
+        ];
+        for (style, template) in cases {
+            assert_eq!(
+                placeholder_order_with_style(template, style).unwrap(),
+                vec![":id", ":name_2"],
+                "{style:?}"
+            );
+            assert!(build_query_with_style(template, style)
+                .unwrap()
+                .ends_with("WHERE a = ? AND b = ?"));
+        }
+        assert_eq!(
+            build_query_with_style("SELECT @@version, @id", PlaceholderStyle::At).unwrap(),
+            "SELECT @@version, ?"
+        );
+        assert_eq!(
+            placeholder_order("SELECT @id, $id, ${id}, :id").unwrap(),
+            vec![":id"]
+        );
+    }
+
+    #[test]
+    fn test_parse_template_spans() {
+        let template = "SELECT ${id}, '${no}' -- ${no}\nWHERE x = ${name} OR y = ${id}";
+        let parsed = parse_template_with_style(template, PlaceholderStyle::DollarBrace);
+        assert_eq!(
+            parsed.sql,
+            "SELECT ?, '${no}' -- ${no}\nWHERE x = ? OR y = ?"
+        );
+        assert_eq!(parsed.names().collect::<Vec<_>>(), [":id", ":name", ":id"]);
+        let written: Vec<&str> = parsed
+            .placeholders
+            .iter()
+            .map(|p| &template[p.span.clone()])
+            .collect();
+        assert_eq!(written, ["${id}", "${name}", "${id}"]);
+        assert_eq!(
+            parse_template("SELECT :a::text").placeholders,
+            [Placeholder {
+                name: ":a".to_owned(),
+                span: 7..9
+            }]
+        );
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let script = "CREATE TABLE t (a TEXT); ;\n\
+                      /* header; */ INSERT INTO t VALUES (':x;'), (`a;b`), (:y) # c;\n\
+                      ;\n-- trailing; comment\n";
+        assert_eq!(
+            split_statements(script),
+            vec![
+                "CREATE TABLE t (a TEXT)",
+                "/* header; */ INSERT INTO t VALUES (':x;'), (`a;b`), (:y) # c;",
+            ]
+        );
+        assert!(split_statements("  -- nothing here\n").is_empty());
+    }
 }
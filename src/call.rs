@@ -0,0 +1,239 @@
+//! Stored procedure calls with named IN parameters and user-variable OUT parameters.
+//!
+//! A [`PreparedCall`] runs a `CALL proc(:in1, :in2, @out)` template: the named
+//! placeholders are bound like any [`PreparedQuery`], the `@name` user variables
+//! receive the procedure's `OUT` and `INOUT` values, and they are read back with one
+//! `SELECT @out` on the same connection, right after the call. The rows of the result
+//! sets the procedure returns are collected as well, so a call needs no raw sqlx.
+//!
+//! The tables a procedure writes cannot be read from its `CALL`, so declare them with
+//! [`PreparedCall::writes`] to keep [`invalidation`](crate::invalidation) hooks running.
+
+use crate::invalidation;
+use crate::lexer::{tokenize, TokenKind};
+use crate::query::Q;
+use crate::PreparedQuery;
+use sqlx::mysql::MySqlRow;
+use sqlx::{Decode, MySql, MySqlConnection, Row, Type};
+
+/// A `CALL` statement with named IN parameters and `@name` OUT parameters.
+///
+/// # Type Parameters
+///
+/// * `F` - Binder function type, as for [`PreparedQuery`]
+pub struct PreparedCall<F> {
+    sql: String,
+    out: Vec<String>,
+    writes: Vec<String>,
+    binder: F,
+}
+
+impl<F> PreparedCall<F>
+where
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+{
+    /// Creates a call from a `CALL` template and a binder.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - A `CALL` statement with named placeholders for IN parameters and
+    ///   `@name` user variables for OUT parameters
+    /// * `binder` - Binds values to the placeholders by name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTemplate`](crate::Error::InvalidTemplate) if the template
+    /// is not a `CALL` statement, or an error if it cannot be parsed or is rejected by
+    /// an installed [`allowlist`](crate::allowlist) policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::call::PreparedCall;
+    ///
+    /// let call = PreparedCall::new(
+    ///     "CALL transfer(:from, :to, :amount, @balance, @status)",
+    ///     |q, _| q,
+    /// )?;
+    /// assert_eq!(call.out_params(), ["balance", "status"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let sql = template.into();
+        let tokens = tokenize(&sql);
+        if !tokens
+            .first()
+            .is_some_and(|token| token.is_keyword(&sql, "CALL"))
+        {
+            return Err(crate::Error::InvalidTemplate(format!(
+                "not a CALL statement: {sql}"
+            )));
+        }
+        PreparedQuery::new(sql.as_str(), |q, _| q)?;
+
+        let mut out: Vec<String> = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            // `@@name` is a system variable, not an OUT parameter.
+            let after_at = i > 0 && tokens[i - 1].is_punct(&sql, '@');
+            let system = i > 1 && tokens[i - 2].is_punct(&sql, '@');
+            if token.kind == TokenKind::Word && after_at && !system {
+                let name = token.ident(&sql);
+                if tokens[i - 1].end == token.start && !out.iter().any(|o| o == name) {
+                    out.push(name.to_owned());
+                }
+            }
+        }
+        Ok(Self {
+            sql,
+            out,
+            writes: Vec::new(),
+            binder,
+        })
+    }
+
+    /// Declares the tables the procedure writes to, so every successful call runs their
+    /// [`invalidation`](crate::invalidation) hooks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::call::PreparedCall;
+    ///
+    /// let call = PreparedCall::new("CALL archive_orders(:before)", |q, _| q)?
+    ///     .writes(["orders", "archived_orders"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn writes<I, S>(mut self, tables: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.writes.extend(tables.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns the names of the OUT user variables, without `@`, in order of first
+    /// appearance.
+    pub fn out_params(&self) -> &[String] {
+        &self.out
+    }
+
+    /// Calls the procedure on `conn` and reads back its OUT parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the call or reading the OUT parameters fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::call::PreparedCall;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut conn = pool.acquire().await?;
+    /// let output = PreparedCall::new(
+    ///     "CALL transfer(:from, :to, :amount, @balance)",
+    ///     |q, key| match key {
+    ///         ":from" => q.bind(1_i64),
+    ///         ":to" => q.bind(2_i64),
+    ///         ":amount" => q.bind(100_i64),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .call(&mut conn)
+    /// .await?;
+    /// let balance: i64 = output.out("balance")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call(&mut self, conn: &mut MySqlConnection) -> crate::Result<CallOutput> {
+        // The template was checked against the allow-list in `new`.
+        let rows = PreparedQuery::new_unlisted(self.sql.as_str(), &mut self.binder)?
+            .fetch_rows(&mut *conn)
+            .await?;
+        invalidation::notify_tables(&self.writes);
+        let out = if self.out.is_empty() {
+            None
+        } else {
+            // Sent with sqlx directly, so an installed allow-list does not reject it.
+            let select = out_select(&self.out);
+            Some(sqlx::query(&select).fetch_one(&mut *conn).await?)
+        };
+        Ok(CallOutput { rows, out })
+    }
+}
+
+/// Returns the `SELECT` reading the user variables `names`, each aliased to its name.
+fn out_select(names: &[String]) -> String {
+    let columns: Vec<String> = names
+        .iter()
+        .map(|name| format!("@`{0}` AS `{0}`", name.replace('`', "``")))
+        .collect();
+    format!("SELECT {}", columns.join(", "))
+}
+
+/// The result of a [`PreparedCall`].
+#[derive(Debug)]
+pub struct CallOutput {
+    rows: Vec<MySqlRow>,
+    out: Option<MySqlRow>,
+}
+
+impl CallOutput {
+    /// Returns the rows of every result set the procedure returned, in order.
+    pub fn rows(&self) -> &[MySqlRow] {
+        &self.rows
+    }
+
+    /// Returns the rows of every result set the procedure returned, consuming the
+    /// output.
+    pub fn into_rows(self) -> Vec<MySqlRow> {
+        self.rows
+    }
+
+    /// Decodes the OUT parameter `name`, given with or without `@`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the call has no such OUT parameter or its value cannot be
+    /// decoded as `T`.
+    pub fn out<'r, T>(&'r self, name: &str) -> crate::Result<T>
+    where
+        T: Decode<'r, MySql> + Type<MySql>,
+    {
+        let name = name.strip_prefix('@').unwrap_or(name);
+        let row = self
+            .out
+            .as_ref()
+            .ok_or_else(|| crate::Error::UnknownPlaceholder(format!("@{name}")))?;
+        Ok(row.try_get(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_out_params() {
+        let call = PreparedCall::new(
+            "CALL p(:a, @x, ':b @y', @@sql_mode, @`odd name`, @x) -- @z",
+            |q, _| q,
+        )
+        .unwrap();
+        assert_eq!(call.out_params(), ["x", "odd name"]);
+        assert_eq!(
+            out_select(call.out_params()),
+            "SELECT @`x` AS `x`, @`odd name` AS `odd name`"
+        );
+        assert!(matches!(
+            PreparedCall::new("SELECT @x", |q, _| q),
+            Err(crate::Error::InvalidTemplate(_))
+        ));
+    }
+}
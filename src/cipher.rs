@@ -0,0 +1,235 @@
+//! Application-level encryption of designated parameters and result columns.
+//!
+//! A [`FieldCipher`] installed with [`install`] encrypts the values of placeholders
+//! marked with [`PreparedQuery::encrypt`](crate::PreparedQuery::encrypt) or
+//! [`PreparedQueryAs::encrypt`](crate::PreparedQueryAs::encrypt) right after the binder
+//! binds them, so the plaintext never reaches the server. Result columns read into a
+//! [`Decrypted`] field are decrypted with the same cipher while the row is decoded.
+//! Together they keep the handling of PII columns in one place instead of in every
+//! query.
+//!
+//! Only text, binary and `NULL` values can be encrypted; ciphertext is bound as binary,
+//! so encrypted columns should be `VARBINARY` or `BLOB`. Validators attached to an
+//! encrypted placeholder see the plaintext. Ciphers that use a random nonce produce
+//! different ciphertext for equal values, so encrypted columns cannot be compared
+//! with `=` in a `WHERE` clause unless the cipher is deterministic.
+
+use crate::validators::ParamValue;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySqlArguments, MySqlTypeInfo, MySqlValueRef};
+use sqlx::{Arguments, Decode, MySql, Type};
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+
+static CIPHER: RwLock<Option<Arc<dyn FieldCipher>>> = RwLock::new(None);
+
+/// Encrypts parameters and decrypts result columns.
+///
+/// Errors are returned as a reason, reported in
+/// [`Error::Cipher`](crate::Error::Cipher) or as a column decode error.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::cipher::{self, FieldCipher};
+///
+/// /// Not encryption; for illustration only.
+/// struct Xor(u8);
+///
+/// impl FieldCipher for Xor {
+///     fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+///         Ok(plaintext.iter().map(|b| b ^ self.0).collect())
+///     }
+///
+///     fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+///         Ok(ciphertext.iter().map(|b| b ^ self.0).collect())
+///     }
+/// }
+///
+/// cipher::install(Xor(0x5a));
+/// # cipher::uninstall();
+/// ```
+pub trait FieldCipher: Send + Sync + 'static {
+    /// Encrypts `plaintext`.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String>;
+
+    /// Decrypts `ciphertext` produced by [`encrypt`](Self::encrypt).
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Installs `cipher` for every subsequent execution and row decode in the process,
+/// replacing any cipher installed before.
+pub fn install(cipher: impl FieldCipher) {
+    *CIPHER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(cipher));
+}
+
+/// Removes the installed cipher.
+pub fn uninstall() {
+    *CIPHER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn current() -> Option<Arc<dyn FieldCipher>> {
+    CIPHER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Appends `values`, bound for `key`, to `arguments` encrypted with the installed
+/// cipher.
+pub(crate) fn encrypt_values(
+    key: &str,
+    values: Vec<ParamValue>,
+    arguments: &mut MySqlArguments,
+) -> crate::Result<()> {
+    let failed = |reason: String| crate::Error::Cipher(format!("{key}: {reason}"));
+    let cipher = current().ok_or_else(|| failed("no FieldCipher is installed".to_owned()))?;
+    for value in values {
+        let ciphertext = match value {
+            ParamValue::Null => None,
+            ParamValue::Text(text) => Some(cipher.encrypt(text.as_bytes()).map_err(failed)?),
+            ParamValue::Bytes(bytes) => Some(cipher.encrypt(&bytes).map_err(failed)?),
+            other => {
+                return Err(failed(format!(
+                    "only text and binary values can be encrypted, got {other:?}"
+                )))
+            }
+        };
+        arguments.add(ciphertext).map_err(sqlx::Error::Encode)?;
+    }
+    Ok(())
+}
+
+/// A value that can be built from decrypted bytes.
+pub trait FromPlaintext: Sized {
+    /// Builds the value from `plaintext`.
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, String>;
+}
+
+impl FromPlaintext for Vec<u8> {
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, String> {
+        Ok(plaintext)
+    }
+}
+
+impl FromPlaintext for String {
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, String> {
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+}
+
+/// A result column decrypted with the installed [`FieldCipher`] while the row is
+/// decoded.
+///
+/// Decoding fails if no cipher is installed or the cipher rejects the value.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{FromRow, MySqlPool};
+/// use sqlx_named_bind::{cipher::Decrypted, PreparedQueryAs};
+///
+/// #[derive(FromRow)]
+/// struct Patient {
+///     id: i64,
+///     ssn: Decrypted<String>,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let patient = PreparedQueryAs::<Patient, _>::new(
+///     "SELECT id, ssn FROM patients WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42_i64),
+///         _ => q,
+///     },
+/// )?
+/// .fetch_one(&pool)
+/// .await?;
+/// println!("{}: {}", patient.id, *patient.ssn);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decrypted<T>(pub T);
+
+impl<T> Decrypted<T> {
+    /// Returns the decrypted value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Decrypted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Type<MySql> for Decrypted<T> {
+    fn type_info() -> MySqlTypeInfo {
+        <Vec<u8> as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <Vec<u8> as Type<MySql>>::compatible(ty) || <String as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl<'r, T: FromPlaintext> Decode<'r, MySql> for Decrypted<T> {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        let ciphertext = <&[u8] as Decode<MySql>>::decode(value)?;
+        let cipher = current().ok_or("no FieldCipher is installed")?;
+        let plaintext = cipher.decrypt(ciphertext)?;
+        Ok(Decrypted(T::from_plaintext(plaintext)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validators::BindChecks;
+
+    struct Reverse;
+
+    impl FieldCipher for Reverse {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+            Ok(plaintext.iter().rev().copied().collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+            self.encrypt(ciphertext)
+        }
+    }
+
+    #[test]
+    fn test_encrypt_binds_ciphertext() {
+        let checks = BindChecks {
+            encrypted: vec![":ssn".to_owned()],
+            ..BindChecks::default()
+        };
+        let mut plain = MySqlArguments::default();
+        plain.add("123").unwrap();
+        plain.add(None::<String>).unwrap();
+
+        let mut arguments = MySqlArguments::default();
+        assert!(matches!(
+            checks.encrypt(":ssn", &plain, &mut arguments),
+            Err(crate::Error::Cipher(_))
+        ));
+
+        install(Reverse);
+        checks.encrypt(":ssn", &plain, &mut arguments).unwrap();
+        let mut expected = MySqlArguments::default();
+        expected.add(Some(b"321".to_vec())).unwrap();
+        expected.add(None::<Vec<u8>>).unwrap();
+        assert_eq!(format!("{arguments:?}"), format!("{expected:?}"));
+
+        let mut number = MySqlArguments::default();
+        number.add(5_i64).unwrap();
+        assert!(matches!(
+            checks.encrypt(":ssn", &number, &mut arguments),
+            Err(crate::Error::Cipher(_))
+        ));
+        uninstall();
+    }
+}
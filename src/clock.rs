@@ -0,0 +1,215 @@
+//! An injectable source of the current time for time-based bindings.
+//!
+//! Templates can use the reserved `:__now` placeholder (e.g. for `updated_at` audit
+//! columns) and wrap their binder with [`bind_now`] or [`bind_now_as`], which bind it
+//! from a [`Clock`]. Production code uses [`SystemClock`]; tests use a [`FixedClock`]
+//! so the bound value is known in advance, without sleeping or patching the database.
+
+use crate::query::Q;
+use crate::query_as::QA;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The reserved placeholder bound to the clock's current time.
+pub const NOW_PLACEHOLDER: &str = ":__now";
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// The system's wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use sqlx_named_bind::clock::{format_datetime, Clock, FixedClock};
+///
+/// let clock = FixedClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+/// assert_eq!(format_datetime(clock.now()), "2023-11-14 22:13:20.000000");
+///
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(format_datetime(clock.now()), "2023-11-14 22:14:20.000000");
+/// ```
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<SystemTime>,
+}
+
+impl FixedClock {
+    /// Creates a clock stopped at `now`.
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Moves the current time forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Formats `time` as a UTC MySQL `DATETIME(6)` literal, e.g. `2024-06-01 12:30:00.000000`.
+///
+/// Times before the Unix epoch are clamped to the epoch.
+pub fn format_datetime(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:06}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Wraps a [`PreparedQuery`](crate::PreparedQuery) binder so `:__now` is bound from `clock`.
+///
+/// The time is bound as a UTC `DATETIME(6)` string (see [`format_datetime`]), read from
+/// the clock each time the query is bound. Every other placeholder is passed on to
+/// `binder`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{clock::{bind_now, SystemClock}, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let mut query = PreparedQuery::new(
+///     "UPDATE users SET name = :name, updated_at = :__now WHERE id = :id",
+///     bind_now(SystemClock, |q, key| match key {
+///         ":name" => q.bind("Jane"),
+///         ":id" => q.bind(42),
+///         _ => q,
+///     }),
+/// )?;
+/// query.execute(&pool).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn bind_now<C, F>(clock: C, mut binder: F) -> impl for<'q> FnMut(Q<'q>, &str) -> Q<'q>
+where
+    C: Clock,
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+{
+    move |q, key| {
+        if key == NOW_PLACEHOLDER {
+            q.bind(format_datetime(clock.now()))
+        } else {
+            binder(q, key)
+        }
+    }
+}
+
+/// Wraps a [`PreparedQueryAs`](crate::PreparedQueryAs) binder so `:__now` is bound
+/// from `clock`.
+///
+/// See [`bind_now`].
+pub fn bind_now_as<R, C, F>(
+    clock: C,
+    mut binder: F,
+) -> impl for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>
+where
+    C: Clock,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    move |q, key| {
+        if key == NOW_PLACEHOLDER {
+            q.bind(format_datetime(clock.now()))
+        } else {
+            binder(q, key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Execute;
+
+    #[test]
+    fn test_format_datetime() {
+        assert_eq!(format_datetime(UNIX_EPOCH), "1970-01-01 00:00:00.000000");
+        assert_eq!(
+            format_datetime(UNIX_EPOCH + Duration::from_micros(951_782_400_000_001)),
+            "2000-02-29 00:00:00.000001"
+        );
+        assert_eq!(
+            format_datetime(UNIX_EPOCH + Duration::from_secs(4_102_444_799)),
+            "2099-12-31 23:59:59.000000"
+        );
+    }
+
+    #[test]
+    fn test_bind_now_binds_only_reserved_placeholder() {
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let mut calls = Vec::new();
+        let mut binder = bind_now(&clock, |q, key| {
+            calls.push(key.to_owned());
+            q.bind(1)
+        });
+
+        let mut q = sqlx::query("UPDATE t SET a = ?, b = ?");
+        q = binder(q, ":__now");
+        q = binder(q, ":id");
+        assert!(q.take_arguments().unwrap().is_some());
+        drop(binder);
+        assert_eq!(calls, vec![":id"]);
+    }
+}
@@ -0,0 +1,153 @@
+//! Caps on concurrent executions of individual queries.
+//!
+//! A single expensive query, such as a report behind a dashboard many users refresh at
+//! once, can otherwise take every connection of the pool and starve all other queries.
+//! Limits are configured once for the whole process with [`install`], keyed by the
+//! query's [`fingerprint`], so they apply to every execution of a registry entry or
+//! template without any change at the call site. An execution over the limit waits for
+//! a running one to finish before it takes a connection.
+//!
+//! While limits are installed, every execution computes the fingerprint of its SQL to
+//! look up its limit.
+
+use crate::fingerprint::fingerprint;
+use crate::registry::RegistryEntry;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+static LIMITS: RwLock<Option<Arc<ConcurrencyLimits>>> = RwLock::new(None);
+
+/// Concurrency limits keyed by query fingerprint.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use sqlx_named_bind::concurrency::{self, ConcurrencyLimits};
+///
+/// let report = "SELECT team, SUM(amount) FROM orders WHERE placed_at >= :since GROUP BY team";
+/// let limits = ConcurrencyLimits::new()
+///     .query(report, 2)
+///     .max_wait(Duration::from_secs(5));
+/// assert_eq!(limits.available(report), Some(2));
+/// assert_eq!(limits.available("SELECT 1"), None);
+///
+/// concurrency::install(limits);
+/// # concurrency::uninstall();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimits {
+    by_fingerprint: HashMap<String, Arc<Semaphore>>,
+    max_wait: Option<Duration>,
+}
+
+impl ConcurrencyLimits {
+    /// Creates an empty set of limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows at most `max` concurrent executions of queries with the fingerprint of
+    /// `sql`, a template or converted SQL, replacing any previous limit for it.
+    ///
+    /// A `max` of `0` is treated as `1`.
+    pub fn query(mut self, sql: &str, max: usize) -> Self {
+        let permits = max.clamp(1, Semaphore::MAX_PERMITS);
+        self.by_fingerprint
+            .insert(fingerprint(sql), Arc::new(Semaphore::new(permits)));
+        self
+    }
+
+    /// Allows at most `max` concurrent executions of the registry `entry`.
+    pub fn entry(self, entry: &RegistryEntry, max: usize) -> Self {
+        self.query(entry.template(), max)
+    }
+
+    /// Fails executions that wait longer than `max_wait` for a free slot with
+    /// [`Error::ConcurrencyLimited`](crate::Error::ConcurrencyLimited), instead of
+    /// waiting until one is free.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// Returns how many more executions of `sql` could start now, or `None` if it is
+    /// not limited.
+    pub fn available(&self, sql: &str) -> Option<usize> {
+        self.by_fingerprint
+            .get(&fingerprint(sql))
+            .map(|semaphore| semaphore.available_permits())
+    }
+}
+
+/// Installs `limits` for every subsequent execution in the process, replacing any
+/// limits installed before.
+///
+/// Executions already holding a slot of the replaced limits keep it until they finish,
+/// but do not count against the new ones.
+pub fn install(limits: ConcurrencyLimits) {
+    *LIMITS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(limits));
+}
+
+/// Removes the installed limits.
+pub fn uninstall() {
+    *LIMITS.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Waits for a slot of the installed limit for `sql`, if any; the slot is held until
+/// the returned permit is dropped.
+pub(crate) async fn acquire(sql: &str) -> crate::Result<Option<OwnedSemaphorePermit>> {
+    let (semaphore, max_wait, key) = {
+        let limits = LIMITS.read().unwrap_or_else(|e| e.into_inner());
+        let Some(limits) = limits.as_ref() else {
+            return Ok(None);
+        };
+        let key = fingerprint(sql);
+        let Some(semaphore) = limits.by_fingerprint.get(&key) else {
+            return Ok(None);
+        };
+        (semaphore.clone(), limits.max_wait, key)
+    };
+    let permit = semaphore.acquire_owned();
+    let permit = match max_wait {
+        Some(max_wait) => tokio::time::timeout(max_wait, permit)
+            .await
+            .map_err(|_| crate::Error::ConcurrencyLimited(key))?,
+        None => permit.await,
+    };
+    Ok(Some(
+        permit.expect("concurrency limit semaphores are never closed"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limit_by_fingerprint() {
+        let template = "SELECT SUM(amount) FROM orders WHERE team IN (:teams)";
+        let limits = ConcurrencyLimits::new()
+            .query(template, 1)
+            .max_wait(Duration::from_millis(10));
+        let semaphore = limits.by_fingerprint[&fingerprint(template)].clone();
+        install(limits);
+
+        let executed = "SELECT /*+ MAX_EXECUTION_TIME(500) */ SUM(amount) \
+                        FROM orders WHERE team IN (?, ?)";
+        let permit = acquire(executed).await.unwrap();
+        assert!(permit.is_some());
+        assert_eq!(semaphore.available_permits(), 0);
+        assert!(matches!(
+            acquire(template).await,
+            Err(crate::Error::ConcurrencyLimited(_))
+        ));
+        assert!(acquire("SELECT 1").await.unwrap().is_none());
+
+        drop(permit);
+        assert!(acquire(template).await.unwrap().is_some());
+        uninstall();
+    }
+}
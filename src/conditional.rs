@@ -0,0 +1,594 @@
+//! Conditional fragments with late, type-erased binding.
+//!
+//! A [`ConditionalTemplate`] marks optional parts of a template with `[[ ... ]]`. Values
+//! are collected up front in [`NamedBinds`], keyed by placeholder name, and a fragment
+//! is kept only if every placeholder in it has a value. The binder returned by
+//! [`NamedBinds::binder`] then binds exactly the placeholders left in the rendered
+//! template, so it never has to repeat the inclusion logic:
+//!
+//! ```
+//! use sqlx_named_bind::conditional::{ConditionalTemplate, NamedBinds};
+//!
+//! let template = ConditionalTemplate::new(
+//!     "SELECT id FROM users WHERE tenant_id = :tenant_id \
+//!      [[AND name LIKE :name]] [[AND created_at >= :since]] ORDER BY id",
+//! )?;
+//!
+//! let mut binds = NamedBinds::new();
+//! binds.set(":tenant_id", 7_i64).set(":since", "2024-01-01");
+//!
+//! assert_eq!(
+//!     template.render(&binds)?,
+//!     "SELECT id FROM users WHERE tenant_id = :tenant_id  AND created_at >= :since ORDER BY id"
+//! );
+//! # Ok::<(), sqlx_named_bind::Error>(())
+//! ```
+//!
+//! Binding `None` counts as a value: the fragment is kept and `NULL` is bound.
+
+use crate::builder::placeholder_order;
+use crate::lexer::{skip_line, skip_quoted};
+use crate::query::Q;
+use crate::query_as::QA;
+use sqlx::encode::{Encode, IsNull};
+use sqlx::error::BoxDynError;
+use sqlx::mysql::MySqlTypeInfo;
+use sqlx::{MySql, Type};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A value that can be bound without knowing its type.
+///
+/// Implemented for every type that can be bound to a MySQL query.
+pub trait AnyBind: Send + Sync {
+    /// Returns the MySQL type the value is sent as.
+    fn type_info(&self) -> MySqlTypeInfo;
+
+    /// Encodes the value into `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be encoded.
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<IsNull, BoxDynError>;
+}
+
+impl<T> AnyBind for T
+where
+    T: for<'q> Encode<'q, MySql> + Type<MySql> + Send + Sync,
+{
+    fn type_info(&self) -> MySqlTypeInfo {
+        self.produces().unwrap_or_else(T::type_info)
+    }
+
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<IsNull, BoxDynError> {
+        self.encode_by_ref(buf)
+    }
+}
+
+/// A type-erased, cheaply clonable value stored in [`NamedBinds`].
+#[derive(Clone)]
+pub struct AnyValue(Arc<dyn AnyBind>);
+
+impl AnyValue {
+    /// Wraps `value`.
+    pub fn new<T: AnyBind + 'static>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl std::fmt::Debug for AnyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AnyValue")
+            .field(&self.0.type_info())
+            .finish()
+    }
+}
+
+impl Type<MySql> for AnyValue {
+    fn type_info() -> MySqlTypeInfo {
+        // Only used when `produces` returns `None`, which it never does.
+        <&[u8] as Type<MySql>>::type_info()
+    }
+}
+
+impl Encode<'_, MySql> for AnyValue {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> Result<IsNull, BoxDynError> {
+        self.0.encode_to(buf)
+    }
+
+    fn produces(&self) -> Option<MySqlTypeInfo> {
+        Some(self.0.type_info())
+    }
+}
+
+/// Values collected by placeholder name, bound later to whichever placeholders a
+/// rendered template contains.
+#[derive(Debug, Clone, Default)]
+pub struct NamedBinds {
+    values: BTreeMap<String, AnyValue>,
+}
+
+impl NamedBinds {
+    /// Creates an empty set of values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the placeholder `name` (including the leading `:`), replacing
+    /// any previous value.
+    pub fn set<T: AnyBind + 'static>(&mut self, name: &str, value: T) -> &mut Self {
+        self.values.insert(name.to_owned(), AnyValue::new(value));
+        self
+    }
+
+    /// Removes the value of the placeholder `name`.
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.values.remove(name);
+        self
+    }
+
+    /// Returns `true` if the placeholder `name` has a value.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// Returns the names of the placeholders with a value, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// Collects the fields of `value`, which must serialize to a map such as a struct or
+    /// a JSON object.
+    ///
+    /// Each key names a placeholder, with the leading `:` added if missing. Values are
+    /// bound as in [`json::import_ndjson`](crate::json::import_ndjson): `null` as `NULL`,
+    /// numbers as integers where exact, strings as strings, and nested arrays or objects
+    /// as their JSON text.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`](crate::Error::Json) if `value` fails to serialize, or
+    /// [`Error::InvalidParams`](crate::Error::InvalidParams) if it is not a map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::conditional::NamedBinds;
+    ///
+    /// let payload = serde_json::json!({ "name": "Jane", "age": 34, "tags": ["a", "b"] });
+    /// let binds = NamedBinds::from_serialize(&payload)?;
+    /// assert_eq!(binds.names().collect::<Vec<_>>(), [":age", ":name", ":tags"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<S: serde::Serialize + ?Sized>(value: &S) -> crate::Result<Self> {
+        let serde_json::Value::Object(fields) = serde_json::to_value(value)? else {
+            return Err(crate::Error::InvalidParams(
+                "parameters must serialize to a map of placeholder names to values".to_owned(),
+            ));
+        };
+        let mut binds = Self::new();
+        for (key, value) in &fields {
+            binds.values.insert(
+                crate::query::placeholder_key(key),
+                crate::json::json_to_value(value),
+            );
+        }
+        Ok(binds)
+    }
+
+    /// Returns a [`PreparedQuery`](crate::PreparedQuery) binder that owns these values.
+    pub(crate) fn into_binder(self) -> impl for<'q> FnMut(Q<'q>, &str) -> Q<'q> + Send + Sync {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => q,
+        }
+    }
+
+    /// Returns a [`PreparedQueryAs`](crate::PreparedQueryAs) binder that owns these values.
+    pub(crate) fn into_binder_as<R>(
+        self,
+    ) -> impl for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + Send + Sync {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => q,
+        }
+    }
+
+    /// Returns the first name that is not in `order`, if any.
+    pub(crate) fn unknown_name(&self, order: &[String]) -> Option<&str> {
+        self.names().find(|name| !order.iter().any(|key| key == name))
+    }
+
+    /// Returns the first name in `order` without a value, if any.
+    pub(crate) fn unbound_name<'o>(&self, order: &'o [String]) -> Option<&'o str> {
+        order
+            .iter()
+            .map(String::as_str)
+            .find(|key| !self.contains(key))
+    }
+
+    /// Checks that `order` and these values name exactly the same placeholders.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) for a
+    /// placeholder without a value, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) for a value whose
+    /// placeholder is not in `order`.
+    pub(crate) fn check_exact(&self, order: &[String]) -> crate::Result<()> {
+        if let Some(name) = self.unbound_name(order) {
+            return Err(crate::Error::UnboundPlaceholder(name.to_owned()));
+        }
+        match self.unknown_name(order) {
+            Some(name) => Err(crate::Error::UnknownPlaceholder(name.to_owned())),
+            None => Ok(()),
+        }
+    }
+
+    /// Wraps `binder` so that placeholders with a value here are bound from it instead.
+    pub(crate) fn overriding<'a, F>(
+        &'a self,
+        binder: &'a mut F,
+    ) -> impl for<'q> FnMut(Q<'q>, &str) -> Q<'q> + 'a
+    where
+        F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+    {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => binder(q, key),
+        }
+    }
+
+    /// Like [`overriding`](Self::overriding), for a
+    /// [`PreparedQueryAs`](crate::PreparedQueryAs) binder.
+    pub(crate) fn overriding_as<'a, R, F>(
+        &'a self,
+        binder: &'a mut F,
+    ) -> impl for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + 'a
+    where
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => binder(q, key),
+        }
+    }
+
+    /// Returns a [`PreparedQuery`](crate::PreparedQuery) binder that binds each placeholder from these values.
+    ///
+    /// Placeholders without a value are left unbound; use
+    /// [`ConditionalTemplate::render`] to check for them first.
+    pub fn binder(&self) -> impl for<'q> FnMut(Q<'q>, &str) -> Q<'q> + '_ {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => q,
+        }
+    }
+
+    /// Returns a [`PreparedQueryAs`](crate::PreparedQueryAs) binder that binds each placeholder from these
+    /// values.
+    ///
+    /// See [`binder`](Self::binder).
+    pub fn binder_as<R>(&self) -> impl for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + '_ {
+        move |q, key| match self.values.get(key) {
+            Some(value) => q.bind(value.clone()),
+            None => q,
+        }
+    }
+}
+
+/// What [`ConditionalTemplate::render`] does with values in [`NamedBinds`] whose
+/// placeholder appears nowhere in the template, such as a misspelled key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownKeys {
+    /// Fail with [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder)
+    #[default]
+    Error,
+    /// Emit a `tracing` warning and render anyway
+    Warn,
+    /// Render without complaint
+    Ignore,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    text: String,
+    optional: bool,
+    placeholders: Vec<String>,
+}
+
+/// A template with optional `[[ ... ]]` fragments.
+///
+/// Fragments cannot be nested, and each must contain at least one placeholder.
+/// `[[` and `]]` inside string literals, quoted identifiers and comments are ignored.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{MySqlPool, FromRow};
+/// use sqlx_named_bind::{conditional::{ConditionalTemplate, NamedBinds}, PreparedQueryAs};
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # async fn example(name_filter: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let template = ConditionalTemplate::new(
+///     "SELECT id, name FROM users WHERE active = 1 [[AND name LIKE :name]]",
+/// )?;
+///
+/// let mut binds = NamedBinds::new();
+/// if let Some(name) = name_filter {
+///     binds.set(":name", name);
+/// }
+///
+/// let users: Vec<User> = PreparedQueryAs::new(template.render(&binds)?, binds.binder_as())?
+///     .fetch_all(&pool)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConditionalTemplate {
+    segments: Vec<Segment>,
+    unknown_keys: UnknownKeys,
+}
+
+impl ConditionalTemplate {
+    /// Parses `template`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTemplate`](crate::Error::InvalidTemplate) if a fragment
+    /// is unterminated, nested or has no placeholder.
+    pub fn new(template: &str) -> crate::Result<Self> {
+        let bytes = template.as_bytes();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut in_fragment = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i, quote),
+                b'#' => i = skip_line(bytes, i),
+                b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+                b'[' if bytes[i..].starts_with(b"[[") => {
+                    if in_fragment {
+                        return Err(invalid("conditional fragments cannot be nested"));
+                    }
+                    segments.push(Segment::new(&template[start..i], false)?);
+                    in_fragment = true;
+                    i += 2;
+                    start = i;
+                }
+                b']' if in_fragment && bytes[i..].starts_with(b"]]") => {
+                    let segment = Segment::new(&template[start..i], true)?;
+                    if segment.placeholders.is_empty() {
+                        return Err(invalid(&format!(
+                            "conditional fragment '[[{}]]' has no placeholder",
+                            segment.text
+                        )));
+                    }
+                    segments.push(segment);
+                    in_fragment = false;
+                    i += 2;
+                    start = i;
+                }
+                _ => i += 1,
+            }
+        }
+        if in_fragment {
+            return Err(invalid("unterminated conditional fragment"));
+        }
+        segments.push(Segment::new(&template[start..], false)?);
+        segments.retain(|segment| !segment.text.is_empty());
+        Ok(Self {
+            segments,
+            unknown_keys: UnknownKeys::default(),
+        })
+    }
+
+    /// Sets how values for placeholders that the template does not use are treated.
+    ///
+    /// Defaults to [`UnknownKeys::Error`].
+    pub fn on_unknown_keys(mut self, policy: UnknownKeys) -> Self {
+        self.unknown_keys = policy;
+        self
+    }
+
+    /// Returns the template with the fragments whose placeholders all have a value in
+    /// `binds`, and without the others.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) if a
+    /// placeholder outside the fragments has no value, and
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) for a value whose
+    /// placeholder the template does not use, unless allowed by
+    /// [`on_unknown_keys`](Self::on_unknown_keys).
+    pub fn render(&self, binds: &NamedBinds) -> crate::Result<String> {
+        if self.unknown_keys != UnknownKeys::Ignore {
+            let unknown = binds.values.keys().find(|key| {
+                !self
+                    .segments
+                    .iter()
+                    .any(|segment| segment.placeholders.contains(key))
+            });
+            if let Some(key) = unknown {
+                if self.unknown_keys == UnknownKeys::Error {
+                    return Err(crate::Error::UnknownPlaceholder(key.clone()));
+                }
+                tracing::warn!(placeholder = %key, "value supplied for a placeholder the template does not use");
+            }
+        }
+
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            let missing = segment.placeholders.iter().find(|p| !binds.contains(p));
+            match (missing, segment.optional) {
+                (Some(_), true) => continue,
+                (Some(key), false) => return Err(crate::Error::UnboundPlaceholder(key.clone())),
+                (None, _) => rendered.push_str(&segment.text),
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+impl Segment {
+    fn new(text: &str, optional: bool) -> crate::Result<Self> {
+        Ok(Self {
+            text: text.to_owned(),
+            optional,
+            placeholders: placeholder_order(text)?,
+        })
+    }
+}
+
+fn invalid(message: &str) -> crate::Error {
+    crate::Error::InvalidTemplate(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validators::BindChecks;
+    use sqlx::{Arguments, Execute};
+
+    const TEMPLATE: &str = "SELECT id FROM users WHERE tenant_id = :tenant_id\
+                            [[ AND name = :name]][[ AND age BETWEEN :min AND :max]]\
+                            [[ AND :tenant_id = :tenant_id]] AND note <> '[[x]]'";
+
+    #[test]
+    fn test_overriding_replaces_only_given_values() {
+        fn binder<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+            match key {
+                ":tenant_id" => q.bind(1_i64),
+                ":name" => q.bind("jane"),
+                _ => q,
+            }
+        }
+
+        let order: Vec<String> = vec![":tenant_id".into(), ":name".into()];
+        let mut binder = binder;
+        let checks = BindChecks::default();
+        let base = crate::query::bind_arguments("?", &order, &mut binder, &checks).unwrap();
+
+        let mut overrides = NamedBinds::new();
+        overrides.set(":tenant_id", 2_i64);
+        let mut overridden = overrides.overriding(&mut binder);
+        let bound = crate::query::bind_arguments("?", &order, &mut overridden, &checks).unwrap();
+        assert_eq!(bound.arguments.len(), 2);
+        assert_ne!(format!("{:?}", bound.arguments), format!("{:?}", base.arguments));
+
+        let mut expected = NamedBinds::new();
+        expected.set(":tenant_id", 2_i64).set(":name", "jane");
+        let mut binder = expected.binder();
+        let expected = crate::query::bind_arguments("?", &order, &mut binder, &checks).unwrap();
+        assert_eq!(format!("{:?}", bound.arguments), format!("{:?}", expected.arguments));
+    }
+
+    #[test]
+    fn test_render_keeps_fully_bound_fragments() {
+        let template = ConditionalTemplate::new(TEMPLATE).unwrap();
+        let mut binds = NamedBinds::new();
+        binds.set(":tenant_id", 1_i64).set(":min", 18_i32);
+        assert_eq!(
+            template.render(&binds).unwrap(),
+            "SELECT id FROM users WHERE tenant_id = :tenant_id AND :tenant_id = :tenant_id \
+             AND note <> '[[x]]'"
+        );
+
+        binds.set(":max", 65_i32).set(":name", None::<String>);
+        assert_eq!(
+            template.render(&binds).unwrap(),
+            "SELECT id FROM users WHERE tenant_id = :tenant_id AND name = :name \
+             AND age BETWEEN :min AND :max AND :tenant_id = :tenant_id AND note <> '[[x]]'"
+        );
+
+        binds.remove(":tenant_id");
+        assert!(matches!(
+            template.render(&binds),
+            Err(crate::Error::UnboundPlaceholder(key)) if key == ":tenant_id"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_fragments() {
+        for template in [
+            "SELECT 1 [[AND a = :a",
+            "SELECT 1 [[AND a = :a [[AND b = :b]]]]",
+            "SELECT 1 [[AND a = 1]]",
+        ] {
+            assert!(matches!(
+                ConditionalTemplate::new(template),
+                Err(crate::Error::InvalidTemplate(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_binder_binds_rendered_placeholders() {
+        let mut binds = NamedBinds::new();
+        binds.set(":tenant_id", 1_i64).set(":name", "jane");
+        let template = ConditionalTemplate::new(TEMPLATE).unwrap();
+        let rendered = template.render(&binds).unwrap();
+        let mut binder = binds.binder();
+        let mut q = sqlx::query("SELECT ?, ?, ?, ?");
+        for key in placeholder_order(&rendered).unwrap() {
+            q = binder(q, &key);
+        }
+        q = binder(q, ":missing");
+        assert_eq!(q.take_arguments().unwrap().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_unknown_keys_policy() {
+        let mut binds = NamedBinds::new();
+        binds.set(":tenant_id", 1_i64).set(":nmae", "jane");
+
+        let template = ConditionalTemplate::new(TEMPLATE).unwrap();
+        assert!(matches!(
+            template.render(&binds),
+            Err(crate::Error::UnknownPlaceholder(key)) if key == ":nmae"
+        ));
+        for policy in [UnknownKeys::Warn, UnknownKeys::Ignore] {
+            let template = template.clone().on_unknown_keys(policy);
+            assert!(!template.render(&binds).unwrap().contains(":name"));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_serialize_maps_fields() {
+        #[derive(serde::Serialize)]
+        struct Payload {
+            id: i64,
+            name: Option<String>,
+            tags: Vec<&'static str>,
+        }
+
+        let payload = Payload {
+            id: 7,
+            name: None,
+            tags: vec!["a"],
+        };
+        let binds = NamedBinds::from_serialize(&payload).unwrap();
+        assert_eq!(binds.names().collect::<Vec<_>>(), [":id", ":name", ":tags"]);
+        assert_eq!(
+            binds.values[":id"].0.type_info(),
+            <i64 as Type<MySql>>::type_info()
+        );
+
+        let binds = NamedBinds::from_serialize(&serde_json::json!({ ":ok": true })).unwrap();
+        assert!(binds.contains(":ok"));
+        assert!(matches!(
+            NamedBinds::from_serialize(&[1, 2]),
+            Err(crate::Error::InvalidParams(_))
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Running a sequence of queries on one pooled connection.
+//!
+//! Each query run against a pool may be served by a different connection. Session
+//! state such as temporary tables, user variables and `LAST_INSERT_ID()` only exists
+//! on the connection that created it, so a sequence of queries that depends on it
+//! has to be pinned to a single connection.
+
+use crate::hint::validate_identifier;
+use crate::query_log::{self, QueryLog};
+use crate::shutdown;
+use crate::PreparedQueryAs;
+use futures_util::future::BoxFuture;
+use sqlx::{MySqlConnection, MySqlPool};
+
+/// Checks out one connection from `pool` and runs `f` with it.
+///
+/// Every query executed on the connection handle passed to `f` runs on the same
+/// connection, without opening a transaction. The connection is returned to the pool
+/// when `f` completes. Once [`shutdown`](crate::shutdown) begins, `f` is not started,
+/// but a running `f` is waited for. Session state created by `f` (temporary tables, user variables)
+/// stays on the connection, so drop it before returning if later users must not see it.
+///
+/// # Arguments
+///
+/// * `pool` - Pool to check the connection out from
+/// * `f` - Runs the queries; return the future boxed with `Box::pin(async move { ... })`
+///
+/// # Errors
+///
+/// Returns [`Error::ShuttingDown`](crate::Error::ShuttingDown) once shutdown has begun,
+/// an error if no connection can be acquired, or the error returned by `f`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{connection::with_connection, PreparedQuery, PreparedQueryAs};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let order_id = with_connection(&pool, |conn| {
+///     Box::pin(async move {
+///         PreparedQuery::new(
+///             "INSERT INTO orders (customer_id) VALUES (:customer_id)",
+///             |q, key| match key {
+///                 ":customer_id" => q.bind(42),
+///                 _ => q,
+///             },
+///         )?
+///         .execute(&mut *conn)
+///         .await?;
+///
+///         // LAST_INSERT_ID() is per connection, so this must run on the same one
+///         let (id,) = PreparedQueryAs::<(u64,), _>::new("SELECT LAST_INSERT_ID()", |q, _| q)?
+///             .fetch_one(&mut *conn)
+///             .await?;
+///         Ok(id)
+///     })
+/// })
+/// .await?;
+/// println!("Created order {}", order_id);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_connection<T, F>(pool: &MySqlPool, f: F) -> crate::Result<T>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    shutdown::scope(async {
+        let mut conn = pool.acquire().await?;
+        f(&mut conn).await
+    })
+    .await
+}
+
+/// Runs `f` in a transaction on one connection from `pool`.
+///
+/// The transaction is committed if `f` returns `Ok` and rolled back if it returns an
+/// error. Once [`shutdown`](crate::shutdown) begins, no new transaction is started,
+/// but a running one is waited for, including statements it has yet to run.
+///
+/// # Arguments
+///
+/// * `pool` - Pool to check the connection out from
+/// * `f` - Runs the queries; return the future boxed with `Box::pin(async move { ... })`
+///
+/// # Errors
+///
+/// Returns [`Error::ShuttingDown`](crate::Error::ShuttingDown) once shutdown has begun,
+/// an error if the transaction cannot be started, committed or rolled back, or the
+/// error returned by `f`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{connection::with_transaction, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// with_transaction(&pool, |conn| {
+///     Box::pin(async move {
+///         for (id, delta) in [(1, -10), (2, 10)] {
+///             PreparedQuery::new(
+///                 "UPDATE accounts SET balance = balance + :delta WHERE id = :id",
+///                 |q, key| match key {
+///                     ":delta" => q.bind(delta),
+///                     ":id" => q.bind(id),
+///                     _ => q,
+///                 },
+///             )?
+///             .execute(&mut *conn)
+///             .await?;
+///         }
+///         Ok(())
+///     })
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_transaction<T, F>(pool: &MySqlPool, f: F) -> crate::Result<T>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    shutdown::scope(async {
+        let mut tx = pool.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    })
+    .await
+}
+
+/// Like [`with_transaction`], and also returns the log of every named query executed
+/// by `f`, whether the transaction was committed or rolled back.
+///
+/// See [`query_log`](crate::query_log) for what is recorded.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{connection::with_logged_transaction, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let (result, log) = with_logged_transaction(&pool, |conn| {
+///     Box::pin(async move {
+///         PreparedQuery::new("DELETE FROM carts WHERE user_id = :id", |q, key| match key {
+///             ":id" => q.bind(42),
+///             _ => q,
+///         })?
+///         .execute(&mut *conn)
+///         .await?;
+///         Ok(())
+///     })
+/// })
+/// .await;
+/// println!("request ran {} queries in {:?}", log.len(), log.total_duration());
+/// for entry in log.entries() {
+///     println!("{:?} {:?} rows={:?}", entry.duration, entry.fingerprint, entry.rows);
+/// }
+/// result?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_logged_transaction<T, F>(
+    pool: &MySqlPool,
+    f: F,
+) -> (crate::Result<T>, QueryLog)
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    let log = QueryLog::new();
+    let result = query_log::scope(log.clone(), with_transaction(pool, f)).await;
+    (result, log)
+}
+
+/// Runs `f` on `conn` with the connection character set and collation switched by
+/// `SET NAMES charset COLLATE collation`, restoring the previous settings afterwards.
+///
+/// Use this to control case or accent sensitivity of comparisons against string
+/// literals and bound values for a few queries, without changing the database default.
+/// The settings are restored whether `f` succeeds or not.
+///
+/// # Arguments
+///
+/// * `conn` - Connection to run on; pin it with [`with_connection`] or a transaction
+/// * `charset` - Character set name, e.g. `utf8mb4`
+/// * `collation` - Collation name, e.g. `utf8mb4_0900_as_cs`
+/// * `f` - Runs the queries; return the future boxed with `Box::pin(async move { ... })`
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if `charset`
+/// or `collation` is not a plain identifier, the error returned by `f`, or an error if
+/// changing or restoring the settings fails.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{connection::with_collation, PreparedQueryAs};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let mut conn = pool.acquire().await?;
+/// let matches = with_collation(&mut conn, "utf8mb4", "utf8mb4_0900_as_cs", |conn| {
+///     Box::pin(async move {
+///         PreparedQueryAs::<(i64,), _>::new(
+///             "SELECT COUNT(*) FROM tags WHERE label = CONVERT(:label USING utf8mb4)",
+///             |q, key| match key {
+///                 ":label" => q.bind("Rust"),
+///                 _ => q,
+///             },
+///         )?
+///         .fetch_one(conn)
+///         .await
+///     })
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_collation<T, F>(
+    conn: &mut MySqlConnection,
+    charset: &str,
+    collation: &str,
+    f: F,
+) -> crate::Result<T>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    validate_identifier(charset)?;
+    validate_identifier(collation)?;
+
+    let (previous_charset, previous_collation) =
+        PreparedQueryAs::<(String, String), _>::new_unlisted(
+            "SELECT @@character_set_client, @@collation_connection",
+            |q, _| q,
+        )?
+        .fetch_one(&mut *conn)
+        .await?;
+
+    set_names(conn, charset, collation).await?;
+    let result = f(&mut *conn).await;
+    let restored = set_names(conn, &previous_charset, &previous_collation).await;
+    let value = result?;
+    restored?;
+    Ok(value)
+}
+
+async fn set_names(
+    conn: &mut MySqlConnection,
+    charset: &str,
+    collation: &str,
+) -> crate::Result<()> {
+    validate_identifier(charset)?;
+    validate_identifier(collation)?;
+    let sql = format!("SET NAMES {charset} COLLATE {collation}");
+    crate::PreparedQuery::new_unlisted(sql, |q, _| q)?
+        .execute(conn)
+        .await?;
+    Ok(())
+}
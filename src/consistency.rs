@@ -0,0 +1,148 @@
+//! Read-your-writes consistency for MySQL replicas, based on GTIDs.
+//!
+//! When reads are sent to a replica, a request that just wrote to the primary may not
+//! see its own writes yet. [`ReadYourWrites`] records the primary's executed GTID set
+//! after a write and makes a replica wait until it has applied that set before a read
+//! is run on it. [`ReadYourWrites::execute`] records automatically after each write,
+//! and [`ReplicaRouter::consistent_reader`](crate::routing::ReplicaRouter::consistent_reader)
+//! waits on the replica it picks before handing out the connection.
+//!
+//! Requires `gtid_mode=ON` on the primary and the replicas.
+
+use crate::query::Q;
+use crate::{ExecuteResult, PreparedQuery, PreparedQueryAs};
+use sqlx::{Executor, MySql, MySqlPool};
+use std::time::Duration;
+
+/// Session-level read-your-writes tracker.
+///
+/// Keep one of these per request (or per user session): run writes through
+/// [`execute`](Self::execute), or call [`record`](Self::record) on the primary after
+/// each write, and [`wait`](Self::wait) on the chosen replica before each read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{consistency::ReadYourWrites, PreparedQuery, PreparedQueryAs};
+///
+/// # async fn example(primary: MySqlPool, replica: MySqlPool) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut session = ReadYourWrites::new(Duration::from_secs(1));
+///
+/// let mut update = PreparedQuery::new(
+///     "UPDATE users SET name = :name WHERE id = :id",
+///     |q, key| match key {
+///         ":name" => q.bind("Jane"),
+///         ":id" => q.bind(42),
+///         _ => q,
+///     }
+/// )?;
+/// session.execute(&mut update, &primary).await?;
+///
+/// // The replica now waits until it has applied the update before reading.
+/// session.wait(&replica).await?;
+/// let mut select = PreparedQueryAs::<(String,), _>::new(
+///     "SELECT name FROM users WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42),
+///         _ => q,
+///     }
+/// )?;
+/// let (name,) = select.fetch_one(&replica).await?;
+/// assert_eq!(name, "Jane");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadYourWrites {
+    gtid_set: Option<String>,
+    timeout: Duration,
+}
+
+impl ReadYourWrites {
+    /// Creates a tracker that waits at most `timeout` for a replica to catch up.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            gtid_set: None,
+            timeout,
+        }
+    }
+
+    /// Returns the GTID set recorded by the last call to [`record`](Self::record).
+    pub fn gtid_set(&self) -> Option<&str> {
+        self.gtid_set.as_deref()
+    }
+
+    /// Records the primary's executed GTID set.
+    ///
+    /// Call this after a write has been committed on the primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn record<'e, E>(&mut self, primary: E) -> crate::Result<()>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let mut query = PreparedQueryAs::<(String,), _>::new_unlisted(
+            "SELECT @@GLOBAL.gtid_executed",
+            |q, _| q,
+        )?;
+        let (gtid_set,) = query.fetch_one(primary).await?;
+        self.gtid_set = Some(gtid_set);
+        Ok(())
+    }
+
+    /// Executes `query` on `primary`, then records the primary's executed GTID set.
+    ///
+    /// Use it for writes that commit on their own; after a transaction, call
+    /// [`record`](Self::record) once it has committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write or recording the GTID set fails.
+    pub async fn execute<F>(
+        &mut self,
+        query: &mut PreparedQuery<F>,
+        primary: &MySqlPool,
+    ) -> crate::Result<ExecuteResult>
+    where
+        F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+    {
+        let result = query.execute(primary).await?;
+        self.record(primary).await?;
+        Ok(result)
+    }
+
+    /// Waits until the replica has applied every recorded transaction.
+    ///
+    /// Returns immediately if nothing has been recorded yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::GtidWaitTimeout`](crate::Error::GtidWaitTimeout) if the replica
+    /// does not catch up within the timeout, or an error if the query fails.
+    pub async fn wait<'e, E>(&self, replica: E) -> crate::Result<()>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let Some(gtid_set) = self.gtid_set.as_deref() else {
+            return Ok(());
+        };
+        let timeout = self.timeout.as_secs_f64();
+
+        let mut query = PreparedQueryAs::<(Option<i64>,), _>::new_unlisted(
+            "SELECT WAIT_FOR_EXECUTED_GTID_SET(:gtid_set, :timeout)",
+            |q, key| match key {
+                ":gtid_set" => q.bind(gtid_set.to_owned()),
+                ":timeout" => q.bind(timeout),
+                _ => q,
+            },
+        )?;
+        match query.fetch_one(replica).await? {
+            (Some(0),) => Ok(()),
+            _ => Err(crate::Error::GtidWaitTimeout(gtid_set.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Reusable common table expressions (`WITH` clauses) with named placeholders.
+//!
+//! A [`CteFragment`] is a derived table defined once and shared by many statements,
+//! e.g. the set of active customers used by several reports. [`with_ctes`] merges
+//! fragments into the prologue of a template before it is converted, so placeholders
+//! of the fragments and of the statement are bound in the order they appear in the
+//! final SQL with no extra work in the binder.
+//!
+//! [`RecursiveCte`] assembles `WITH RECURSIVE` statements from an anchor member and a
+//! recursive member, optionally bounded by a depth-limit placeholder.
+
+use crate::builder::placeholder_order;
+use crate::hint::validate_identifier;
+use crate::lexer::{tokenize, TokenKind};
+use crate::query_as::QA;
+use crate::PreparedQueryAs;
+use sqlx::mysql::MySqlRow;
+
+/// A named `WITH` clause member that can be merged into any template.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::cte::CteFragment;
+///
+/// let active = CteFragment::new(
+///     "active_customers",
+///     "SELECT id FROM customers WHERE last_order_at >= :since",
+/// )?;
+/// assert_eq!(
+///     active.to_string(),
+///     "active_customers AS (SELECT id FROM customers WHERE last_order_at >= :since)"
+/// );
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CteFragment {
+    name: String,
+    template: String,
+}
+
+impl CteFragment {
+    /// Creates a fragment named `name` defined by the `SELECT` in `template`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a plain identifier or the template cannot be parsed.
+    pub fn new<N, T>(name: N, template: T) -> crate::Result<Self>
+    where
+        N: Into<String>,
+        T: Into<String>,
+    {
+        let name = name.into();
+        let template = template.into();
+        validate_identifier(&name)?;
+        placeholder_order(&template)?;
+        Ok(Self { name, template })
+    }
+
+    /// Returns the name the statement refers to the fragment by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the fragment's SQL template.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+}
+
+impl std::fmt::Display for CteFragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} AS ({})", self.name, self.template)
+    }
+}
+
+/// Merges `fragments` into the `WITH` prologue of `template`, in the given order.
+///
+/// If the template has no `WITH` clause, one is added. If it already has one, the
+/// fragments are placed before the template's own members, so those can refer to the
+/// fragments. The result is still a template with named placeholders, ready to be
+/// passed to [`PreparedQuery::new`](crate::PreparedQuery::new) or
+/// [`PreparedQueryAs::new`](crate::PreparedQueryAs::new).
+///
+/// # Errors
+///
+/// Returns an error if the merged template cannot be parsed.
+///
+/// # Examples
+///
+/// ```rust
+/// use sqlx_named_bind::{cte::{with_ctes, CteFragment}, PreparedQueryAs};
+///
+/// let active = CteFragment::new(
+///     "active_customers",
+///     "SELECT id FROM customers WHERE last_order_at >= :since",
+/// )?;
+///
+/// let template = with_ctes(
+///     "SELECT c.id, SUM(o.amount) AS revenue FROM active_customers c \
+///      JOIN orders o ON o.customer_id = c.id WHERE o.status = :status GROUP BY c.id",
+///     [&active],
+/// )?;
+/// assert!(template.starts_with("WITH active_customers AS (SELECT id FROM customers"));
+///
+/// let query = PreparedQueryAs::<(i64, f64), _>::new(template, |q, key| match key {
+///     ":since" => q.bind("2024-01-01"),
+///     ":status" => q.bind("paid"),
+///     _ => q,
+/// })?;
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn with_ctes<'a, I>(template: &str, fragments: I) -> crate::Result<String>
+where
+    I: IntoIterator<Item = &'a CteFragment>,
+{
+    let members = fragments
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if members.is_empty() {
+        return Ok(template.to_owned());
+    }
+
+    let tokens = tokenize(template);
+    let merged = match tokens.first() {
+        Some(with) if with.is_keyword(template, "WITH") => {
+            let prologue_end = match tokens.get(1) {
+                Some(t) if t.is_keyword(template, "RECURSIVE") => t.end,
+                _ => with.end,
+            };
+            format!(
+                "{} {},{}",
+                &template[..prologue_end],
+                members,
+                &template[prologue_end..]
+            )
+        }
+        _ => format!("WITH {} {}", members, template),
+    };
+
+    placeholder_order(&merged)?;
+    Ok(merged)
+}
+
+/// Builder for `WITH RECURSIVE` statements.
+///
+/// The generated template has the form
+///
+/// ```sql
+/// WITH RECURSIVE name (columns) AS (
+///     anchor
+///     UNION ALL
+///     recursive [WHERE depth < :max_depth]
+/// ) SELECT * FROM name
+/// ```
+///
+/// Placeholders may appear in both members and in the final select; they are bound in
+/// the order they appear in the generated SQL, so one binder handles all of them.
+///
+/// # Examples
+///
+/// ```rust
+/// use sqlx_named_bind::cte::RecursiveCte;
+///
+/// let query = RecursiveCte::new(
+///     "subtree",
+///     "SELECT id, parent_id, 0 AS depth FROM categories WHERE id = :root",
+///     "SELECT c.id, c.parent_id, s.depth + 1 FROM categories c \
+///      JOIN subtree s ON c.parent_id = s.id",
+/// )
+/// .max_depth("s.depth", ":max_depth")
+/// .select("SELECT id, depth FROM subtree ORDER BY depth, id")
+/// .build::<(i64, i64), _>(|q, key| match key {
+///     ":root" => q.bind(1),
+///     ":max_depth" => q.bind(5),
+///     _ => q,
+/// })?;
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecursiveCte {
+    name: String,
+    columns: Vec<String>,
+    anchor: String,
+    recursive: String,
+    union_distinct: bool,
+    max_depth: Option<(String, String)>,
+    select: Option<String>,
+}
+
+impl RecursiveCte {
+    /// Creates a recursive CTE named `name` from its anchor and recursive members.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name the members and the final select refer to the CTE by
+    /// * `anchor` - Non-recursive `SELECT` producing the initial rows
+    /// * `recursive` - `SELECT` referring to `name` that produces the next level of rows
+    pub fn new<N, A, R>(name: N, anchor: A, recursive: R) -> Self
+    where
+        N: Into<String>,
+        A: Into<String>,
+        R: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            columns: Vec::new(),
+            anchor: anchor.into(),
+            recursive: recursive.into(),
+            union_distinct: false,
+            max_depth: None,
+            select: None,
+        }
+    }
+
+    /// Names the CTE's columns explicitly, instead of taking them from the anchor.
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Combines the members with `UNION DISTINCT` instead of `UNION ALL`, which stops
+    /// cycles in the data from recursing forever.
+    pub fn union_distinct(mut self) -> Self {
+        self.union_distinct = true;
+        self
+    }
+
+    /// Stops the recursion once `depth_expr` reaches the value bound to `placeholder`.
+    ///
+    /// `depth_expr` is a (qualified) column of the CTE as seen from the recursive member,
+    /// e.g. `s.depth`. The condition `depth_expr < placeholder` is added to the recursive
+    /// member's `WHERE` clause.
+    pub fn max_depth<D, P>(mut self, depth_expr: D, placeholder: P) -> Self
+    where
+        D: Into<String>,
+        P: Into<String>,
+    {
+        self.max_depth = Some((depth_expr.into(), placeholder.into()));
+        self
+    }
+
+    /// Sets the statement that uses the CTE. Defaults to `SELECT * FROM name`.
+    pub fn select<S: Into<String>>(mut self, select: S) -> Self {
+        self.select = Some(select.into());
+        self
+    }
+
+    /// Returns the generated template, with named placeholders.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if the CTE
+    /// name, a column or the depth expression is not a plain (qualified) identifier, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the depth-limit
+    /// placeholder is not a valid placeholder name.
+    pub fn template(&self) -> crate::Result<String> {
+        validate_identifier(&self.name)?;
+        for column in &self.columns {
+            validate_identifier(column)?;
+        }
+
+        let recursive = match &self.max_depth {
+            Some((depth_expr, placeholder)) => {
+                for part in depth_expr.split('.') {
+                    validate_identifier(part)?;
+                }
+                if placeholder_order(placeholder)? != [placeholder.as_str()] {
+                    return Err(crate::Error::UnknownPlaceholder(placeholder.clone()));
+                }
+                add_condition(&self.recursive, &format!("{depth_expr} < {placeholder}"))
+            }
+            None => self.recursive.clone(),
+        };
+
+        let columns = if self.columns.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.columns.join(", "))
+        };
+        let union = if self.union_distinct {
+            "UNION DISTINCT"
+        } else {
+            "UNION ALL"
+        };
+        let select = match &self.select {
+            Some(select) => select.clone(),
+            None => format!("SELECT * FROM {}", self.name),
+        };
+
+        Ok(format!(
+            "WITH RECURSIVE {}{} AS ({} {} {}) {}",
+            self.name, columns, self.anchor, union, recursive, select
+        ))
+    }
+
+    /// Builds a [`PreparedQueryAs`] from the generated template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be generated or parsed.
+    pub fn build<R, F>(&self, binder: F) -> crate::Result<PreparedQueryAs<R, F>>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        PreparedQueryAs::new(self.template()?, binder)
+    }
+}
+
+/// Adds `condition` to the top-level `WHERE` clause of `select`, creating one if needed.
+fn add_condition(select: &str, condition: &str) -> String {
+    let tokens = tokenize(select);
+    let mut depth = 0usize;
+    let mut where_end = None;
+    let mut clause_end = select.len();
+    for token in &tokens {
+        if token.kind == TokenKind::Punct {
+            if token.is_punct(select, '(') {
+                depth += 1;
+            } else if token.is_punct(select, ')') {
+                depth = depth.saturating_sub(1);
+            }
+        } else if depth == 0 && token.is_keyword(select, "WHERE") {
+            where_end = Some(token.end);
+        } else if depth == 0 && token.is_keyword(select, "LIMIT") {
+            clause_end = token.start;
+        }
+    }
+
+    let rest = &select[clause_end..];
+    let separator = if rest.is_empty() { "" } else { " " };
+    match where_end {
+        Some(where_end) => format!(
+            "{} ({}) AND {}{}{}",
+            &select[..where_end],
+            select[where_end..clause_end].trim(),
+            condition,
+            separator,
+            rest
+        ),
+        None => format!(
+            "{} WHERE {}{}{}",
+            select[..clause_end].trim_end(),
+            condition,
+            separator,
+            rest
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(name: &str, template: &str) -> CteFragment {
+        CteFragment::new(name, template).unwrap()
+    }
+
+    #[test]
+    fn test_with_ctes_adds_with_clause() {
+        let a = fragment("a", "SELECT id FROM t WHERE x = :x");
+        let b = fragment("b", "SELECT id FROM a WHERE y = :y");
+        let sql = with_ctes("SELECT * FROM b WHERE z = :z", [&a, &b]).unwrap();
+        assert_eq!(
+            sql,
+            "WITH a AS (SELECT id FROM t WHERE x = :x), b AS (SELECT id FROM a WHERE y = :y) \
+             SELECT * FROM b WHERE z = :z"
+        );
+        assert_eq!(placeholder_order(&sql).unwrap(), vec![":x", ":y", ":z"]);
+    }
+
+    #[test]
+    fn test_with_ctes_merges_into_existing_with() {
+        let a = fragment("a", "SELECT id FROM t WHERE x = :x");
+        let sql = with_ctes(
+            "WITH RECURSIVE c AS (SELECT id FROM a WHERE w = :w) SELECT * FROM c",
+            [&a],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "WITH RECURSIVE a AS (SELECT id FROM t WHERE x = :x), \
+             c AS (SELECT id FROM a WHERE w = :w) SELECT * FROM c"
+        );
+        assert_eq!(placeholder_order(&sql).unwrap(), vec![":x", ":w"]);
+    }
+
+    #[test]
+    fn test_cte_fragment_rejects_invalid_names() {
+        assert!(CteFragment::new("a b", "SELECT 1").is_err());
+        assert_eq!(with_ctes("SELECT 1", []).unwrap(), "SELECT 1");
+    }
+
+    #[test]
+    fn test_recursive_cte_template() {
+        let cte = RecursiveCte::new(
+            "tree",
+            "SELECT id, 0 AS depth FROM nodes WHERE id = :root",
+            "SELECT n.id, t.depth + 1 FROM nodes n JOIN tree t ON n.parent_id = t.id \
+             WHERE n.kind = :kind OR n.kind IS NULL",
+        )
+        .columns(["id", "depth"])
+        .max_depth("t.depth", ":max_depth");
+
+        let template = cte.template().unwrap();
+        assert_eq!(
+            template,
+            "WITH RECURSIVE tree (id, depth) AS (\
+             SELECT id, 0 AS depth FROM nodes WHERE id = :root UNION ALL \
+             SELECT n.id, t.depth + 1 FROM nodes n JOIN tree t ON n.parent_id = t.id \
+             WHERE (n.kind = :kind OR n.kind IS NULL) AND t.depth < :max_depth) \
+             SELECT * FROM tree"
+        );
+        assert_eq!(
+            placeholder_order(&template).unwrap(),
+            vec![":root", ":kind", ":max_depth"]
+        );
+    }
+
+    #[test]
+    fn test_add_condition() {
+        assert_eq!(
+            add_condition("SELECT a FROM t JOIN (SELECT b FROM u WHERE c) x", "d < :n"),
+            "SELECT a FROM t JOIN (SELECT b FROM u WHERE c) x WHERE d < :n"
+        );
+        assert_eq!(
+            add_condition("SELECT a FROM t WHERE b = 1 LIMIT 10", "d < :n"),
+            "SELECT a FROM t WHERE (b = 1) AND d < :n LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn test_recursive_cte_rejects_invalid_depth_limit() {
+        let cte = RecursiveCte::new("t", "SELECT 1", "SELECT n + 1 FROM t");
+        assert!(cte.clone().max_depth("n; DROP", ":d").template().is_err());
+        assert!(matches!(
+            cte.max_depth("n", "max").template(),
+            Err(crate::Error::UnknownPlaceholder(_))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+//! async-graphql data loaders backed by a named query.
+//!
+//! A GraphQL resolver typically needs one row per parent object, which turns into one
+//! query per object unless the lookups are batched. [`KeyLoader`] and [`GroupLoader`]
+//! implement the async-graphql [`Loader`] trait on top of
+//! [`PreparedQueryAs::fetch_by_keys`] and
+//! [`PreparedQueryAs::fetch_grouped_by_keys`]: every batch of keys collected by a
+//! `DataLoader` runs the template once, with the keys bound to an `IN (:placeholder)`
+//! list, and each key gets its rows back.
+//!
+//! Errors are shared between the waiting resolvers, so they are returned as
+//! `Arc<Error>`.
+//!
+//! ```rust,no_run
+//! use async_graphql::dataloader::DataLoader;
+//! use sqlx::MySqlPool;
+//! use sqlx_named_bind::dataloader::KeyLoader;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+//! let users = DataLoader::new(
+//!     KeyLoader::new(
+//!         pool,
+//!         "SELECT id, name FROM users WHERE id IN (:ids)",
+//!         "ids",
+//!         |(id, _): &(i64, String)| *id,
+//!     )?,
+//!     tokio::spawn,
+//! );
+//! let author = users.load_one(42_i64).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder::placeholder_order;
+use crate::conditional::NamedBinds;
+use crate::query::placeholder_key;
+use crate::PreparedQueryAs;
+use async_graphql::dataloader::Loader;
+use sqlx::mysql::MySqlRow;
+use sqlx::{Encode, FromRow, MySql, MySqlPool, Type};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The query and key extraction shared by both loaders.
+struct Source<K, R> {
+    pool: MySqlPool,
+    template: String,
+    placeholder: String,
+    params: NamedBinds,
+    key: Arc<dyn Fn(&R) -> K + Send + Sync>,
+}
+
+impl<K, R> Source<K, R> {
+    fn new(
+        pool: MySqlPool,
+        template: String,
+        placeholder: &str,
+        key: impl Fn(&R) -> K + Send + Sync + 'static,
+    ) -> crate::Result<Self> {
+        let placeholder = placeholder_key(placeholder);
+        if !placeholder_order(&template)?.contains(&placeholder) {
+            return Err(crate::Error::UnknownPlaceholder(placeholder));
+        }
+        Ok(Self {
+            pool,
+            template,
+            placeholder,
+            params: NamedBinds::new(),
+            key: Arc::new(key),
+        })
+    }
+}
+
+/// Loads the first row of each key.
+///
+/// Keys without a row are left out of the result, so `DataLoader::load_one` returns
+/// `None` for them.
+pub struct KeyLoader<K, R>(Source<K, R>);
+
+impl<K, R> KeyLoader<K, R> {
+    /// Creates a loader running `template` with each batch of keys bound to the list
+    /// placeholder `placeholder`, matching rows back to keys with `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if it has no
+    /// placeholder `placeholder`.
+    pub fn new<T>(
+        pool: MySqlPool,
+        template: T,
+        placeholder: &str,
+        key: impl Fn(&R) -> K + Send + Sync + 'static,
+    ) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        Source::new(pool, template.into(), placeholder, key).map(Self)
+    }
+
+    /// Binds the template's other placeholders from `params` in every batch.
+    pub fn params(mut self, params: NamedBinds) -> Self {
+        self.0.params = params;
+        self
+    }
+}
+
+impl<K, R> Loader<K> for KeyLoader<K, R>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    K: for<'q> Encode<'q, MySql> + Type<MySql>,
+    for<'row> R: FromRow<'row, MySqlRow> + Send + Sync + Unpin + Clone + 'static,
+{
+    type Value = R;
+    type Error = Arc<crate::Error>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, R>, Self::Error> {
+        let source = &self.0;
+        let rows =
+            PreparedQueryAs::<R, _>::new(source.template.as_str(), source.params.binder_as())?
+                .fetch_by_keys(&source.pool, &source.placeholder, keys, |row| {
+                    (source.key)(row)
+                })
+                .await?;
+        Ok(keys
+            .iter()
+            .cloned()
+            .zip(rows)
+            .filter_map(|(key, row)| Some((key, row?)))
+            .collect())
+    }
+}
+
+/// Loads every row of each key, in the order they arrived.
+///
+/// Every requested key is in the result, with an empty `Vec` if no row matched it.
+pub struct GroupLoader<K, R>(Source<K, R>);
+
+impl<K, R> GroupLoader<K, R> {
+    /// Creates a loader running `template` with each batch of keys bound to the list
+    /// placeholder `placeholder`, grouping rows by `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if it has no
+    /// placeholder `placeholder`.
+    pub fn new<T>(
+        pool: MySqlPool,
+        template: T,
+        placeholder: &str,
+        key: impl Fn(&R) -> K + Send + Sync + 'static,
+    ) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        Source::new(pool, template.into(), placeholder, key).map(Self)
+    }
+
+    /// Binds the template's other placeholders from `params` in every batch.
+    pub fn params(mut self, params: NamedBinds) -> Self {
+        self.0.params = params;
+        self
+    }
+}
+
+impl<K, R> Loader<K> for GroupLoader<K, R>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    K: for<'q> Encode<'q, MySql> + Type<MySql>,
+    for<'row> R: FromRow<'row, MySqlRow> + Send + Sync + Unpin + Clone + 'static,
+{
+    type Value = Vec<R>;
+    type Error = Arc<crate::Error>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, Vec<R>>, Self::Error> {
+        let source = &self.0;
+        let groups =
+            PreparedQueryAs::<R, _>::new(source.template.as_str(), source.params.binder_as())?
+                .fetch_grouped_by_keys(&source.pool, &source.placeholder, keys, |row| {
+                    (source.key)(row)
+                })
+                .await?;
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_loader_checks_placeholder() {
+        let pool = MySqlPool::connect_lazy("mysql://localhost/test").unwrap();
+        let template = "SELECT id FROM users WHERE id IN (:ids) AND active = :active";
+        let key = |(id,): &(i64,)| *id;
+        assert!(KeyLoader::new(pool.clone(), template, ":ids", key).is_ok());
+        assert!(matches!(
+            GroupLoader::new(pool, template, "id", key),
+            Err(crate::Error::UnknownPlaceholder(name)) if name == ":id"
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+//! End-to-end deadlines for everything a request executes.
+//!
+//! A request handler usually knows when its caller stops waiting, but the queries it
+//! runs are created several layers further down. [`scope`] makes a deadline current
+//! for a future, and every execution inside it, through any number of layers, is
+//! bounded by it: the execution is cancelled when the deadline passes, failing with
+//! [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded), and one that starts
+//! after it passed fails without querying.
+//!
+//! The time left is measured at each execution, so it shrinks by the time already
+//! spent on earlier statements, retries and the delays between them.
+//! [`retry`](crate::retry::retry) makes the deadline of its
+//! [`RetryBudget`](crate::retry::RetryBudget) current for every attempt. Nested scopes
+//! keep the earlier deadline.
+//!
+//! The deadline is enforced on the client side only, and the SQL text is the same
+//! whatever the time left, so each query stays one cached prepared statement. A
+//! cancelled statement may keep running on the server until it finishes; give `SELECT`s
+//! a fixed server-side limit with
+//! [`PreparedQuery::timeout`](crate::PreparedQuery::timeout).
+//!
+//! ```rust,no_run
+//! use std::time::{Duration, Instant};
+//! use sqlx::MySqlPool;
+//! use sqlx_named_bind::{deadline, PreparedQuery};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+//! let request_deadline = Instant::now() + Duration::from_millis(250);
+//! let rows = deadline::scope(request_deadline, async {
+//!     PreparedQuery::new("SELECT * FROM orders WHERE user_id = :id", |q, _| q.bind(42))?
+//!         .fetch_rows(&pool)
+//!         .await
+//! })
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Runs `future` with `deadline` as the current deadline, or the current one if it is
+/// earlier, cancelling it when the deadline passes.
+///
+/// # Errors
+///
+/// Returns [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded) if the deadline
+/// passes before `future` completes, or the error of `future`.
+pub async fn scope<T, Fut>(deadline: Instant, future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let deadline = current().map_or(deadline, |outer| outer.min(deadline));
+    let cancelled = tokio::time::timeout_at(deadline.into(), future);
+    DEADLINE
+        .scope(deadline, cancelled)
+        .await
+        .unwrap_or(Err(crate::Error::DeadlineExceeded))
+}
+
+/// Like [`scope`], with a deadline `timeout` from now.
+pub async fn scope_timeout<T, Fut>(timeout: Duration, future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    scope(Instant::now() + timeout, future).await
+}
+
+/// Returns the current deadline, or `None` outside of a [`scope`].
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Returns the time left until the current deadline, or `None` outside of a [`scope`].
+///
+/// Returns `Some(Duration::ZERO)` once the deadline has passed.
+pub fn remaining() -> Option<Duration> {
+    current().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Fails if the current deadline has passed, before an execution starts.
+///
+/// # Errors
+///
+/// Returns [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded) if the deadline
+/// has passed.
+pub(crate) fn check() -> crate::Result<()> {
+    match remaining() {
+        Some(remaining) if remaining.is_zero() => Err(crate::Error::DeadlineExceeded),
+        _ => Ok(()),
+    }
+}
+
+/// Cancels `future` at the current deadline, if there is one.
+pub(crate) async fn enforce<T, Fut>(future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    match current() {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), future)
+            .await
+            .unwrap_or(Err(crate::Error::DeadlineExceeded)),
+        None => future.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert_eq!(current(), None);
+
+        let outer = Instant::now() + Duration::from_secs(5);
+        scope(outer, async {
+            assert_eq!(current(), Some(outer));
+            let later = scope(outer + Duration::from_secs(5), async { Ok(current()) });
+            assert_eq!(later.await.unwrap(), Some(outer));
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        let cancelled = scope_timeout(Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        });
+        assert!(matches!(
+            cancelled.await,
+            Err(crate::Error::DeadlineExceeded)
+        ));
+
+        check().unwrap();
+        let passed = scope(Instant::now(), async { check() });
+        assert!(matches!(passed.await, Err(crate::Error::DeadlineExceeded)));
+    }
+}
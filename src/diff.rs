@@ -0,0 +1,149 @@
+//! Comparison of two result sets.
+//!
+//! This is meant for validating a rewritten query against the original before
+//! switching over: run both with the same binds, then diff the typed results by
+//! their key columns.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Differences between two result sets, as reported by [`diff_results`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultDiff<R> {
+    /// Rows whose key only appears in the second result set, in its order
+    pub added: Vec<R>,
+    /// Rows whose key only appears in the first result set, in its order
+    pub removed: Vec<R>,
+    /// Rows with the same key but different values, as `(before, after)` pairs
+    pub changed: Vec<(R, R)>,
+}
+
+impl<R> ResultDiff<R> {
+    /// Returns `true` if both result sets contained the same rows.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two result sets row by row, matching rows by the key returned from `key`.
+///
+/// Row order does not matter. Keys are expected to be unique; if a key appears more
+/// than once, its rows are matched up in the order they appear in each result set.
+///
+/// # Arguments
+///
+/// * `a` - The original result set
+/// * `b` - The result set to compare against the original
+/// * `key` - Extracts the key columns of a row
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{MySqlPool, FromRow};
+/// use sqlx_named_bind::{diff::diff_results, PreparedQueryAs};
+///
+/// #[derive(Debug, FromRow, PartialEq)]
+/// struct Total {
+///     customer_id: i64,
+///     amount: i64,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let original = PreparedQueryAs::<Total, _>::new(
+///     "SELECT customer_id, SUM(amount) AS amount FROM orders \
+///      WHERE created_at >= :since GROUP BY customer_id",
+///     |q, key| match key {
+///         ":since" => q.bind("2024-01-01"),
+///         _ => q,
+///     },
+/// )?
+/// .fetch_all(&pool)
+/// .await?;
+///
+/// let rewritten = PreparedQueryAs::<Total, _>::new(
+///     "SELECT customer_id, amount FROM order_totals WHERE day >= :since",
+///     |q, key| match key {
+///         ":since" => q.bind("2024-01-01"),
+///         _ => q,
+///     },
+/// )?
+/// .fetch_all(&pool)
+/// .await?;
+///
+/// let diff = diff_results(original, rewritten, |row| row.customer_id);
+/// assert!(diff.is_empty(), "rewrite changed the results: {:?}", diff);
+/// # Ok(())
+/// # }
+/// ```
+pub fn diff_results<R, K, F>(a: Vec<R>, b: Vec<R>, mut key: F) -> ResultDiff<R>
+where
+    R: PartialEq,
+    K: Eq + Hash,
+    F: FnMut(&R) -> K,
+{
+    let mut unmatched: HashMap<K, VecDeque<(usize, R)>> = HashMap::new();
+    for (index, row) in b.into_iter().enumerate() {
+        unmatched
+            .entry(key(&row))
+            .or_default()
+            .push_back((index, row));
+    }
+
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for before in a {
+        match unmatched
+            .get_mut(&key(&before))
+            .and_then(VecDeque::pop_front)
+        {
+            Some((_, after)) if after == before => {}
+            Some((_, after)) => changed.push((before, after)),
+            None => removed.push(before),
+        }
+    }
+
+    let mut added: Vec<(usize, R)> = unmatched.into_values().flatten().collect();
+    added.sort_by_key(|(index, _)| *index);
+
+    ResultDiff {
+        added: added.into_iter().map(|(_, row)| row).collect(),
+        removed,
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_results() {
+        let a = vec![(1, "a"), (2, "b"), (3, "c")];
+        let b = vec![(5, "e"), (3, "c"), (2, "x"), (4, "d")];
+
+        let diff = diff_results(a, b, |row| row.0);
+        assert_eq!(diff.added, vec![(5, "e"), (4, "d")]);
+        assert_eq!(diff.removed, vec![(1, "a")]);
+        assert_eq!(diff.changed, vec![((2, "b"), (2, "x"))]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_results_ignores_order() {
+        let a = vec![(1, "a"), (2, "b")];
+        let b = vec![(2, "b"), (1, "a")];
+        assert!(diff_results(a, b, |row| row.0).is_empty());
+    }
+
+    #[test]
+    fn test_diff_results_duplicate_keys() {
+        let a = vec![(1, "a"), (1, "b")];
+        let b = vec![(1, "a"), (1, "c"), (1, "d")];
+
+        let diff = diff_results(a, b, |row| row.0);
+        assert_eq!(diff.changed, vec![((1, "b"), (1, "c"))]);
+        assert_eq!(diff.added, vec![(1, "d")]);
+        assert!(diff.removed.is_empty());
+    }
+}
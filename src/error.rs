@@ -1,17 +1,165 @@
 /// Error types for sqlx-named-bind
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    /// Error during SQL template parsing
-    #[error("Failed to parse SQL template: {0}")]
-    Parse(#[from] regex::Error),
-
     /// Error from SQLx database operations
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
+    /// Error parsing or serializing JSON
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// Error reading or writing an external source such as an export file
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A record being imported has no value for a mapped field
+    #[error("Field '{0}' is missing from the record")]
+    MissingField(String),
+
+    /// The SQL is not on the installed allow-list
+    #[error("Query is not on the allow-list: {0}")]
+    NotAllowed(String),
+
+    /// The SQL references a multi-tenant table without the tenant placeholder
+    #[error("Query on tenant table '{table}' has no tenant filter: {sql}")]
+    MissingTenantFilter {
+        /// The tenant table, as written in the SQL
+        table: String,
+        /// The rejected SQL, with positional placeholders
+        sql: String,
+    },
+
+    /// A value could not be encrypted with the installed
+    /// [`FieldCipher`](crate::cipher::FieldCipher)
+    #[error("Encryption failed for placeholder {0}")]
+    Cipher(String),
+
+    /// A bound value was rejected by a validator attached to its placeholder
+    #[error("Invalid value for placeholder '{name}': {reason}")]
+    InvalidParam {
+        /// The placeholder, including the leading `:`
+        name: String,
+        /// Why the validator rejected the value
+        reason: String,
+    },
+
+    /// A parameter source could not be turned into named values, e.g. it did not
+    /// serialize to a map
+    #[error("Invalid parameters: {0}")]
+    InvalidParams(String),
+
+    /// A table, index or other name was not a plain SQL identifier
+    #[error("Invalid SQL identifier: '{0}'")]
+    InvalidIdentifier(String),
+
+    /// A statement modifier could not be applied to the SQL template
+    #[error("Invalid statement modifier: {0}")]
+    InvalidModifier(String),
+
+    /// A template's front-matter header was malformed or did not match the template
+    #[error("Invalid query metadata: {0}")]
+    InvalidMetadata(String),
+
+    /// A template's syntax was invalid, such as an unterminated conditional fragment
+    #[error("Invalid SQL template: {0}")]
+    InvalidTemplate(String),
+
+    /// A table was expected in the SQL template but is never referenced
+    #[error("Table '{0}' is not referenced in the SQL template")]
+    TableNotFound(String),
+
+    /// A replica did not apply the requested GTID set in time
+    #[error("Timed out waiting for replica to apply GTID set '{0}'")]
+    GtidWaitTimeout(String),
+
+    /// A named lock was still held elsewhere when the wait timed out
+    #[error("Timed out waiting for lock '{0}'")]
+    LockTimeout(String),
+
+    /// The optimizer's plan exceeded a configured cost limit, so the query was not run
+    #[error("Query rejected as too expensive: {0}")]
+    TooExpensive(String),
+
+    /// A query ran longer or returned more rows than the budget for its tags allows
+    #[error("Query budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    /// The crate is shutting down and no longer starts new executions
+    #[error("Shutting down; no new queries are accepted")]
+    ShuttingDown,
+
+    /// An execution waited too long for a free slot under a concurrency limit
+    #[error("Concurrency limit reached for query: {0}")]
+    ConcurrencyLimited(String),
+
+    /// The overall deadline of an operation passed before it completed
+    #[error("Deadline exceeded")]
+    DeadlineExceeded,
+
+    /// A chunked batch failed part-way through
+    #[error("Batch failed at parameter set {failed_at} after {committed} were committed: {source}")]
+    BatchFailed {
+        /// Number of parameter sets committed before the failure
+        committed: usize,
+        /// Index of the parameter set whose chunk failed
+        failed_at: usize,
+        /// The underlying error
+        source: Box<Error>,
+    },
+
+    /// A registry report differs from its committed golden file
+    #[error("Golden file '{path}' is out of date:\n{diff}")]
+    GoldenMismatch {
+        /// Path of the golden file
+        path: String,
+        /// Lines removed (`-`) and added (`+`) relative to the golden file
+        diff: String,
+    },
+
+    /// [`QueryRegistry::self_test`](crate::registry::QueryRegistry::self_test) found
+    /// broken queries
+    #[error("Registry self-test failed:\n{0}")]
+    SelfTestFailed(String),
+
+    /// A replayed query was not part of the recording
+    #[error("No recorded result for query: {0}")]
+    NotRecorded(String),
+
+    /// A placeholder name was given that does not appear in the SQL template
+    #[error("Placeholder '{0}' does not appear in the SQL template")]
+    UnknownPlaceholder(String),
+
     /// Placeholder was referenced but not bound by the binder function
     #[error("Placeholder '{0}' was not bound by the binder function")]
     UnboundPlaceholder(String),
+
+    /// Several placeholders were not bound by the binder function, usually because of
+    /// typos in the binder's `match` arms
+    #[error("Placeholders {0:?} were not bound by the binder function")]
+    UnboundPlaceholders(Vec<String>),
+
+    /// The names a caller binds do not match the placeholders of a template
+    #[error("Bound names do not match the placeholders: missing {missing:?}, extra {extra:?}")]
+    PlaceholderMismatch {
+        /// Placeholders of the template that are not among the bound names
+        missing: Vec<String>,
+        /// Bound names that are not placeholders of the template
+        extra: Vec<String>,
+    },
+
+    /// A placeholder used more than once was bound to different values
+    #[error("Placeholder '{0}' appears more than once but was bound to different values")]
+    InconsistentRepeat(String),
+
+    /// A statement has more bound values than allowed, after list expansion
+    #[error("Statement has {count} bound values, more than the maximum of {max}")]
+    TooManyPlaceholders {
+        /// Number of values bound to the statement
+        count: usize,
+        /// The limit set with `limits::set_max_placeholders`
+        max: usize,
+    },
 }
 
 /// Result type alias for sqlx-named-bind operations
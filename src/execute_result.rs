@@ -0,0 +1,220 @@
+//! The outcome of executing a statement, independent of MySQL result quirks.
+//!
+//! sqlx reports every execution as a `MySqlQueryResult` whose counts follow MySQL's
+//! protocol: `last_insert_id` is `0` when no `AUTO_INCREMENT` value was generated, and
+//! what `rows_affected` counts for an `UPDATE` depends on whether the connection was
+//! opened with `CLIENT_FOUND_ROWS`. [`ExecuteResult`] states these explicitly, so
+//! application code does not have to know them.
+//!
+//! MySQL reports silent truncations and conversions only as warnings, which sqlx
+//! drops. [`PreparedQuery::execute_with_warnings`](crate::PreparedQuery::execute_with_warnings)
+//! reads the warning count on the same connection and, when it is not zero, the
+//! warnings with `SHOW WARNINGS`, and attaches them to the result as [`Warning`]s.
+
+use sqlx::mysql::{MySqlConnection, MySqlQueryResult, MySqlRow};
+use sqlx::{Executor, Row};
+use std::fmt;
+
+/// The outcome of [`PreparedQuery::execute`](crate::PreparedQuery::execute).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::PreparedQuery;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let result = PreparedQuery::new(
+///     "INSERT INTO users (name) VALUES (:name)",
+///     |q, _| q.bind("Alice"),
+/// )?
+/// .execute(&pool)
+/// .await?;
+/// if let Some(id) = result.last_insert_id() {
+///     println!("Created user {id}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecuteResult {
+    rows_matched: u64,
+    last_insert_id: Option<u64>,
+    warning_count: Option<u16>,
+    warnings: Vec<Warning>,
+}
+
+impl ExecuteResult {
+    /// Returns the number of rows the statement matched.
+    ///
+    /// sqlx always connects with `CLIENT_FOUND_ROWS`, so an `UPDATE` counts every row
+    /// its `WHERE` clause matched, including rows that already held the new values,
+    /// and "did the row exist" checks can rely on it. An
+    /// `INSERT ... ON DUPLICATE KEY UPDATE` still counts `1` per inserted row and `2`
+    /// per updated row, as MySQL reports it.
+    pub fn rows_matched(&self) -> u64 {
+        self.rows_matched
+    }
+
+    /// Returns [`rows_matched`](Self::rows_matched).
+    ///
+    /// Kept for code written against `MySqlQueryResult`, which
+    /// [`PreparedQuery::execute`](crate::PreparedQuery::execute) returned before.
+    #[deprecated(note = "use `rows_matched`, which counts the same rows")]
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_matched
+    }
+
+    /// Returns the `AUTO_INCREMENT` value generated for the first inserted row, or
+    /// `None` if the statement generated none.
+    pub fn last_insert_id(&self) -> Option<u64> {
+        self.last_insert_id
+    }
+
+    /// Returns the number of warnings the statement raised, as the server counted them
+    /// in `@@warning_count`, or `None` if it was not read.
+    ///
+    /// sqlx does not report the warning count of a statement, so it is `None` unless
+    /// the statement ran through
+    /// [`execute_with_warnings`](crate::PreparedQuery::execute_with_warnings).
+    pub fn warning_count(&self) -> Option<u16> {
+        self.warning_count
+    }
+
+    /// Returns the warnings the statement raised, if they were read.
+    ///
+    /// The server keeps at most `max_error_count` warnings per statement (1024 by
+    /// default), so there may be fewer than [`warning_count`](Self::warning_count).
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Attaches the warning count and the warnings read after the statement.
+    pub(crate) fn with_warnings(mut self, count: u16, warnings: Vec<Warning>) -> Self {
+        self.warning_count = Some(count);
+        self.warnings = warnings;
+        self
+    }
+}
+
+/// The severity of a [`Warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningLevel {
+    /// `Note`: informational, e.g. `DROP TABLE IF EXISTS` on a missing table
+    Note,
+    /// `Warning`: the statement succeeded, but a value was truncated or converted
+    Warning,
+    /// `Error`: reported for the statement that failed
+    Error,
+}
+
+/// A condition raised by a statement, as listed by `SHOW WARNINGS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The severity of the condition
+    pub level: WarningLevel,
+    /// The MySQL error number, e.g. `1265` for "Data truncated"
+    pub code: u32,
+    /// The message text
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}: {}", self.level, self.code, self.message)
+    }
+}
+
+/// Reads the number of warnings the last statement on `conn` raised.
+pub(crate) async fn warning_count(conn: &mut MySqlConnection) -> crate::Result<u16> {
+    // A diagnostic statement, so it leaves the warnings in place for `SHOW WARNINGS`.
+    let row = conn.fetch_one("SHOW COUNT(*) WARNINGS").await?;
+    let count = match row.try_get::<u64, _>(0) {
+        Ok(count) => count,
+        Err(_) => row.try_get::<i64, _>(0)?.try_into().unwrap_or_default(),
+    };
+    Ok(u16::try_from(count).unwrap_or(u16::MAX))
+}
+
+/// Reads the warnings of the last statement on `conn`.
+pub(crate) async fn show_warnings(conn: &mut MySqlConnection) -> crate::Result<Vec<Warning>> {
+    // Sent as a plain text query: preparing a statement would clear the warnings, and
+    // an installed allow-list does not need to list it.
+    let rows = conn.fetch_all("SHOW WARNINGS").await?;
+    rows.iter().map(parse_warning).collect()
+}
+
+fn parse_warning(row: &MySqlRow) -> crate::Result<Warning> {
+    let level = match row.try_get::<String, _>("Level")?.as_str() {
+        "Note" => WarningLevel::Note,
+        "Error" => WarningLevel::Error,
+        _ => WarningLevel::Warning,
+    };
+    Ok(Warning {
+        level,
+        code: row.try_get("Code")?,
+        message: row.try_get("Message")?,
+    })
+}
+
+impl From<MySqlQueryResult> for ExecuteResult {
+    fn from(result: MySqlQueryResult) -> Self {
+        Self {
+            rows_matched: result.rows_affected(),
+            last_insert_id: Some(result.last_insert_id()).filter(|&id| id != 0),
+            warning_count: None,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Extend<ExecuteResult> for ExecuteResult {
+    /// Adds up the rows and warnings of `results`, keeping the last generated
+    /// `AUTO_INCREMENT` value.
+    fn extend<T: IntoIterator<Item = ExecuteResult>>(&mut self, results: T) {
+        for result in results {
+            self.rows_matched += result.rows_matched;
+            self.last_insert_id = result.last_insert_id.or(self.last_insert_id);
+            self.warning_count = match (self.warning_count, result.warning_count) {
+                (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                (a, b) => a.or(b),
+            };
+            self.warnings.extend(result.warnings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend() {
+        let insert = |rows_matched, last_insert_id| ExecuteResult {
+            rows_matched,
+            last_insert_id,
+            ..ExecuteResult::default()
+        };
+        let mut total = ExecuteResult::from(MySqlQueryResult::default());
+        assert_eq!(total, ExecuteResult::default());
+
+        total.extend([insert(2, Some(7)), insert(1, None)]);
+        assert_eq!(total, insert(3, Some(7)));
+        let truncated = Warning {
+            level: WarningLevel::Warning,
+            code: 1265,
+            message: "Data truncated for column 'name' at row 1".to_owned(),
+        };
+        total.extend([insert(0, Some(9)).with_warnings(1, vec![truncated.clone()])]);
+        assert_eq!(
+            (total.last_insert_id(), total.warning_count()),
+            (Some(9), Some(1))
+        );
+        assert_eq!(total.warnings(), std::slice::from_ref(&truncated));
+        assert_eq!(
+            truncated.to_string(),
+            "Warning 1265: Data truncated for column 'name' at row 1"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Helpers built on top of MySQL's `EXPLAIN` statement.
+//!
+//! These are used by the `estimate_rows` and `explain_plan` methods of `PreparedQuery`
+//! and `PreparedQueryAs` to inspect the optimizer's plan for a query with its current
+//! binds, without actually running it.
+//!
+//! A [`CostGuard`] turns a plan into a go/no-go decision, so expensive statements can
+//! be refused before they run (e.g. in admin-facing ad-hoc query features).
+
+use serde_json::Value;
+use sqlx::mysql::MySqlRow;
+use sqlx::Row;
+
+/// Prefixes the converted SQL with `EXPLAIN`.
+pub(crate) fn explain_sql(sql: &str) -> String {
+    format!("EXPLAIN {sql}")
+}
+
+/// Prefixes the converted SQL with `EXPLAIN FORMAT=JSON`.
+pub(crate) fn explain_json_sql(sql: &str) -> String {
+    format!("EXPLAIN FORMAT=JSON {sql}")
+}
+
+/// Sums the `rows` column over every row of a traditional `EXPLAIN` result.
+///
+/// MySQL reports `rows` as `NULL` for plan rows that don't read a table
+/// (e.g. `SELECT 1`); those rows contribute nothing to the estimate.
+pub(crate) fn estimated_rows(plan: &[MySqlRow]) -> u64 {
+    plan.iter().filter_map(rows_column).sum()
+}
+
+fn rows_column(row: &MySqlRow) -> Option<u64> {
+    // The column type of `rows` differs between server versions and forks,
+    // so accept both signed and unsigned integers.
+    if let Ok(rows) = row.try_get::<Option<u64>, _>("rows") {
+        return rows;
+    }
+    row.try_get::<Option<i64>, _>("rows")
+        .ok()
+        .flatten()
+        .and_then(|rows| u64::try_from(rows).ok())
+}
+
+/// How MySQL accesses a table, as reported in the `access_type` field of a JSON plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessType {
+    /// The table has exactly one row (`system`)
+    System,
+    /// At most one matching row, read once at the start of the query (`const`)
+    Const,
+    /// One row read per combination of rows from previous tables (`eq_ref`)
+    EqRef,
+    /// All rows with matching index values are read (`ref`)
+    Ref,
+    /// A `FULLTEXT` index is used (`fulltext`)
+    FullText,
+    /// Like `Ref`, plus an extra search for `NULL` values (`ref_or_null`)
+    RefOrNull,
+    /// The index merge optimization is used (`index_merge`)
+    IndexMerge,
+    /// A unique index lookup replaces an `IN` subquery (`unique_subquery`)
+    UniqueSubquery,
+    /// A non-unique index lookup replaces an `IN` subquery (`index_subquery`)
+    IndexSubquery,
+    /// Only rows in a given range are retrieved, using an index (`range`)
+    Range,
+    /// The whole index tree is scanned (`index`)
+    Index,
+    /// Full table scan (`ALL`)
+    All,
+    /// An access type this crate does not know about
+    Other(String),
+}
+
+impl AccessType {
+    fn parse(value: &str) -> Self {
+        match value {
+            "system" => AccessType::System,
+            "const" => AccessType::Const,
+            "eq_ref" => AccessType::EqRef,
+            "ref" => AccessType::Ref,
+            "fulltext" => AccessType::FullText,
+            "ref_or_null" => AccessType::RefOrNull,
+            "index_merge" => AccessType::IndexMerge,
+            "unique_subquery" => AccessType::UniqueSubquery,
+            "index_subquery" => AccessType::IndexSubquery,
+            "range" => AccessType::Range,
+            "index" => AccessType::Index,
+            "ALL" => AccessType::All,
+            other => AccessType::Other(other.to_owned()),
+        }
+    }
+
+    /// Returns `true` if this access type reads every row of the table or index.
+    pub fn is_full_scan(&self) -> bool {
+        matches!(self, AccessType::All | AccessType::Index)
+    }
+}
+
+/// A single table access in an [`ExplainPlan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableAccess {
+    /// Table name (or alias) as reported by MySQL
+    pub table_name: String,
+    /// How the table is accessed
+    pub access_type: Option<AccessType>,
+    /// Indexes the optimizer considered
+    pub possible_keys: Vec<String>,
+    /// Index actually chosen, if any
+    pub key: Option<String>,
+    /// Estimated rows examined per scan of this table
+    pub rows: Option<u64>,
+    /// Estimated percentage of examined rows that survive the table condition
+    pub filtered: Option<f64>,
+}
+
+/// A typed view of the output of `EXPLAIN FORMAT=JSON`.
+///
+/// Only the per-table access information is extracted; the plan is flattened
+/// into the order the tables appear in the JSON document, which for joins is
+/// the join order chosen by the optimizer.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::explain::{AccessType, ExplainPlan};
+///
+/// let plan = ExplainPlan::from_json(r#"{
+///     "query_block": {
+///         "select_id": 1,
+///         "table": {
+///             "table_name": "users",
+///             "access_type": "ref",
+///             "possible_keys": ["idx_users_email"],
+///             "key": "idx_users_email",
+///             "rows_examined_per_scan": 1,
+///             "filtered": "100.00"
+///         }
+///     }
+/// }"#)?;
+///
+/// assert!(plan.uses_index("idx_users_email"));
+/// assert_eq!(plan.tables[0].access_type, Some(AccessType::Ref));
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExplainPlan {
+    /// Every table access in the plan
+    pub tables: Vec<TableAccess>,
+}
+
+impl ExplainPlan {
+    /// Parses the JSON document produced by `EXPLAIN FORMAT=JSON`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `json` is not valid JSON.
+    pub fn from_json(json: &str) -> crate::Result<Self> {
+        let document: Value = serde_json::from_str(json)?;
+        let mut tables = Vec::new();
+        collect_tables(&document, &mut tables);
+        Ok(Self { tables })
+    }
+
+    /// Returns the access for the given table name, if the plan reads it.
+    pub fn table(&self, name: &str) -> Option<&TableAccess> {
+        self.tables.iter().find(|t| t.table_name == name)
+    }
+
+    /// Returns `true` if any table in the plan is read through the named index.
+    pub fn uses_index(&self, index: &str) -> bool {
+        self.tables.iter().any(|t| t.key.as_deref() == Some(index))
+    }
+
+    /// Returns `true` if any table in the plan is read with a full table or index scan.
+    pub fn has_full_scan(&self) -> bool {
+        self.tables
+            .iter()
+            .any(|t| t.access_type.as_ref().is_some_and(AccessType::is_full_scan))
+    }
+
+    /// Returns the sum of the estimated rows examined per scan over all tables.
+    pub fn total_rows(&self) -> u64 {
+        self.tables.iter().filter_map(|t| t.rows).sum()
+    }
+}
+
+/// Limits on an [`ExplainPlan`] above which a query is refused.
+///
+/// A guard with no limits accepts every plan. Check a query against it with
+/// [`PreparedQuery::check_cost`](crate::PreparedQuery::check_cost) or
+/// [`PreparedQueryAs::check_cost`](crate::PreparedQueryAs::check_cost) before running it.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::explain::{CostGuard, ExplainPlan};
+///
+/// let guard = CostGuard::new().max_rows(10_000).max_full_scans(0);
+///
+/// let plan = ExplainPlan::from_json(r#"{"query_block": {"table": {
+///     "table_name": "orders", "access_type": "ALL", "rows_examined_per_scan": 250000
+/// }}}"#)?;
+/// assert!(guard.check(&plan).is_err());
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostGuard {
+    max_rows: Option<u64>,
+    max_full_scans: Option<usize>,
+    full_scan_min_rows: u64,
+}
+
+impl CostGuard {
+    /// Creates a guard with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuses plans whose [`total_rows`](ExplainPlan::total_rows) exceeds `rows`.
+    pub fn max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Refuses plans that read more than `scans` tables with a full table or index scan,
+    /// i.e. without using an index to narrow the rows read.
+    pub fn max_full_scans(mut self, scans: usize) -> Self {
+        self.max_full_scans = Some(scans);
+        self
+    }
+
+    /// Ignores full scans of tables estimated to have fewer than `rows` rows when
+    /// counting against [`max_full_scans`](Self::max_full_scans), since scanning a small
+    /// lookup table is usually cheaper than using an index.
+    pub fn full_scan_min_rows(mut self, rows: u64) -> Self {
+        self.full_scan_min_rows = rows;
+        self
+    }
+
+    /// Checks `plan` against the limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TooExpensive`](crate::Error::TooExpensive) describing the first
+    /// limit the plan exceeds.
+    pub fn check(&self, plan: &ExplainPlan) -> crate::Result<()> {
+        if let Some(max_rows) = self.max_rows {
+            let rows = plan.total_rows();
+            if rows > max_rows {
+                return Err(crate::Error::TooExpensive(format!(
+                    "an estimated {rows} rows would be examined (limit {max_rows})"
+                )));
+            }
+        }
+
+        if let Some(max_full_scans) = self.max_full_scans {
+            let scanned: Vec<&str> = plan
+                .tables
+                .iter()
+                .filter(|t| t.access_type.as_ref().is_some_and(AccessType::is_full_scan))
+                .filter(|t| t.rows.unwrap_or(u64::MAX) >= self.full_scan_min_rows)
+                .map(|t| t.table_name.as_str())
+                .collect();
+            if scanned.len() > max_full_scans {
+                return Err(crate::Error::TooExpensive(format!(
+                    "full scan of {} (limit {max_full_scans} full scans)",
+                    scanned.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_tables(value: &Value, tables: &mut Vec<TableAccess>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(table)) = map.get("table") {
+                if let Some(access) = table_access(table) {
+                    tables.push(access);
+                }
+            }
+            for child in map.values() {
+                collect_tables(child, tables);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_tables(item, tables);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn table_access(table: &serde_json::Map<String, Value>) -> Option<TableAccess> {
+    let table_name = table.get("table_name")?.as_str()?.to_owned();
+    let access_type = table
+        .get("access_type")
+        .and_then(Value::as_str)
+        .map(AccessType::parse);
+    let possible_keys = table
+        .get("possible_keys")
+        .and_then(Value::as_array)
+        .map(|keys| {
+            keys.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default();
+    let key = table.get("key").and_then(Value::as_str).map(str::to_owned);
+    // MySQL 5.6 reports `rows`; 5.7 and later report `rows_examined_per_scan`.
+    let rows = table
+        .get("rows_examined_per_scan")
+        .or_else(|| table.get("rows"))
+        .and_then(number);
+    let filtered = table.get("filtered").and_then(float);
+
+    Some(TableAccess {
+        table_name,
+        access_type,
+        possible_keys,
+        key,
+        rows,
+        filtered,
+    })
+}
+
+fn number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn float(value: &Value) -> Option<f64> {
+    // Newer servers emit `filtered` as a string such as "100.00".
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_plan_single_table() {
+        let plan = ExplainPlan::from_json(
+            r#"{"query_block": {"select_id": 1, "table": {
+                "table_name": "users",
+                "access_type": "ALL",
+                "rows_examined_per_scan": 1000,
+                "filtered": "10.00"
+            }}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(plan.tables.len(), 1);
+        let users = plan.table("users").unwrap();
+        assert_eq!(users.access_type, Some(AccessType::All));
+        assert_eq!(users.key, None);
+        assert_eq!(users.rows, Some(1000));
+        assert_eq!(users.filtered, Some(10.0));
+        assert!(plan.has_full_scan());
+    }
+
+    #[test]
+    fn test_explain_plan_nested_loop() {
+        let plan = ExplainPlan::from_json(
+            r#"{"query_block": {"select_id": 1, "nested_loop": [
+                {"table": {"table_name": "u", "access_type": "range",
+                           "possible_keys": ["PRIMARY", "idx_users_email"],
+                           "key": "idx_users_email", "rows_examined_per_scan": 5}},
+                {"table": {"table_name": "o", "access_type": "eq_ref",
+                           "key": "PRIMARY", "rows_examined_per_scan": 1}}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let names: Vec<_> = plan.tables.iter().map(|t| t.table_name.as_str()).collect();
+        assert_eq!(names, vec!["u", "o"]);
+        assert_eq!(
+            plan.tables[0].possible_keys,
+            vec!["PRIMARY", "idx_users_email"]
+        );
+        assert!(plan.uses_index("idx_users_email"));
+        assert!(!plan.uses_index("idx_missing"));
+        assert!(!plan.has_full_scan());
+        assert_eq!(plan.total_rows(), 6);
+    }
+
+    #[test]
+    fn test_cost_guard() {
+        let plan = ExplainPlan::from_json(
+            r#"{"query_block": {"nested_loop": [
+                {"table": {"table_name": "countries", "access_type": "ALL",
+                           "rows_examined_per_scan": 200}},
+                {"table": {"table_name": "users", "access_type": "ref",
+                           "key": "idx_users_country", "rows_examined_per_scan": 5000}}
+            ]}}"#,
+        )
+        .unwrap();
+
+        assert!(CostGuard::new().check(&plan).is_ok());
+        assert!(CostGuard::new().max_rows(5200).check(&plan).is_ok());
+        assert!(matches!(
+            CostGuard::new().max_rows(5000).check(&plan),
+            Err(crate::Error::TooExpensive(_))
+        ));
+        assert!(CostGuard::new().max_full_scans(0).check(&plan).is_err());
+        assert!(CostGuard::new()
+            .max_full_scans(0)
+            .full_scan_min_rows(1000)
+            .check(&plan)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_explain_plan_invalid_json() {
+        assert!(ExplainPlan::from_json("not json").is_err());
+    }
+}
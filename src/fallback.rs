@@ -0,0 +1,263 @@
+//! Graceful degradation of reads with a fallback query.
+//!
+//! A [`FallbackQuery`] pairs a primary [`PreparedQueryAs`] with a fallback returning the
+//! same row type, such as a simpler query or one against a cached summary table. The
+//! fallback runs automatically when the primary fails with a configured class of errors
+//! or does not finish within its timeout.
+
+use crate::query_as::{PreparedQueryAs, QA};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Executor, MySql};
+use std::future::Future;
+use std::time::Duration;
+
+/// A primary query paired with a fallback, created by [`PreparedQueryAs::with_fallback`].
+pub struct FallbackQuery<R, F, G>
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    G: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    primary: PreparedQueryAs<R, F>,
+    fallback: PreparedQueryAs<R, G>,
+    falls_back_on: fn(&crate::Error) -> bool,
+    timeout: Option<Duration>,
+    used_fallback: bool,
+}
+
+impl<R, F, G> FallbackQuery<R, F, G>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    G: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    pub(crate) fn new(primary: PreparedQueryAs<R, F>, fallback: PreparedQueryAs<R, G>) -> Self {
+        Self {
+            primary,
+            fallback,
+            falls_back_on: |_| true,
+            timeout: None,
+            used_fallback: false,
+        }
+    }
+
+    /// Restricts fallback to errors for which `classifier` returns `true`.
+    ///
+    /// By default every error of the primary triggers the fallback. Timeouts set with
+    /// [`timeout`](Self::timeout) always trigger it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx::FromRow;
+    /// use sqlx_named_bind::{retry::is_transient, PreparedQueryAs};
+    ///
+    /// #[derive(FromRow)]
+    /// struct Count {
+    ///     total: i64,
+    /// }
+    ///
+    /// let query = PreparedQueryAs::<Count, _>::new(
+    ///     "SELECT COUNT(*) AS total FROM orders",
+    ///     |q, _| q,
+    /// )?
+    /// .with_fallback(PreparedQueryAs::new(
+    ///     "SELECT total FROM order_counts_cache",
+    ///     |q, _| q,
+    /// )?)
+    /// .fall_back_on(is_transient);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn fall_back_on(mut self, classifier: fn(&crate::Error) -> bool) -> Self {
+        self.falls_back_on = classifier;
+        self
+    }
+
+    /// Falls back if the primary does not finish within `timeout`.
+    ///
+    /// The primary is cancelled when the timeout elapses. The fallback itself runs
+    /// without a timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns `true` if the last fetch was served by the fallback.
+    pub fn used_fallback(&self) -> bool {
+        self.used_fallback
+    }
+
+    /// Fetches all rows from the primary, or from the fallback if the primary fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`, used for both queries
+    ///
+    /// # Errors
+    ///
+    /// Returns the primary's error if it does not trigger the fallback, otherwise the
+    /// fallback's error if it fails too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(FromRow)]
+    /// struct Product {
+    ///     id: i64,
+    ///     score: f64,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut recommendations = PreparedQueryAs::<Product, _>::new(
+    ///     "SELECT p.id, SUM(v.weight) AS score FROM products p \
+    ///      JOIN views v ON v.product_id = p.id WHERE v.user_id = :user_id \
+    ///      GROUP BY p.id ORDER BY score DESC LIMIT 10",
+    ///     |q, key| match key {
+    ///         ":user_id" => q.bind(42),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .with_fallback(PreparedQueryAs::new(
+    ///     "SELECT id, score FROM popular_products ORDER BY score DESC LIMIT 10",
+    ///     |q, _| q,
+    /// )?)
+    /// .timeout(Duration::from_millis(200));
+    ///
+    /// let products = recommendations.fetch_all(&pool).await?;
+    /// if recommendations.used_fallback() {
+    ///     println!("Served {} popular products instead", products.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_all<E>(&mut self, executor: E) -> crate::Result<Vec<R>>
+    where
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+    {
+        let primary = with_timeout(self.timeout, self.primary.fetch_all(executor)).await;
+        if self.falls_back(&primary) {
+            self.fallback.fetch_all(executor).await
+        } else {
+            primary
+        }
+    }
+
+    /// Fetches exactly one row from the primary, or from the fallback if the primary fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`, used for both queries
+    ///
+    /// # Errors
+    ///
+    /// Returns the primary's error if it does not trigger the fallback, otherwise the
+    /// fallback's error if it fails too. Note that `RowNotFound` from the primary
+    /// triggers the fallback unless excluded with [`fall_back_on`](Self::fall_back_on).
+    pub async fn fetch_one<E>(&mut self, executor: E) -> crate::Result<R>
+    where
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+    {
+        let primary = with_timeout(self.timeout, self.primary.fetch_one(executor)).await;
+        if self.falls_back(&primary) {
+            self.fallback.fetch_one(executor).await
+        } else {
+            primary
+        }
+    }
+
+    /// Fetches at most one row from the primary, or from the fallback if the primary fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`, used for both queries
+    ///
+    /// # Errors
+    ///
+    /// Returns the primary's error if it does not trigger the fallback, otherwise the
+    /// fallback's error if it fails too.
+    pub async fn fetch_optional<E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    where
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+    {
+        let primary = with_timeout(self.timeout, self.primary.fetch_optional(executor)).await;
+        if self.falls_back(&primary) {
+            self.fallback.fetch_optional(executor).await
+        } else {
+            primary
+        }
+    }
+
+    /// Records and returns whether the primary's result should be replaced by the fallback.
+    fn falls_back<T>(&mut self, primary: &crate::Result<T>) -> bool {
+        self.used_fallback = match primary {
+            Ok(_) => false,
+            Err(e) => self.should_fall_back(e),
+        };
+        self.used_fallback
+    }
+
+    fn should_fall_back(&self, error: &crate::Error) -> bool {
+        matches!(error, crate::Error::DeadlineExceeded) || (self.falls_back_on)(error)
+    }
+}
+
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = crate::Result<T>>,
+) -> crate::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or(Err(crate::Error::DeadlineExceeded)),
+        None => future.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::is_transient;
+
+    #[derive(sqlx::FromRow)]
+    struct TestRow {
+        #[allow(dead_code)]
+        id: i64,
+    }
+
+    type Binder = for<'q> fn(QA<'q, TestRow>, &str) -> QA<'q, TestRow>;
+
+    fn query() -> FallbackQuery<TestRow, Binder, Binder> {
+        PreparedQueryAs::<TestRow, Binder>::new("SELECT id FROM live", |q, _| q)
+            .unwrap()
+            .with_fallback(
+                PreparedQueryAs::<TestRow, Binder>::new("SELECT id FROM cached", |q, _| q).unwrap(),
+            )
+    }
+
+    #[test]
+    fn test_falls_back_on_any_error_by_default() {
+        let query = query();
+        assert!(query.should_fall_back(&crate::Error::Database(sqlx::Error::RowNotFound)));
+        assert!(query.should_fall_back(&crate::Error::DeadlineExceeded));
+    }
+
+    #[test]
+    fn test_fall_back_on_classifier() {
+        let mut query = query().fall_back_on(is_transient);
+        assert!(query.should_fall_back(&crate::Error::Database(sqlx::Error::PoolTimedOut)));
+        assert!(query.should_fall_back(&crate::Error::DeadlineExceeded));
+        assert!(!query.should_fall_back(&crate::Error::Database(sqlx::Error::RowNotFound)));
+
+        let not_found = Err(crate::Error::Database(sqlx::Error::RowNotFound));
+        assert!(!query.falls_back::<()>(&not_found));
+        assert!(!query.used_fallback());
+        assert!(query.falls_back::<()>(&Err(crate::Error::DeadlineExceeded)));
+        assert!(query.used_fallback());
+        assert!(!query.falls_back(&Ok(())));
+        assert!(!query.used_fallback());
+    }
+}
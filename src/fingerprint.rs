@@ -0,0 +1,157 @@
+//! Query fingerprints: SQL normalized so every execution of the same statement shape
+//! maps to the same string.
+//!
+//! A fingerprint drops comments and redundant whitespace, lowercases keywords and
+//! identifiers, and replaces literals, named placeholders and positional placeholders
+//! with `?`. Lists of placeholders such as an expanded `IN (?, ?, ?)` collapse to
+//! `(?+)`, so the list length does not change the fingerprint. A template and its
+//! converted SQL therefore have the same fingerprint.
+
+use crate::lexer::{skip_line, skip_quoted};
+
+/// Returns the fingerprint of `sql`.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::fingerprint::fingerprint;
+///
+/// assert_eq!(
+///     fingerprint("SELECT * FROM users\n WHERE id IN (:a, :b) AND name = 'x' -- lookup"),
+///     "select * from users where id in (?+) and name = ?"
+/// );
+/// assert_eq!(
+///     fingerprint("select *  from users where id in (?, ?, ?) and name = ?"),
+///     fingerprint("SELECT * FROM users WHERE id IN (1) AND name = 'y'")
+/// );
+/// ```
+pub fn fingerprint(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\'' | b'"' => {
+                i = skip_quoted(bytes, i, b);
+                tokens.push("?");
+            }
+            b'`' => {
+                let end = skip_quoted(bytes, i, b'`');
+                tokens.push(&sql[i..end]);
+                i = end;
+            }
+            b'#' => i = skip_line(bytes, i),
+            b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = match sql[i + 2..].find("*/") {
+                    Some(pos) => i + 2 + pos + 2,
+                    None => bytes.len(),
+                };
+            }
+            b':' if bytes.get(i + 1).is_some_and(|&c| is_word_byte(c)) => {
+                i = word_end(bytes, i + 1);
+                tokens.push("?");
+            }
+            b'0'..=b'9' => {
+                while i < bytes.len() && (is_word_byte(bytes[i]) || bytes[i] == b'.') {
+                    i += 1;
+                }
+                tokens.push("?");
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ if is_word_byte(b) => {
+                let end = word_end(bytes, i);
+                tokens.push(&sql[i..end]);
+                i = end;
+            }
+            _ => {
+                let len = sql[i..].chars().next().map_or(1, char::len_utf8);
+                tokens.push(&sql[i..i + len]);
+                i += len;
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(sql.len());
+    let mut prev: Option<&str> = None;
+    let mut t = 0;
+    while t < tokens.len() {
+        let mut token = tokens[t];
+        t += 1;
+        if token == "(" {
+            if let Some(end) = placeholder_list_end(&tokens, t) {
+                token = "(?+)";
+                t = end;
+            }
+        }
+
+        let glued = matches!(token, "," | ")" | ".") || matches!(prev, Some("(" | "."));
+        if prev.is_some() && !glued {
+            out.push(' ');
+        }
+        if token.starts_with('`') {
+            out.push_str(token);
+        } else {
+            out.extend(token.chars().map(|c| c.to_ascii_lowercase()));
+        }
+        prev = Some(token);
+    }
+    out
+}
+
+/// If `tokens[start..]` is `?, ?, ... )`, returns the index just past the `)`.
+fn placeholder_list_end(tokens: &[&str], start: usize) -> Option<usize> {
+    let mut t = start;
+    loop {
+        if tokens.get(t) != Some(&"?") {
+            return None;
+        }
+        match tokens.get(t + 1) {
+            Some(&",") => t += 2,
+            Some(&")") => return Some(t + 2),
+            _ => return None,
+        }
+    }
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn word_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i < bytes.len() && is_word_byte(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_normalizes_literals_and_whitespace() {
+        assert_eq!(
+            fingerprint("UPDATE  accounts /* hot */ SET balance = balance - 10.50\nWHERE id = :id"),
+            "update accounts set balance = balance - ? where id = ?"
+        );
+        assert_eq!(
+            fingerprint("SELECT COUNT(*) FROM `Orders` o WHERE o.note = 'it''s'"),
+            "select count (*) from `Orders` o where o.note = ?"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_collapses_placeholder_lists() {
+        assert_eq!(
+            fingerprint("INSERT INTO t (a, b) VALUES (?, ?)"),
+            "insert into t (a, b) values (?+)"
+        );
+        assert_eq!(
+            fingerprint("SELECT * FROM t WHERE id IN (1, 2, 3) AND f(x, 1)"),
+            "select * from t where id in (?+) and f (x, ?)"
+        );
+    }
+}
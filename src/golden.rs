@@ -0,0 +1,175 @@
+//! Golden-file checks for a [`QueryRegistry`].
+//!
+//! [`render`] produces a deterministic report of every entry's converted SQL and
+//! placeholder order. Committing the report and checking it with [`verify`] in a test
+//! turns any change to a template, including an accidental one, into a reviewable diff.
+//!
+//! Set the `UPDATE_GOLDEN` environment variable to rewrite the golden file instead of
+//! comparing against it, after reviewing an intended change.
+
+use crate::registry::QueryRegistry;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Environment variable that makes [`verify`] rewrite the golden file.
+pub const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+/// Renders the report for `registry`, with entries in name order.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::{golden::render, registry::QueryRegistry};
+///
+/// let mut registry = QueryRegistry::new();
+/// registry.add("find_user", "SELECT id FROM users WHERE email = :email")?;
+/// assert_eq!(
+///     render(&registry),
+///     "## find_user\nplaceholders: :email\nsql:\n    SELECT id FROM users WHERE email = ?\n"
+/// );
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn render(registry: &QueryRegistry) -> String {
+    let mut report = String::new();
+    for (i, entry) in registry.entries().enumerate() {
+        if i > 0 {
+            report.push('\n');
+        }
+        let _ = writeln!(report, "## {}", entry.name());
+        let placeholders = match entry.placeholders() {
+            [] => "(none)".to_owned(),
+            keys => keys.join(", "),
+        };
+        let _ = writeln!(report, "placeholders: {placeholders}");
+        report.push_str("sql:\n");
+        for line in entry.sql().trim().lines() {
+            let _ = writeln!(report, "    {}", line.trim_end());
+        }
+    }
+    report
+}
+
+/// Compares the report for `registry` against the golden file at `path`.
+///
+/// If [`UPDATE_ENV`] is set, or the file does not exist yet, the file is written
+/// instead and the check passes.
+///
+/// # Errors
+///
+/// Returns [`Error::GoldenMismatch`](crate::Error::GoldenMismatch) with a line diff if
+/// the report differs from the file, or an error if the file cannot be read or written.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx_named_bind::{golden, registry::QueryRegistry};
+///
+/// // In a test, so CI fails when a template changes without updating the golden file:
+/// let registry = QueryRegistry::load_dir("sql/queries")?;
+/// golden::verify(&registry, "tests/golden/queries.txt")?;
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn verify<P: AsRef<Path>>(registry: &QueryRegistry, path: P) -> crate::Result<()> {
+    let path = path.as_ref();
+    let actual = render(registry);
+
+    if std::env::var_os(UPDATE_ENV).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, actual)?;
+        return Ok(());
+    }
+
+    let expected = std::fs::read_to_string(path)?;
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(crate::Error::GoldenMismatch {
+            path: path.display().to_string(),
+            diff: diff_lines(&expected, &actual),
+        })
+    }
+}
+
+/// Returns a line diff of `expected` and `actual`, with `-` for removed and `+` for
+/// added lines. Unchanged lines are omitted.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end.
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            let _ = writeln!(diff, "-{}", a[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(diff, "+{}", b[j]);
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(template: &str) -> QueryRegistry {
+        let mut registry = QueryRegistry::new();
+        registry.add("list_users", "SELECT id FROM users").unwrap();
+        registry.add("find_user", template).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_render_is_sorted_by_name() {
+        let report = render(&registry("SELECT id\nFROM users  \nWHERE id = :id\n"));
+        assert_eq!(
+            report,
+            "## find_user\n\
+             placeholders: :id\n\
+             sql:\n    SELECT id\n    FROM users\n    WHERE id = ?\n\
+             \n\
+             ## list_users\n\
+             placeholders: (none)\n\
+             sql:\n    SELECT id FROM users\n"
+        );
+    }
+
+    #[test]
+    fn test_verify_reports_diff() {
+        let path =
+            std::env::temp_dir().join(format!("sqlx-named-bind-golden-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        verify(&registry("SELECT id FROM users WHERE id = :id"), &path).unwrap();
+        verify(&registry("SELECT id FROM users WHERE id = :id"), &path).unwrap();
+        let result = verify(&registry("SELECT * FROM users WHERE id = :id"), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(crate::Error::GoldenMismatch { diff, .. }) => assert_eq!(
+                diff,
+                "-    SELECT id FROM users WHERE id = ?\n+    SELECT * FROM users WHERE id = ?\n"
+            ),
+            other => panic!("expected a mismatch, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Database health checks for readiness probes.
+//!
+//! [`ping`] runs `SELECT 1` and [`check_query`] checks a registered query, each with a
+//! strict timeout, and both map the outcome into a [`HealthStatus`], so every service
+//! using this crate reports database readiness the same way. Once
+//! [`shutdown`](crate::shutdown) has begun, checks report [`HealthStatus::Unhealthy`]
+//! without querying, so load balancers stop routing to the process.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use sqlx::{Executor, MySqlPool};
+//! use sqlx_named_bind::health;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+//! let status = health::ping(&pool, Duration::from_millis(500)).await;
+//! let code = if status.is_healthy() { 200 } else { 503 };
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::conditional::NamedBinds;
+use crate::registry::RegistryEntry;
+use crate::shutdown;
+use sqlx::{Executor, MySqlPool};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// The outcome of a health check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// The check succeeded within its timeout
+    Healthy {
+        /// How long the check took, including acquiring a connection
+        latency: Duration,
+    },
+    /// The check did not finish within its timeout
+    TimedOut {
+        /// The timeout the check was given
+        timeout: Duration,
+    },
+    /// The check failed, or the process is shutting down
+    Unhealthy {
+        /// Why the check failed
+        reason: String,
+    },
+}
+
+impl HealthStatus {
+    /// Returns `true` if the check succeeded.
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, HealthStatus::Healthy { .. })
+    }
+}
+
+/// Runs `SELECT 1` on `pool`, failing if it takes longer than `timeout`.
+///
+/// The statement is sent with sqlx directly, so an installed
+/// [`allowlist`](crate::allowlist) does not need to list it.
+pub async fn ping(pool: &MySqlPool, timeout: Duration) -> HealthStatus {
+    probe(timeout, async {
+        sqlx::query("SELECT 1").execute(pool).await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Checks the registered query `entry` on `pool`, failing if it takes longer than
+/// `timeout` or the entry's own `timeout`, whichever is shorter.
+///
+/// A query without placeholders runs like any other execution of the entry, so it is
+/// checked by installed policies and counted in budgets and query logs. A query with
+/// placeholders has no values to run with, so it is only prepared on the server, which
+/// checks that its syntax is valid and the tables and columns it references exist.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use sqlx::{Executor, MySqlPool};
+/// use sqlx_named_bind::{health, registry::QueryRegistry};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let mut registry = QueryRegistry::new();
+/// let entry = registry.add("readiness", "SELECT 1 FROM schema_migrations LIMIT 1")?;
+/// let status = health::check_query(entry, &pool, Duration::from_secs(1)).await;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn check_query(
+    entry: &RegistryEntry,
+    pool: &MySqlPool,
+    timeout: Duration,
+) -> HealthStatus {
+    let timeout = entry.meta().timeout.map_or(timeout, |own| own.min(timeout));
+    probe(timeout, async {
+        if entry.placeholders().is_empty() {
+            entry
+                .prepare(NamedBinds::new().binder())?
+                .timeout(timeout)
+                .fetch_rows(pool)
+                .await?;
+        } else {
+            pool.prepare(entry.sql()).await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+async fn probe<Fut>(timeout: Duration, check: Fut) -> HealthStatus
+where
+    Fut: Future<Output = crate::Result<()>>,
+{
+    if shutdown::is_shutting_down() {
+        return HealthStatus::Unhealthy {
+            reason: crate::Error::ShuttingDown.to_string(),
+        };
+    }
+    let started = Instant::now();
+    match tokio::time::timeout(timeout, check).await {
+        Ok(Ok(())) => HealthStatus::Healthy {
+            latency: started.elapsed(),
+        },
+        Ok(Err(e)) => HealthStatus::Unhealthy {
+            reason: e.to_string(),
+        },
+        Err(_) => HealthStatus::TimedOut { timeout },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_status() {
+        let timeout = Duration::from_millis(20);
+        assert!(probe(timeout, async { Ok(()) }).await.is_healthy());
+        assert_eq!(
+            probe(timeout, std::future::pending()).await,
+            HealthStatus::TimedOut { timeout }
+        );
+        assert!(matches!(
+            probe(timeout, async { Err(crate::Error::UnboundPlaceholder(":id".to_owned())) })
+                .await,
+            HealthStatus::Unhealthy { reason } if reason.contains(":id")
+        ));
+    }
+}
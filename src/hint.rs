@@ -0,0 +1,545 @@
+//! MySQL index hints (`USE INDEX`, `FORCE INDEX`, `IGNORE INDEX`), statement
+//! priority modifiers (`LOW_PRIORITY`, `HIGH_PRIORITY`, `DELAYED`), the
+//! `MAX_EXECUTION_TIME` optimizer hint and `COLLATE` clauses on bound values.
+//!
+//! Hints are inserted into an already-converted SQL statement, so performance fixes
+//! can be applied where a query is constructed instead of editing the raw SQL template
+//! in every place it is used.
+
+use crate::lexer::{tokenize, Token, TokenKind};
+use std::fmt;
+use std::time::Duration;
+
+/// The kind of index hint to attach to a table reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexHintKind {
+    /// `USE INDEX (...)`: only consider the listed indexes
+    Use,
+    /// `FORCE INDEX (...)`: like `Use`, but a table scan is assumed to be very expensive
+    Force,
+    /// `IGNORE INDEX (...)`: never consider the listed indexes
+    Ignore,
+}
+
+/// An index hint with validated index names.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::hint::IndexHint;
+///
+/// let hint = IndexHint::force(["idx_users_email"])?;
+/// assert_eq!(hint.to_string(), "FORCE INDEX (idx_users_email)");
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexHint {
+    kind: IndexHintKind,
+    indexes: Vec<String>,
+}
+
+impl IndexHint {
+    /// Creates a hint of the given kind for the listed indexes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if an index
+    /// name is not a plain identifier, or if `FORCE`/`IGNORE` is given no indexes.
+    pub fn new<I, S>(kind: IndexHintKind, indexes: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let indexes: Vec<String> = indexes.into_iter().map(Into::into).collect();
+        for index in &indexes {
+            validate_identifier(index)?;
+        }
+        // An empty `USE INDEX ()` is valid MySQL and means "use no indexes".
+        if indexes.is_empty() && kind != IndexHintKind::Use {
+            return Err(crate::Error::InvalidIdentifier(String::new()));
+        }
+        Ok(Self { kind, indexes })
+    }
+
+    /// Creates a `USE INDEX` hint.
+    pub fn use_index<I, S>(indexes: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(IndexHintKind::Use, indexes)
+    }
+
+    /// Creates a `FORCE INDEX` hint.
+    pub fn force<I, S>(indexes: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(IndexHintKind::Force, indexes)
+    }
+
+    /// Creates an `IGNORE INDEX` hint.
+    pub fn ignore<I, S>(indexes: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(IndexHintKind::Ignore, indexes)
+    }
+}
+
+impl fmt::Display for IndexHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match self.kind {
+            IndexHintKind::Use => "USE",
+            IndexHintKind::Force => "FORCE",
+            IndexHintKind::Ignore => "IGNORE",
+        };
+        write!(f, "{} INDEX ({})", keyword, self.indexes.join(", "))
+    }
+}
+
+/// Checks that `name` is a plain, unquoted SQL identifier (`[A-Za-z_][A-Za-z0-9_$]*`).
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) otherwise.
+pub fn validate_identifier(name: &str) -> crate::Result<()> {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(crate::Error::InvalidIdentifier(name.to_owned()))
+    }
+}
+
+/// Inserts `hint` after every reference to `table` that follows `FROM`, `JOIN` or `UPDATE`.
+///
+/// The hint is placed after the table's alias when one is present, which is where
+/// MySQL expects it. String literals and comments are skipped.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if `table` is
+/// not a plain identifier, or [`Error::TableNotFound`](crate::Error::TableNotFound) if
+/// the statement never references it.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::hint::{add_index_hint, IndexHint};
+///
+/// let sql = add_index_hint(
+///     "SELECT * FROM users u WHERE u.email = ?",
+///     "users",
+///     &IndexHint::use_index(["idx_users_email"])?,
+/// )?;
+/// assert_eq!(sql, "SELECT * FROM users u USE INDEX (idx_users_email) WHERE u.email = ?");
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn add_index_hint(sql: &str, table: &str, hint: &IndexHint) -> crate::Result<String> {
+    validate_identifier(table)?;
+
+    let tokens = tokenize(sql);
+    let mut positions = Vec::new();
+    for i in 0..tokens.len() {
+        let is_table_keyword = ["FROM", "JOIN", "UPDATE"]
+            .into_iter()
+            .any(|keyword| tokens[i].is_keyword(sql, keyword));
+        if is_table_keyword {
+            if let Some(end) = table_reference_end(sql, &tokens, i + 1, table) {
+                positions.push(end);
+            }
+        }
+    }
+
+    if positions.is_empty() {
+        return Err(crate::Error::TableNotFound(table.to_owned()));
+    }
+
+    let mut result = String::with_capacity(sql.len() + positions.len() * 32);
+    let mut last = 0;
+    for pos in positions {
+        result.push_str(&sql[last..pos]);
+        result.push(' ');
+        result.push_str(&hint.to_string());
+        last = pos;
+    }
+    result.push_str(&sql[last..]);
+    Ok(result)
+}
+
+/// A MySQL statement priority modifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// `LOW_PRIORITY`: wait until no other clients read the table
+    /// (`INSERT`, `REPLACE`, `UPDATE`, `DELETE`)
+    Low,
+    /// `HIGH_PRIORITY`: run ahead of pending writes (`INSERT`, `SELECT`)
+    High,
+    /// `DELAYED` (`INSERT`, `REPLACE`). MySQL 5.7 and later accept it but ignore it.
+    Delayed,
+}
+
+impl Priority {
+    fn keyword(self) -> &'static str {
+        match self {
+            Priority::Low => "LOW_PRIORITY",
+            Priority::High => "HIGH_PRIORITY",
+            Priority::Delayed => "DELAYED",
+        }
+    }
+
+    fn statements(self) -> &'static [&'static str] {
+        match self {
+            Priority::Low => &["INSERT", "REPLACE", "UPDATE", "DELETE"],
+            Priority::High => &["INSERT", "SELECT"],
+            Priority::Delayed => &["INSERT", "REPLACE"],
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.keyword())
+    }
+}
+
+/// Inserts the `priority` modifier right after the statement's leading keyword.
+///
+/// Comments before the keyword are skipped.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidModifier`](crate::Error::InvalidModifier) if the statement
+/// does not start with a keyword that accepts the modifier, or already has a priority
+/// modifier.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::hint::{add_priority, Priority};
+///
+/// let sql = add_priority("DELETE FROM sessions WHERE expires_at < ?", Priority::Low)?;
+/// assert_eq!(sql, "DELETE LOW_PRIORITY FROM sessions WHERE expires_at < ?");
+///
+/// assert!(add_priority("UPDATE users SET name = ?", Priority::High).is_err());
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn add_priority(sql: &str, priority: Priority) -> crate::Result<String> {
+    let tokens = tokenize(sql);
+    let statement = tokens
+        .first()
+        .filter(|t| {
+            priority
+                .statements()
+                .iter()
+                .any(|keyword| t.is_keyword(sql, keyword))
+        })
+        .ok_or_else(|| {
+            crate::Error::InvalidModifier(format!(
+                "{} can only be applied to {} statements",
+                priority,
+                priority.statements().join("/")
+            ))
+        })?;
+
+    let has_priority = tokens.get(1).is_some_and(|t| {
+        [Priority::Low, Priority::High, Priority::Delayed]
+            .into_iter()
+            .any(|p| t.is_keyword(sql, p.keyword()))
+    });
+    if has_priority {
+        return Err(crate::Error::InvalidModifier(
+            "statement already has a priority modifier".to_owned(),
+        ));
+    }
+
+    Ok(format!(
+        "{} {}{}",
+        &sql[..statement.end],
+        priority,
+        &sql[statement.end..]
+    ))
+}
+
+/// Adds a `MAX_EXECUTION_TIME` optimizer hint right after the statement's leading
+/// `SELECT`, so the server aborts the statement once it runs longer than `timeout`.
+///
+/// Returns `None` if the statement is not a `SELECT`, the only statement MySQL applies
+/// the hint to, or already has an optimizer hint comment after its `SELECT`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use sqlx_named_bind::hint::add_max_execution_time;
+///
+/// let sql = add_max_execution_time("SELECT * FROM users", Duration::from_secs(2));
+/// assert_eq!(sql.as_deref(), Some("SELECT /*+ MAX_EXECUTION_TIME(2000) */ * FROM users"));
+///
+/// assert_eq!(add_max_execution_time("DELETE FROM users", Duration::from_secs(2)), None);
+/// ```
+pub fn add_max_execution_time(sql: &str, timeout: Duration) -> Option<String> {
+    let tokens = tokenize(sql);
+    let statement = tokens.first().filter(|t| t.is_keyword(sql, "SELECT"))?;
+    if sql[statement.end..].trim_start().starts_with("/*+") {
+        return None;
+    }
+    // Zero disables the limit, so round sub-millisecond timeouts up.
+    let millis = timeout.as_millis().max(1);
+    Some(format!(
+        "{} /*+ MAX_EXECUTION_TIME({millis}) */{}",
+        &sql[..statement.end],
+        &sql[statement.end..]
+    ))
+}
+
+/// Words that may directly follow a table reference and therefore are never aliases.
+pub(crate) const NON_ALIAS_KEYWORDS: &[&str] = &[
+    "WHERE",
+    "ON",
+    "USING",
+    "JOIN",
+    "INNER",
+    "LEFT",
+    "RIGHT",
+    "CROSS",
+    "NATURAL",
+    "STRAIGHT_JOIN",
+    "GROUP",
+    "ORDER",
+    "LIMIT",
+    "HAVING",
+    "SET",
+    "UNION",
+    "FOR",
+    "LOCK",
+    "WINDOW",
+    "USE",
+    "FORCE",
+    "IGNORE",
+    "PARTITION",
+    "INTO",
+    "VALUES",
+    "SELECT",
+    "EXCEPT",
+    "INTERSECT",
+];
+
+/// Appends `COLLATE collation` to every positional placeholder bound to `placeholder`.
+///
+/// `order` is the placeholder order of the converted `sql`, so the n-th `?` outside
+/// string literals and comments is bound to `order[n]`.
+pub(crate) fn add_collation(
+    sql: &str,
+    order: &[String],
+    placeholder: &str,
+    collation: &str,
+) -> crate::Result<String> {
+    validate_identifier(collation)?;
+    if !order.iter().any(|key| key == placeholder) {
+        return Err(crate::Error::UnknownPlaceholder(placeholder.to_owned()));
+    }
+
+    let positions = tokenize(sql)
+        .into_iter()
+        .filter(|t| t.is_punct(sql, '?'))
+        .zip(order)
+        .filter(|(_, key)| *key == placeholder)
+        .map(|(t, _)| t.end);
+
+    let mut result = String::with_capacity(sql.len() + 32);
+    let mut last = 0;
+    for pos in positions {
+        result.push_str(&sql[last..pos]);
+        result.push_str(" COLLATE ");
+        result.push_str(collation);
+        last = pos;
+    }
+    result.push_str(&sql[last..]);
+    Ok(result)
+}
+
+/// If the tokens starting at `start` reference `table` (optionally schema-qualified and
+/// aliased), returns the byte offset right after the reference.
+fn table_reference_end(sql: &str, tokens: &[Token], start: usize, table: &str) -> Option<usize> {
+    let mut i = start;
+    // Skip an optional `schema.` qualifier.
+    if tokens.get(i + 1).is_some_and(|t| t.is_punct(sql, '.')) {
+        i += 2;
+    }
+    let name = tokens.get(i)?;
+    if name.kind != TokenKind::Word || name.ident(sql) != table {
+        return None;
+    }
+    let mut end = name.end;
+
+    match tokens.get(i + 1) {
+        Some(t) if t.is_keyword(sql, "AS") => {
+            let alias = tokens.get(i + 2)?;
+            if alias.kind == TokenKind::Word {
+                end = alias.end;
+            }
+        }
+        Some(t) if t.kind == TokenKind::Word => {
+            let word = &sql[t.start..t.end];
+            let is_keyword = !t.quoted
+                && NON_ALIAS_KEYWORDS
+                    .iter()
+                    .any(|k| k.eq_ignore_ascii_case(word));
+            if !is_keyword {
+                end = t.end;
+            }
+        }
+        _ => {}
+    }
+    Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn force(index: &str) -> IndexHint {
+        IndexHint::force([index]).unwrap()
+    }
+
+    #[test]
+    fn test_add_index_hint_without_alias() {
+        let sql = add_index_hint(
+            "SELECT * FROM users WHERE id = ?",
+            "users",
+            &force("PRIMARY"),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM users FORCE INDEX (PRIMARY) WHERE id = ?"
+        );
+    }
+
+    #[test]
+    fn test_add_index_hint_with_alias() {
+        let sql = add_index_hint(
+            "SELECT * FROM orders o JOIN users AS u ON u.id = o.user_id",
+            "users",
+            &force("idx_users_id"),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders o JOIN users AS u FORCE INDEX (idx_users_id) ON u.id = o.user_id"
+        );
+    }
+
+    #[test]
+    fn test_add_index_hint_quoted_and_qualified() {
+        let sql = add_index_hint(
+            "UPDATE app.`users` SET name = ? WHERE email = ?",
+            "users",
+            &IndexHint::use_index(["idx_users_email"]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "UPDATE app.`users` USE INDEX (idx_users_email) SET name = ? WHERE email = ?"
+        );
+    }
+
+    #[test]
+    fn test_add_index_hint_skips_literals_and_comments() {
+        let sql = add_index_hint(
+            "SELECT 'from users' FROM /* from users */ users",
+            "users",
+            &force("idx"),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT 'from users' FROM /* from users */ users FORCE INDEX (idx)"
+        );
+    }
+
+    #[test]
+    fn test_add_index_hint_table_not_found() {
+        let result = add_index_hint("SELECT * FROM users", "orders", &force("idx"));
+        assert!(matches!(result, Err(crate::Error::TableNotFound(t)) if t == "orders"));
+    }
+
+    #[test]
+    fn test_index_hint_rejects_invalid_identifiers() {
+        assert!(IndexHint::force(["idx; DROP TABLE users"]).is_err());
+        assert!(IndexHint::force(Vec::<String>::new()).is_err());
+        assert!(IndexHint::use_index(Vec::<String>::new()).is_ok());
+        assert!(add_index_hint("SELECT * FROM users", "users`", &force("idx")).is_err());
+    }
+
+    #[test]
+    fn test_add_priority() {
+        assert_eq!(
+            add_priority("INSERT INTO logs (msg) VALUES (?)", Priority::Delayed).unwrap(),
+            "INSERT DELAYED INTO logs (msg) VALUES (?)"
+        );
+        assert_eq!(
+            add_priority("/* report */ select * from t", Priority::High).unwrap(),
+            "/* report */ select HIGH_PRIORITY * from t"
+        );
+        assert_eq!(
+            add_priority("UPDATE users SET name = ?", Priority::Low).unwrap(),
+            "UPDATE LOW_PRIORITY users SET name = ?"
+        );
+    }
+
+    #[test]
+    fn test_add_max_execution_time() {
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            add_max_execution_time("/* report */ select id FROM t", second).as_deref(),
+            Some("/* report */ select /*+ MAX_EXECUTION_TIME(1000) */ id FROM t")
+        );
+        assert_eq!(
+            add_max_execution_time("SELECT id FROM t", Duration::from_micros(10)).as_deref(),
+            Some("SELECT /*+ MAX_EXECUTION_TIME(1) */ id FROM t")
+        );
+        assert_eq!(add_max_execution_time("SELECT /*+ BKA(t) */ id FROM t", second), None);
+        assert_eq!(add_max_execution_time("(SELECT 1) UNION (SELECT 2)", second), None);
+        assert_eq!(add_max_execution_time("UPDATE t SET a = 1", second), None);
+    }
+
+    #[test]
+    fn test_add_collation() {
+        let order = [":name".to_owned(), ":id".to_owned(), ":name".to_owned()];
+        assert_eq!(
+            add_collation(
+                "SELECT * FROM t WHERE a = ? AND id = ? OR b = ?",
+                &order,
+                ":name",
+                "utf8mb4_bin"
+            )
+            .unwrap(),
+            "SELECT * FROM t WHERE a = ? COLLATE utf8mb4_bin AND id = ? \
+             OR b = ? COLLATE utf8mb4_bin"
+        );
+        assert!(matches!(
+            add_collation("SELECT ?", &order[..1], ":other", "utf8mb4_bin"),
+            Err(crate::Error::UnknownPlaceholder(_))
+        ));
+        assert!(add_collation("SELECT ?", &order[..1], ":name", "x; DROP").is_err());
+    }
+
+    #[test]
+    fn test_add_priority_rejects_invalid_statements() {
+        assert!(add_priority("SELECT * FROM t", Priority::Low).is_err());
+        assert!(add_priority("DELETE FROM t", Priority::Delayed).is_err());
+        assert!(add_priority("WITH x AS (SELECT 1) SELECT * FROM x", Priority::High).is_err());
+        assert!(add_priority("INSERT LOW_PRIORITY INTO t VALUES (?)", Priority::High).is_err());
+    }
+}
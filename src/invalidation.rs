@@ -0,0 +1,198 @@
+//! Cache invalidation hooks triggered by writes to a table.
+//!
+//! Callbacks registered with [`on_write`] run after every successful
+//! [`PreparedQuery::execute`](crate::PreparedQuery::execute) (or `execute_with`) whose
+//! statement modifies their table, as found by
+//! [`written_tables`](crate::tables::written_tables), and after every statement of the
+//! [`batch`](crate::batch) helpers and [`import_ndjson`](crate::json::import_ndjson). A
+//! [`PreparedCall`](crate::call::PreparedCall) runs the hooks of the tables declared with
+//! [`writes`](crate::call::PreparedCall::writes), since the tables a procedure writes
+//! cannot be read from its `CALL`. [`on_registry_write`] turns a
+//! write into the names of the registered queries reading the table, so an application
+//! cache keyed by query name stays coherent without manual bookkeeping.
+//!
+//! Hooks run as soon as the statement succeeds, even inside a transaction that is later
+//! rolled back, so a stale read cached between the write and the commit is possible.
+//! Invalidate again after committing if that matters.
+
+use crate::registry::QueryRegistry;
+use crate::tables::written_tables;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+type Callback = Arc<dyn Fn(&str) + Send + Sync>;
+
+static HOOKS: RwLock<Vec<Hook>> = RwLock::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+struct Hook {
+    id: HookId,
+    table: String,
+    callback: Callback,
+}
+
+/// Identifies hooks registered together, for [`remove`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// Calls `callback` with the table name after each successful write to `table`.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::invalidation;
+///
+/// let hook = invalidation::on_write("users", |table| {
+///     println!("purge cached reads of {table}");
+/// });
+/// invalidation::remove(hook);
+/// ```
+pub fn on_write<F>(table: &str, callback: F) -> HookId
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    let id = next_id();
+    add(id, table, Arc::new(callback));
+    id
+}
+
+/// Calls `callback` with the name of every query in `registry` that references a table,
+/// after each successful write to that table.
+///
+/// The registry's [`table_inventory`](QueryRegistry::table_inventory) is taken when the
+/// hook is registered; queries added later are not covered.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::{invalidation, registry::QueryRegistry};
+///
+/// let mut registry = QueryRegistry::new();
+/// registry.add("find_user", "SELECT id, name FROM users WHERE id = :id")?;
+///
+/// let hook = invalidation::on_registry_write(&registry, |query| {
+///     println!("drop cached results of {query}");
+/// });
+/// # invalidation::remove(hook);
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn on_registry_write<F>(registry: &QueryRegistry, callback: F) -> HookId
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    let id = next_id();
+    let callback = Arc::new(callback);
+    let readers: BTreeMap<String, Vec<String>> = registry
+        .table_inventory()
+        .into_iter()
+        .map(|(table, usage)| (table, usage.queries))
+        .collect();
+    for (table, queries) in readers {
+        let callback = Arc::clone(&callback);
+        add(
+            id,
+            &table,
+            Arc::new(move |_: &str| queries.iter().for_each(|query| callback(query))),
+        );
+    }
+    id
+}
+
+/// Removes the hooks registered under `id`.
+pub fn remove(id: HookId) {
+    HOOKS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|hook| hook.id != id);
+}
+
+/// Runs the hooks of every table `sql` writes to.
+pub(crate) fn notify(sql: &str) {
+    notify_with(|| written_tables(sql));
+}
+
+/// Runs the hooks of `tables`, for writes whose tables cannot be read from their SQL,
+/// such as a stored procedure call.
+pub(crate) fn notify_tables(tables: &[String]) {
+    notify_with(|| tables.to_vec());
+}
+
+/// Runs the hooks of the tables returned by `tables`, which is only called if any hook
+/// is registered.
+fn notify_with(tables: impl FnOnce() -> Vec<String>) {
+    let callbacks: Vec<(String, Callback)> = {
+        let hooks = HOOKS.read().unwrap_or_else(|e| e.into_inner());
+        if hooks.is_empty() {
+            return;
+        }
+        let tables = tables();
+        hooks
+            .iter()
+            .filter(|hook| tables.contains(&hook.table))
+            .map(|hook| (hook.table.clone(), Arc::clone(&hook.callback)))
+            .collect()
+    };
+    // Called without the lock held, so callbacks may register or remove hooks.
+    for (table, callback) in callbacks {
+        callback(&table);
+    }
+}
+
+fn next_id() -> HookId {
+    HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+fn add(id: HookId, table: &str, callback: Callback) {
+    HOOKS.write().unwrap_or_else(|e| e.into_inner()).push(Hook {
+        id,
+        table: table.to_owned(),
+        callback,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_hooks_follow_written_tables() {
+        let mut registry = QueryRegistry::new();
+        registry
+            .add("find_hook_user", "SELECT * FROM hook_users WHERE id = :id")
+            .unwrap();
+        registry
+            .add("count_hook_orders", "SELECT COUNT(*) FROM hook_orders")
+            .unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let tables = Arc::clone(&seen);
+        let by_table = on_write("hook_users", move |table| {
+            tables.lock().unwrap().push(format!("table:{table}"))
+        });
+        let queries = Arc::clone(&seen);
+        let by_query = on_registry_write(&registry, move |query| {
+            queries.lock().unwrap().push(format!("query:{query}"))
+        });
+
+        notify("SELECT * FROM hook_users");
+        notify("UPDATE hook_users SET name = ? WHERE id = ?");
+        notify("CALL archive_hook_orders()");
+        notify_tables(&["hook_orders".to_owned()]);
+        remove(by_table);
+        remove(by_query);
+        notify("DELETE FROM hook_users");
+
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(
+            seen,
+            [
+                "query:count_hook_orders",
+                "query:find_hook_user",
+                "table:hook_users"
+            ]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Dynamic conversion between MySQL rows and JSON values, and NDJSON import.
+//!
+//! This is used where a query's columns are not known at compile time, such as
+//! exports and generic admin tooling. Columns are converted based on their MySQL
+//! type:
+//!
+//! | MySQL type | JSON value |
+//! |------------|------------|
+//! | `NULL` | `null` |
+//! | `BOOLEAN` | boolean |
+//! | integer types, `YEAR`, `BIT` | number |
+//! | `FLOAT`, `DOUBLE` | number |
+//! | `DECIMAL` | string (to preserve precision) |
+//! | `DATE`, `DATETIME`, `TIMESTAMP`, `TIME` | string in MySQL's own format |
+//! | `JSON` | the embedded JSON value |
+//! | `BINARY`, `VARBINARY`, `BLOB` types, `GEOMETRY` | lowercase hex string |
+//! | everything else | string |
+
+use crate::allowlist;
+use crate::builder::{build_query, placeholder_order};
+use crate::conditional::AnyValue;
+use crate::query::{execute_bound, Q};
+use crate::shutdown;
+use crate::tenancy;
+use futures_util::{Stream, TryStreamExt};
+use serde_json::{Map, Number, Value};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Column, MySqlPool, Row, TypeInfo, ValueRef};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+/// A line of an NDJSON import that could not be inserted.
+#[derive(Debug)]
+pub struct LineError {
+    /// Line number in the input, starting at 1
+    pub line: usize,
+    /// Why the line was rejected
+    pub error: crate::Error,
+}
+
+/// Summary of an NDJSON import.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Number of records inserted
+    pub imported: usize,
+    /// Total rows affected over all inserts
+    pub rows_affected: u64,
+    /// Lines that were rejected, in input order
+    pub errors: Vec<LineError>,
+}
+
+/// Converts a row into a JSON object keyed by column name, in column order.
+///
+/// # Errors
+///
+/// Returns an error if a column value cannot be decoded.
+pub fn row_to_json(row: &MySqlRow) -> crate::Result<Value> {
+    let mut object = Map::with_capacity(row.len());
+    for (index, column) in row.columns().iter().enumerate() {
+        let value = column_to_json(row, index, column.type_info().name())?;
+        object.insert(column.name().to_owned(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+/// Writes every row of `rows` to `writer` as one JSON object per line, as it arrives.
+///
+/// Returns the number of rows written. The writer is flushed once the stream ends.
+pub(crate) async fn write_ndjson<S, W>(rows: S, mut writer: W) -> crate::Result<u64>
+where
+    S: Stream<Item = Result<MySqlRow, sqlx::Error>>,
+    W: Write,
+{
+    let mut rows = std::pin::pin!(rows);
+    let mut written = 0;
+    while let Some(row) = rows.try_next().await? {
+        serde_json::to_writer(&mut writer, &row_to_json(&row)?)?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Reads NDJSON records from `reader` and inserts each one with `insert_template`.
+///
+/// Every line must be a JSON object. `field_to_placeholder` maps record fields to the
+/// template's placeholders (with or without the leading `:`), and every placeholder in
+/// the template must be mapped. JSON values are bound as follows: `null` as `NULL`,
+/// booleans, integers and floats as the matching SQL types, strings as text, and
+/// arrays or objects as their JSON text.
+///
+/// Lines are read and inserted one at a time, committing every `batch_size` records in
+/// their own transaction. A line that is not valid JSON, lacks a mapped field, or is
+/// rejected by the database is recorded in [`ImportReport::errors`] with its line number
+/// and the import continues with the next line. Blank lines are skipped.
+///
+/// # Arguments
+///
+/// * `pool` - Pool to open one transaction per batch from
+/// * `reader` - Source of NDJSON lines
+/// * `insert_template` - SQL template with named placeholders
+/// * `field_to_placeholder` - Pairs of (record field, placeholder name)
+/// * `batch_size` - Number of lines per transaction (a value of `0` is treated as `1`)
+///
+/// # Errors
+///
+/// Returns an error if the template cannot be parsed or is rejected by an installed
+/// [`allowlist`] or [`tenancy`] policy, a placeholder
+/// is not mapped to a field, reading from `reader` fails, or a transaction cannot be
+/// started or committed. Batches committed before such an error stay committed.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::json::import_ndjson;
+/// use std::{fs::File, io::BufReader};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let report = import_ndjson(
+///     &pool,
+///     BufReader::new(File::open("users.ndjson")?),
+///     "INSERT INTO users (name, email) VALUES (:name, :email)",
+///     [("full_name", ":name"), ("email", ":email")],
+///     1_000,
+/// )
+/// .await?;
+///
+/// println!("Imported {} users", report.imported);
+/// for rejected in &report.errors {
+///     eprintln!("line {}: {}", rejected.line, rejected.error);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn import_ndjson<Rd, M, K, V>(
+    pool: &MySqlPool,
+    reader: Rd,
+    insert_template: &str,
+    field_to_placeholder: M,
+    batch_size: usize,
+) -> crate::Result<ImportReport>
+where
+    Rd: BufRead,
+    M: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: AsRef<str>,
+{
+    let mut order: Arc<[String]> = placeholder_order(insert_template)?.into();
+    let mut sql: Arc<str> = build_query(insert_template)?.into();
+    allowlist::check(&sql)?;
+    tenancy::enforce(&mut sql, &mut order)?;
+    let fields = fields_in_order(&order, field_to_placeholder)?;
+    let batch_size = batch_size.max(1);
+
+    let mut report = ImportReport::default();
+    let mut lines = reader.lines().enumerate().peekable();
+    while lines.peek().is_some() {
+        // Registered as one execution, so shutdown waits for the whole batch.
+        let in_flight = shutdown::enter()?;
+        let batch = async {
+            let mut tx = pool.begin().await?;
+            for (index, line) in lines.by_ref().take(batch_size) {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let inserted = match parse_record(&fields, &line) {
+                    Ok(values) => {
+                        // The binder is called once per placeholder occurrence, in order.
+                        let mut values = values.iter();
+                        execute_bound(
+                            &sql,
+                            &order,
+                            &mut |q, _| match values.next() {
+                                Some(value) => bind_json(q, value),
+                                None => q,
+                            },
+                            &mut *tx,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                match inserted {
+                    Ok(result) => {
+                        report.imported += 1;
+                        report.rows_affected += result.rows_matched();
+                    }
+                    Err(error) => report.errors.push(LineError {
+                        line: index + 1,
+                        error,
+                    }),
+                }
+            }
+            tx.commit().await?;
+            Ok::<_, crate::Error>(())
+        };
+        shutdown::within(in_flight, batch).await?;
+    }
+    Ok(report)
+}
+
+/// Resolves the record field to bind for each placeholder occurrence in `order`.
+fn fields_in_order<M, K, V>(order: &[String], field_to_placeholder: M) -> crate::Result<Vec<String>>
+where
+    M: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: AsRef<str>,
+{
+    let by_placeholder: HashMap<String, String> = field_to_placeholder
+        .into_iter()
+        .map(|(field, placeholder)| {
+            let placeholder = placeholder.as_ref();
+            let placeholder = if placeholder.starts_with(':') {
+                placeholder.to_owned()
+            } else {
+                format!(":{placeholder}")
+            };
+            (placeholder, field.into())
+        })
+        .collect();
+
+    order
+        .iter()
+        .map(|key| {
+            by_placeholder
+                .get(key)
+                .cloned()
+                .ok_or_else(|| crate::Error::UnboundPlaceholder(key.clone()))
+        })
+        .collect()
+}
+
+/// Parses one NDJSON line and returns the values of `fields`, in placeholder order.
+fn parse_record(fields: &[String], line: &str) -> crate::Result<Vec<Value>> {
+    let record: Map<String, Value> = serde_json::from_str(line)?;
+    fields
+        .iter()
+        .map(|field| {
+            record
+                .get(field)
+                .cloned()
+                .ok_or_else(|| crate::Error::MissingField(field.clone()))
+        })
+        .collect()
+}
+
+fn bind_json<'q>(q: Q<'q>, value: &Value) -> Q<'q> {
+    q.bind(json_to_value(value))
+}
+
+/// Converts a JSON value to the MySQL value it is bound as: `null` as `NULL`, numbers
+/// as integers where exact, arrays and objects as their JSON text.
+pub(crate) fn json_to_value(value: &Value) -> AnyValue {
+    match value {
+        Value::Null => AnyValue::new(None::<String>),
+        Value::Bool(b) => AnyValue::new(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => AnyValue::new(i),
+            (None, Some(u)) => AnyValue::new(u),
+            _ => AnyValue::new(n.as_f64()),
+        },
+        Value::String(s) => AnyValue::new(s.clone()),
+        Value::Array(_) | Value::Object(_) => AnyValue::new(value.to_string()),
+    }
+}
+
+fn column_to_json(row: &MySqlRow, index: usize, type_name: &str) -> crate::Result<Value> {
+    if row.try_get_raw(index)?.is_null() {
+        return Ok(Value::Null);
+    }
+
+    let value = match type_name {
+        "BOOLEAN" => Value::Bool(row.try_get_unchecked::<i64, _>(index)? != 0),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            Value::from(row.try_get_unchecked::<i64, _>(index)?)
+        }
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" | "YEAR" => Value::from(row.try_get_unchecked::<u64, _>(index)?),
+        "FLOAT" => float(f64::from(row.try_get_unchecked::<f32, _>(index)?)),
+        "DOUBLE" => float(row.try_get_unchecked::<f64, _>(index)?),
+        "BIT" => {
+            let bytes = row.try_get_unchecked::<&[u8], _>(index)?;
+            Value::from(bytes.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+        }
+        "DATE" => Value::String(format_date(
+            row.try_get_unchecked::<&[u8], _>(index)?,
+            false,
+        )),
+        "DATETIME" | "TIMESTAMP" => {
+            Value::String(format_date(row.try_get_unchecked::<&[u8], _>(index)?, true))
+        }
+        "TIME" => Value::String(format_time(row.try_get_unchecked::<&[u8], _>(index)?)),
+        "JSON" => {
+            let text = row.try_get_unchecked::<String, _>(index)?;
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        }
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+            Value::String(hex(row.try_get_unchecked::<&[u8], _>(index)?))
+        }
+        _ => Value::String(row.try_get_unchecked::<String, _>(index)?),
+    };
+    Ok(value)
+}
+
+fn float(value: f64) -> Value {
+    // JSON has no representation for NaN or infinities.
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Formats a `DATE`/`DATETIME`/`TIMESTAMP` value as `YYYY-MM-DD[ HH:MM:SS[.ffffff]]`.
+///
+/// Prepared statements return temporal values in MySQL's binary encoding (a length
+/// byte followed by the date parts); plain text queries return them as strings.
+fn format_date(bytes: &[u8], with_time: bool) -> String {
+    if !is_binary_temporal(bytes, &[0, 4, 7, 11]) {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+
+    let len = bytes[0] as usize;
+    let part = |i: usize| if i <= len { bytes[i] } else { 0 };
+    let year = if len >= 4 {
+        u16::from_le_bytes([bytes[1], bytes[2]])
+    } else {
+        0
+    };
+    let mut text = format!("{:04}-{:02}-{:02}", year, part(3), part(4));
+    if with_time {
+        text.push_str(&format!(" {:02}:{:02}:{:02}", part(5), part(6), part(7)));
+        if len == 11 {
+            let micros = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+            text.push_str(&format!(".{micros:06}"));
+        }
+    }
+    text
+}
+
+/// Formats a `TIME` value as `[-]HH:MM:SS[.ffffff]`, with hours including whole days.
+fn format_time(bytes: &[u8]) -> String {
+    if !is_binary_temporal(bytes, &[0, 8, 12]) {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    if bytes[0] == 0 {
+        return "00:00:00".to_owned();
+    }
+
+    let negative = bytes[1] == 1;
+    let days = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]);
+    let hours = days * 24 + u32::from(bytes[6]);
+    let mut text = format!(
+        "{}{:02}:{:02}:{:02}",
+        if negative { "-" } else { "" },
+        hours,
+        bytes[7],
+        bytes[8]
+    );
+    if bytes[0] == 12 {
+        let micros = u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]]);
+        text.push_str(&format!(".{micros:06}"));
+    }
+    text
+}
+
+fn is_binary_temporal(bytes: &[u8], lengths: &[u8]) -> bool {
+    bytes
+        .first()
+        .is_some_and(|&len| lengths.contains(&len) && bytes.len() == len as usize + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date_binary() {
+        assert_eq!(format_date(&[4, 0xe8, 0x07, 3, 9], false), "2024-03-09");
+        assert_eq!(
+            format_date(&[7, 0xe8, 0x07, 3, 9, 13, 5, 59], true),
+            "2024-03-09 13:05:59"
+        );
+        assert_eq!(
+            format_date(
+                &[11, 0xe8, 0x07, 3, 9, 13, 5, 59, 0x40, 0xe2, 0x01, 0],
+                true
+            ),
+            "2024-03-09 13:05:59.123456"
+        );
+        assert_eq!(format_date(&[0], true), "0000-00-00 00:00:00");
+    }
+
+    #[test]
+    fn test_format_date_text() {
+        assert_eq!(
+            format_date(b"2024-03-09 13:05:59", true),
+            "2024-03-09 13:05:59"
+        );
+    }
+
+    #[test]
+    fn test_format_time_binary() {
+        assert_eq!(format_time(&[0]), "00:00:00");
+        assert_eq!(format_time(&[8, 1, 1, 0, 0, 0, 2, 30, 0]), "-26:30:00");
+        assert_eq!(
+            format_time(&[12, 0, 0, 0, 0, 0, 10, 0, 1, 1, 0, 0, 0]),
+            "10:00:01.000001"
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0xab, 0x10]), "00ab10");
+    }
+
+    #[test]
+    fn test_fields_in_order() {
+        let order = vec![
+            ":name".to_string(),
+            ":email".to_string(),
+            ":name".to_string(),
+        ];
+        let fields = fields_in_order(&order, [("full_name", "name"), ("email", ":email")]).unwrap();
+        assert_eq!(fields, vec!["full_name", "email", "full_name"]);
+
+        let err = fields_in_order(&order, [("email", ":email")]).unwrap_err();
+        assert!(matches!(err, crate::Error::UnboundPlaceholder(p) if p == ":name"));
+    }
+
+    #[test]
+    fn test_parse_record_rejects_bad_lines() {
+        let fields = vec!["name".to_string(), "age".to_string(), "name".to_string()];
+
+        assert_eq!(
+            parse_record(&fields, r#"{"name": "Jane", "age": 41}"#).unwrap(),
+            [Value::from("Jane"), Value::from(41), Value::from("Jane")]
+        );
+        assert!(matches!(
+            parse_record(&fields, r#"{"name": "x"}"#),
+            Err(crate::Error::MissingField(f)) if f == "age"
+        ));
+        assert!(matches!(
+            parse_record(&fields, "[1, 2]"),
+            Err(crate::Error::Json(_))
+        ));
+    }
+}
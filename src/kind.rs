@@ -0,0 +1,152 @@
+//! Classification of statements by what they do.
+//!
+//! [`StatementKind`] is derived from a statement's leading keyword with the crate's
+//! tokenizer, so routing, read-only checks, caching and metrics can agree on what a
+//! query is without each sniffing the SQL text. Both
+//! [`PreparedQuery::kind`](crate::PreparedQuery::kind) and
+//! [`PreparedQueryAs::kind`](crate::PreparedQueryAs::kind) return it.
+
+use crate::lexer::{tokenize, Token};
+use std::fmt;
+
+/// Keywords that start a data definition statement.
+const DDL_KEYWORDS: &[&str] = &["CREATE", "ALTER", "DROP", "TRUNCATE", "RENAME"];
+
+/// What a statement does, judged by its leading keyword.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::kind::StatementKind;
+///
+/// assert_eq!(StatementKind::of("SELECT * FROM users"), StatementKind::Select);
+/// assert_eq!(
+///     StatementKind::of("WITH old AS (SELECT id FROM t) DELETE FROM t WHERE id = 1"),
+///     StatementKind::Delete
+/// );
+/// assert_eq!(StatementKind::of("REPLACE INTO t VALUES (1)"), StatementKind::Insert);
+/// assert_eq!(StatementKind::of("SHOW TABLES").to_string(), "other");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementKind {
+    /// `SELECT`, including `WITH ... SELECT` and parenthesized unions
+    Select,
+    /// `INSERT` or `REPLACE`
+    Insert,
+    /// `UPDATE`, including `WITH ... UPDATE`
+    Update,
+    /// `DELETE`, including `WITH ... DELETE`
+    Delete,
+    /// `CREATE`, `ALTER`, `DROP`, `TRUNCATE` or `RENAME`
+    Ddl,
+    /// Anything else, such as `SHOW`, `EXPLAIN`, `SET` or `CALL`
+    Other,
+}
+
+impl StatementKind {
+    /// Classifies the first statement of `sql`.
+    pub fn of(sql: &str) -> Self {
+        Self::of_tokens(sql, &tokenize(sql))
+    }
+
+    /// Classifies the statement made of `tokens` of `sql`.
+    pub(crate) fn of_tokens(sql: &str, tokens: &[Token]) -> Self {
+        let Some(first) = tokens.iter().position(|token| !token.is_punct(sql, '(')) else {
+            return StatementKind::Other;
+        };
+        let leading = tokens[first];
+        if !leading.is_keyword(sql, "WITH") {
+            return Self::of_keyword(sql, leading);
+        }
+        // The statement after the common table expressions, whose bodies are all
+        // parenthesized.
+        let mut depth = 0_usize;
+        for token in &tokens[first + 1..] {
+            if token.is_punct(sql, '(') {
+                depth += 1;
+            } else if token.is_punct(sql, ')') {
+                depth = depth.saturating_sub(1);
+            } else if depth == 0 && token.is_punct(sql, ';') {
+                break;
+            } else if depth == 0 {
+                let kind = Self::of_keyword(sql, *token);
+                if kind != StatementKind::Other {
+                    return kind;
+                }
+            }
+        }
+        StatementKind::Other
+    }
+
+    fn of_keyword(sql: &str, token: Token) -> Self {
+        let is = |keyword: &str| token.is_keyword(sql, keyword);
+        if is("SELECT") {
+            StatementKind::Select
+        } else if is("INSERT") || is("REPLACE") {
+            StatementKind::Insert
+        } else if is("UPDATE") {
+            StatementKind::Update
+        } else if is("DELETE") {
+            StatementKind::Delete
+        } else if DDL_KEYWORDS.iter().any(|keyword| is(keyword)) {
+            StatementKind::Ddl
+        } else {
+            StatementKind::Other
+        }
+    }
+
+    /// Returns `true` for `INSERT`, `UPDATE` and `DELETE`.
+    pub fn is_write(self) -> bool {
+        matches!(
+            self,
+            StatementKind::Insert | StatementKind::Update | StatementKind::Delete
+        )
+    }
+
+    /// Returns the lowercase name of the kind, e.g. `select`, for use as a metrics label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatementKind::Select => "select",
+            StatementKind::Insert => "insert",
+            StatementKind::Update => "update",
+            StatementKind::Delete => "delete",
+            StatementKind::Ddl => "ddl",
+            StatementKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for StatementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_kind() {
+        for (sql, kind) in [
+            ("/* report */ select 1", StatementKind::Select),
+            ("(SELECT 1) UNION (SELECT 2)", StatementKind::Select),
+            (
+                "WITH RECURSIVE n AS (SELECT 1 UNION SELECT n + 1 FROM n) SELECT * FROM n",
+                StatementKind::Select,
+            ),
+            ("insert into t values (1)", StatementKind::Insert),
+            (
+                "WITH x AS (SELECT 1) UPDATE t JOIN x SET t.a = 1",
+                StatementKind::Update,
+            ),
+            ("DELETE FROM t", StatementKind::Delete),
+            ("TRUNCATE TABLE t", StatementKind::Ddl),
+            ("EXPLAIN SELECT 1", StatementKind::Other),
+            ("WITH x AS (SELECT 1)", StatementKind::Other),
+            ("", StatementKind::Other),
+        ] {
+            assert_eq!(StatementKind::of(sql), kind, "{sql}");
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! A minimal SQL lexer shared by the statement rewriting helpers.
+//!
+//! It only distinguishes words (keywords and identifiers, including backtick-quoted
+//! ones) from punctuation, and skips whitespace, string literals and comments. This is
+//! enough to find clauses and table references without a full SQL parser.
+//!
+//! MySQL runs the body of an executable comment (`/*! ... */`, optionally with a version
+//! number after the `!`) as part of the statement, so the lexer tokenizes that body
+//! like the code around it. Optimizer hints (`/*+ ... */`) are skipped as comments.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Word,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) quoted: bool,
+}
+
+impl Token {
+    pub(crate) fn is_keyword(&self, sql: &str, keyword: &str) -> bool {
+        self.kind == TokenKind::Word
+            && !self.quoted
+            && sql[self.start..self.end].eq_ignore_ascii_case(keyword)
+    }
+
+    pub(crate) fn is_punct(&self, sql: &str, c: char) -> bool {
+        self.kind == TokenKind::Punct && sql[self.start..].starts_with(c)
+    }
+
+    /// The identifier text, without backtick quoting.
+    ///
+    /// An unterminated quoted identifier runs to the end of `sql`, so it has no closing
+    /// backtick to strip.
+    pub(crate) fn ident<'a>(&self, sql: &'a str) -> &'a str {
+        let text = &sql[self.start..self.end];
+        if !self.quoted {
+            return text;
+        }
+        let inner = text.strip_prefix('`').unwrap_or(text);
+        inner.strip_suffix('`').unwrap_or(inner)
+    }
+}
+
+/// Splits `sql` into words and punctuation, skipping whitespace, string literals and comments.
+///
+/// The body of an executable comment is tokenized, not skipped.
+pub(crate) fn tokenize(sql: &str) -> Vec<Token> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut executable = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'*' if executable && bytes[i..].starts_with(b"*/") => {
+                executable = false;
+                i += 2;
+            }
+            b'/' if bytes[i..].starts_with(b"/*!") => {
+                executable = true;
+                i += 3;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            b'\'' | b'"' => i = skip_quoted(bytes, i, b),
+            b'`' => {
+                let end = skip_quoted(bytes, i, b'`');
+                tokens.push(Token {
+                    kind: TokenKind::Word,
+                    start: i,
+                    end,
+                    quoted: true,
+                });
+                i = end;
+            }
+            b'#' => i = skip_line(bytes, i),
+            b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = match sql[i + 2..].find("*/") {
+                    Some(pos) => i + 2 + pos + 2,
+                    None => bytes.len(),
+                };
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ if b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80 => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] == b'_'
+                        || bytes[i] == b'$'
+                        || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word,
+                    start,
+                    end: i,
+                    quoted: false,
+                });
+            }
+            _ => {
+                let len = sql[i..].chars().next().map_or(1, char::len_utf8);
+                tokens.push(Token {
+                    kind: TokenKind::Punct,
+                    start: i,
+                    end: i + len,
+                    quoted: false,
+                });
+                i += len;
+            }
+        }
+    }
+    tokens
+}
+
+/// Returns the offset just past the closing `quote`, honoring doubled quotes and backslash escapes.
+pub(crate) fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && quote != b'`' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+pub(crate) fn skip_line(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |pos| start + pos + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(sql: &str) -> Vec<&str> {
+        tokenize(sql)
+            .iter()
+            .filter(|token| token.kind == TokenKind::Word)
+            .map(|token| token.ident(sql))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_skips_string_literals() {
+        assert_eq!(
+            words("SELECT 'FROM t', \"it\"\"s\", 'a\\'b' FROM users"),
+            ["SELECT", "FROM", "users"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_quoted_identifiers() {
+        let sql = "SELECT `order`, `a``b` FROM `my table`";
+        let tokens = tokenize(sql);
+        assert!(tokens[1].quoted);
+        assert!(!tokens[1].is_keyword(sql, "order"));
+        assert_eq!(words(sql), ["SELECT", "order", "a``b", "FROM", "my table"]);
+    }
+
+    #[test]
+    fn test_tokenize_skips_comments() {
+        let sql = "SELECT id -- FROM a\n# FROM b\nFROM /* JOIN c */ users";
+        assert_eq!(words(sql), ["SELECT", "id", "FROM", "users"]);
+        assert_eq!(words("SELECT 1 /* unterminated FROM t"), ["SELECT", "1"]);
+        assert_eq!(
+            words("SELECT /*+ BKA(t) */ id FROM t"),
+            ["SELECT", "id", "FROM", "t"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_executable_comments() {
+        assert_eq!(
+            words("SELECT id FROM users /*! FOR UPDATE */"),
+            ["SELECT", "id", "FROM", "users", "FOR", "UPDATE"]
+        );
+        assert_eq!(
+            words("SELECT 1 /*!50100 INTO OUTFILE '/tmp/x' */ /* FROM t */"),
+            ["SELECT", "1", "INTO", "OUTFILE"]
+        );
+        let sql = "SELECT 2 /*!*/* 3";
+        let tokens = tokenize(sql);
+        assert_eq!(tokens.len(), 4);
+        assert!(tokens[2].is_punct(sql, '*'));
+    }
+
+    #[test]
+    fn test_tokenize_multibyte() {
+        let sql = "SELECT 名前 FROM `ユーザー` WHERE 名前=?";
+        assert_eq!(
+            words(sql),
+            ["SELECT", "名前", "FROM", "ユーザー", "WHERE", "名前"]
+        );
+        let tokens = tokenize(sql);
+        assert!(tokens[tokens.len() - 2].is_punct(sql, '='));
+        assert!(tokens[tokens.len() - 1].is_punct(sql, '?'));
+    }
+
+    #[test]
+    fn test_tokenize_unterminated() {
+        assert_eq!(words("SELECT `"), ["SELECT", ""]);
+        assert_eq!(words("SELECT `abc"), ["SELECT", "abc"]);
+        assert_eq!(words("SELECT 'abc FROM t"), ["SELECT"]);
+        assert_eq!(words("SELECT 'abc\\"), ["SELECT"]);
+    }
+}
@@ -45,7 +45,7 @@
 //! )?;
 //!
 //! let result = query.execute(&pool).await?;
-//! println!("Inserted {} rows", result.rows_affected());
+//! println!("Inserted {} rows", result.rows_matched());
 //! # Ok(())
 //! # }
 //! ```
@@ -164,26 +164,89 @@
 //!
 //! ## Limitations
 //!
-//! - Currently only supports MySQL (PostgreSQL and SQLite support planned)
-//! - Placeholder names must match `[a-zA-Z0-9_]+`
-//! - All placeholders in the SQL must be handled by the binder function
+//! - MySQL by default; PostgreSQL and SQLite through the `postgres` and `sqlite` features
+//! - Placeholders are written `:name` by default, or `@name`, `$name` or `${name}` with
+//!   [`builder::PlaceholderStyle`]; names must match `[a-zA-Z0-9_]+`, and a placeholder
+//!   inside a quoted string literal or a comment is plain text
+//! - All placeholders in the SQL must be handled by the binder function; one the binder
+//!   leaves unbound fails with [`Error::UnboundPlaceholder`] before the query is sent
 //!
 //! ## License
 //!
 //! Licensed under either of Apache License, Version 2.0 or MIT license at your option.
 
+// Lets code generated by `#[derive(NamedParams)]` name the crate from inside it.
+extern crate self as sqlx_named_bind;
+
+pub mod allowlist;
+pub mod batch;
+pub mod bench;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod budget;
 pub mod builder;
+pub mod call;
+pub mod checksum;
+pub mod cipher;
+pub mod clock;
+pub mod concurrency;
+pub mod conditional;
+pub mod connection;
+pub mod consistency;
+pub mod cte;
+#[cfg(feature = "dataloader")]
+pub mod dataloader;
+pub mod deadline;
+pub mod diff;
 pub mod error;
+pub mod execute_result;
+pub mod explain;
+pub mod fallback;
+pub mod fingerprint;
+pub mod golden;
+pub mod health;
+pub mod hint;
+pub mod invalidation;
+pub mod json;
+pub mod kind;
+pub mod list;
+mod lexer;
+pub mod limits;
+pub mod lock;
+mod macros;
+pub mod pagination;
+pub mod params;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 pub mod query;
 pub mod query_as;
+pub mod query_log;
+pub mod readonly;
+pub mod routing;
+pub mod registry;
+pub mod retry;
+pub mod script;
+pub mod session;
+pub mod shutdown;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod tables;
+pub mod tags;
+pub mod template_cache;
+pub mod tenancy;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+pub mod validators;
 
 pub use error::{Error, Result};
+pub use execute_result::ExecuteResult;
 pub use query::PreparedQuery;
 pub use query_as::PreparedQueryAs;
 
 /// Convenience re-exports for common use cases
 pub mod prelude {
     pub use crate::error::{Error, Result};
+    pub use crate::ExecuteResult;
     pub use crate::PreparedQuery;
     pub use crate::PreparedQueryAs;
 }
@@ -0,0 +1,75 @@
+//! Limits checked before a statement is sent to the server.
+//!
+//! MySQL accepts at most 65,535 parameters per prepared statement. A statement over
+//! the limit, typically after an [`IN` list](crate::list) or a bulk statement was
+//! expanded, otherwise fails with an obscure protocol error. Every execution counts its
+//! bound values after expansion and fails early with
+//! [`Error::TooManyPlaceholders`](crate::Error::TooManyPlaceholders) instead.
+//!
+//! APIs that can split their work do so rather than fail:
+//! [`bulk_insert`](crate::batch::bulk_insert) and
+//! [`bulk_update`](crate::batch::bulk_update) cap their chunk size, and
+//! [`PreparedQueryAs::fetch_by_keys`](crate::PreparedQueryAs::fetch_by_keys) and
+//! [`PreparedQueryAs::fetch_grouped_by_keys`](crate::PreparedQueryAs::fetch_grouped_by_keys)
+//! run one query per chunk of keys.
+//!
+//! The limit can be lowered with [`set_max_placeholders`], e.g. to keep statements
+//! small for a proxy with a lower limit.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The most parameters MySQL accepts in one prepared statement.
+pub const MYSQL_MAX_PLACEHOLDERS: usize = u16::MAX as usize;
+
+static MAX_PLACEHOLDERS: AtomicUsize = AtomicUsize::new(MYSQL_MAX_PLACEHOLDERS);
+
+/// Sets the most bound values one statement may have, for every subsequent execution
+/// in the process.
+///
+/// The value is clamped to `1..=`[`MYSQL_MAX_PLACEHOLDERS`].
+pub fn set_max_placeholders(max: usize) {
+    MAX_PLACEHOLDERS.store(max.clamp(1, MYSQL_MAX_PLACEHOLDERS), Ordering::Relaxed);
+}
+
+/// Returns the most bound values one statement may have.
+pub fn max_placeholders() -> usize {
+    MAX_PLACEHOLDERS.load(Ordering::Relaxed)
+}
+
+/// Fails if a statement with `count` bound values would exceed the limit.
+pub(crate) fn check_placeholders(count: usize) -> crate::Result<()> {
+    let max = max_placeholders();
+    if count > max {
+        return Err(crate::Error::TooManyPlaceholders { count, max });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::BindList;
+    use crate::query::{bind_arguments, Q};
+    use crate::validators::BindChecks;
+
+    fn bind_ids<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+        match key {
+            ":ids" => q.bind_list(0..MYSQL_MAX_PLACEHOLDERS as i64),
+            _ => q.bind(1_i64),
+        }
+    }
+
+    #[test]
+    fn test_too_many_placeholders() {
+        let checks = BindChecks::default();
+        let order = vec![":ids".to_owned()];
+        assert!(bind_arguments("?", &order, &mut bind_ids, &checks).is_ok());
+
+        let order = vec![":ids".to_owned(), ":team".to_owned()];
+        assert!(matches!(
+            bind_arguments("? ?", &order, &mut bind_ids, &checks),
+            Err(crate::Error::TooManyPlaceholders { count, max })
+                if count == MYSQL_MAX_PLACEHOLDERS + 1 && max == MYSQL_MAX_PLACEHOLDERS
+        ));
+    }
+}
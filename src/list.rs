@@ -0,0 +1,159 @@
+//! List placeholders for `IN (...)` clauses.
+//!
+//! MySQL needs one `?` per element of an `IN` list, so a `Vec` cannot be bound to a
+//! single placeholder. Instead, a binder binds every element with
+//! [`BindList::bind_list`]; when a placeholder receives more than one value, its `?` is
+//! expanded to as many comma-separated `?` at execution time, in every statement the
+//! query runs (including `EXPLAIN` and offset or row-number pages; keyset pages bind
+//! the template directly and do not support lists).
+//!
+//! An empty list binds a single `NULL`, so `id IN (:ids)` matches no rows instead of
+//! producing the invalid `IN ()`. Beware that `id NOT IN (:ids)` then matches no rows
+//! either.
+//!
+//! ```rust,no_run
+//! use sqlx::MySqlPool;
+//! use sqlx_named_bind::{list::BindList, PreparedQueryAs};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+//! let ids = vec![3_i64, 5, 8];
+//! let mut query = PreparedQueryAs::<(i64, String), _>::new(
+//!     "SELECT id, name FROM users WHERE id IN (:ids) AND active = :active",
+//!     |q, key| match key {
+//!         ":ids" => q.bind_list(ids.clone()),
+//!         ":active" => q.bind(true),
+//!         _ => q,
+//!     },
+//! )?;
+//! // Runs `... WHERE id IN (?, ?, ?) AND active = ?`
+//! let users = query.fetch_all(&pool).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::lexer::{skip_line, skip_quoted};
+use crate::query::Q;
+use crate::query_as::QA;
+use sqlx::{Encode, MySql, Type};
+use std::borrow::Cow;
+
+/// Binding every element of a list to one placeholder.
+pub trait BindList<'q>: Sized {
+    /// Binds each element of `values` in order, or a single `NULL` if there are none.
+    fn bind_list<T, I>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: 'q + Encode<'q, MySql> + Type<MySql>;
+}
+
+impl<'q> BindList<'q> for Q<'q> {
+    fn bind_list<T, I>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: 'q + Encode<'q, MySql> + Type<MySql>,
+    {
+        let mut values = values.into_iter().peekable();
+        if values.peek().is_none() {
+            return self.bind(None::<T>);
+        }
+        values.fold(self, |q, value| q.bind(value))
+    }
+}
+
+impl<'q, R> BindList<'q> for QA<'q, R> {
+    fn bind_list<T, I>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: 'q + Encode<'q, MySql> + Type<MySql>,
+    {
+        let mut values = values.into_iter().peekable();
+        if values.peek().is_none() {
+            return self.bind(None::<T>);
+        }
+        values.fold(self, |q, value| q.bind(value))
+    }
+}
+
+/// Wraps `binder` so that `placeholder` is bound to every element of `values`.
+pub(crate) fn binding_list_as<'a, R, F, T>(
+    placeholder: &'a str,
+    values: &'a [T],
+    binder: &'a mut F,
+) -> impl for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + 'a
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    T: Clone + Send + for<'q> Encode<'q, MySql> + Type<MySql> + 'static,
+{
+    move |q, key| {
+        if key == placeholder {
+            q.bind_list(values.iter().cloned())
+        } else {
+            binder(q, key)
+        }
+    }
+}
+
+/// Replaces the `i`-th `?` of `sql` with `counts[i]` comma-separated `?`.
+///
+/// `?` in string literals and comments are skipped, and those past the end of `counts`
+/// (such as an appended `LIMIT ?`) are left alone.
+pub(crate) fn expand_lists<'s>(sql: &'s str, counts: &[usize]) -> Cow<'s, str> {
+    if counts.iter().all(|&count| count == 1) {
+        return Cow::Borrowed(sql);
+    }
+    let bytes = sql.as_bytes();
+    let mut expanded = String::with_capacity(sql.len() + 3 * counts.iter().sum::<usize>());
+    let mut copied = 0;
+    let mut index = 0;
+    let mut i = 0;
+    while i < bytes.len() && index < counts.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i, quote),
+            b'#' => i = skip_line(bytes, i),
+            b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = match sql[i + 2..].find("*/") {
+                    Some(pos) => i + 2 + pos + 2,
+                    None => bytes.len(),
+                };
+            }
+            b'?' => {
+                expanded.push_str(&sql[copied..i]);
+                for n in 0..counts[index] {
+                    expanded.push_str(if n == 0 { "?" } else { ", ?" });
+                }
+                index += 1;
+                i += 1;
+                copied = i;
+            }
+            _ => i += 1,
+        }
+    }
+    expanded.push_str(&sql[copied..]);
+    Cow::Owned(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{Arguments, Execute};
+
+    #[test]
+    fn test_expand_lists() {
+        let sql = "SELECT '?' FROM t WHERE a = ? AND id IN (?) /* ? */ LIMIT ?";
+        assert!(matches!(expand_lists(sql, &[1, 1]), Cow::Borrowed(_)));
+        assert_eq!(
+            expand_lists(sql, &[1, 3]),
+            "SELECT '?' FROM t WHERE a = ? AND id IN (?, ?, ?) /* ? */ LIMIT ?"
+        );
+    }
+
+    #[test]
+    fn test_bind_list() {
+        let mut q = sqlx::query::<MySql>("").bind_list([1_i64, 2, 3]);
+        assert_eq!(q.take_arguments().unwrap().unwrap().len(), 3);
+        let mut q = sqlx::query::<MySql>("").bind_list(Vec::<i64>::new());
+        assert_eq!(q.take_arguments().unwrap().unwrap().len(), 1);
+    }
+}
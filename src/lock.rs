@@ -0,0 +1,179 @@
+//! Named advisory locks based on MySQL `GET_LOCK`/`RELEASE_LOCK`.
+//!
+//! A named lock is held by a connection, not by a transaction, and is a common way
+//! to make sure only one instance of a job runs at a time across many processes.
+
+use crate::PreparedQueryAs;
+use futures_util::future::BoxFuture;
+use sqlx::pool::PoolConnection;
+use sqlx::{MySql, MySqlConnection, MySqlPool};
+use std::time::Duration;
+
+/// A held named lock, released by [`release`](Self::release) or on drop.
+///
+/// The lock belongs to the connection it was acquired on, which the guard keeps checked
+/// out of the pool; use [`connection`](Self::connection) to run queries on it. If the
+/// guard is dropped without calling `release`, the connection is closed instead of
+/// being returned to the pool, which makes the server release the lock.
+#[derive(Debug)]
+pub struct NamedLock {
+    name: String,
+    conn: Option<PoolConnection<MySql>>,
+}
+
+impl NamedLock {
+    /// Acquires the lock `name`, waiting at most `timeout` for another holder to release it.
+    ///
+    /// The timeout is rounded up to whole seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LockTimeout`](crate::Error::LockTimeout) if the lock is still
+    /// held elsewhere after `timeout`, or an error if the query fails. The connection is
+    /// then closed, as it is when the call is cancelled, so a lock the server granted
+    /// anyway is released.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::lock::NamedLock;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let lock = NamedLock::acquire(&pool, "nightly-report", Duration::from_secs(5)).await?;
+    /// // ... generate the report ...
+    /// lock.release().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn acquire(pool: &MySqlPool, name: &str, timeout: Duration) -> crate::Result<Self> {
+        let seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        let mut query = PreparedQueryAs::<(Option<i64>,), _>::new_unlisted(
+            "SELECT GET_LOCK(:name, :timeout)",
+            |q, key| match key {
+                ":name" => q.bind(name.to_owned()),
+                ":timeout" => q.bind(seconds),
+                _ => q,
+            },
+        )?;
+
+        // Until `GET_LOCK` answers, dropping `lock` closes the connection, so a lock the
+        // server granted to a failed or cancelled call is released with it.
+        let mut lock = Self {
+            name: name.to_owned(),
+            conn: Some(pool.acquire().await?),
+        };
+        match query.fetch_one(lock.connection()).await? {
+            (Some(1),) => Ok(lock),
+            _ => {
+                // Not granted, so the connection goes back to the pool.
+                lock.conn.take();
+                Err(crate::Error::LockTimeout(name.to_owned()))
+            }
+        }
+    }
+
+    /// Returns the name of the lock.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the connection holding the lock.
+    pub fn connection(&mut self) -> &mut MySqlConnection {
+        self.conn
+            .as_mut()
+            .expect("connection is only taken by release")
+    }
+
+    /// Releases the lock and returns the connection to the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails. The connection is then closed, which
+    /// releases the lock on the server.
+    pub async fn release(mut self) -> crate::Result<()> {
+        let name = self.name.clone();
+        let mut query = PreparedQueryAs::<(Option<i64>,), _>::new_unlisted(
+            "SELECT RELEASE_LOCK(:name)",
+            |q, key| match key {
+                ":name" => q.bind(name.clone()),
+                _ => q,
+            },
+        )?;
+
+        let mut conn = self
+            .conn
+            .take()
+            .expect("connection is only taken by release");
+        if let Err(e) = query.fetch_one(&mut *conn).await {
+            conn.close_on_drop();
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for NamedLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.as_mut() {
+            conn.close_on_drop();
+        }
+    }
+}
+
+/// Runs `f` while holding the named lock `name`.
+///
+/// The lock is acquired on a connection checked out from `pool`, which is passed to `f`,
+/// and released after `f` completes, whether it succeeded or not.
+///
+/// # Arguments
+///
+/// * `pool` - Pool to check the lock's connection out from
+/// * `name` - Name of the lock, shared by every process that must not run concurrently
+/// * `timeout` - How long to wait for the lock, rounded up to whole seconds
+/// * `f` - Runs while the lock is held; return the future boxed with `Box::pin(async move { ... })`
+///
+/// # Errors
+///
+/// Returns [`Error::LockTimeout`](crate::Error::LockTimeout) if the lock cannot be
+/// acquired in time, the error returned by `f`, or an error if releasing the lock fails.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{lock::with_named_lock, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// with_named_lock(&pool, "expire-sessions", Duration::from_secs(0), |conn| {
+///     Box::pin(async move {
+///         PreparedQuery::new("DELETE FROM sessions WHERE expires_at < NOW()", |q, _| q)?
+///             .execute(conn)
+///             .await?;
+///         Ok(())
+///     })
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_named_lock<T, F>(
+    pool: &MySqlPool,
+    name: &str,
+    timeout: Duration,
+    f: F,
+) -> crate::Result<T>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    let mut lock = NamedLock::acquire(pool, name, timeout).await?;
+    let result = f(lock.connection()).await;
+    let released = lock.release().await;
+    let value = result?;
+    released?;
+    Ok(value)
+}
@@ -0,0 +1,97 @@
+//! Macros building binders from local variables.
+
+/// Creates a query whose placeholders are bound from local variables of the same
+/// name.
+///
+/// `bind_locals!(template, id, name)` expands to
+///
+/// ```text
+/// PreparedQuery::new(template, |q, key| match key {
+///     ":id" => q.bind(id.to_owned()),
+///     ":name" => q.bind(name.to_owned()),
+///     _ => q,
+/// })
+/// ```
+///
+/// and `bind_locals!(as User, template, id, name)` to the same for
+/// `PreparedQueryAs::<User, _>`. Each execution binds an owned copy of every variable,
+/// so `&str` locals are bound as `String`s; the variables are borrowed by the query
+/// and stay usable after it. A placeholder without a variable fails on execution with
+/// [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder), so a misspelled
+/// variable is reported instead of silently binding too few values.
+///
+/// # Errors
+///
+/// The expression returns the error of
+/// [`PreparedQuery::new`](crate::PreparedQuery::new) or
+/// [`PreparedQueryAs::new`](crate::PreparedQueryAs::new).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::bind_locals;
+///
+/// # async fn example(pool: MySqlPool, email: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// let id = 42_i64;
+/// let name = String::from("Alice");
+/// bind_locals!(
+///     "UPDATE users SET name = :name, email = :email WHERE id = :id",
+///     id,
+///     name,
+///     email
+/// )?
+/// .execute(&pool)
+/// .await?;
+///
+/// let users: Vec<(i64, String)> =
+///     bind_locals!(as (i64, String), "SELECT id, name FROM users WHERE name = :name", name)?
+///         .fetch_all(&pool)
+///         .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! bind_locals {
+    (as $row:ty, $template:expr $(, $var:ident)* $(,)?) => {
+        $crate::PreparedQueryAs::<$row, _>::new(
+            $template,
+            |q, key| match key {
+                $(concat!(":", stringify!($var)) => q.bind($var.to_owned()),)*
+                _ => q,
+            },
+        )
+    };
+    ($template:expr $(, $var:ident)* $(,)?) => {
+        $crate::PreparedQuery::new($template, |q, key| match key {
+            $(concat!(":", stringify!($var)) => q.bind($var.to_owned()),)*
+            _ => q,
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{Arguments, Execute};
+
+    #[test]
+    fn test_bind_locals() {
+        let id = 7_i64;
+        let name = "Alice";
+        let mut query = bind_locals!(
+            "UPDATE users SET name = :name WHERE id = :id OR parent_id = :id",
+            id,
+            name
+        )
+        .unwrap();
+        let mut q = query.into_sqlx_query().unwrap();
+        assert_eq!(q.take_arguments().unwrap().unwrap().len(), 3);
+        assert_eq!((id, name), (7, "Alice"));
+
+        let mut typo = bind_locals!("DELETE FROM users WHERE id = :user_id", id).unwrap();
+        assert!(matches!(
+            typo.into_sqlx_query(),
+            Err(crate::Error::UnboundPlaceholder(key)) if key == ":user_id"
+        ));
+    }
+}
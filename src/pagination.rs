@@ -0,0 +1,272 @@
+//! Auto-advancing page iterators over large result sets.
+//!
+//! Every iterator fetches one page per call to `next()`, stopping after the first
+//! short page:
+//!
+//! - [`OffsetPages`] pages with `LIMIT ? OFFSET ?`. It works with any query that has a
+//!   total `ORDER BY`, but each page gets slower as the offset grows.
+//! - [`KeysetPages`] binds the key of the last row seen to a cursor placeholder in the
+//!   template (e.g. `WHERE id > :after ORDER BY id`), so every page is an index seek.
+//! - [`WindowPages`] numbers the rows with `ROW_NUMBER()` and selects a range of row
+//!   numbers, for orderings that cannot be expressed as a keyset seek (e.g. by a
+//!   computed score with ties).
+//!
+//! The template must not contain its own `LIMIT` clause.
+
+use crate::query_as::{bind_arguments_as, PreparedQueryAs, QA};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Encode, Executor, MySql, Type};
+
+/// Pages through a query with `LIMIT ? OFFSET ?`.
+///
+/// Created by [`PreparedQueryAs::iter_pages`].
+pub struct OffsetPages<'a, R, F, E>
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    query: &'a mut PreparedQueryAs<R, F>,
+    executor: E,
+    sql: String,
+    page_size: u64,
+    offset: u64,
+    done: bool,
+}
+
+impl<'a, R, F, E> OffsetPages<'a, R, F, E>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    E: for<'c> Executor<'c, Database = MySql> + Copy,
+{
+    pub(crate) fn new(query: &'a mut PreparedQueryAs<R, F>, executor: E, page_size: u64) -> Self {
+        let sql = format!("{} LIMIT ? OFFSET ?", query.sql());
+        Self {
+            query,
+            executor,
+            sql,
+            page_size: page_size.max(1),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Fetches the next page, or returns `None` once the result set is exhausted.
+    ///
+    /// After an error is returned, the iterator is exhausted.
+    pub async fn next(&mut self) -> Option<crate::Result<Vec<R>>> {
+        if self.done {
+            return None;
+        }
+
+        let (order, binder, checks) = self.query.binder_parts();
+        let bound = match bind_arguments_as(&self.sql, order, binder, checks) {
+            Ok(bound) => bound,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        let sql = bound.expand(&self.sql);
+        let result = sqlx::query_as_with::<_, R, _>(&sql, bound.arguments)
+            .bind(self.page_size)
+            .bind(self.offset)
+            .fetch_all(self.executor)
+            .await;
+
+        match result {
+            Ok(rows) => {
+                self.done = (rows.len() as u64) < self.page_size;
+                self.offset += rows.len() as u64;
+                (!rows.is_empty()).then_some(Ok(rows))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// Pages through a query by binding the key of the last row seen to a cursor placeholder.
+///
+/// Created by [`PreparedQueryAs::iter_pages_by_key`].
+pub struct KeysetPages<'a, R, F, E, K, G>
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    query: &'a mut PreparedQueryAs<R, F>,
+    executor: E,
+    sql: String,
+    page_size: u64,
+    cursor_placeholder: String,
+    cursor: K,
+    key: G,
+    done: bool,
+}
+
+impl<'a, R, F, E, K, G> KeysetPages<'a, R, F, E, K, G>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    E: for<'c> Executor<'c, Database = MySql> + Copy,
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send + 'static,
+    G: FnMut(&R) -> K,
+{
+    pub(crate) fn new(
+        query: &'a mut PreparedQueryAs<R, F>,
+        executor: E,
+        page_size: u64,
+        cursor_placeholder: String,
+        start: K,
+        key: G,
+    ) -> Self {
+        let sql = format!("{} LIMIT ?", query.sql());
+        Self {
+            query,
+            executor,
+            sql,
+            page_size: page_size.max(1),
+            cursor_placeholder,
+            cursor: start,
+            key,
+            done: false,
+        }
+    }
+
+    /// Fetches the next page, or returns `None` once the result set is exhausted.
+    ///
+    /// After an error is returned, the iterator is exhausted.
+    pub async fn next(&mut self) -> Option<crate::Result<Vec<R>>> {
+        if self.done {
+            return None;
+        }
+
+        let (order, binder, _) = self.query.binder_parts();
+        let mut q = sqlx::query_as(&self.sql);
+        for key in order.iter() {
+            q = if *key == self.cursor_placeholder {
+                q.bind(self.cursor.clone())
+            } else {
+                binder(q, key)
+            };
+        }
+        let result = q.bind(self.page_size).fetch_all(self.executor).await;
+
+        match result {
+            Ok(rows) => {
+                self.done = (rows.len() as u64) < self.page_size;
+                let last = rows.last()?;
+                self.cursor = (self.key)(last);
+                Some(Ok(rows))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// Wraps converted SQL so its rows are numbered by `order_by` and filtered to a range.
+///
+/// The row number is appended as the last column, `__rn`, so it does not shift the
+/// positions of the template's own columns.
+pub(crate) fn window_sql(sql: &str, order_by: &str) -> String {
+    format!(
+        "SELECT * FROM (SELECT __inner.*, ROW_NUMBER() OVER (ORDER BY {order_by}) AS __rn \
+         FROM ({sql}) AS __inner) AS __paged WHERE __rn BETWEEN ? AND ? ORDER BY __rn"
+    )
+}
+
+/// Pages through a query by numbering its rows with `ROW_NUMBER()`.
+///
+/// Created by [`PreparedQueryAs::iter_pages_windowed`].
+pub struct WindowPages<'a, R, F, E>
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    query: &'a mut PreparedQueryAs<R, F>,
+    executor: E,
+    sql: String,
+    page_size: u64,
+    next_row: u64,
+    done: bool,
+}
+
+impl<'a, R, F, E> WindowPages<'a, R, F, E>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    E: for<'c> Executor<'c, Database = MySql> + Copy,
+{
+    pub(crate) fn new(
+        query: &'a mut PreparedQueryAs<R, F>,
+        executor: E,
+        order_by: &str,
+        page_size: u64,
+    ) -> Self {
+        let sql = window_sql(query.sql(), order_by);
+        Self {
+            query,
+            executor,
+            sql,
+            page_size: page_size.max(1),
+            next_row: 1,
+            done: false,
+        }
+    }
+
+    /// Fetches the next page, or returns `None` once the result set is exhausted.
+    ///
+    /// After an error is returned, the iterator is exhausted.
+    pub async fn next(&mut self) -> Option<crate::Result<Vec<R>>> {
+        if self.done {
+            return None;
+        }
+
+        let (order, binder, checks) = self.query.binder_parts();
+        let bound = match bind_arguments_as(&self.sql, order, binder, checks) {
+            Ok(bound) => bound,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        let sql = bound.expand(&self.sql);
+        let result = sqlx::query_as_with::<_, R, _>(&sql, bound.arguments)
+            .bind(self.next_row)
+            .bind(self.next_row + self.page_size - 1)
+            .fetch_all(self.executor)
+            .await;
+
+        match result {
+            Ok(rows) => {
+                self.done = (rows.len() as u64) < self.page_size;
+                self.next_row += rows.len() as u64;
+                (!rows.is_empty()).then_some(Ok(rows))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_sql() {
+        assert_eq!(
+            window_sql(
+                "SELECT id, score FROM players WHERE team = ?",
+                "score DESC, id"
+            ),
+            "SELECT * FROM (SELECT __inner.*, ROW_NUMBER() OVER (ORDER BY score DESC, id) AS __rn \
+             FROM (SELECT id, score FROM players WHERE team = ?) AS __inner) AS __paged \
+             WHERE __rn BETWEEN ? AND ? ORDER BY __rn"
+        );
+    }
+}
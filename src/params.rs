@@ -0,0 +1,114 @@
+//! Binding placeholders from the fields of a struct.
+//!
+//! A type implementing [`NamedParams`] knows how to bind its fields to the placeholders
+//! of the same name, so [`PreparedQuery::from_struct`](crate::PreparedQuery::from_struct)
+//! and [`PreparedQueryAs::from_struct`](crate::PreparedQueryAs::from_struct) need no
+//! binder closure. With the `derive` feature, `#[derive(NamedParams)]` writes the
+//! implementation; fields can be renamed with `#[named_params(rename = "...")]` or left
+//! out with `#[named_params(skip)]`.
+
+use crate::query::Q;
+use crate::query_as::QA;
+
+#[cfg(feature = "derive")]
+pub use sqlx_named_bind_derive::NamedParams;
+
+/// Binder of a query built by `from_struct`, borrowing the struct.
+pub type StructBinder<'a> = Box<dyn for<'q> FnMut(Q<'q>, &str) -> Q<'q> + Send + Sync + 'a>;
+
+/// Binder of a typed query built by `from_struct`, borrowing the struct.
+pub type StructBinderAs<'a, R> =
+    Box<dyn for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + Send + Sync + 'a>;
+
+/// A struct whose fields are bound to the placeholders named after them.
+///
+/// Implementations return the query unchanged for names they have no field for, which
+/// execution reports as [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder).
+///
+/// # Examples
+///
+/// ```rust
+/// use sqlx_named_bind::params::NamedParams;
+/// use sqlx_named_bind::query::Q;
+/// use sqlx_named_bind::query_as::QA;
+/// use sqlx_named_bind::PreparedQuery;
+///
+/// // With the `derive` feature this is `#[derive(NamedParams)]`.
+/// struct NewUser {
+///     name: String,
+///     email: String,
+/// }
+///
+/// impl NamedParams for NewUser {
+///     fn bind_field<'q>(&self, query: Q<'q>, name: &str) -> Q<'q> {
+///         match name {
+///             ":name" => query.bind(self.name.clone()),
+///             ":email" => query.bind(self.email.clone()),
+///             _ => query,
+///         }
+///     }
+///
+///     fn bind_field_as<'q, R>(&self, query: QA<'q, R>, name: &str) -> QA<'q, R> {
+///         match name {
+///             ":name" => query.bind(self.name.clone()),
+///             ":email" => query.bind(self.email.clone()),
+///             _ => query,
+///         }
+///     }
+/// }
+///
+/// let user = NewUser { name: "Jane".into(), email: "jane@example.com".into() };
+/// let query = PreparedQuery::from_struct(
+///     "INSERT INTO users (name, email) VALUES (:name, :email)",
+///     &user,
+/// )?;
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub trait NamedParams {
+    /// Binds the field for the placeholder `name` (including the leading `:`) onto
+    /// `query`.
+    fn bind_field<'q>(&self, query: Q<'q>, name: &str) -> Q<'q>;
+
+    /// Binds the field for the placeholder `name` onto a typed `query`.
+    fn bind_field_as<'q, R>(&self, query: QA<'q, R>, name: &str) -> QA<'q, R>;
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::builder::placeholder_order;
+    use crate::query::bind_arguments;
+    use crate::validators::BindChecks;
+    use sqlx::Arguments;
+
+    #[derive(NamedParams)]
+    struct NewUser {
+        name: String,
+        #[named_params(rename = "mail")]
+        email: String,
+        #[named_params(skip)]
+        #[allow(dead_code)]
+        password: String,
+    }
+
+    #[test]
+    fn test_derive_binds_fields() {
+        let user = NewUser {
+            name: "Jane".into(),
+            email: "jane@example.com".into(),
+            password: "secret".into(),
+        };
+        let mut binder: StructBinder = Box::new(|q, key| user.bind_field(q, key));
+
+        let order = placeholder_order("VALUES (:name, :mail, :name)").unwrap();
+        let checks = BindChecks::default();
+        let bound = bind_arguments("", &order, &mut binder, &checks).unwrap();
+        assert_eq!(bound.arguments.len(), 3);
+
+        let order = placeholder_order("SET password = :password").unwrap();
+        assert!(matches!(
+            bind_arguments("", &order, &mut binder, &checks),
+            Err(crate::Error::UnboundPlaceholder(key)) if key == ":password"
+        ));
+    }
+}
@@ -0,0 +1,325 @@
+//! PostgreSQL support, behind the `postgres` feature.
+//!
+//! [`PgPreparedQuery`] and [`PgPreparedQueryAs`] mirror [`PreparedQuery`](crate::PreparedQuery)
+//! and [`PreparedQueryAs`](crate::PreparedQueryAs) for `Postgres` executors. Named
+//! placeholders are converted to numbered ones (`$1`, `$2`, ...). A placeholder used
+//! more than once shares one number, so the binder is called once per distinct name,
+//! in order of first appearance.
+//!
+//! `::type` casts, string literals, quoted identifiers, dollar-quoted strings and
+//! comments are left untouched.
+
+use crate::lexer::{skip_line, skip_quoted};
+use sqlx::postgres::{PgArguments, PgQueryResult, PgRow};
+use sqlx::query::{Query, QueryAs};
+use sqlx::{Executor, Postgres};
+
+/// Type alias for SQLx Query with PostgreSQL arguments
+pub type PgQ<'q> = Query<'q, Postgres, PgArguments>;
+
+/// Type alias for SQLx QueryAs with PostgreSQL arguments
+pub type PgQA<'q, R> = QueryAs<'q, Postgres, R, PgArguments>;
+
+/// Converts named placeholders (`:name`) to numbered placeholders (`$1`, `$2`, ...).
+///
+/// Returns the converted SQL and the distinct placeholder names, where the name at
+/// index `i` is bound to `$i+1`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidTemplate`](crate::Error::InvalidTemplate) if a dollar-quoted
+/// string is not terminated.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::postgres::build_pg_query;
+///
+/// let (sql, names) = build_pg_query(
+///     "SELECT id::text FROM users WHERE id = :id OR parent_id = :id AND name = :name",
+/// )?;
+/// assert_eq!(sql, "SELECT id::text FROM users WHERE id = $1 OR parent_id = $1 AND name = $2");
+/// assert_eq!(names, vec![":id", ":name"]);
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub fn build_pg_query(template: &str) -> crate::Result<(String, Vec<String>)> {
+    let bytes = template.as_bytes();
+    let mut sql = String::with_capacity(template.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => i = skip_quoted(bytes, i, quote),
+            b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = match template[i + 2..].find("*/") {
+                    Some(pos) => i + 2 + pos + 2,
+                    None => bytes.len(),
+                };
+            }
+            b'$' => i = skip_dollar_quoted(template, i)?,
+            b':' if bytes.get(i + 1) == Some(&b':') => i += 2,
+            b':' if bytes.get(i + 1).is_some_and(|&c| is_name_byte(c)) => {
+                let mut end = i + 1;
+                while end < bytes.len() && is_name_byte(bytes[end]) {
+                    end += 1;
+                }
+                let name = &template[i..end];
+                let number = match names.iter().position(|n| n == name) {
+                    Some(index) => index + 1,
+                    None => {
+                        names.push(name.to_owned());
+                        names.len()
+                    }
+                };
+                sql.push_str(&template[copied..i]);
+                sql.push('$');
+                sql.push_str(&number.to_string());
+                i = end;
+                copied = end;
+            }
+            _ => i += 1,
+        }
+    }
+    sql.push_str(&template[copied..]);
+    Ok((sql, names))
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// If a `$tag$` dollar quote starts at `start`, returns the offset past its closing tag;
+/// otherwise (e.g. for `$1`) the offset past the `$`.
+fn skip_dollar_quoted(sql: &str, start: usize) -> crate::Result<usize> {
+    let bytes = sql.as_bytes();
+    let mut end = start + 1;
+    if bytes.get(end).is_some_and(u8::is_ascii_digit) {
+        return Ok(end);
+    }
+    while end < bytes.len() && is_name_byte(bytes[end]) {
+        end += 1;
+    }
+    if bytes.get(end) != Some(&b'$') {
+        return Ok(start + 1);
+    }
+    let tag = &sql[start..=end];
+    match sql[end + 1..].find(tag) {
+        Some(pos) => Ok(end + 1 + pos + tag.len()),
+        None => Err(crate::Error::InvalidTemplate(format!(
+            "unterminated dollar-quoted string starting with '{tag}'"
+        ))),
+    }
+}
+
+/// A prepared PostgreSQL query with named placeholders.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::PgPool;
+/// use sqlx_named_bind::postgres::PgPreparedQuery;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = PgPool::connect("postgres://localhost/test").await?;
+/// let mut query = PgPreparedQuery::new(
+///     "UPDATE users SET name = :name WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42_i64),
+///         ":name" => q.bind("Jane"),
+///         _ => q,
+///     },
+/// )?;
+///
+/// let result = query.execute(&pool).await?;
+/// println!("Updated {} rows", result.rows_affected());
+/// # Ok(())
+/// # }
+/// ```
+pub struct PgPreparedQuery<F> {
+    sql: String,
+    order: Vec<String>,
+    binder: F,
+}
+
+impl<F> PgPreparedQuery<F>
+where
+    F: for<'q> FnMut(PgQ<'q>, &str) -> PgQ<'q>,
+{
+    /// Creates a new `PgPreparedQuery` from an SQL template and binder function.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `binder` - Function that binds values to placeholders based on their names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be converted.
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let (sql, order) = build_pg_query(&template.into())?;
+        Ok(Self { sql, order, binder })
+    }
+
+    /// Executes the query and returns the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor for `Postgres` (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn execute<'e, E>(&mut self, executor: E) -> crate::Result<PgQueryResult>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let mut q = sqlx::query::<Postgres>(&self.sql);
+        for key in &self.order {
+            q = (self.binder)(q, key);
+        }
+        Ok(q.execute(executor).await?)
+    }
+}
+
+/// A prepared PostgreSQL query with named placeholders that returns typed rows.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{FromRow, PgPool};
+/// use sqlx_named_bind::postgres::PgPreparedQueryAs;
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = PgPool::connect("postgres://localhost/test").await?;
+/// let mut query = PgPreparedQueryAs::<User, _>::new(
+///     "SELECT id, name FROM users WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42_i64),
+///         _ => q,
+///     },
+/// )?;
+///
+/// let user = query.fetch_one(&pool).await?;
+/// println!("User: {} ({})", user.name, user.id);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PgPreparedQueryAs<R, F>
+where
+    for<'row> R: sqlx::FromRow<'row, PgRow> + Send + Unpin,
+    F: for<'q> FnMut(PgQA<'q, R>, &str) -> PgQA<'q, R>,
+{
+    sql: String,
+    order: Vec<String>,
+    binder: F,
+    _pd: std::marker::PhantomData<R>,
+}
+
+impl<R, F> PgPreparedQueryAs<R, F>
+where
+    for<'row> R: sqlx::FromRow<'row, PgRow> + Send + Unpin,
+    F: for<'q> FnMut(PgQA<'q, R>, &str) -> PgQA<'q, R>,
+{
+    /// Creates a new `PgPreparedQueryAs` from an SQL template and binder function.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `binder` - Function that binds values to placeholders based on their names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be converted.
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let (sql, order) = build_pg_query(&template.into())?;
+        Ok(Self {
+            sql,
+            order,
+            binder,
+            _pd: std::marker::PhantomData,
+        })
+    }
+
+    fn query(&mut self) -> PgQA<'_, R> {
+        let mut q = sqlx::query_as(&self.sql);
+        for key in &self.order {
+            q = (self.binder)(q, key);
+        }
+        q
+    }
+
+    /// Fetches all rows matching the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails or rows cannot be decoded.
+    pub async fn fetch_all<'e, E>(&mut self, executor: E) -> crate::Result<Vec<R>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        Ok(self.query().fetch_all(executor).await?)
+    }
+
+    /// Fetches exactly one row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails, no rows are found, or the row
+    /// cannot be decoded.
+    pub async fn fetch_one<'e, E>(&mut self, executor: E) -> crate::Result<R>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        Ok(self.query().fetch_one(executor).await?)
+    }
+
+    /// Fetches at most one row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails or the row cannot be decoded.
+    pub async fn fetch_optional<'e, E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        Ok(self.query().fetch_optional(executor).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_pg_query_skips_casts_literals_and_comments() {
+        let (sql, names) = build_pg_query(
+            "SELECT ':a', $$ :b $$, $fn$ :c $fn$, \":d\" -- :e\n\
+             FROM t WHERE x = :x::int /* :f */ AND z = :x",
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT ':a', $$ :b $$, $fn$ :c $fn$, \":d\" -- :e\n\
+             FROM t WHERE x = $1::int /* :f */ AND z = $1"
+        );
+        assert_eq!(names, vec![":x"]);
+        assert!(matches!(
+            build_pg_query("SELECT $body$ :x"),
+            Err(crate::Error::InvalidTemplate(_))
+        ));
+    }
+}
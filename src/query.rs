@@ -1,12 +1,351 @@
-use crate::builder::build_query;
-use regex::Regex;
-use sqlx::mysql::MySqlArguments;
+use crate::allowlist;
+use crate::budget::{self, Budget};
+use crate::builder::{placeholder_order_with_style, PlaceholderStyle};
+use crate::checksum::{checksum_rows, RowChecksum};
+use crate::concurrency;
+use crate::conditional::{AnyBind, NamedBinds};
+use crate::deadline;
+use crate::execute_result::{show_warnings, warning_count, ExecuteResult};
+use crate::explain::{estimated_rows, explain_json_sql, explain_sql, CostGuard, ExplainPlan};
+use crate::hint::{
+    add_collation, add_index_hint, add_max_execution_time, add_priority, IndexHint, Priority,
+};
+use crate::invalidation;
+use crate::json::write_ndjson;
+use crate::kind::StatementKind;
+use crate::limits;
+use crate::list::expand_lists;
+use crate::params::{NamedParams, StructBinder};
+use sqlx::mysql::{MySqlArguments, MySqlColumn, MySqlConnection, MySqlRow, MySqlStatement};
 use sqlx::query::Query;
-use sqlx::{mysql::MySqlQueryResult, Executor, MySql};
+use sqlx::{Arguments, Either, Execute, Executor, MySql, Row, Statement};
+use crate::query_log;
+use crate::registry::DeprecatedUse;
+use crate::shutdown;
+use crate::tags::Tags;
+use crate::template_cache;
+use crate::tenancy;
+use crate::validators::{BindChecks, Validator};
+use smallvec::SmallVec;
+use std::borrow::{Borrow, Cow};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
 
 /// Type alias for SQLx Query with MySQL arguments
 pub type Q<'q> = Query<'q, MySql, MySqlArguments>;
 
+/// Binder of a [`PreparedQuery`] built by [`PreparedQuery::with_params`]
+pub type ParamsBinder = Box<dyn for<'q> FnMut(Q<'q>, &str) -> Q<'q> + Send + Sync>;
+
+/// Binds every placeholder in `order` onto the queries `query` builds for `sql`, such as
+/// [`sqlx::query_with`] or [`sqlx::query_as_with`].
+///
+/// Each binder call must bind at least one more value; a call that binds nothing means
+/// the binder has no arm for that placeholder, and one that binds several is a list
+/// placeholder expanded by [`Bound::expand`]. With `checks.repeats`, every occurrence
+/// of a repeated placeholder must also receive the same value, as compared by
+/// [`RepeatCheck`]. The validators in `checks` then run on the bound values, and a
+/// query prepared from a deprecated registry entry counts the execution.
+///
+/// # Errors
+///
+/// Returns [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) for a
+/// placeholder the binder left unbound, or
+/// [`Error::UnboundPlaceholders`](crate::Error::UnboundPlaceholders) listing every one
+/// if it left several,
+/// [`Error::InconsistentRepeat`](crate::Error::InconsistentRepeat) for a repeated
+/// placeholder bound to different values,
+/// [`Error::InvalidParam`](crate::Error::InvalidParam) for a value rejected by a
+/// validator, or an error if a value cannot be encoded.
+pub(crate) fn bind_with<'s, A, F>(
+    sql: &'s str,
+    order: &[String],
+    binder: &mut F,
+    checks: &BindChecks,
+    query: impl Fn(&'s str, MySqlArguments) -> A,
+) -> crate::Result<Bound>
+where
+    A: Execute<'s, MySql>,
+    F: FnMut(A, &str) -> A,
+{
+    let mut bind = |arguments, key: &str| -> crate::Result<MySqlArguments> {
+        let mut q = binder(query(sql, arguments), key);
+        let arguments = q.take_arguments().map_err(sqlx::Error::Encode)?;
+        Ok(arguments.unwrap_or_default())
+    };
+    let mut repeats = RepeatCheck::new(order, checks);
+    let mut unbound = Vec::new();
+    let mut starts = Vec::new();
+    let mut counts = Counts::new();
+    let mut arguments = MySqlArguments::default();
+    for key in order {
+        let bound = arguments.len();
+        if checks.encrypts(key) {
+            // Binds the plaintext on its own, so repeats are compared before encryption.
+            let plain = bind(MySqlArguments::default(), key)?;
+            if plain.len() == 0 {
+                unbound.push(key);
+            } else {
+                repeats.compare(key, &plain)?;
+                checks.encrypt(key, &plain, &mut arguments)?;
+            }
+        } else {
+            if repeats.checks(key) {
+                repeats.compare(key, &bind(MySqlArguments::default(), key)?)?;
+            }
+            arguments = bind(arguments, key)?;
+            if arguments.len() == bound {
+                unbound.push(key);
+            }
+        }
+        if checks.validates() {
+            starts.push(bound);
+        }
+        counts.push(arguments.len() - bound);
+    }
+    check_unbound(unbound)?;
+    checks.validate(order, &starts, &arguments)?;
+    limits::check_placeholders(arguments.len())?;
+    if let Some(deprecation) = &checks.deprecation {
+        deprecation.record();
+    }
+    Ok(Bound::new(arguments, counts))
+}
+
+/// Binds every placeholder in `order` onto a query for `sql`, with [`bind_with`].
+pub(crate) fn bind_arguments<F>(
+    sql: &str,
+    order: &[String],
+    binder: &mut F,
+    checks: &BindChecks,
+) -> crate::Result<Bound>
+where
+    F: for<'a> FnMut(Q<'a>, &str) -> Q<'a>,
+{
+    bind_with(sql, order, binder, checks, sqlx::query_with)
+}
+
+/// Fails if the binder left any placeholders in `unbound` without a value, naming
+/// each one once.
+pub(crate) fn check_unbound(unbound: Vec<&String>) -> crate::Result<()> {
+    let mut names: Vec<String> = Vec::new();
+    for key in unbound {
+        if !names.contains(key) {
+            names.push(key.clone());
+        }
+    }
+    match names.len() {
+        0 => Ok(()),
+        1 => Err(crate::Error::UnboundPlaceholder(names.remove(0))),
+        _ => Err(crate::Error::UnboundPlaceholders(names)),
+    }
+}
+
+/// Number of values bound per placeholder, kept inline for typical queries so binding
+/// does not allocate for them.
+pub(crate) type Counts = SmallVec<[usize; 8]>;
+
+/// Arguments bound for a query, with the number of values bound per placeholder.
+#[derive(Debug)]
+pub(crate) struct Bound {
+    pub(crate) arguments: MySqlArguments,
+    counts: Counts,
+}
+
+impl Bound {
+    pub(crate) fn new(arguments: MySqlArguments, counts: Counts) -> Self {
+        Self { arguments, counts }
+    }
+
+    /// Returns `sql` with the `?` of every list placeholder expanded to one `?` per
+    /// bound value.
+    pub(crate) fn expand<'s>(&self, sql: &'s str) -> Cow<'s, str> {
+        expand_lists(sql, &self.counts)
+    }
+
+    /// Returns `sql` as it is sent, [expanded](Self::expand), once the current
+    /// deadline is checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded) if the
+    /// deadline has passed.
+    pub(crate) fn sql<'s>(&self, sql: &'s str) -> crate::Result<Cow<'s, str>> {
+        deadline::check()?;
+        Ok(self.expand(sql))
+    }
+}
+
+/// Compares the values bound for each occurrence of a repeated placeholder, in queries
+/// with [`strict_repeats`](PreparedQuery::strict_repeats).
+///
+/// `MySqlArguments` keeps its encoded buffer private, so each occurrence is bound once
+/// more on its own, into empty arguments, and their `Debug` output, which covers the
+/// encoded bytes, the type and whether the value is `NULL`, is compared with the first
+/// occurrence's. Only the occurrence's own value is formatted, so the check stays linear
+/// in the size of the arguments. Placeholders marked as long data are never compared.
+pub(crate) struct RepeatCheck<'o> {
+    repeated: HashSet<&'o str>,
+    seen: HashMap<&'o str, String>,
+}
+
+impl<'o> RepeatCheck<'o> {
+    pub(crate) fn new(order: &'o [String], checks: &BindChecks) -> Self {
+        let mut repeated = HashSet::new();
+        if checks.repeats {
+            let mut keys = HashSet::new();
+            for key in order {
+                if !keys.insert(key.as_str()) && !checks.is_long_data(key) {
+                    repeated.insert(key.as_str());
+                }
+            }
+        }
+        Self {
+            repeated,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the values bound for `key` are compared.
+    pub(crate) fn checks(&self, key: &str) -> bool {
+        self.repeated.contains(key)
+    }
+
+    /// Records `own`, the values bound for one occurrence of `key` on their own,
+    /// failing if they differ from an earlier occurrence's.
+    pub(crate) fn compare(&mut self, key: &'o str, own: &MySqlArguments) -> crate::Result<()> {
+        if !self.checks(key) {
+            return Ok(());
+        }
+        let value = format!("{own:?}");
+        match self.seen.get(key) {
+            Some(first) if *first != value => Err(crate::Error::InconsistentRepeat(key.to_owned())),
+            Some(_) => Ok(()),
+            None => {
+                self.seen.insert(key, value);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Runs the query `query` builds from `sql` and `arguments` with `run`, within the
+/// shutdown gate, concurrency limits, tag budgets, the current deadline and the query
+/// log, which counts the rows of the result with `rows`.
+pub(crate) async fn run_query<'q, A, T, Fut, Run, N>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    query: impl FnOnce(&'q str, MySqlArguments) -> A,
+    run: Run,
+    rows: N,
+) -> crate::Result<T>
+where
+    Run: FnOnce(A, Budget) -> Fut,
+    Fut: Future<Output = crate::Result<T>>,
+    N: FnOnce(&T) -> u64,
+{
+    let params = query_log::digest(&arguments);
+    let q = query(sql, arguments);
+    let _in_flight = shutdown::enter()?;
+    let _permit = concurrency::acquire(sql).await?;
+    let budget = budget::current(tags).within(timeout);
+    let run = deadline::enforce(budget::run(budget, tags, run(q, budget)));
+    query_log::observe(sql, params, tags, run, rows)
+        .instrument(tags.span())
+        .await
+}
+
+/// Executes `sql` with `arguments` through [`run_query`], then runs the invalidation
+/// hooks of the tables it wrote to.
+pub(crate) async fn execute_arguments<'q, 'e, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    query: impl FnOnce(&'q str, MySqlArguments) -> Q<'q>,
+    executor: E,
+) -> crate::Result<ExecuteResult>
+where
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: Q<'q>, _| async move { Ok(q.execute(executor).await?) };
+    let result = run_query(sql, tags, timeout, arguments, query, run, |result| {
+        result.rows_affected()
+    })
+    .await?;
+    invalidation::notify(sql);
+    Ok(result.into())
+}
+
+/// Binds every placeholder in `order` with `binder` and executes `sql`, like
+/// [`PreparedQuery::execute`] without tags or a timeout.
+///
+/// For helpers that run one template over many parameter sets, each with its own
+/// binder.
+pub(crate) async fn execute_bound<'e, F, E>(
+    sql: &str,
+    order: &[String],
+    binder: &mut F,
+    executor: E,
+) -> crate::Result<ExecuteResult>
+where
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+    E: Executor<'e, Database = MySql>,
+{
+    let bound = bind_arguments(sql, order, binder, &BindChecks::default())?;
+    let sql = bound.sql(sql)?;
+    let tags = Tags::new();
+    execute_arguments(&sql, &tags, None, bound.arguments, sqlx::query_with, executor).await
+}
+
+/// Fetches all rows of `sql` with `arguments` as untyped `MySqlRow`s, through
+/// [`run_query`].
+async fn fetch_rows_arguments<'q, 'e, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    query: impl FnOnce(&'q str, MySqlArguments) -> Q<'q>,
+    executor: E,
+) -> crate::Result<Vec<MySqlRow>>
+where
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: Q<'q>, budget: Budget| async move {
+        match budget.row_limit() {
+            Some(_) => budget::collect_rows(budget, tags, q.fetch(executor)).await,
+            None => Ok(q.fetch_all(executor).await?),
+        }
+    };
+    run_query(sql, tags, timeout, arguments, query, run, |rows| rows.len() as u64).await
+}
+
+/// Like [`fetch_rows_arguments`], for at most one row.
+async fn fetch_optional_row_arguments<'q, 'e, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    query: impl FnOnce(&'q str, MySqlArguments) -> Q<'q>,
+    executor: E,
+) -> crate::Result<Option<MySqlRow>>
+where
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: Q<'q>, _| async move { Ok(q.fetch_optional(executor).await?) };
+    run_query(sql, tags, timeout, arguments, query, run, |row| {
+        u64::from(row.is_some())
+    })
+    .await
+}
+
 /// A prepared query builder that supports named placeholders.
 ///
 /// `PreparedQuery` allows you to use named placeholders (`:name`) in your SQL templates
@@ -39,7 +378,7 @@ pub type Q<'q> = Query<'q, MySql, MySqlArguments>;
 /// )?;
 ///
 /// let result = query.execute(&pool).await?;
-/// println!("Inserted {} rows", result.rows_affected());
+/// println!("Inserted {} rows", result.rows_matched());
 /// # Ok(())
 /// # }
 /// ```
@@ -69,9 +408,27 @@ pub type Q<'q> = Query<'q, MySql, MySqlArguments>;
 /// # }
 /// ```
 pub struct PreparedQuery<F> {
-    sql: String,
-    order: Vec<String>,
+    template: Arc<str>,
+    sql: Arc<str>,
+    order: Arc<[String]>,
     binder: F,
+    tags: Tags,
+    checks: BindChecks,
+    timeout: Option<Duration>,
+    /// Expanded SQL borrowed by the query returned from `into_sqlx_query`.
+    expanded_sql: String,
+}
+
+/// Shows the converted SQL and placeholder order, never bound values.
+impl<F> fmt::Debug for PreparedQuery<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedQuery")
+            .field("sql", &self.sql)
+            .field("placeholders", &self.order)
+            .field("tags", &self.tags)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<F> PreparedQuery<F>
@@ -90,7 +447,11 @@ where
     ///
     /// # Errors
     ///
-    /// Returns an error if the SQL template cannot be parsed (invalid regex pattern).
+    /// Returns an error if the SQL template cannot be parsed,
+    /// [`Error::NotAllowed`](crate::Error::NotAllowed) if an installed
+    /// [`allowlist`](crate::allowlist) rejects it, or
+    /// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if an installed
+    /// [`tenancy`](crate::tenancy) policy does.
     ///
     /// # Examples
     ///
@@ -110,106 +471,1877 @@ where
     where
         T: Into<String>,
     {
-        let template = template.into();
-        let order = Regex::new(r":[a-zA-Z0-9_]+")?
-            .find_iter(&template)
-            .map(|m| m.as_str().to_owned())
-            .collect();
-        let sql = build_query(&template)?;
-        Ok(Self { sql, order, binder })
+        Self::new_with_style(template, PlaceholderStyle::Colon, binder)
     }
 
-    /// Executes the prepared query using the provided executor.
+    /// Creates a new `PreparedQuery` from a template whose placeholders are written in
+    /// `style`, such as `@name` in SQL migrated from .NET.
     ///
-    /// This method constructs a fresh `Query` on each call, avoiding self-referential
-    /// lifetime issues. It works with any SQLx `Executor` implementation, including
-    /// `MySqlPool`, `Transaction`, and others.
+    /// The binder still receives each placeholder as `:name`.
     ///
     /// # Arguments
     ///
-    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
-    ///
-    /// # Returns
-    ///
-    /// Returns the MySQL query result containing information about affected rows,
-    /// last insert ID, etc.
+    /// * `template` - SQL query template with named placeholders in `style`
+    /// * `style` - How placeholders are written in `template`
+    /// * `binder` - Function that binds values to placeholders based on their names
     ///
     /// # Errors
     ///
-    /// Returns an error if the database query fails.
+    /// Returns an error if the SQL template cannot be parsed,
+    /// [`Error::NotAllowed`](crate::Error::NotAllowed) if an installed
+    /// [`allowlist`](crate::allowlist) rejects it, or
+    /// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if an installed
+    /// [`tenancy`](crate::tenancy) policy does.
     ///
     /// # Examples
     ///
-    /// ```rust,no_run
-    /// use sqlx::MySqlPool;
-    /// use sqlx_named_bind::PreparedQuery;
+    /// ```rust
+    /// use sqlx_named_bind::{builder::PlaceholderStyle, PreparedQuery};
     ///
-    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
-    /// let mut query = PreparedQuery::new(
-    ///     "DELETE FROM users WHERE id = :id",
+    /// let query = PreparedQuery::new_with_style(
+    ///     "UPDATE users SET name = @name WHERE id = @id",
+    ///     PlaceholderStyle::At,
     ///     |q, key| match key {
     ///         ":id" => q.bind(42),
+    ///         ":name" => q.bind("Jane"),
     ///         _ => q,
     ///     }
     /// )?;
-    ///
-    /// let result = query.execute(&pool).await?;
-    /// println!("Deleted {} rows", result.rows_affected());
-    /// # Ok(())
-    /// # }
+    /// # Ok::<(), sqlx_named_bind::Error>(())
     /// ```
-    pub async fn execute<'e, E>(&mut self, executor: E) -> crate::Result<MySqlQueryResult>
+    pub fn new_with_style<T>(
+        template: T,
+        style: PlaceholderStyle,
+        binder: F,
+    ) -> crate::Result<Self>
     where
-        E: Executor<'e, Database = MySql>,
+        T: Into<String>,
     {
-        let &mut PreparedQuery {
-            ref sql,
-            ref order,
-            ref mut binder,
-        } = self;
+        Self::parse(template.into(), style, binder, true)
+    }
+
+    /// Like [`new`](Self::new), without checking the installed allow-list.
+    ///
+    /// For statements the crate sends on its own behalf, such as `GET_LOCK`, and for
+    /// templates checked when the wrapper running them was created.
+    pub(crate) fn new_unlisted<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        Self::parse(template.into(), PlaceholderStyle::Colon, binder, false)
+    }
 
-        let mut q = sqlx::query::<MySql>(sql);
-        for key in order.iter() {
-            q = binder(q, key);
+    fn parse(
+        template: String,
+        style: PlaceholderStyle,
+        binder: F,
+        check_allowlist: bool,
+    ) -> crate::Result<Self> {
+        let parsed = template_cache::parse(&template, style)?;
+        let (mut sql, mut order) = (parsed.sql, parsed.order);
+        if check_allowlist {
+            allowlist::check(&sql)?;
         }
-        Ok(q.execute(executor).await?)
+        tenancy::enforce(&mut sql, &mut order)?;
+        Ok(Self {
+            template: parsed.template,
+            sql,
+            order,
+            binder,
+            tags: Tags::new(),
+            checks: BindChecks::default(),
+            timeout: None,
+            expanded_sql: String::new(),
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Attaches the tag `key=value` to the query, replacing any previous value.
+    ///
+    /// Executions run inside a `named_query` tracing span listing the tags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// let query = PreparedQuery::new("DELETE FROM sessions WHERE expires_at < NOW()", |q, _| q)?
+    ///     .tag("team", "auth")
+    ///     .tag("criticality", "low");
+    /// assert_eq!(query.tags().get("team"), Some("auth"));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.insert(key, value);
+        self
+    }
 
-    #[test]
-    fn test_prepared_query_new() {
-        let result = PreparedQuery::new(
-            "SELECT * FROM users WHERE id = :id",
-            |q, _| q,
-        );
-        assert!(result.is_ok());
+    /// Attaches every tag of `tags`, replacing previous values of the same keys.
+    pub fn with_tags(mut self, tags: &Tags) -> Self {
+        self.tags.extend(tags);
+        self
     }
 
-    #[test]
-    fn test_prepared_query_placeholder_order() {
-        let query = PreparedQuery::new(
-            "SELECT * FROM users WHERE id = :id AND name = :name",
-            |q, _| q,
-        ).unwrap();
+    /// Counts every execution as a use of a deprecated registry entry.
+    pub(crate) fn deprecation(mut self, deprecation: Option<DeprecatedUse>) -> Self {
+        self.checks.deprecation = deprecation;
+        self
+    }
 
-        assert_eq!(query.order, vec![":id", ":name"]);
-        assert_eq!(query.sql, "SELECT * FROM users WHERE id = ? AND name = ?");
+    /// Returns the tags attached to the query.
+    pub fn tags(&self) -> &Tags {
+        &self.tags
     }
 
-    #[test]
-    fn test_prepared_query_repeated_placeholders() {
-        let query = PreparedQuery::new(
-            "SELECT * FROM users WHERE id = :id OR user_id = :id",
-            |q, _| q,
-        ).unwrap();
+    /// Limits how long each execution of the query may run.
+    ///
+    /// An execution still running after `timeout` is cancelled and fails with
+    /// [`Error::BudgetExceeded`](crate::Error::BudgetExceeded); a stricter
+    /// [`budget`](crate::budget) of the query's tags still applies. A `SELECT` also gets
+    /// a `MAX_EXECUTION_TIME` optimizer hint, so the server stops working on it too;
+    /// only the first call adds the hint.
+    /// Queries prepared from a [`registry`](crate::registry) entry get the `timeout` of
+    /// its front matter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// let query = PreparedQuery::new("DELETE FROM sessions WHERE expires_at < NOW()", |q, _| q)?
+    ///     .timeout(Duration::from_secs(5));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        if self.timeout.is_none() {
+            if let Some(sql) = add_max_execution_time(&self.sql, timeout) {
+                self.sql = sql.into();
+            }
+        }
+        self.timeout = Some(timeout);
+        self
+    }
 
-        // Both occurrences should be captured
-        assert_eq!(query.order, vec![":id", ":id"]);
-        assert_eq!(query.sql, "SELECT * FROM users WHERE id = ? OR user_id = ?");
+    /// Returns the template the query was created from, as written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "SELECT name FROM users WHERE id = :id OR parent_id = :id",
+    ///     |q, _| q,
+    /// )?;
+    /// assert_eq!(query.template(), "SELECT name FROM users WHERE id = :id OR parent_id = :id");
+    /// assert_eq!(query.sql(), "SELECT name FROM users WHERE id = ? OR parent_id = ?");
+    /// assert_eq!(query.placeholders(), [":id", ":id"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the SQL sent to the server, with positional placeholders.
+    ///
+    /// Hints and filters added since the query was created are included. List
+    /// placeholders are expanded when the query runs, so each is a single `?` here.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the placeholders in the order they are bound, as `:name`, once per
+    /// occurrence.
+    pub fn placeholders(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Checks that `provided`, the names the binder handles, are exactly the
+    /// placeholders of the template, with or without the leading `:`.
+    ///
+    /// Binders silently skip keys they do not match, so a typo in a template or binder
+    /// otherwise only shows when the query runs. Call this for every query at startup
+    /// to find such typos early.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PlaceholderMismatch`](crate::Error::PlaceholderMismatch) listing
+    /// the placeholders missing from `provided` and the names in `provided` the
+    /// template does not have.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{Error, PreparedQuery};
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "UPDATE users SET name = :name WHERE id = :id",
+    ///     |q, _| q,
+    /// )?;
+    /// query.validate_bindings(&[":name", "id"])?;
+    /// assert!(matches!(
+    ///     query.validate_bindings(&[":name", ":user_id"]),
+    ///     Err(Error::PlaceholderMismatch { missing, extra })
+    ///         if missing == [":id"] && extra == [":user_id"]
+    /// ));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn validate_bindings(&self, provided: &[&str]) -> crate::Result<()> {
+        check_names(&self.order, provided)
+    }
+
+    /// Returns what kind of statement the query is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{kind::StatementKind, PreparedQuery};
+    ///
+    /// let query = PreparedQuery::new("DELETE FROM sessions WHERE id = :id", |q, _| q)?;
+    /// assert_eq!(query.kind(), StatementKind::Delete);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn kind(&self) -> StatementKind {
+        StatementKind::of(&self.sql)
+    }
+
+    /// Requires every occurrence of a repeated placeholder to be bound to the same value.
+    ///
+    /// The binder is called once per occurrence, so a stateful binder can bind `:id`
+    /// twice with different values, which is usually a bug. With this set, executing
+    /// such a query fails with
+    /// [`Error::InconsistentRepeat`](crate::Error::InconsistentRepeat) before anything
+    /// is sent. The binder is then called twice for each occurrence of a repeated
+    /// placeholder, once more to bind its value on its own for the comparison, so enable
+    /// it in tests or while tracking down such a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx::Execute;
+    /// use sqlx_named_bind::{Error, PreparedQuery};
+    ///
+    /// let mut ids = 1..;
+    /// let mut query = PreparedQuery::new(
+    ///     "INSERT INTO pairs (a, b) VALUES (:next, :next)",
+    ///     move |q, _| q.bind(ids.next()),
+    /// )?
+    /// .strict_repeats();
+    /// assert!(matches!(query.into_sqlx_query(), Err(Error::InconsistentRepeat(_))));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn strict_repeats(mut self) -> Self {
+        self.checks.repeats = true;
+        self
+    }
+
+    /// Attaches `validator` to the placeholder `name`, with or without the leading `:`.
+    ///
+    /// Before each execution, the value the binder bound for the placeholder is checked
+    /// and a rejected value fails with
+    /// [`Error::InvalidParam`](crate::Error::InvalidParam), without contacting the
+    /// server. Several validators may be attached to one placeholder; they run in the
+    /// order they were attached.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{validators::Validator, PreparedQuery};
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "DELETE FROM events WHERE created_at < NOW() LIMIT :limit",
+    ///     |q, key| match key {
+    ///         ":limit" => q.bind(1_000_i64),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .validate("limit", Validator::range(1, 10_000))?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn validate(mut self, name: &str, validator: Validator) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.validators.push((key, validator));
+        Ok(self)
+    }
+
+    /// Encrypts the values bound for the placeholder `name`, with or without the
+    /// leading `:`, with the installed [`FieldCipher`](crate::cipher::FieldCipher).
+    ///
+    /// The binder binds the plaintext as usual; it is encrypted before the query is
+    /// sent. Executing without an installed cipher fails with
+    /// [`Error::Cipher`](crate::Error::Cipher).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// PreparedQuery::new(
+    ///     "UPDATE patients SET ssn = :ssn WHERE id = :id",
+    ///     |q, key| match key {
+    ///         ":ssn" => q.bind("078-05-1120"),
+    ///         ":id" => q.bind(42_i64),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .encrypt("ssn")?
+    /// .execute(&pool)
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encrypt(mut self, name: &str) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.encrypted.push(key);
+        Ok(self)
+    }
+
+    /// Marks the placeholder `name`, with or without the leading `:`, as carrying a
+    /// large text or binary payload, such as a document or an image.
+    ///
+    /// MySQL can receive such values in pieces with `COM_STMT_SEND_LONG_DATA` instead of
+    /// inline in the execute packet. sqlx does not expose that command yet, so marked
+    /// values are still sent inline over the binary protocol. Marking them keeps them
+    /// out of the checks that copy bound values, such as the
+    /// [`strict_repeats`](Self::strict_repeats) comparison of repeated placeholders.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example(pdf: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// PreparedQuery::new(
+    ///     "INSERT INTO attachments (name, body) VALUES (:name, :body)",
+    ///     move |q, key| match key {
+    ///         ":name" => q.bind("report.pdf"),
+    ///         ":body" => q.bind(pdf.clone()),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .long_data("body")?
+    /// .execute(&pool)
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn long_data(mut self, name: &str) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.long_data.push(key);
+        Ok(self)
+    }
+
+    /// Attaches an index hint to every reference of `table` in the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Name of the table to hint, as written in the template
+    /// * `hint` - The `USE`/`FORCE`/`IGNORE INDEX` hint to attach
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `table` is not a plain identifier or is not referenced
+    /// after `FROM`, `JOIN` or `UPDATE` in the template.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{hint::IndexHint, PreparedQuery};
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "UPDATE users SET name = :name WHERE email = :email",
+    ///     |q, key| match key {
+    ///         ":name" => q.bind("Jane"),
+    ///         ":email" => q.bind("jane@example.com"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .index_hint("users", IndexHint::force(["idx_users_email"])?)?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn index_hint(mut self, table: &str, hint: IndexHint) -> crate::Result<Self> {
+        self.sql = add_index_hint(&self.sql, table, &hint)?.into();
+        Ok(self)
+    }
+
+    /// Adds a `LOW_PRIORITY`, `HIGH_PRIORITY` or `DELAYED` modifier to the statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The modifier to insert after the statement's leading keyword
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement type does not accept the modifier or already
+    /// has one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{hint::Priority, PreparedQuery};
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "DELETE FROM audit_log WHERE created_at < :cutoff",
+    ///     |q, key| match key {
+    ///         ":cutoff" => q.bind("2024-01-01"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .priority(Priority::Low)?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn priority(mut self, priority: Priority) -> crate::Result<Self> {
+        self.sql = add_priority(&self.sql, priority)?.into();
+        Ok(self)
+    }
+
+    /// Appends a `COLLATE` clause to every occurrence of `placeholder`.
+    ///
+    /// The bound value is compared using `collation` instead of the column's or
+    /// connection's collation, e.g. `utf8mb4_bin` for a case-sensitive lookup. For
+    /// session-wide settings, see
+    /// [`with_collation`](crate::connection::with_collation).
+    ///
+    /// # Arguments
+    ///
+    /// * `placeholder` - Placeholder whose values to collate (e.g. `:email`)
+    /// * `collation` - Collation name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `placeholder` does not appear in the template or
+    /// `collation` is not a plain identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// let query = PreparedQuery::new(
+    ///     "UPDATE users SET verified = 1 WHERE email = :email",
+    ///     |q, key| match key {
+    ///         ":email" => q.bind("Jane@Example.com"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .collate(":email", "utf8mb4_bin")?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn collate(mut self, placeholder: &str, collation: &str) -> crate::Result<Self> {
+        self.sql = add_collation(&self.sql, &self.order, placeholder, collation)?.into();
+        Ok(self)
+    }
+
+    /// Executes the prepared query using the provided executor.
+    ///
+    /// This method constructs a fresh `Query` on each call, avoiding self-referential
+    /// lifetime issues. It works with any SQLx `Executor` implementation, including
+    /// `MySqlPool`, `Transaction`, and others.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Returns
+    ///
+    /// Returns an [`ExecuteResult`] with the rows matched and the generated
+    /// `AUTO_INCREMENT` value, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "DELETE FROM users WHERE id = :id",
+    ///     |q, key| match key {
+    ///         ":id" => q.bind(42),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let result = query.execute(&pool).await?;
+    /// println!("Deleted {} rows", result.rows_matched());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute<'e, E>(&mut self, executor: E) -> crate::Result<ExecuteResult>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = bound.sql(sql)?;
+        execute_arguments(&sql, tags, timeout, bound.arguments, sqlx::query_with, executor).await
+    }
+
+    /// Fetches all result rows as untyped `MySqlRow`s.
+    ///
+    /// Use this when the columns are only known at runtime, e.g. in admin tooling or a
+    /// generic export; columns are read with `sqlx::Row::try_get` by name or index. For
+    /// rows with a known shape, use [`PreparedQueryAs`](crate::PreparedQueryAs).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{Column, MySqlPool, Row};
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let rows = PreparedQuery::new("SELECT * FROM audit_log WHERE actor = :actor", |q, key| {
+    ///     match key {
+    ///         ":actor" => q.bind("admin"),
+    ///         _ => q,
+    ///     }
+    /// })?
+    /// .fetch_rows(&pool)
+    /// .await?;
+    /// for row in &rows {
+    ///     let names: Vec<&str> = row.columns().iter().map(|c| c.name()).collect();
+    ///     println!("{}", names.join(", "));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_rows<'e, E>(&mut self, executor: E) -> crate::Result<Vec<MySqlRow>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = bound.sql(sql)?;
+        let arguments = bound.arguments;
+        fetch_rows_arguments(&sql, tags, timeout, arguments, sqlx::query_with, executor).await
+    }
+
+    /// Fetches exactly one result row as an untyped `MySqlRow`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails or no row is found.
+    pub async fn fetch_row<'e, E>(&mut self, executor: E) -> crate::Result<MySqlRow>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        self.fetch_optional_row(executor)
+            .await?
+            .ok_or(crate::Error::Database(sqlx::Error::RowNotFound))
+    }
+
+    /// Fetches at most one result row as an untyped `MySqlRow`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn fetch_optional_row<'e, E>(
+        &mut self,
+        executor: E,
+    ) -> crate::Result<Option<MySqlRow>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = bound.sql(sql)?;
+        let arguments = bound.arguments;
+        fetch_optional_row_arguments(&sql, tags, timeout, arguments, sqlx::query_with, executor)
+            .await
+    }
+
+    /// Fetches all result rows, converting each with `f`.
+    ///
+    /// Like `sqlx`'s `try_map`: `f` decodes the row by hand, e.g. into an enum or a
+    /// composite key, without a `FromRow` implementation. The first error returned by `f`
+    /// is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails, or the first error returned by `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, Row};
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// enum Status {
+    ///     Open,
+    ///     Closed,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let tickets = PreparedQuery::new("SELECT id, closed FROM tickets", |q, _| q)?
+    ///     .fetch_all_map(&pool, |row| {
+    ///         let id: i64 = row.try_get("id")?;
+    ///         let closed: bool = row.try_get("closed")?;
+    ///         let status = if closed { Status::Closed } else { Status::Open };
+    ///         Ok((id, status))
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_all_map<'e, E, T, M>(
+        &mut self,
+        executor: E,
+        f: M,
+    ) -> crate::Result<Vec<T>>
+    where
+        E: Executor<'e, Database = MySql>,
+        M: FnMut(MySqlRow) -> crate::Result<T>,
+    {
+        self.fetch_rows(executor).await?.into_iter().map(f).collect()
+    }
+
+    /// Fetches exactly one row, converting it with `f`.
+    ///
+    /// See [`fetch_all_map`](Self::fetch_all_map).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails, if it does not return exactly one
+    /// row, or the error returned by `f`.
+    pub async fn fetch_one_map<'e, E, T, M>(&mut self, executor: E, f: M) -> crate::Result<T>
+    where
+        E: Executor<'e, Database = MySql>,
+        M: FnOnce(MySqlRow) -> crate::Result<T>,
+    {
+        f(self.fetch_row(executor).await?)
+    }
+
+    /// Fetches at most one row, converting it with `f`.
+    ///
+    /// See [`fetch_all_map`](Self::fetch_all_map).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails, or the error returned by `f`.
+    pub async fn fetch_optional_map<'e, E, T, M>(
+        &mut self,
+        executor: E,
+        f: M,
+    ) -> crate::Result<Option<T>>
+    where
+        E: Executor<'e, Database = MySql>,
+        M: FnOnce(MySqlRow) -> crate::Result<T>,
+    {
+        self.fetch_optional_row(executor).await?.map(f).transpose()
+    }
+
+    /// Executes the query with some placeholders bound from `overrides` instead of the
+    /// binder, for this call only.
+    ///
+    /// This runs a stored query for, say, a different tenant without rebuilding it: the
+    /// binder still binds every placeholder missing from `overrides`.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `overrides` - Values replacing the binder's, keyed by placeholder (e.g. `:tenant_id`)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if an
+    /// override names a placeholder the query does not have, or an error if the
+    /// database query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{conditional::NamedBinds, PreparedQuery};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "DELETE FROM sessions WHERE tenant_id = :tenant_id AND expires_at < :now",
+    ///     |q, key| match key {
+    ///         ":tenant_id" => q.bind(1_i64),
+    ///         ":now" => q.bind("2024-06-01 00:00:00"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let mut overrides = NamedBinds::new();
+    /// overrides.set(":tenant_id", 2_i64);
+    /// query.execute_with(&pool, &overrides).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_with<'e, E>(
+        &mut self,
+        executor: E,
+        overrides: &NamedBinds,
+    ) -> crate::Result<ExecuteResult>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        if let Some(name) = overrides.unknown_name(order) {
+            return Err(crate::Error::UnknownPlaceholder(name.to_owned()));
+        }
+        let mut binder = overrides.overriding(binder);
+        let bound = bind_arguments(sql, order, &mut binder, checks)?;
+        let sql = bound.sql(sql)?;
+        execute_arguments(&sql, tags, timeout, bound.arguments, sqlx::query_with, executor).await
+    }
+
+    /// Executes the query on `conn`, then reads its warning count and, if it raised
+    /// any, its warnings with `SHOW WARNINGS`, and attaches them to the result.
+    ///
+    /// MySQL reports truncated strings, out-of-range numbers and invalid dates outside
+    /// strict mode only as warnings, which [`execute`](Self::execute) drops. Warnings
+    /// are also logged through `tracing`, so silent conversions show up in logs and can
+    /// be asserted on in tests. Reading the count costs one more round trip, and
+    /// reading the warnings another one when there are any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement fails or its warnings cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut conn = pool.acquire().await?;
+    /// let result = PreparedQuery::new(
+    ///     "INSERT INTO users (name) VALUES (:name)",
+    ///     |q, _| q.bind("a name longer than the column"),
+    /// )?
+    /// .execute_with_warnings(&mut conn)
+    /// .await?;
+    /// for warning in result.warnings() {
+    ///     println!("{warning}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_with_warnings(
+        &mut self,
+        conn: &mut MySqlConnection,
+    ) -> crate::Result<ExecuteResult> {
+        let result = self.execute(&mut *conn).await?;
+        let count = warning_count(&mut *conn).await?;
+        let warnings = match count {
+            0 => Vec::new(),
+            _ => show_warnings(conn).await?,
+        };
+        for warning in &warnings {
+            tracing::warn!(sql = %self.sql, %warning, "statement raised a warning");
+        }
+        Ok(result.with_warnings(count, warnings))
+    }
+
+    /// Executes the query once per parameter set, on one connection, and returns the
+    /// total number of rows affected.
+    ///
+    /// The template is parsed once and every execution sends the same SQL, so the
+    /// connection's cached prepared statement is reused. Each parameter set binds its
+    /// placeholders as in [`execute_with`](Self::execute_with); placeholders it leaves
+    /// out are bound by the binder, which suits values shared by the whole batch.
+    ///
+    /// Execution stops at the first failure, leaving earlier statements applied. Pass a
+    /// transaction (`&mut *tx`) to make the batch all-or-nothing, or use
+    /// [`batch::execute_batch_chunked`](crate::batch::execute_batch_chunked) for very
+    /// large batches.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of [`execute_with`](Self::execute_with).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{conditional::NamedBinds, PreparedQuery};
+    ///
+    /// # async fn example(prices: Vec<(String, i64)>) -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let params: Vec<NamedBinds> = prices
+    ///     .into_iter()
+    ///     .map(|(sku, cents)| {
+    ///         let mut params = NamedBinds::new();
+    ///         params.set(":sku", sku).set(":cents", cents);
+    ///         params
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut tx = pool.begin().await?;
+    /// let updated = PreparedQuery::new(
+    ///     "UPDATE products SET price_cents = :cents, source = :source WHERE sku = :sku",
+    ///     |q, key| match key {
+    ///         ":source" => q.bind("nightly-import"),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .execute_many(&mut *tx, &params)
+    /// .await?;
+    /// tx.commit().await?;
+    /// println!("updated {updated} products");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_many<I>(
+        &mut self,
+        conn: &mut MySqlConnection,
+        param_sets: I,
+    ) -> crate::Result<u64>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<NamedBinds>,
+    {
+        let mut rows_affected = 0;
+        for params in param_sets {
+            let result = self.execute_with(&mut *conn, params.borrow()).await?;
+            rows_affected += result.rows_matched();
+        }
+        Ok(rows_affected)
+    }
+
+    /// Estimates how many rows the query will examine, without executing it.
+    ///
+    /// Runs `EXPLAIN` on the converted SQL with the current binds and returns the
+    /// optimizer's estimate, summed over every row of the plan. This is useful for
+    /// batch jobs that pick chunk sizes based on the expected scan size, or that want
+    /// to bail out before running an unexpectedly huge statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `EXPLAIN` statement fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "DELETE FROM events WHERE created_at < :cutoff",
+    ///     |q, key| match key {
+    ///         ":cutoff" => q.bind("2024-01-01"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// if query.estimate_rows(&pool).await? > 1_000_000 {
+    ///     println!("Too many rows, run this in chunks instead");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn estimate_rows<'e, E>(&mut self, executor: E) -> crate::Result<u64>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let explain = explain_sql(&bound.expand(sql));
+        let plan = sqlx::query_with(&explain, bound.arguments)
+            .fetch_all(executor)
+            .await?;
+        Ok(estimated_rows(&plan))
+    }
+
+    /// Returns the optimizer's plan for the query, without executing it.
+    ///
+    /// Runs `EXPLAIN FORMAT=JSON` on the converted SQL with the current binds and
+    /// parses the result into an [`ExplainPlan`].
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `EXPLAIN` statement fails or its output cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "UPDATE users SET name = :name WHERE email = :email",
+    ///     |q, key| match key {
+    ///         ":name" => q.bind("Jane"),
+    ///         ":email" => q.bind("jane@example.com"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let plan = query.explain_plan(&pool).await?;
+    /// assert!(plan.uses_index("idx_users_email"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain_plan<'e, E>(&mut self, executor: E) -> crate::Result<ExplainPlan>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let explain = explain_json_sql(&bound.expand(sql));
+        let row = sqlx::query_with(&explain, bound.arguments)
+            .fetch_one(executor)
+            .await?;
+        ExplainPlan::from_json(&row.try_get::<String, _>(0)?)
+    }
+
+    /// Runs `EXPLAIN FORMAT=JSON` for the query and checks the plan against `guard`.
+    ///
+    /// Call this before executing the query to refuse statements the optimizer expects
+    /// to be expensive. The plan is returned when it is within the limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `guard` - Limits the plan must stay within
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TooExpensive`](crate::Error::TooExpensive) if the plan exceeds a
+    /// limit, or an error if the `EXPLAIN` statement fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{explain::CostGuard, PreparedQuery};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let guard = CostGuard::new().max_rows(100_000).max_full_scans(0);
+    ///
+    /// let mut query = PreparedQuery::new(
+    ///     "DELETE FROM audit_log WHERE created_at < :before",
+    ///     |q, key| match key {
+    ///         ":before" => q.bind("2020-01-01"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// query.check_cost(&pool, &guard).await?;
+    /// query.execute(&pool).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn check_cost<'e, E>(
+        &mut self,
+        executor: E,
+        guard: &CostGuard,
+    ) -> crate::Result<ExplainPlan>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let plan = self.explain_plan(executor).await?;
+        guard.check(&plan)?;
+        Ok(plan)
+    }
+
+    /// Streams the query's result rows to `writer` as newline-delimited JSON.
+    ///
+    /// Each row is converted with [`row_to_json`](crate::json::row_to_json) and written
+    /// as one line as soon as it is received, so the full result set is never held in
+    /// memory. Wrap the writer in a `BufWriter` when writing to a file or socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `writer` - Destination for the JSON lines
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails, a column cannot be converted, or writing fails.
+    /// Rows written before the error stay in the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    /// use std::{fs::File, io::BufWriter};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "SELECT * FROM orders WHERE created_at >= :since",
+    ///     |q, key| match key {
+    ///         ":since" => q.bind("2024-01-01"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let file = BufWriter::new(File::create("orders.ndjson")?);
+    /// let rows = query.export_ndjson(&pool, file).await?;
+    /// println!("Exported {} orders", rows);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn export_ndjson<'e, E, W>(&mut self, executor: E, writer: W) -> crate::Result<u64>
+    where
+        E: Executor<'e, Database = MySql>,
+        W: Write,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = bound.expand(sql);
+        write_ndjson(sqlx::query_with(&sql, bound.arguments).fetch(executor), writer).await
+    }
+
+    /// Computes a deterministic checksum over the query's result rows.
+    ///
+    /// Rows are streamed and hashed as they arrive. See [`RowChecksum`] for the
+    /// encoding; add a total `ORDER BY` so the checksum does not depend on row order.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a column cannot be converted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let primary = MySqlPool::connect("mysql://primary/test").await?;
+    /// # let replica = MySqlPool::connect("mysql://replica/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "SELECT * FROM orders WHERE customer_id = :customer_id ORDER BY id",
+    ///     |q, key| match key {
+    ///         ":customer_id" => q.bind(42),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// if query.checksum(&primary).await? != query.checksum(&replica).await? {
+    ///     println!("Replica has drifted");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn checksum<'e, E>(&mut self, executor: E) -> crate::Result<RowChecksum>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQuery {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = bound.expand(sql);
+        checksum_rows(sqlx::query_with(&sql, bound.arguments).fetch(executor)).await
+    }
+
+    /// Prepares the statement on `conn` once and returns a handle that executes it
+    /// there repeatedly, exposing its parameter and column metadata.
+    ///
+    /// sqlx keeps prepared statements in a per-connection cache keyed by their SQL, so
+    /// executions through the handle reuse the server-side statement as long as the
+    /// cache does not evict it. When a [list placeholder](crate::list) is bound to a
+    /// different number of values than before, the expanded statement is prepared
+    /// again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server cannot prepare the statement.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{Column, MySqlPool};
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut conn = pool.acquire().await?;
+    /// let mut id = 0_i64;
+    /// let mut query = PreparedQuery::new(
+    ///     "SELECT id, name FROM users WHERE id = :id",
+    ///     |q, _| q.bind(id),
+    /// )?;
+    /// let mut statement = query.prepare(&mut conn).await?;
+    /// assert_eq!(statement.parameters(), 1);
+    /// let columns: Vec<&str> = statement.columns().iter().map(|c| c.name()).collect();
+    /// for _ in 0..10 {
+    ///     let rows = statement.fetch_rows().await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn prepare<'c>(
+        &'c mut self,
+        conn: &'c mut MySqlConnection,
+    ) -> crate::Result<PreparedStatement<'c, F>> {
+        let statement = prepare_statement(conn, &self.sql).await?;
+        Ok(PreparedStatement {
+            query: self,
+            conn,
+            statement,
+        })
+    }
+
+    /// Binds the query's placeholders and returns the underlying sqlx [`Query`] instead
+    /// of executing it, for sqlx APIs this crate does not wrap.
+    ///
+    /// Validators, encryption and list expansion apply as for any execution. The
+    /// returned query bypasses everything that happens around an execution:
+    /// [`shutdown`](crate::shutdown), budgets, the query's timeout, concurrency limits,
+    /// the [`query_log`](crate::query_log) and [`invalidation`](crate::invalidation)
+    /// hooks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQuery::new(
+    ///     "DELETE FROM sessions WHERE user_id = :id",
+    ///     |q, _| q.bind(42_i64),
+    /// )?;
+    /// let result = query.into_sqlx_query()?.persistent(false).execute(&pool).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_sqlx_query(&mut self) -> crate::Result<Query<'_, MySql, MySqlArguments>> {
+        let &mut PreparedQuery {
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref checks,
+            ref mut expanded_sql,
+            ..
+        } = self;
+
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let sql = match bound.expand(sql) {
+            Cow::Borrowed(sql) => sql,
+            Cow::Owned(expanded) => {
+                *expanded_sql = expanded;
+                expanded_sql.as_str()
+            }
+        };
+        Ok(sqlx::query_with(sql, bound.arguments))
+    }
+}
+
+/// Prepares `sql` on `conn`, detached from the borrow of `sql`.
+async fn prepare_statement(
+    conn: &mut MySqlConnection,
+    sql: &str,
+) -> crate::Result<MySqlStatement<'static>> {
+    let statement = conn.prepare(sql).await?;
+    Ok(Statement::to_owned(&statement))
+}
+
+/// A [`PreparedQuery`] prepared on one connection, created with
+/// [`PreparedQuery::prepare`].
+///
+/// Executions bind the query's placeholders with its binder, run the server-side
+/// statement and go through the same budgets, query log and hooks as the query's own
+/// methods, including the client-side enforcement of the current [deadline].
+pub struct PreparedStatement<'c, F> {
+    query: &'c mut PreparedQuery<F>,
+    conn: &'c mut MySqlConnection,
+    statement: MySqlStatement<'static>,
+}
+
+impl<F> PreparedStatement<'_, F>
+where
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+{
+    /// Returns the prepared SQL, with positional placeholders.
+    pub fn sql(&self) -> &str {
+        self.statement.sql()
+    }
+
+    /// Returns the number of parameters the server expects.
+    pub fn parameters(&self) -> usize {
+        match self.statement.parameters() {
+            Some(Either::Left(types)) => types.len(),
+            Some(Either::Right(count)) => count,
+            None => 0,
+        }
+    }
+
+    /// Returns the columns of the result set, empty for statements without one.
+    pub fn columns(&self) -> &[MySqlColumn] {
+        self.statement.columns()
+    }
+
+    /// Binds the query's placeholders, preparing the expanded statement again if a list
+    /// placeholder changed length.
+    async fn bind(&mut self) -> crate::Result<MySqlArguments> {
+        deadline::check()?;
+        let PreparedQuery {
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref checks,
+            ..
+        } = *self.query;
+        let bound = bind_arguments(sql, order, binder, checks)?;
+        let expanded = bound.expand(sql);
+        if expanded != self.statement.sql() {
+            self.statement = prepare_statement(self.conn, &expanded).await?;
+        }
+        Ok(bound.arguments)
+    }
+
+    /// Executes the statement, like [`PreparedQuery::execute`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement fails.
+    pub async fn execute(&mut self) -> crate::Result<ExecuteResult> {
+        let arguments = self.bind().await?;
+        let PreparedQuery { ref tags, timeout, .. } = *self.query;
+        let statement = &self.statement;
+        let query = |_, arguments| statement.query_with(arguments);
+        execute_arguments(statement.sql(), tags, timeout, arguments, query, &mut *self.conn).await
+    }
+
+    /// Fetches all result rows, like [`PreparedQuery::fetch_rows`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement fails.
+    pub async fn fetch_rows(&mut self) -> crate::Result<Vec<MySqlRow>> {
+        let arguments = self.bind().await?;
+        let PreparedQuery { ref tags, timeout, .. } = *self.query;
+        let statement = &self.statement;
+        let query = |_, arguments| statement.query_with(arguments);
+        fetch_rows_arguments(statement.sql(), tags, timeout, arguments, query, &mut *self.conn)
+            .await
+    }
+
+    /// Fetches at most one result row, like [`PreparedQuery::fetch_optional_row`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement fails.
+    pub async fn fetch_optional_row(&mut self) -> crate::Result<Option<MySqlRow>> {
+        let arguments = self.bind().await?;
+        let PreparedQuery { ref tags, timeout, .. } = *self.query;
+        let statement = &self.statement;
+        let query = |_, arguments| statement.query_with(arguments);
+        let sql = statement.sql();
+        fetch_optional_row_arguments(sql, tags, timeout, arguments, query, &mut *self.conn).await
+    }
+}
+
+impl PreparedQuery<ParamsBinder> {
+    /// Creates a new `PreparedQuery` whose placeholders are bound from `params`, without
+    /// a binder closure.
+    ///
+    /// This suits parameters built at runtime, e.g. from request data.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `params` - Values keyed by placeholder, including the leading `:`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if
+    /// `params` has a value for a placeholder the template does not use. A placeholder
+    /// without a value fails with
+    /// [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) on execution.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{conditional::NamedBinds, PreparedQuery};
+    ///
+    /// let mut params = NamedBinds::new();
+    /// params.set(":name", "Jane").set(":id", 42_i64);
+    /// let query = PreparedQuery::with_params(
+    ///     "UPDATE users SET name = :name WHERE id = :id",
+    ///     params,
+    /// )?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn with_params<T>(template: T, params: NamedBinds) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order_with_style(&template, PlaceholderStyle::Colon)?;
+        if let Some(name) = params.unknown_name(&order) {
+            return Err(crate::Error::UnknownPlaceholder(name.to_owned()));
+        }
+        Self::new(template, Box::new(params.into_binder()) as ParamsBinder)
+    }
+
+    /// Creates a new `PreparedQuery` whose placeholders are bound from the fields of a
+    /// serializable value, such as a JSON payload.
+    ///
+    /// The value is converted with [`NamedBinds::from_serialize`] and passed to
+    /// [`with_params`](Self::with_params).
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `params` - Value serializing to a map keyed by placeholder name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidParams`](crate::Error::InvalidParams) if `params` does not
+    /// serialize to a map, and
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if it has a key
+    /// the template does not use.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// let payload = serde_json::json!({ "id": 42, "name": "Jane" });
+    /// let query = PreparedQuery::from_serialize(
+    ///     "UPDATE users SET name = :name WHERE id = :id",
+    ///     &payload,
+    /// )?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<T, S>(template: T, params: &S) -> crate::Result<Self>
+    where
+        T: Into<String>,
+        S: serde::Serialize + ?Sized,
+    {
+        Self::with_params(template, NamedBinds::from_serialize(params)?)
+    }
+}
+
+impl PreparedQuery<ParamsBinder> {
+    /// Starts a [`PreparedQueryBuilder`] for `template`, whose values are given by name
+    /// with [`bind_named`](PreparedQueryBuilder::bind_named) instead of a binder closure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQuery;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let result = PreparedQuery::builder("UPDATE users SET name = :name WHERE id = :id")?
+    ///     .bind_named("id", 42_i64)
+    ///     .bind_named("name", "Jane")
+    ///     .build()?
+    ///     .execute(&pool)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder<T>(template: T) -> crate::Result<PreparedQueryBuilder>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order_with_style(&template, PlaceholderStyle::Colon)?;
+        Ok(PreparedQueryBuilder {
+            template,
+            order,
+            params: NamedBinds::new(),
+        })
+    }
+}
+
+/// Collects values by placeholder name for a [`PreparedQuery`], checking them against
+/// the template on [`build`](Self::build).
+///
+/// Created by [`PreparedQuery::builder`].
+#[derive(Debug, Clone)]
+pub struct PreparedQueryBuilder {
+    template: String,
+    order: Vec<String>,
+    params: NamedBinds,
+}
+
+impl PreparedQueryBuilder {
+    /// Sets the value of the placeholder `name`, with or without the leading `:`,
+    /// replacing any previous value.
+    pub fn bind_named<T: AnyBind + 'static>(mut self, name: &str, value: T) -> Self {
+        self.params.set(&placeholder_key(name), value);
+        self
+    }
+
+    /// Builds the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) if a
+    /// placeholder of the template has no value, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if a value was
+    /// given for a placeholder the template does not use.
+    pub fn build(self) -> crate::Result<PreparedQuery<ParamsBinder>> {
+        self.params.check_exact(&self.order)?;
+        PreparedQuery::with_params(self.template, self.params)
+    }
+}
+
+/// Compares the placeholders in `order` with the names in `provided`, with or without
+/// the leading `:`.
+pub(crate) fn check_names(order: &[String], provided: &[&str]) -> crate::Result<()> {
+    let provided: Vec<String> = provided.iter().map(|name| placeholder_key(name)).collect();
+    let mut missing: Vec<String> = Vec::new();
+    for name in order {
+        if !provided.contains(name) && !missing.contains(name) {
+            missing.push(name.clone());
+        }
+    }
+    let mut extra: Vec<String> = Vec::new();
+    for name in provided {
+        if !order.contains(&name) && !extra.contains(&name) {
+            extra.push(name);
+        }
+    }
+    match missing.is_empty() && extra.is_empty() {
+        true => Ok(()),
+        false => Err(crate::Error::PlaceholderMismatch { missing, extra }),
+    }
+}
+
+/// Returns `name` as a placeholder key, with the leading `:`.
+pub(crate) fn placeholder_key(name: &str) -> String {
+    if name.starts_with(':') {
+        name.to_owned()
+    } else {
+        format!(":{name}")
+    }
+}
+
+impl<'a> PreparedQuery<StructBinder<'a>> {
+    /// Creates a new `PreparedQuery` whose placeholders are bound from the fields of
+    /// `params`.
+    ///
+    /// Each execution binds clones of the current field values. See [`NamedParams`].
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `params` - Struct whose fields are named after the placeholders
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed. A placeholder without a
+    /// matching field fails with
+    /// [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) on execution.
+    pub fn from_struct<T, P>(template: T, params: &'a P) -> crate::Result<Self>
+    where
+        T: Into<String>,
+        P: NamedParams + Sync,
+    {
+        let binder: StructBinder<'a> = Box::new(move |q, key| params.bind_field(q, key));
+        Self::new(template, binder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepared_query_new() {
+        let result = PreparedQuery::new(
+            "SELECT * FROM users WHERE id = :id",
+            |q, _| q,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_prepared_query_placeholder_order() {
+        let query = PreparedQuery::new(
+            "SELECT * FROM users WHERE id = :id AND name = :name",
+            |q, _| q,
+        ).unwrap();
+
+        assert_eq!(*query.order, [":id", ":name"]);
+        assert_eq!(&*query.sql, "SELECT * FROM users WHERE id = ? AND name = ?");
+    }
+
+    #[test]
+    fn test_prepared_query_repeated_placeholders() {
+        let query = PreparedQuery::new(
+            "SELECT * FROM users WHERE id = :id OR user_id = :id",
+            |q, _| q,
+        ).unwrap();
+
+        // Both occurrences should be captured
+        assert_eq!(*query.order, [":id", ":id"]);
+        assert_eq!(&*query.sql, "SELECT * FROM users WHERE id = ? OR user_id = ?");
+    }
+
+    #[test]
+    fn test_debug_omits_bound_values() {
+        let query = PreparedQuery::new("SELECT * FROM users WHERE email = :email", |q, _| {
+            q.bind("secret@example.com")
+        })
+        .unwrap();
+        let debug = format!("{query:?}");
+        assert!(debug.starts_with(
+            "PreparedQuery { sql: \"SELECT * FROM users WHERE email = ?\", \
+             placeholders: [\":email\"]"
+        ));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    fn test_check_names() {
+        let order = [":a", ":b", ":a"].map(String::from);
+        assert!(check_names(&order, &["b", ":a"]).is_ok());
+        match check_names(&order, &[":b", ":c", "c"]) {
+            Err(crate::Error::PlaceholderMismatch { missing, extra }) => {
+                assert_eq!((missing, extra), (vec![":a".to_owned()], vec![":c".to_owned()]));
+            }
+            other => panic!("expected a mismatch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bind_arguments_reports_unbound_placeholder() {
+        fn binder<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+            match key {
+                ":id" => q.bind(1),
+                _ => q,
+            }
+        }
+
+        let mut query = PreparedQuery::new(
+            "UPDATE users SET email = :email WHERE id = :id",
+            binder,
+        ).unwrap();
+        let order = vec![":id".to_owned()];
+        let checks = BindChecks::default();
+        let bound = bind_arguments(&query.sql, &order, &mut query.binder, &checks).unwrap();
+        assert_eq!(bound.arguments.len(), 1);
+
+        match bind_arguments(&query.sql, &query.order, &mut query.binder, &checks) {
+            Err(crate::Error::UnboundPlaceholder(key)) => assert_eq!(key, ":email"),
+            other => panic!("expected an unbound placeholder, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_bind_arguments_lists_every_unbound_placeholder() {
+        let mut query = PreparedQuery::new(
+            "UPDATE users SET email = :email WHERE id = :user_id OR parent_id = :user_id",
+            |q, key| match key {
+                ":userid" => q.bind(1),
+                _ => q,
+            },
+        )
+        .unwrap();
+        let checks = BindChecks::default();
+        match bind_arguments(&query.sql, &query.order, &mut query.binder, &checks) {
+            Err(crate::Error::UnboundPlaceholders(keys)) => {
+                assert_eq!(keys, [":email", ":user_id"]);
+            }
+            other => panic!("expected unbound placeholders, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_with_params() {
+        let mut params = NamedBinds::new();
+        params.set(":id", 1_i64).set(":name", "jane");
+        let mut query =
+            PreparedQuery::with_params("UPDATE users SET name = :name WHERE id = :id", params)
+                .unwrap();
+        let bound = bind_arguments(&query.sql, &query.order, &mut query.binder, &query.checks)
+            .unwrap();
+        assert_eq!(bound.arguments.len(), 2);
+
+        let mut params = NamedBinds::new();
+        params.set(":id", 1_i64).set(":nme", "jane");
+        match PreparedQuery::with_params("UPDATE users SET name = :name WHERE id = :id", params) {
+            Err(crate::Error::UnknownPlaceholder(key)) => assert_eq!(key, ":nme"),
+            other => panic!("expected an unknown placeholder, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_builder_validates_bindings() {
+        const SQL: &str = "UPDATE users SET name = :name WHERE id = :id OR parent = :id";
+        let mut query = PreparedQuery::builder(SQL)
+            .unwrap()
+            .bind_named("id", 1_i64)
+            .bind_named(":name", "jane")
+            .build()
+            .unwrap();
+        let bound = bind_arguments(&query.sql, &query.order, &mut query.binder, &query.checks)
+            .unwrap();
+        assert_eq!(bound.arguments.len(), 3);
+
+        let missing = PreparedQuery::builder(SQL).unwrap().bind_named("id", 1_i64).build();
+        assert!(matches!(missing, Err(crate::Error::UnboundPlaceholder(key)) if key == ":name"));
+
+        let extra = PreparedQuery::builder(SQL)
+            .unwrap()
+            .bind_named("id", 1_i64)
+            .bind_named("name", "jane")
+            .bind_named("nmae", "jane")
+            .build();
+        assert!(matches!(extra, Err(crate::Error::UnknownPlaceholder(key)) if key == ":nmae"));
+    }
+
+    #[test]
+    fn test_validate_checks_bound_values() {
+        fn binder<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+            match key {
+                ":limit" => q.bind(500_i64),
+                ":slug" => q.bind("ok"),
+                _ => q,
+            }
+        }
+
+        let sql = "SELECT * FROM posts WHERE slug = :slug LIMIT :limit";
+        let mut query = PreparedQuery::new(sql, binder)
+            .unwrap()
+            .validate("slug", Validator::non_empty())
+            .unwrap()
+            .validate(":limit", Validator::range(1, 100))
+            .unwrap();
+        match bind_arguments(&query.sql, &query.order, &mut query.binder, &query.checks) {
+            Err(crate::Error::InvalidParam { name, .. }) => assert_eq!(name, ":limit"),
+            other => panic!("expected an invalid param, got {:?}", other.err()),
+        }
+        assert!(matches!(
+            PreparedQuery::new(sql, binder).unwrap().validate("offset", Validator::not_null()),
+            Err(crate::Error::UnknownPlaceholder(key)) if key == ":offset"
+        ));
+    }
+
+    #[test]
+    fn test_bind_arguments_reports_inconsistent_repeat() {
+        fn binder<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+            static CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+            match key {
+                ":id" => q.bind(CALLS.fetch_add(1, std::sync::atomic::Ordering::Relaxed)),
+                ":same" => q.bind("x"),
+                _ => q,
+            }
+        }
+
+        let sql = "SELECT * FROM t WHERE a = ? AND b = ? AND c = ? AND d = ?";
+        let order: Vec<String> = [":same", ":id", ":same", ":id"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        let strict = BindChecks {
+            repeats: true,
+            ..BindChecks::default()
+        };
+        match bind_arguments(sql, &order, &mut binder, &strict) {
+            Err(crate::Error::InconsistentRepeat(key)) => assert_eq!(key, ":id"),
+            other => panic!("expected an inconsistent repeat, got {:?}", other.err()),
+        }
+        let bound = bind_arguments(sql, &order, &mut binder, &BindChecks::default()).unwrap();
+        assert_eq!(bound.arguments.len(), 4);
+        let checks = BindChecks {
+            long_data: vec![":id".to_owned()],
+            ..strict
+        };
+        assert!(bind_arguments(sql, &order, &mut binder, &checks).is_ok());
+
+        let order: Vec<String> = (0..1_000).map(|_| ":same".to_owned()).collect();
+        let sql = "?,".repeat(order.len());
+        let bound = bind_arguments(&sql, &order, &mut binder, &checks).unwrap();
+        assert_eq!(bound.arguments.len(), 1_000);
+    }
+
+    #[test]
+    fn test_bind_arguments_expands_lists() {
+        use crate::list::BindList;
+
+        fn binder<'q>(q: Q<'q>, key: &str) -> Q<'q> {
+            match key {
+                ":ids" => q.bind_list([3_i64, 5, 8]),
+                ":state" => q.bind("open"),
+                _ => q,
+            }
+        }
+
+        let mut query =
+            PreparedQuery::new("UPDATE t SET state = :state WHERE id IN (:ids)", binder).unwrap();
+        let bound =
+            bind_arguments(&query.sql, &query.order, &mut query.binder, &query.checks).unwrap();
+        assert_eq!(bound.arguments.len(), 4);
+        assert_eq!(bound.expand(&query.sql), "UPDATE t SET state = ? WHERE id IN (?, ?, ?)");
+
+        let mut raw = query.into_sqlx_query().unwrap();
+        assert_eq!(raw.sql(), "UPDATE t SET state = ? WHERE id IN (?, ?, ?)");
+        assert_eq!(raw.take_arguments().unwrap().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_keeps_sql_fixed() {
+        use std::time::{Duration, Instant};
+
+        let mut query =
+            PreparedQuery::new("SELECT * FROM t WHERE id = :id", |q, _| q.bind(1)).unwrap();
+        let mut sent = Vec::new();
+        for remaining in [Duration::from_millis(250), Duration::from_secs(30)] {
+            let sql = crate::deadline::scope(Instant::now() + remaining, async {
+                let bound =
+                    bind_arguments(&query.sql, &query.order, &mut query.binder, &query.checks)?;
+                Ok(bound.sql(&query.sql)?.into_owned())
+            });
+            sent.push(sql.await.unwrap());
+        }
+        assert_eq!(sent, ["SELECT * FROM t WHERE id = ?"; 2]);
     }
 }
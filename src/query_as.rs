@@ -1,14 +1,147 @@
-use crate::builder::build_query;
-use regex::Regex;
+use crate::allowlist;
+use crate::budget::{self, Budget};
+use crate::builder::{placeholder_order_with_style, PlaceholderStyle};
+use crate::checksum::{checksum_rows, RowChecksum};
+use crate::concurrency;
+use crate::deadline;
+use crate::explain::{estimated_rows, explain_json_sql, explain_sql, CostGuard, ExplainPlan};
+use crate::fallback::FallbackQuery;
+use crate::hint::{
+    add_collation, add_index_hint, add_max_execution_time, add_priority, IndexHint, Priority,
+};
+use crate::json::write_ndjson;
+use crate::kind::StatementKind;
+use crate::list::binding_list_as;
+use crate::pagination::{KeysetPages, OffsetPages, WindowPages};
+use crate::params::{NamedParams, StructBinderAs};
 use sqlx::{
     mysql::{MySqlArguments, MySqlRow},
     query::QueryAs,
-    Executor, MySql,
+    Acquire, Encode, Executor, MySql, Row, Type,
 };
+use crate::conditional::{AnyBind, NamedBinds};
+use crate::query::{bind_with, check_names, placeholder_key, run_query, Bound};
+use crate::query_log;
+use crate::registry::DeprecatedUse;
+use crate::shutdown;
+use crate::tags::Tags;
+use crate::template_cache;
+use crate::tenancy;
+use crate::validators::{BindChecks, Validator};
+use futures_util::{FutureExt, Stream, StreamExt, TryStreamExt};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
 
 /// Type alias for SQLx QueryAs with MySQL arguments
 pub type QA<'q, R> = QueryAs<'q, MySql, R, MySqlArguments>;
 
+/// Binder of a [`PreparedQueryAs`] built by [`PreparedQueryAs::with_params`]
+pub type ParamsBinderAs<R> = Box<dyn for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R> + Send + Sync>;
+
+/// Binds every placeholder in `order` onto a typed query for `sql`, with
+/// [`bind_with`](crate::query::bind_with).
+pub(crate) fn bind_arguments_as<R, F>(
+    sql: &str,
+    order: &[String],
+    binder: &mut F,
+    checks: &BindChecks,
+) -> crate::Result<Bound>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    F: for<'a> FnMut(QA<'a, R>, &str) -> QA<'a, R>,
+{
+    bind_with(sql, order, binder, checks, sqlx::query_as_with)
+}
+
+/// Fetches all rows of `sql` with `arguments`, through
+/// [`run_query`](crate::query::run_query).
+async fn fetch_all_arguments<'q, 'e, R, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    executor: E,
+) -> crate::Result<Vec<R>>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: QA<'q, R>, budget: Budget| async move {
+        match budget.row_limit() {
+            Some(_) => budget::collect_rows(budget, tags, q.fetch(executor)).await,
+            None => Ok(q.fetch_all(executor).await?),
+        }
+    };
+    let query = sqlx::query_as_with;
+    run_query(sql, tags, timeout, arguments, query, run, |rows| rows.len() as u64).await
+}
+
+/// Like [`fetch_all_arguments`], mapping each row with `map` and collecting the results
+/// into `C`.
+async fn fetch_collect_arguments<'q, 'e, R, T, C, E, M>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    executor: E,
+    map: M,
+) -> crate::Result<C>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    C: Default + Extend<T>,
+    E: Executor<'e, Database = MySql>,
+    M: FnMut(R) -> T,
+{
+    let run = |q: QA<'q, R>, budget: Budget| {
+        budget::collect_into(budget, tags, q.fetch(executor).map_ok(map))
+    };
+    let query = sqlx::query_as_with;
+    let (collected, _) =
+        run_query(sql, tags, timeout, arguments, query, run, |(_, rows)| *rows).await?;
+    Ok(collected)
+}
+
+/// Like [`fetch_all_arguments`], for exactly one row.
+async fn fetch_one_arguments<'q, 'e, R, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    executor: E,
+) -> crate::Result<R>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: QA<'q, R>, _| async move { Ok(q.fetch_one(executor).await?) };
+    run_query(sql, tags, timeout, arguments, sqlx::query_as_with, run, |_| 1).await
+}
+
+/// Like [`fetch_all_arguments`], for at most one row.
+async fn fetch_optional_arguments<'q, 'e, R, E>(
+    sql: &'q str,
+    tags: &Tags,
+    timeout: Option<Duration>,
+    arguments: MySqlArguments,
+    executor: E,
+) -> crate::Result<Option<R>>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+    E: Executor<'e, Database = MySql>,
+{
+    let run = |q: QA<'q, R>, _| async move { Ok(q.fetch_optional(executor).await?) };
+    run_query(sql, tags, timeout, arguments, sqlx::query_as_with, run, |row| {
+        u64::from(row.is_some())
+    })
+    .await
+}
+
 /// A prepared query builder that returns typed results from named placeholders.
 ///
 /// `PreparedQueryAs` is similar to `PreparedQuery` but returns strongly-typed results
@@ -52,10 +185,31 @@ pub struct PreparedQueryAs<R, F>
 where
     F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
 {
-    sql: String,
-    order: Vec<String>,
+    template: Arc<str>,
+    sql: Arc<str>,
+    order: Arc<[String]>,
     binder: F,
     _pd: std::marker::PhantomData<R>,
+    tags: Tags,
+    checks: BindChecks,
+    timeout: Option<Duration>,
+    /// Expanded SQL borrowed by the query returned from `into_sqlx_query`.
+    expanded_sql: String,
+}
+
+/// Shows the converted SQL and placeholder order, never bound values.
+impl<R, F> fmt::Debug for PreparedQueryAs<R, F>
+where
+    F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedQueryAs")
+            .field("sql", &self.sql)
+            .field("placeholders", &self.order)
+            .field("tags", &self.tags)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<R, F> PreparedQueryAs<R, F>
@@ -72,7 +226,11 @@ where
     ///
     /// # Errors
     ///
-    /// Returns an error if the SQL template cannot be parsed.
+    /// Returns an error if the SQL template cannot be parsed,
+    /// [`Error::NotAllowed`](crate::Error::NotAllowed) if an installed
+    /// [`allowlist`](crate::allowlist) rejects it, or
+    /// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if an installed
+    /// [`tenancy`](crate::tenancy) policy does.
     ///
     /// # Examples
     ///
@@ -99,80 +257,405 @@ where
     where
         T: Into<String>,
     {
-        let template = template.into();
-        let order = Regex::new(r":[a-zA-Z0-9_]+")?
-            .find_iter(&template)
-            .map(|m| m.as_str().to_owned())
-            .collect();
-        let sql = build_query(&template)?;
+        Self::new_with_style(template, PlaceholderStyle::Colon, binder)
+    }
+
+    /// Creates a new `PreparedQueryAs` from a template whose placeholders are written in
+    /// `style`, such as `@name` in SQL migrated from .NET.
+    ///
+    /// The binder still receives each placeholder as `:name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders in `style`
+    /// * `style` - How placeholders are written in `template`
+    /// * `binder` - Function that binds values to placeholders based on their names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed,
+    /// [`Error::NotAllowed`](crate::Error::NotAllowed) if an installed
+    /// [`allowlist`](crate::allowlist) rejects it, or
+    /// [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if an installed
+    /// [`tenancy`](crate::tenancy) policy does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{builder::PlaceholderStyle, PreparedQueryAs};
+    ///
+    /// let query = PreparedQueryAs::<(i32, String), _>::new_with_style(
+    ///     "SELECT id, name FROM users WHERE tenant_id = ${tenant_id}",
+    ///     PlaceholderStyle::DollarBrace,
+    ///     |q, key| match key {
+    ///         ":tenant_id" => q.bind(7),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn new_with_style<T>(
+        template: T,
+        style: PlaceholderStyle,
+        binder: F,
+    ) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        Self::parse(template.into(), style, binder, true)
+    }
+
+    /// Like [`new`](Self::new), without checking the installed allow-list.
+    ///
+    /// For statements the crate sends on its own behalf, such as `GET_LOCK`, and for
+    /// templates checked when the wrapper running them was created.
+    pub(crate) fn new_unlisted<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        Self::parse(template.into(), PlaceholderStyle::Colon, binder, false)
+    }
+
+    fn parse(
+        template: String,
+        style: PlaceholderStyle,
+        binder: F,
+        check_allowlist: bool,
+    ) -> crate::Result<Self> {
+        let parsed = template_cache::parse(&template, style)?;
+        let (mut sql, mut order) = (parsed.sql, parsed.order);
+        if check_allowlist {
+            allowlist::check(&sql)?;
+        }
+        tenancy::enforce(&mut sql, &mut order)?;
         Ok(Self {
+            template: parsed.template,
             sql,
             order,
             binder,
             _pd: std::marker::PhantomData,
+            tags: Tags::new(),
+            checks: BindChecks::default(),
+            timeout: None,
+            expanded_sql: String::new(),
         })
     }
 
-    /// Executes the query and returns all matching rows.
+    /// Attaches the tag `key=value` to the query, replacing any previous value.
     ///
-    /// # Arguments
+    /// Executions run inside a `named_query` tracing span listing the tags.
     ///
-    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// # Examples
     ///
-    /// # Returns
+    /// ```rust
+    /// use sqlx::FromRow;
+    /// use sqlx_named_bind::PreparedQueryAs;
     ///
-    /// Returns a vector of all rows matching the query.
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    /// }
     ///
-    /// # Errors
+    /// let query = PreparedQueryAs::<User, _>::new("SELECT id FROM users", |q, _| q)?
+    ///     .tag("team", "accounts")
+    ///     .tag("criticality", "high");
+    /// assert_eq!(query.tags().to_string(), "criticality=high,team=accounts");
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.insert(key, value);
+        self
+    }
+
+    /// Attaches every tag of `tags`, replacing previous values of the same keys.
+    pub fn with_tags(mut self, tags: &Tags) -> Self {
+        self.tags.extend(tags);
+        self
+    }
+
+    /// Counts every execution as a use of a deprecated registry entry.
+    pub(crate) fn deprecation(mut self, deprecation: Option<DeprecatedUse>) -> Self {
+        self.checks.deprecation = deprecation;
+        self
+    }
+
+    /// Returns the tags attached to the query.
+    pub fn tags(&self) -> &Tags {
+        &self.tags
+    }
+
+    /// Limits how long each execution of the query may run.
     ///
-    /// Returns an error if the query fails or if any row cannot be converted to type `R`.
+    /// An execution still running after `timeout` is cancelled and fails with
+    /// [`Error::BudgetExceeded`](crate::Error::BudgetExceeded); a stricter
+    /// [`budget`](crate::budget) of the query's tags still applies. A `SELECT` also gets
+    /// a `MAX_EXECUTION_TIME` optimizer hint, so the server stops working on it too;
+    /// only the first call adds the hint.
+    /// Queries prepared from a [`registry`](crate::registry) entry get the `timeout` of
+    /// its front matter.
     ///
     /// # Examples
     ///
-    /// ```rust,no_run
-    /// use sqlx::{MySqlPool, FromRow};
+    /// ```rust
+    /// use std::time::Duration;
     /// use sqlx_named_bind::PreparedQueryAs;
     ///
+    /// let query = PreparedQueryAs::<(i64,), _>::new("SELECT id FROM users", |q, _| q)?
+    ///     .timeout(Duration::from_secs(2));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        if self.timeout.is_none() {
+            if let Some(sql) = add_max_execution_time(&self.sql, timeout) {
+                self.sql = sql.into();
+            }
+        }
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the template the query was created from, as written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// let query = PreparedQueryAs::<(String,), _>::new(
+    ///     "SELECT name FROM users WHERE id = :id OR parent_id = :id",
+    ///     |q, _| q,
+    /// )?;
+    /// assert_eq!(query.template(), "SELECT name FROM users WHERE id = :id OR parent_id = :id");
+    /// assert_eq!(query.sql(), "SELECT name FROM users WHERE id = ? OR parent_id = ?");
+    /// assert_eq!(query.placeholders(), [":id", ":id"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the SQL sent to the server, with positional placeholders.
+    ///
+    /// Hints and filters added since the query was created are included. List
+    /// placeholders are expanded when the query runs, so each is a single `?` here.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the placeholders in the order they are bound, as `:name`, once per
+    /// occurrence.
+    pub fn placeholders(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Checks that `provided`, the names the binder handles, are exactly the
+    /// placeholders of the template, with or without the leading `:`.
+    ///
+    /// See [`PreparedQuery::validate_bindings`](crate::PreparedQuery::validate_bindings).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PlaceholderMismatch`](crate::Error::PlaceholderMismatch) listing
+    /// the placeholders missing from `provided` and the names in `provided` the
+    /// template does not have.
+    pub fn validate_bindings(&self, provided: &[&str]) -> crate::Result<()> {
+        check_names(&self.order, provided)
+    }
+
+    /// Returns what kind of statement the query is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{kind::StatementKind, PreparedQueryAs};
+    ///
+    /// let query = PreparedQueryAs::<(i64,), _>::new("SELECT id FROM users", |q, _| q)?;
+    /// assert_eq!(query.kind(), StatementKind::Select);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn kind(&self) -> StatementKind {
+        StatementKind::of(&self.sql)
+    }
+
+    /// Requires every occurrence of a repeated placeholder to be bound to the same value.
+    ///
+    /// See [`PreparedQuery::strict_repeats`](crate::PreparedQuery::strict_repeats).
+    pub fn strict_repeats(mut self) -> Self {
+        self.checks.repeats = true;
+        self
+    }
+
+    /// Attaches `validator` to the placeholder `name`, with or without the leading `:`.
+    ///
+    /// See [`PreparedQuery::validate`](crate::PreparedQuery::validate).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    pub fn validate(mut self, name: &str, validator: Validator) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.validators.push((key, validator));
+        Ok(self)
+    }
+
+    /// Encrypts the values bound for the placeholder `name`, with or without the
+    /// leading `:`, with the installed [`FieldCipher`](crate::cipher::FieldCipher).
+    ///
+    /// See [`PreparedQuery::encrypt`](crate::PreparedQuery::encrypt).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    pub fn encrypt(mut self, name: &str) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.encrypted.push(key);
+        Ok(self)
+    }
+
+    /// Marks the placeholder `name`, with or without the leading `:`, as carrying a
+    /// large text or binary payload.
+    ///
+    /// See [`PreparedQuery::long_data`](crate::PreparedQuery::long_data).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `name`.
+    pub fn long_data(mut self, name: &str) -> crate::Result<Self> {
+        let key = placeholder_key(name);
+        if !self.order.contains(&key) {
+            return Err(crate::Error::UnknownPlaceholder(key));
+        }
+        self.checks.long_data.push(key);
+        Ok(self)
+    }
+
+
+    /// Attaches an index hint to every reference of `table` in the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Name of the table to hint, as written in the template
+    /// * `hint` - The `USE`/`FORCE`/`IGNORE INDEX` hint to attach
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `table` is not a plain identifier or is not referenced
+    /// after `FROM`, `JOIN` or `UPDATE` in the template.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx::FromRow;
+    /// use sqlx_named_bind::{hint::IndexHint, PreparedQueryAs};
+    ///
     /// #[derive(FromRow)]
     /// struct User {
     ///     id: i32,
     ///     name: String,
     /// }
     ///
-    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
-    /// let mut query = PreparedQueryAs::<User, _>::new(
-    ///     "SELECT id, name FROM users WHERE age > :min_age",
+    /// let query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users u WHERE u.email = :email",
     ///     |q, key| match key {
-    ///         ":min_age" => q.bind(18),
+    ///         ":email" => q.bind("user@example.com"),
     ///         _ => q,
     ///     }
-    /// )?;
+    /// )?
+    /// .index_hint("users", IndexHint::use_index(["idx_users_email"])?)?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn index_hint(mut self, table: &str, hint: IndexHint) -> crate::Result<Self> {
+        self.sql = add_index_hint(&self.sql, table, &hint)?.into();
+        Ok(self)
+    }
+
+    /// Adds a `HIGH_PRIORITY` modifier to the statement.
     ///
-    /// let users: Vec<User> = query.fetch_all(&pool).await?;
-    /// println!("Found {} users", users.len());
-    /// # Ok(())
-    /// # }
+    /// Only `SELECT` accepts a modifier among read statements, so this is useful with
+    /// [`Priority::High`]; the other priorities are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The modifier to insert after the statement's leading keyword
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement type does not accept the modifier or already
+    /// has one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx::FromRow;
+    /// use sqlx_named_bind::{hint::Priority, PreparedQueryAs};
+    ///
+    /// #[derive(FromRow)]
+    /// struct Setting {
+    ///     value: String,
+    /// }
+    ///
+    /// let query = PreparedQueryAs::<Setting, _>::new(
+    ///     "SELECT value FROM settings WHERE name = :name",
+    ///     |q, key| match key {
+    ///         ":name" => q.bind("maintenance_mode"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .priority(Priority::High)?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
     /// ```
-    pub async fn fetch_all<'e, E>(&mut self, executor: E) -> crate::Result<Vec<R>>
-    where
-        E: Executor<'e, Database = MySql>,
-    {
-        let &mut PreparedQueryAs {
-            ref sql,
-            ref order,
-            ref mut binder,
-            _pd,
-        } = self;
+    pub fn priority(mut self, priority: Priority) -> crate::Result<Self> {
+        self.sql = add_priority(&self.sql, priority)?.into();
+        Ok(self)
+    }
 
-        let mut q = sqlx::query_as(sql);
-        for key in order.iter() {
-            q = binder(q, key);
-        }
-        Ok(q.fetch_all(executor).await?)
+    /// Appends a `COLLATE` clause to every occurrence of `placeholder`.
+    ///
+    /// The bound value is compared using `collation` instead of the column's or
+    /// connection's collation, e.g. `utf8mb4_bin` for a case-sensitive lookup. For
+    /// session-wide settings, see
+    /// [`with_collation`](crate::connection::with_collation).
+    ///
+    /// # Arguments
+    ///
+    /// * `placeholder` - Placeholder whose values to collate (e.g. `:email`)
+    /// * `collation` - Collation name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `placeholder` does not appear in the template or
+    /// `collation` is not a plain identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// let query = PreparedQueryAs::<(i64,), _>::new(
+    ///     "SELECT id FROM users WHERE login = :login",
+    ///     |q, key| match key {
+    ///         ":login" => q.bind("jane"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .collate(":login", "utf8mb4_0900_as_cs")?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn collate(mut self, placeholder: &str, collation: &str) -> crate::Result<Self> {
+        self.sql = add_collation(&self.sql, &self.order, placeholder, collation)?.into();
+        Ok(self)
     }
 
-    /// Executes the query and returns exactly one row.
+    /// Executes the query and returns all matching rows.
     ///
     /// # Arguments
     ///
@@ -180,15 +663,11 @@ where
     ///
     /// # Returns
     ///
-    /// Returns the single row matching the query.
+    /// Returns a vector of all rows matching the query.
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - No rows are found
-    /// - More than one row is found
-    /// - The query fails
-    /// - The row cannot be converted to type `R`
+    /// Returns an error if the query fails or if any row cannot be converted to type `R`.
     ///
     /// # Examples
     ///
@@ -205,52 +684,985 @@ where
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
     /// let mut query = PreparedQueryAs::<User, _>::new(
-    ///     "SELECT id, name FROM users WHERE id = :id",
+    ///     "SELECT id, name FROM users WHERE age > :min_age",
     ///     |q, key| match key {
-    ///         ":id" => q.bind(42),
+    ///         ":min_age" => q.bind(18),
     ///         _ => q,
     ///     }
     /// )?;
     ///
-    /// let user: User = query.fetch_one(&pool).await?;
-    /// println!("Found user: {}", user.name);
+    /// let users: Vec<User> = query.fetch_all(&pool).await?;
+    /// println!("Found {} users", users.len());
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn fetch_one<'e, E>(&mut self, executor: E) -> crate::Result<R>
+    pub async fn fetch_all<'e, E>(&mut self, executor: E) -> crate::Result<Vec<R>>
     where
         E: Executor<'e, Database = MySql>,
     {
         let &mut PreparedQueryAs {
+            template: _,
             ref sql,
             ref order,
             ref mut binder,
             _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
         } = self;
 
-        let mut q = sqlx::query_as(sql);
-        for key in order.iter() {
-            q = binder(q, key);
-        }
-        Ok(q.fetch_one(executor).await?)
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        fetch_all_arguments(&bound.sql(sql)?, tags, timeout, bound.arguments, executor).await
     }
 
-    /// Executes the query and returns at most one row.
+    /// Streams the result rows as they arrive, instead of collecting them.
+    ///
+    /// Rows are read from the server one at a time as the stream is polled, so a large
+    /// result set never has to fit in memory; a consumer that stops polling stops the
+    /// reading. Like `fetch_all`, the query runs within the shutdown gate, tag budgets
+    /// and query log, and is logged once the stream ends. Dropping the stream early
+    /// cancels the query.
+    ///
+    /// The stream is not `Unpin`; pin it with `std::pin::pin!` or `Box::pin` to call
+    /// `next` on it.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields an error, then ends, if the query fails, a row cannot be
+    /// converted to type `R`, or a budget is exceeded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use futures_util::TryStreamExt;
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<(i64, String), _>::new(
+    ///     "SELECT id, payload FROM events WHERE created_at >= :since",
+    ///     |q, key| match key {
+    ///         ":since" => q.bind("2024-01-01"),
+    ///         _ => q,
+    ///     },
+    /// )?;
+    /// let mut events = std::pin::pin!(query.fetch(&pool));
+    /// while let Some((id, payload)) = events.try_next().await? {
+    ///     println!("{id}: {} bytes", payload.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fetch<'a, 'e, E>(
+        &'a mut self,
+        executor: E,
+    ) -> impl Stream<Item = crate::Result<R>> + 'a
+    where
+        E: Executor<'e, Database = MySql> + 'a,
+        'e: 'a,
+        R: 'a,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks);
+        // The rows borrow the expanded SQL, so they are read by a future that owns it
+        // and handed over through a one-row channel.
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let reader = async move {
+            let result = async {
+                let bound = bound?;
+                let sql = bound.sql(sql)?;
+                let params = query_log::digest(&bound.arguments);
+                let q = sqlx::query_as_with::<_, R, _>(&sql, bound.arguments);
+                let _in_flight = shutdown::enter()?;
+                let _permit = concurrency::acquire(&sql).await?;
+                let budget = budget::current(tags).within(timeout);
+                let run = budget::run(budget, tags, async {
+                    let mut rows = q.fetch(executor);
+                    let mut received = 0;
+                    while let Some(row) = rows.try_next().await? {
+                        budget::check_row(budget, tags, received)?;
+                        received += 1;
+                        if sender.send(Ok(row)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(received)
+                });
+                let run = deadline::enforce(run);
+                query_log::observe(&sql, params, tags, run, |received| *received)
+                    .instrument(tags.span())
+                    .await
+            }
+            .await;
+            if let Err(e) = result {
+                let _ = sender.send(Err(e)).await;
+            }
+        };
+        let rows = futures_util::stream::poll_fn(move |cx| receiver.poll_recv(cx));
+        let reader = reader
+            .into_stream()
+            .filter_map(|()| futures_util::future::ready(None));
+        futures_util::stream::select(rows, reader)
+    }
+
+    /// Fetches all rows, sorted by `key`.
+    ///
+    /// Without a total `ORDER BY`, MySQL may return rows in any order, so tests that
+    /// compare result vectors become flaky. Sorting on the client by a unique key makes
+    /// the order deterministic without changing the template. The sort is stable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or if any row cannot be converted to type `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let users = PreparedQueryAs::<(i64, String), _>::new(
+    ///     "SELECT id, name FROM users WHERE team = :team",
+    ///     |q, key| match key {
+    ///         ":team" => q.bind("search"),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .fetch_all_sorted(&pool, |(id, _)| *id)
+    /// .await?;
+    /// assert_eq!(users, vec![(1, "Jane".to_owned()), (2, "John".to_owned())]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_all_sorted<'e, E, K, G>(
+        &mut self,
+        executor: E,
+        key: G,
+    ) -> crate::Result<Vec<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+        K: Ord,
+        G: FnMut(&R) -> K,
+    {
+        let mut rows = self.fetch_all(executor).await?;
+        rows.sort_by_key(key);
+        Ok(rows)
+    }
+
+    /// Fetches the rows for a batch of keys in one query and returns them in the order
+    /// of `keys`, as a dataloader-style batching layer needs.
+    ///
+    /// If the keys would exceed [`limits::max_placeholders`], they are split into
+    /// chunks queried one after another on the same connection.
+    ///
+    /// Every key is bound to the list placeholder `placeholder` (see
+    /// [`list`](crate::list)), so the template should filter with
+    /// `... IN (:placeholder)`; the other placeholders are bound by the binder. Each row
+    /// is matched back to its key with `key`. The result has one entry per element of
+    /// `keys`: the first row with that key, or `None` if there is none. Use
+    /// [`fetch_grouped_by_keys`](Self::fetch_grouped_by_keys) when a key can match
+    /// several rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if the
+    /// template has no placeholder `placeholder`, or an error if the query fails or if
+    /// any row cannot be converted to type `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let users = PreparedQueryAs::<(i64, String), _>::new(
+    ///     "SELECT id, name FROM users WHERE id IN (:ids)",
+    ///     |q, _| q,
+    /// )?
+    /// .fetch_by_keys(&pool, "ids", &[3_i64, 1, 99], |(id, _)| *id)
+    /// .await?;
+    /// assert_eq!(users[2], None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_by_keys<'e, A, K, G>(
+        &mut self,
+        executor: A,
+        placeholder: &str,
+        keys: &[K],
+        mut key: G,
+    ) -> crate::Result<Vec<Option<R>>>
+    where
+        A: Acquire<'e, Database = MySql>,
+        R: Clone,
+        K: Eq + Hash + Clone + Send + for<'q> Encode<'q, MySql> + Type<MySql> + 'static,
+        G: FnMut(&R) -> K,
+    {
+        let rows = self.fetch_for_keys(executor, placeholder, keys).await?;
+        let mut by_key = HashMap::with_capacity(rows.len());
+        for row in rows {
+            by_key.entry(key(&row)).or_insert(row);
+        }
+        Ok(keys.iter().map(|k| by_key.get(k).cloned()).collect())
+    }
+
+    /// Fetches the rows for a batch of keys in one query, or one per chunk of keys, grouped
+    /// by key.
+    ///
+    /// Like [`fetch_by_keys`](Self::fetch_by_keys), but keeps every row of each key, in
+    /// the order they arrived. Every element of `keys` has an entry, empty if no row
+    /// matched it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`fetch_by_keys`](Self::fetch_by_keys).
+    pub async fn fetch_grouped_by_keys<'e, A, K, G>(
+        &mut self,
+        executor: A,
+        placeholder: &str,
+        keys: &[K],
+        mut key: G,
+    ) -> crate::Result<HashMap<K, Vec<R>>>
+    where
+        A: Acquire<'e, Database = MySql>,
+        K: Eq + Hash + Clone + Send + for<'q> Encode<'q, MySql> + Type<MySql> + 'static,
+        G: FnMut(&R) -> K,
+    {
+        let rows = self.fetch_for_keys(executor, placeholder, keys).await?;
+        let mut groups: HashMap<K, Vec<R>> =
+            keys.iter().map(|k| (k.clone(), Vec::new())).collect();
+        for row in rows {
+            groups.entry(key(&row)).or_default().push(row);
+        }
+        Ok(groups)
+    }
+
+    /// Fetches all rows with `keys` bound to the list placeholder `placeholder`.
+    async fn fetch_for_keys<'e, A, K>(
+        &mut self,
+        executor: A,
+        placeholder: &str,
+        keys: &[K],
+    ) -> crate::Result<Vec<R>>
+    where
+        A: Acquire<'e, Database = MySql>,
+        K: Clone + Send + for<'q> Encode<'q, MySql> + Type<MySql> + 'static,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let placeholder = placeholder_key(placeholder);
+        let occurrences = order.iter().filter(|key| **key == placeholder).count();
+        if occurrences == 0 {
+            return Err(crate::Error::UnknownPlaceholder(placeholder));
+        }
+
+        // Runs one query per chunk of keys when they would exceed the placeholder limit.
+        let mut conn = executor.acquire().await?;
+        let mut rows = Vec::new();
+        let mut chunk_size = keys.len().max(1);
+        let mut remaining = keys;
+        loop {
+            let (chunk, rest) = remaining.split_at(chunk_size.min(remaining.len()));
+            let mut list_binder = binding_list_as(&placeholder, chunk, binder);
+            let bound = match bind_arguments_as(sql, order, &mut list_binder, checks) {
+                Err(crate::Error::TooManyPlaceholders { count, max }) if chunk.len() > 1 => {
+                    let others = count - chunk.len() * occurrences;
+                    chunk_size = (max.saturating_sub(others) / occurrences).max(1);
+                    continue;
+                }
+                bound => bound?,
+            };
+            let sql = bound.sql(sql)?;
+            rows.extend(
+                fetch_all_arguments(&sql, tags, timeout, bound.arguments, &mut *conn).await?,
+            );
+            remaining = rest;
+            if remaining.is_empty() {
+                return Ok(rows);
+            }
+        }
+    }
+
+    /// Fetches all result rows straight into any collection, such as a `HashSet` or a
+    /// `SmallVec`, without collecting them into a `Vec` first.
+    ///
+    /// Rows are added one at a time as they arrive, so the collection only needs
+    /// `Default` and `Extend`. See [`fetch_collect_keyed`](Self::fetch_collect_keyed) for
+    /// maps.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or if any row cannot be converted to type `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::collections::HashSet;
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let banned: HashSet<(i64,)> = PreparedQueryAs::<(i64,), _>::new(
+    ///     "SELECT user_id FROM bans WHERE expires_at > NOW()",
+    ///     |q, _| q,
+    /// )?
+    /// .fetch_collect(&pool)
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_collect<'e, C, E>(&mut self, executor: E) -> crate::Result<C>
+    where
+        C: Default + Extend<R>,
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        let sql = bound.sql(sql)?;
+        fetch_collect_arguments(&sql, tags, timeout, bound.arguments, executor, |row| row).await
+    }
+
+    /// Fetches all result rows into a map, such as a `BTreeMap` or `HashMap`, keyed by
+    /// `key`.
+    ///
+    /// When several rows have the same key, the map keeps whichever its `Extend`
+    /// implementation keeps; for the standard maps that is the last row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or if any row cannot be converted to type `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::collections::BTreeMap;
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let users: BTreeMap<i64, (i64, String)> = PreparedQueryAs::<(i64, String), _>::new(
+    ///     "SELECT id, name FROM users WHERE team = :team",
+    ///     |q, key| match key {
+    ///         ":team" => q.bind("search"),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .fetch_collect_keyed(&pool, |(id, _)| *id)
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_collect_keyed<'e, C, E, K, G>(
+        &mut self,
+        executor: E,
+        mut key: G,
+    ) -> crate::Result<C>
+    where
+        C: Default + Extend<(K, R)>,
+        E: Executor<'e, Database = MySql>,
+        G: FnMut(&R) -> K,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        let sql = bound.sql(sql)?;
+        let keyed = |row: R| (key(&row), row);
+        fetch_collect_arguments(&sql, tags, timeout, bound.arguments, executor, keyed).await
+    }
+
+    /// Executes the query and returns exactly one row.
     ///
     /// # Arguments
     ///
     /// * `executor` - Any SQLx executor (pool, transaction, etc.)
     ///
-    /// # Returns
+    /// # Returns
+    ///
+    /// Returns the single row matching the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No rows are found
+    /// - More than one row is found
+    /// - The query fails
+    /// - The row cannot be converted to type `R`
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE id = :id",
+    ///     |q, key| match key {
+    ///         ":id" => q.bind(42),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let user: User = query.fetch_one(&pool).await?;
+    /// println!("Found user: {}", user.name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_one<'e, E>(&mut self, executor: E) -> crate::Result<R>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        fetch_one_arguments(&bound.sql(sql)?, tags, timeout, bound.arguments, executor).await
+    }
+
+    /// Executes the query and returns at most one row.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(row)` if exactly one row matches, `None` if no rows match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - More than one row is found
+    /// - The query fails
+    /// - The row cannot be converted to type `R`
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE email = :email",
+    ///     |q, key| match key {
+    ///         ":email" => q.bind("user@example.com"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// match query.fetch_optional(&pool).await? {
+    ///     Some(user) => println!("Found user: {}", user.name),
+    ///     None => println!("User not found"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_optional<'e, E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            ref tags,
+            ref checks,
+            timeout,
+            expanded_sql: _,
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        fetch_optional_arguments(&bound.sql(sql)?, tags, timeout, bound.arguments, executor).await
+    }
+
+    /// Fetches all rows, with some placeholders bound from `overrides` instead of the
+    /// binder, for this call only.
+    ///
+    /// Together with [`with_params`](PreparedQueryAs::with_params) and an empty
+    /// [`NamedBinds`], this runs one query many times with values computed in a loop,
+    /// without tying them to the lifetime of a binder closure.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `overrides` - Values for some or all placeholders, keyed with the leading `:`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if an
+    /// override names a placeholder the query does not have, or an error if the
+    /// database query fails or rows cannot be decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{conditional::NamedBinds, PreparedQueryAs};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<(i64,), _>::with_params(
+    ///     "SELECT id FROM orders WHERE customer = :customer",
+    ///     NamedBinds::new(),
+    /// )?;
+    ///
+    /// for customer in ["alice", "bob"] {
+    ///     let mut args = NamedBinds::new();
+    ///     args.set(":customer", format!("{customer}@example.com"));
+    ///     let orders = query.fetch_all_with(&pool, &args).await?;
+    ///     println!("{customer}: {} orders", orders.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_all_with<'e, E>(
+        &mut self,
+        executor: E,
+        overrides: &NamedBinds,
+    ) -> crate::Result<Vec<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.overridden_arguments(overrides)?;
+        fetch_all_arguments(
+            &bound.sql(&self.sql)?,
+            &self.tags,
+            self.timeout,
+            bound.arguments,
+            executor,
+        )
+        .await
+    }
+
+    /// Fetches exactly one row, with some placeholders bound from `overrides`.
+    ///
+    /// See [`fetch_all_with`](Self::fetch_all_with).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if an
+    /// override names a placeholder the query does not have, or the errors of
+    /// [`fetch_one`](Self::fetch_one).
+    pub async fn fetch_one_with<'e, E>(
+        &mut self,
+        executor: E,
+        overrides: &NamedBinds,
+    ) -> crate::Result<R>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.overridden_arguments(overrides)?;
+        fetch_one_arguments(
+            &bound.sql(&self.sql)?,
+            &self.tags,
+            self.timeout,
+            bound.arguments,
+            executor,
+        )
+        .await
+    }
+
+    /// Fetches at most one row, with some placeholders bound from `overrides`.
+    ///
+    /// See [`fetch_all_with`](Self::fetch_all_with).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if an
+    /// override names a placeholder the query does not have, or the errors of
+    /// [`fetch_optional`](Self::fetch_optional).
+    pub async fn fetch_optional_with<'e, E>(
+        &mut self,
+        executor: E,
+        overrides: &NamedBinds,
+    ) -> crate::Result<Option<R>>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.overridden_arguments(overrides)?;
+        fetch_optional_arguments(
+            &bound.sql(&self.sql)?,
+            &self.tags,
+            self.timeout,
+            bound.arguments,
+            executor,
+        )
+        .await
+    }
+
+    /// Binds the placeholders from `overrides`, falling back to the binder.
+    fn overridden_arguments(&mut self, overrides: &NamedBinds) -> crate::Result<Bound> {
+        let &mut PreparedQueryAs {
+            template: _,
+            ref sql,
+            ref order,
+            ref mut binder,
+            _pd,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
+        } = self;
+
+        if let Some(name) = overrides.unknown_name(order) {
+            return Err(crate::Error::UnknownPlaceholder(name.to_owned()));
+        }
+        let mut binder = overrides.overriding_as(binder);
+        bind_arguments_as(sql, order, &mut binder, checks)
+    }
+
+    /// Estimates how many rows the query will examine, without executing it.
+    ///
+    /// Runs `EXPLAIN` on the converted SQL with the current binds and returns the
+    /// optimizer's estimate, summed over every row of the plan.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bound value cannot be encoded or the `EXPLAIN` statement fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE name LIKE :pattern",
+    ///     |q, key| match key {
+    ///         ":pattern" => q.bind("%son"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let estimate = query.estimate_rows(&pool).await?;
+    /// println!("Expecting to scan about {} rows", estimate);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn estimate_rows<'e, E>(&mut self, executor: E) -> crate::Result<u64>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.bound_arguments()?;
+        let explain = explain_sql(&bound.expand(&self.sql));
+        let plan = sqlx::query_with(&explain, bound.arguments)
+            .fetch_all(executor)
+            .await?;
+        Ok(estimated_rows(&plan))
+    }
+
+    /// Returns the optimizer's plan for the query, without executing it.
+    ///
+    /// Runs `EXPLAIN FORMAT=JSON` on the converted SQL with the current binds and
+    /// parses the result into an [`ExplainPlan`].
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bound value cannot be encoded, the `EXPLAIN` statement
+    /// fails, or its output cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE email = :email",
+    ///     |q, key| match key {
+    ///         ":email" => q.bind("user@example.com"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let plan = query.explain_plan(&pool).await?;
+    /// assert!(plan.uses_index("idx_users_email"), "lookup by email must use the index");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain_plan<'e, E>(&mut self, executor: E) -> crate::Result<ExplainPlan>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.bound_arguments()?;
+        let explain = explain_json_sql(&bound.expand(&self.sql));
+        let row = sqlx::query_with(&explain, bound.arguments)
+            .fetch_one(executor)
+            .await?;
+        ExplainPlan::from_json(&row.try_get::<String, _>(0)?)
+    }
+
+    /// Runs `EXPLAIN FORMAT=JSON` for the query and checks the plan against `guard`.
+    ///
+    /// Call this before executing the query to refuse statements the optimizer expects
+    /// to be expensive. The plan is returned when it is within the limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `guard` - Limits the plan must stay within
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TooExpensive`](crate::Error::TooExpensive) if the plan exceeds a
+    /// limit, or an error if the `EXPLAIN` statement fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{explain::CostGuard, PreparedQueryAs};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let guard = CostGuard::new().max_rows(100_000).max_full_scans(0);
+    ///
+    /// let mut query = PreparedQueryAs::<(i64, String), _>::new(
+    ///     "SELECT id, name FROM users WHERE name LIKE :pattern",
+    ///     |q, key| match key {
+    ///         ":pattern" => q.bind("%smith%"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// query.check_cost(&pool, &guard).await?;
+    /// let users = query.fetch_all(&pool).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn check_cost<'e, E>(
+        &mut self,
+        executor: E,
+        guard: &CostGuard,
+    ) -> crate::Result<ExplainPlan>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let plan = self.explain_plan(executor).await?;
+        guard.check(&plan)?;
+        Ok(plan)
+    }
+
+    /// Streams the query's result rows to `writer` as newline-delimited JSON.
+    ///
+    /// Rows are converted dynamically with [`row_to_json`](crate::json::row_to_json)
+    /// rather than through `R`, so every selected column is exported as it is received.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    /// * `writer` - Destination for the JSON lines
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bound value cannot be encoded, the query fails, a column
+    /// cannot be converted, or writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE age >= :min_age",
+    ///     |q, key| match key {
+    ///         ":min_age" => q.bind(18),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// query.export_ndjson(&pool, std::io::stdout().lock()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn export_ndjson<'e, E, W>(&mut self, executor: E, writer: W) -> crate::Result<u64>
+    where
+        E: Executor<'e, Database = MySql>,
+        W: Write,
+    {
+        let bound = self.bound_arguments()?;
+        let sql = bound.expand(&self.sql);
+        let rows = sqlx::query_with(&sql, bound.arguments).fetch(executor);
+        write_ndjson(rows, writer).await
+    }
+
+    /// Computes a deterministic checksum over the query's result rows.
+    ///
+    /// Rows are hashed dynamically rather than through `R`, so every selected column
+    /// contributes. See [`RowChecksum`] for the encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bound value cannot be encoded, the query fails, or a
+    /// column cannot be converted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let staging = MySqlPool::connect("mysql://staging/test").await?;
+    /// # let production = MySqlPool::connect("mysql://production/test").await?;
+    /// let mut query = PreparedQueryAs::<User, _>::new(
+    ///     "SELECT id, name FROM users WHERE team_id = :team_id ORDER BY id",
+    ///     |q, key| match key {
+    ///         ":team_id" => q.bind(7),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let expected = query.checksum(&production).await?;
+    /// let actual = query.checksum(&staging).await?;
+    /// println!("production {}, staging {}", expected, actual);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn checksum<'e, E>(&mut self, executor: E) -> crate::Result<RowChecksum>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let bound = self.bound_arguments()?;
+        let sql = bound.expand(&self.sql);
+        let rows = sqlx::query_with(&sql, bound.arguments).fetch(executor);
+        checksum_rows(rows).await
+    }
+
+    /// Returns an iterator that fetches the result set one page at a time using
+    /// `LIMIT ? OFFSET ?`.
     ///
-    /// Returns `Some(row)` if exactly one row matches, `None` if no rows match.
+    /// The template should have a total `ORDER BY` and must not contain a `LIMIT`
+    /// clause. The executor must be `Copy`, such as `&MySqlPool`, since it is used
+    /// once per page. For large tables prefer [`iter_pages_by_key`](Self::iter_pages_by_key).
     ///
-    /// # Errors
+    /// # Arguments
     ///
-    /// Returns an error if:
-    /// - More than one row is found
-    /// - The query fails
-    /// - The row cannot be converted to type `R`
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`
+    /// * `page_size` - Maximum number of rows per page (a value of `0` is treated as `1`)
     ///
     /// # Examples
     ///
@@ -267,42 +1679,454 @@ where
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
     /// let mut query = PreparedQueryAs::<User, _>::new(
-    ///     "SELECT id, name FROM users WHERE email = :email",
+    ///     "SELECT id, name FROM users WHERE active = :active ORDER BY id",
     ///     |q, key| match key {
-    ///         ":email" => q.bind("user@example.com"),
+    ///         ":active" => q.bind(true),
     ///         _ => q,
     ///     }
     /// )?;
     ///
-    /// match query.fetch_optional(&pool).await? {
-    ///     Some(user) => println!("Found user: {}", user.name),
-    ///     None => println!("User not found"),
+    /// let mut pages = query.iter_pages(&pool, 500);
+    /// while let Some(batch) = pages.next().await {
+    ///     for user in batch? {
+    ///         println!("{}: {}", user.id, user.name);
+    ///     }
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn fetch_optional<'e, E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    pub fn iter_pages<E>(&mut self, executor: E, page_size: u64) -> OffsetPages<'_, R, F, E>
     where
-        E: Executor<'e, Database = MySql>,
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+    {
+        OffsetPages::new(self, executor, page_size)
+    }
+
+    /// Returns an iterator that fetches the result set one page at a time by keyset.
+    ///
+    /// The template must compare the ordering key with `cursor_placeholder`, e.g.
+    /// `WHERE id > :after ORDER BY id`, and must not contain a `LIMIT` clause. The
+    /// first page is fetched with `start` bound to the cursor; every following page
+    /// binds the key of the last row of the previous page, as returned by `key`. The
+    /// binder is not called for the cursor placeholder.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`
+    /// * `page_size` - Maximum number of rows per page (a value of `0` is treated as `1`)
+    /// * `cursor_placeholder` - Placeholder the last key is bound to (e.g. `:after`)
+    /// * `start` - Cursor value for the first page
+    /// * `key` - Extracts the ordering key from a row
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `cursor_placeholder` does not appear in the template.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct Event {
+    ///     id: i64,
+    ///     kind: String,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<Event, _>::new(
+    ///     "SELECT id, kind FROM events WHERE kind = :kind AND id > :after ORDER BY id",
+    ///     |q, key| match key {
+    ///         ":kind" => q.bind("signup"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let mut pages = query.iter_pages_by_key(&pool, 1_000, ":after", 0i64, |e| e.id)?;
+    /// while let Some(batch) = pages.next().await {
+    ///     println!("Fetched {} events", batch?.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_pages_by_key<E, K, G>(
+        &mut self,
+        executor: E,
+        page_size: u64,
+        cursor_placeholder: &str,
+        start: K,
+        key: G,
+    ) -> crate::Result<KeysetPages<'_, R, F, E, K, G>>
+    where
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+        K: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send + 'static,
+        G: FnMut(&R) -> K,
+    {
+        if !self.order.iter().any(|k| k == cursor_placeholder) {
+            return Err(crate::Error::UnknownPlaceholder(cursor_placeholder.to_owned()));
+        }
+        Ok(KeysetPages::new(
+            self,
+            executor,
+            page_size,
+            cursor_placeholder.to_owned(),
+            start,
+            key,
+        ))
+    }
+
+    /// Returns an iterator that fetches the result set one page at a time by row number.
+    ///
+    /// The query is wrapped as a derived table whose rows are numbered with
+    /// `ROW_NUMBER() OVER (ORDER BY order_by)`, and each page selects the next range of
+    /// row numbers. Use this when the ordering cannot support a keyset seek; it needs
+    /// MySQL 8.0 or later. `order_by` is inserted verbatim and refers to the template's
+    /// output columns. The row number is returned as an extra trailing column, `__rn`,
+    /// which `FromRow` ignores. The template must not contain a `LIMIT` clause.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - A copyable SQLx executor such as `&MySqlPool`
+    /// * `order_by` - Total ordering of the rows, e.g. `score DESC, id`
+    /// * `page_size` - Maximum number of rows per page (a value of `0` is treated as `1`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::{MySqlPool, FromRow};
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// #[derive(FromRow)]
+    /// struct Player {
+    ///     id: i64,
+    ///     score: f64,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<Player, _>::new(
+    ///     "SELECT id, wins * 3 + draws AS score FROM players WHERE league = :league",
+    ///     |q, key| match key {
+    ///         ":league" => q.bind("premier"),
+    ///         _ => q,
+    ///     }
+    /// )?;
+    ///
+    /// let mut pages = query.iter_pages_windowed(&pool, "score DESC, id", 100);
+    /// while let Some(batch) = pages.next().await {
+    ///     for player in batch? {
+    ///         println!("{}: {}", player.id, player.score);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_pages_windowed<E>(
+        &mut self,
+        executor: E,
+        order_by: &str,
+        page_size: u64,
+    ) -> WindowPages<'_, R, F, E>
+    where
+        E: for<'c> Executor<'c, Database = MySql> + Copy,
+    {
+        WindowPages::new(self, executor, order_by, page_size)
+    }
+
+    /// Pairs this query with a fallback that runs when this one fails or times out.
+    ///
+    /// See [`FallbackQuery`] for configuring which errors trigger the fallback.
+    ///
+    /// # Arguments
+    ///
+    /// * `fallback` - Query returning the same row type, e.g. against a cached table
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx::FromRow;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(FromRow)]
+    /// struct Stats {
+    ///     visitors: i64,
+    /// }
+    ///
+    /// let query = PreparedQueryAs::<Stats, _>::new(
+    ///     "SELECT COUNT(DISTINCT visitor_id) AS visitors FROM visits WHERE day = :day",
+    ///     |q, key| match key {
+    ///         ":day" => q.bind("2024-06-01"),
+    ///         _ => q,
+    ///     }
+    /// )?
+    /// .with_fallback(PreparedQueryAs::new(
+    ///     "SELECT visitors FROM daily_stats WHERE day = :day",
+    ///     |q, key| match key {
+    ///         ":day" => q.bind("2024-06-01"),
+    ///         _ => q,
+    ///     }
+    /// )?)
+    /// .timeout(Duration::from_millis(500));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn with_fallback<G>(self, fallback: PreparedQueryAs<R, G>) -> FallbackQuery<R, F, G>
+    where
+        G: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
     {
+        FallbackQuery::new(self, fallback)
+    }
+
+    /// Binds the query's placeholders and returns the underlying sqlx [`QueryAs`]
+    /// instead of executing it, for sqlx APIs this crate does not wrap.
+    ///
+    /// See [`PreparedQuery::into_sqlx_query`](crate::PreparedQuery::into_sqlx_query);
+    /// the returned query likewise bypasses shutdown, budgets, the query's timeout,
+    /// concurrency limits and the query log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use futures_util::TryStreamExt;
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut query = PreparedQueryAs::<(i64,), _>::new(
+    ///     "SELECT id FROM users WHERE team = :team",
+    ///     |q, _| q.bind("search"),
+    /// )?;
+    /// let mut results = query.into_sqlx_query()?.fetch_many(&pool);
+    /// while let Some(result) = results.try_next().await? {
+    ///     // Either a query result or a row
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_sqlx_query(&mut self) -> crate::Result<QueryAs<'_, MySql, R, MySqlArguments>> {
+        let &mut PreparedQueryAs {
+            ref sql,
+            ref order,
+            ref mut binder,
+            ref checks,
+            ref mut expanded_sql,
+            ..
+        } = self;
+
+        let bound = bind_arguments_as(sql, order, binder, checks)?;
+        let sql = match bound.expand(sql) {
+            Cow::Borrowed(sql) => sql,
+            Cow::Owned(expanded) => {
+                *expanded_sql = expanded;
+                expanded_sql.as_str()
+            }
+        };
+        Ok(sqlx::query_as_with(sql, bound.arguments))
+    }
+
+    /// Returns the placeholder order, the binder and the bind checks, for binding onto
+    /// derived statements.
+    pub(crate) fn binder_parts(&mut self) -> (&[String], &mut F, &BindChecks) {
+        (&self.order, &mut self.binder, &self.checks)
+    }
+
+    /// Runs the binder and returns the resulting arguments, detached from any query.
+    ///
+    /// The binder only knows how to bind onto a `QueryAs<R>`, so this is how the
+    /// current binds are moved onto other statements such as `EXPLAIN`; expand their
+    /// SQL with [`Bound::expand`].
+    pub(crate) fn bound_arguments(&mut self) -> crate::Result<Bound> {
         let &mut PreparedQueryAs {
+            template: _,
             ref sql,
             ref order,
             ref mut binder,
             _pd,
+            tags: _,
+            ref checks,
+            timeout: _,
+            expanded_sql: _,
         } = self;
 
-        let mut q = sqlx::query_as(sql);
-        for key in order.iter() {
-            q = binder(q, key);
+        bind_arguments_as(sql, order, binder, checks)
+    }
+}
+
+impl<R> PreparedQueryAs<R, ParamsBinderAs<R>>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + 'static,
+{
+    /// Creates a new `PreparedQueryAs` whose placeholders are bound from `params`,
+    /// without a binder closure.
+    ///
+    /// See [`PreparedQuery::with_params`](crate::PreparedQuery::with_params).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if
+    /// `params` has a value for a placeholder the template does not use.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::{conditional::NamedBinds, PreparedQueryAs};
+    ///
+    /// let mut params = NamedBinds::new();
+    /// params.set(":tenant_id", 7_i64);
+    /// let query = PreparedQueryAs::<(i64, String), _>::with_params(
+    ///     "SELECT id, name FROM users WHERE tenant_id = :tenant_id",
+    ///     params,
+    /// )?;
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn with_params<T>(template: T, params: NamedBinds) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order_with_style(&template, PlaceholderStyle::Colon)?;
+        if let Some(name) = params.unknown_name(&order) {
+            return Err(crate::Error::UnknownPlaceholder(name.to_owned()));
         }
-        Ok(q.fetch_optional(executor).await?)
+        Self::new(template, Box::new(params.into_binder_as()) as ParamsBinderAs<R>)
+    }
+
+    /// Creates a new `PreparedQueryAs` whose placeholders are bound from the fields of a
+    /// serializable value.
+    ///
+    /// See [`PreparedQuery::from_serialize`](crate::PreparedQuery::from_serialize).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidParams`](crate::Error::InvalidParams) if `params` does not
+    /// serialize to a map, and
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if it has a key
+    /// the template does not use.
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<T, S>(template: T, params: &S) -> crate::Result<Self>
+    where
+        T: Into<String>,
+        S: serde::Serialize + ?Sized,
+    {
+        Self::with_params(template, NamedBinds::from_serialize(params)?)
+    }
+}
+
+impl<R> PreparedQueryAs<R, ParamsBinderAs<R>>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + 'static,
+{
+    /// Starts a [`PreparedQueryAsBuilder`] for `template`, whose values are given by
+    /// name instead of a binder closure.
+    ///
+    /// See [`PreparedQuery::builder`](crate::PreparedQuery::builder).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::PreparedQueryAs;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let names: Vec<(String,)> =
+    ///     PreparedQueryAs::builder("SELECT name FROM users WHERE tenant_id = :tenant_id")?
+    ///         .bind_named("tenant_id", 7_i64)
+    ///         .build()?
+    ///         .fetch_all(&pool)
+    ///         .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder<T>(template: T) -> crate::Result<PreparedQueryAsBuilder<R>>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order_with_style(&template, PlaceholderStyle::Colon)?;
+        Ok(PreparedQueryAsBuilder {
+            template,
+            order,
+            params: NamedBinds::new(),
+            _pd: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Collects values by placeholder name for a [`PreparedQueryAs`], checking them
+/// against the template on [`build`](Self::build).
+///
+/// Created by [`PreparedQueryAs::builder`].
+#[derive(Debug, Clone)]
+pub struct PreparedQueryAsBuilder<R> {
+    template: String,
+    order: Vec<String>,
+    params: NamedBinds,
+    _pd: std::marker::PhantomData<fn() -> R>,
+}
+
+impl<R> PreparedQueryAsBuilder<R>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + 'static,
+{
+    /// Sets the value of the placeholder `name`, with or without the leading `:`,
+    /// replacing any previous value.
+    pub fn bind_named<T: AnyBind + 'static>(mut self, name: &str, value: T) -> Self {
+        self.params.set(&placeholder_key(name), value);
+        self
+    }
+
+    /// Builds the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnboundPlaceholder`](crate::Error::UnboundPlaceholder) if a
+    /// placeholder of the template has no value, or
+    /// [`Error::UnknownPlaceholder`](crate::Error::UnknownPlaceholder) if a value was
+    /// given for a placeholder the template does not use.
+    pub fn build(self) -> crate::Result<PreparedQueryAs<R, ParamsBinderAs<R>>> {
+        self.params.check_exact(&self.order)?;
+        PreparedQueryAs::with_params(self.template, self.params)
+    }
+}
+
+impl<'a, R> PreparedQueryAs<R, StructBinderAs<'a, R>>
+where
+    for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + 'a,
+{
+    /// Creates a new `PreparedQueryAs` whose placeholders are bound from the fields of
+    /// `params`.
+    ///
+    /// See [`PreparedQuery::from_struct`](crate::PreparedQuery::from_struct).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed.
+    pub fn from_struct<T, P>(template: T, params: &'a P) -> crate::Result<Self>
+    where
+        T: Into<String>,
+        P: NamedParams + Sync,
+    {
+        let binder: StructBinderAs<'a, R> = Box::new(move |q, key| params.bind_field_as(q, key));
+        Self::new(template, binder)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Arguments;
 
     // Mock struct for testing (requires sqlx::FromRow)
     // In real tests, this would use a real database connection
@@ -335,7 +2159,91 @@ mod tests {
             |q, _| q,
         ).unwrap();
 
-        assert_eq!(query.order, vec![":id", ":name"]);
-        assert_eq!(query.sql, "SELECT id FROM users WHERE id = ? AND name = ?");
+        assert_eq!(*query.order, [":id", ":name"]);
+        assert_eq!(&*query.sql, "SELECT id FROM users WHERE id = ? AND name = ?");
+    }
+
+    #[tokio::test]
+    async fn test_iter_pages_by_key_requires_cursor_placeholder() {
+        #[derive(sqlx::FromRow)]
+        struct TestRow {
+            id: i64,
+        }
+
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://localhost/test").unwrap();
+        let mut query = PreparedQueryAs::<TestRow, _>::new(
+            "SELECT id FROM users WHERE id > :after ORDER BY id",
+            |q, _| q,
+        ).unwrap();
+
+        assert!(query.iter_pages_by_key(&pool, 10, ":after", 0i64, |r| r.id).is_ok());
+        assert!(matches!(
+            query.iter_pages_by_key(&pool, 10, ":cursor", 0i64, |r| r.id),
+            Err(crate::Error::UnknownPlaceholder(p)) if p == ":cursor"
+        ));
+    }
+
+    #[test]
+    fn test_overridden_arguments() {
+        let mut query = PreparedQueryAs::<(i64,), _>::with_params(
+            "SELECT id FROM orders WHERE customer = :customer AND status = :status",
+            NamedBinds::new(),
+        )
+        .unwrap();
+
+        let mut args = NamedBinds::new();
+        args.set(":customer", "alice").set(":status", "open");
+        assert_eq!(query.overridden_arguments(&args).unwrap().arguments.len(), 2);
+
+        args.remove(":status");
+        assert!(matches!(
+            query.overridden_arguments(&args),
+            Err(crate::Error::UnboundPlaceholder(key)) if key == ":status"
+        ));
+        args.set(":state", "open");
+        assert!(matches!(
+            query.overridden_arguments(&args),
+            Err(crate::Error::UnknownPlaceholder(key)) if key == ":state"
+        ));
+    }
+
+    #[test]
+    fn test_timeout_from_registry_entry() {
+        let entry = crate::registry::RegistryEntry::parse(
+            "recent",
+            "-- timeout: 2s\nSELECT id FROM events WHERE id > :id",
+        )
+        .unwrap();
+        let query = entry.prepare_as::<(i64,), _>(|q, _| q).unwrap();
+        assert_eq!(query.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(
+            &*query.sql,
+            "SELECT /*+ MAX_EXECUTION_TIME(2000) */ id FROM events WHERE id > ?"
+        );
+
+        let query = query.timeout(Duration::from_millis(500));
+        assert_eq!(query.timeout, Some(Duration::from_millis(500)));
+        assert_eq!(query.sql.matches("MAX_EXECUTION_TIME").count(), 1);
+    }
+
+    #[test]
+    fn test_binding_list_for_keys() {
+        let mut query = PreparedQueryAs::<(i64,), _>::new(
+            "SELECT id FROM t WHERE id IN (:ids) AND a = :a",
+            |q, key| match key {
+                ":a" => q.bind(1_i64),
+                _ => q,
+            },
+        )
+        .unwrap();
+        let keys = [3_i64, 1, 2];
+        let mut binder = binding_list_as(":ids", &keys, &mut query.binder);
+        let bound =
+            bind_arguments_as(&query.sql, &query.order, &mut binder, &query.checks).unwrap();
+        assert_eq!(
+            bound.expand(&query.sql),
+            "SELECT id FROM t WHERE id IN (?, ?, ?) AND a = ?"
+        );
+        assert_eq!(bound.arguments.len(), 4);
     }
 }
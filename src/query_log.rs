@@ -0,0 +1,319 @@
+//! Opt-in log of the named queries executed within a scope, such as one transaction.
+//!
+//! While a future runs inside [`scope`], every `execute` of a
+//! [`PreparedQuery`](crate::PreparedQuery) and every `fetch_all`, `fetch_one` and
+//! `fetch_optional` of a [`PreparedQueryAs`](crate::PreparedQueryAs) awaited by it is
+//! appended to the scope's [`QueryLog`] with its fingerprint, duration, row count and
+//! [`Tags`].
+//! [`with_logged_transaction`](crate::connection::with_logged_transaction) opens such a
+//! scope around a transaction.
+//!
+//! Queries run by tasks spawned from the scope are not logged.
+//!
+//! A log created with [`QueryLog::detect_n_plus_one`] also watches for the N+1 pattern:
+//! the same query fingerprint running with more than N distinct sets of parameters in
+//! one scope, typically one query per row of an earlier result. The first time a
+//! fingerprint crosses the threshold, a `tracing` warning is emitted with the
+//! fingerprint and the stack of the execution that crossed it.
+
+use crate::fingerprint::fingerprint;
+use crate::tags::Tags;
+use std::backtrace::Backtrace;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static CURRENT: QueryLog;
+}
+
+/// One query execution recorded in a [`QueryLog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedQuery {
+    /// Fingerprint of the executed SQL (see [`fingerprint`])
+    pub fingerprint: String,
+    /// The executed SQL, with positional placeholders
+    pub sql: String,
+    /// Time from sending the query to receiving the complete result
+    pub duration: Duration,
+    /// Rows returned, or affected for statements without a result set; `None` if the
+    /// query failed
+    pub rows: Option<u64>,
+    /// Hash of the bound parameter values; equal hashes mean the same parameters
+    pub params: u64,
+    /// Tags attached to the query
+    pub tags: Tags,
+}
+
+/// A shared, append-only list of executed queries.
+///
+/// Clones share the same entries.
+#[derive(Debug, Clone, Default)]
+pub struct QueryLog {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: Vec<LoggedQuery>,
+    n_plus_one: Option<usize>,
+    /// Distinct parameter hashes seen per fingerprint, tracked only when detecting N+1
+    params: BTreeMap<String, BTreeSet<u64>>,
+}
+
+impl QueryLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Warns when one fingerprint runs with more than `threshold` distinct sets of
+    /// parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::{query_log::{self, QueryLog}, PreparedQueryAs};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let log = QueryLog::new().detect_n_plus_one(10);
+    /// query_log::scope(log.clone(), async {
+    ///     for id in 1..=50_i64 {
+    ///         PreparedQueryAs::<(String,), _>::new(
+    ///             "SELECT name FROM users WHERE id = :id",
+    ///             |q, key| match key {
+    ///                 ":id" => q.bind(id),
+    ///                 _ => q,
+    ///             },
+    ///         )?
+    ///         .fetch_optional(&pool)
+    ///         .await?;
+    ///     }
+    ///     Ok::<_, sqlx_named_bind::Error>(())
+    /// })
+    /// .await?;
+    /// for (fingerprint, count) in log.n_plus_one() {
+    ///     eprintln!("{count} lookups of: {fingerprint}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn detect_n_plus_one(self, threshold: usize) -> Self {
+        self.lock().n_plus_one = Some(threshold);
+        self
+    }
+
+    /// Returns the fingerprints that ran with more distinct sets of parameters than
+    /// the [`detect_n_plus_one`](Self::detect_n_plus_one) threshold, with that count.
+    ///
+    /// Empty if detection is not enabled.
+    pub fn n_plus_one(&self) -> Vec<(String, usize)> {
+        let state = self.lock();
+        let Some(threshold) = state.n_plus_one else {
+            return Vec::new();
+        };
+        state
+            .params
+            .iter()
+            .filter(|(_, params)| params.len() > threshold)
+            .map(|(fingerprint, params)| (fingerprint.clone(), params.len()))
+            .collect()
+    }
+
+    /// Returns a copy of the entries, in execution order.
+    pub fn entries(&self) -> Vec<LoggedQuery> {
+        self.lock().entries.clone()
+    }
+
+    /// Returns the number of logged queries.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if no queries were logged.
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// Returns the total time spent in the logged queries.
+    pub fn total_duration(&self) -> Duration {
+        self.lock().entries.iter().map(|entry| entry.duration).sum()
+    }
+
+    fn push(&self, entry: LoggedQuery) {
+        let mut state = self.lock();
+        if let Some(threshold) = state.n_plus_one {
+            let params = state.params.entry(entry.fingerprint.clone()).or_default();
+            // Warns once per fingerprint, when it first crosses the threshold.
+            if params.insert(entry.params) && params.len() == threshold + 1 {
+                tracing::warn!(
+                    fingerprint = %entry.fingerprint,
+                    executions = params.len(),
+                    backtrace = %Backtrace::force_capture(),
+                    "possible N+1 query: same statement ran with more than {threshold} \
+                     distinct parameter sets in one scope"
+                );
+            }
+        }
+        state.entries.push(entry);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Runs `future`, logging the named queries it executes to `log`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{query_log::{self, QueryLog}, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let log = QueryLog::new();
+/// query_log::scope(log.clone(), async {
+///     PreparedQuery::new("DELETE FROM sessions WHERE user_id = :id", |q, key| match key {
+///         ":id" => q.bind(42),
+///         _ => q,
+///     })?
+///     .execute(&pool)
+///     .await
+/// })
+/// .await?;
+/// println!("ran {} queries in {:?}", log.len(), log.total_duration());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn scope<Fut: Future>(log: QueryLog, future: Fut) -> Fut::Output {
+    CURRENT.scope(log, future).await
+}
+
+/// Hashes bound parameter values for [`LoggedQuery::params`], or returns `None` outside
+/// a scope so that unlogged executions skip the work.
+pub(crate) fn digest(arguments: &impl Debug) -> Option<u64> {
+    CURRENT.try_with(|_| ()).ok()?;
+    let mut hasher = DefaultHasher::new();
+    format!("{arguments:?}").hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Awaits `future`, logging it to the current scope's log if there is one.
+///
+/// `params` is the [`digest`] of the bound arguments.
+pub(crate) async fn observe<T, Fut, N>(
+    sql: &str,
+    params: Option<u64>,
+    tags: &Tags,
+    future: Fut,
+    rows: N,
+) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+    N: FnOnce(&T) -> u64,
+{
+    let Ok(log) = CURRENT.try_with(QueryLog::clone) else {
+        return future.await;
+    };
+    let started = Instant::now();
+    let result = future.await;
+    log.push(LoggedQuery {
+        fingerprint: fingerprint(sql),
+        sql: sql.to_owned(),
+        duration: started.elapsed(),
+        rows: result.as_ref().ok().map(rows),
+        params: params.unwrap_or_default(),
+        tags: tags.clone(),
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_observe_logs_only_inside_scope() {
+        let log = QueryLog::new();
+        let mut tags = Tags::new();
+        tags.insert("team", "accounts");
+        scope(log.clone(), async {
+            observe(
+                "SELECT * FROM t WHERE id = ?",
+                digest(&1),
+                &tags,
+                async { Ok(vec![1, 2]) },
+                |rows| rows.len() as u64,
+            )
+            .await
+            .unwrap();
+            let failed: crate::Result<()> = observe(
+                "DELETE FROM t",
+                digest(&()),
+                &Tags::new(),
+                async { Err(crate::Error::DeadlineExceeded) },
+                |_| 0,
+            )
+            .await;
+            assert!(failed.is_err());
+        })
+        .await;
+        assert_eq!(digest(&1), None);
+        observe("SELECT 1", None, &tags, async { Ok(()) }, |_| 1)
+            .await
+            .unwrap();
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].fingerprint, "select * from t where id = ?");
+        assert_eq!(entries[0].rows, Some(2));
+        assert_eq!(entries[0].tags, tags);
+        assert_eq!(entries[1].rows, None);
+        assert!(entries[1].tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_n_plus_one_counts_distinct_params() {
+        let log = QueryLog::new().detect_n_plus_one(2);
+        scope(log.clone(), async {
+            for id in [1, 2, 2, 1, 3, 4] {
+                observe(
+                    "SELECT * FROM t WHERE id = ?",
+                    digest(&id),
+                    &Tags::new(),
+                    async { Ok(()) },
+                    |_| 1,
+                )
+                .await
+                .unwrap();
+            }
+            for _ in 0..5 {
+                observe(
+                    "SELECT * FROM u",
+                    digest(&()),
+                    &Tags::new(),
+                    async { Ok(()) },
+                    |_| 1,
+                )
+                .await
+                .unwrap();
+            }
+        })
+        .await;
+
+        assert_eq!(log.len(), 11);
+        assert_eq!(
+            log.n_plus_one(),
+            vec![("select * from t where id = ?".to_owned(), 4)]
+        );
+        assert!(QueryLog::new().n_plus_one().is_empty());
+    }
+}
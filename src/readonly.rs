@@ -0,0 +1,217 @@
+//! An executor wrapper that only lets read-only statements through.
+//!
+//! [`ReadOnly`] wraps any MySQL executor (a pool, connection or transaction) and checks
+//! every statement before it is sent: anything but a plain `SELECT`, `WITH ... SELECT`,
+//! `SHOW`, `EXPLAIN` or `DESCRIBE` fails with an error instead of reaching the server.
+//! Hand it to reporting or plugin code that should never write.
+//!
+//! The check is syntactic, made with the crate's tokenizer: a `SELECT` calling a stored
+//! function that writes is let through. Pair it with a read-only database user where
+//! that matters.
+
+use crate::kind::StatementKind;
+use crate::lexer::{tokenize, Token};
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::{FutureExt, StreamExt};
+use sqlx::mysql::{MySqlQueryResult, MySqlRow, MySqlStatement, MySqlTypeInfo};
+use sqlx::{Describe, Either, Execute, Executor, MySql};
+
+/// Statements besides `SELECT` that only read.
+const READ_STATEMENTS: &[&str] = &["SHOW", "EXPLAIN", "DESCRIBE", "DESC"];
+
+/// Keywords that make an otherwise reading statement write or lock rows.
+const WRITE_KEYWORDS: &[&str] = &["UPDATE", "DELETE", "INTO"];
+
+/// Returns `true` if every statement in `sql` only reads.
+///
+/// `SELECT ... INTO`, `SELECT ... FOR UPDATE` and `WITH ... UPDATE`/`DELETE` are not
+/// read-only, including when the clause is inside an executable `/*! ... */` comment,
+/// which MySQL runs as part of the statement.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::readonly::is_read_only;
+///
+/// assert!(is_read_only("SELECT id FROM users WHERE name = ?"));
+/// assert!(is_read_only("(SELECT 1) UNION (SELECT 2)"));
+/// assert!(!is_read_only("UPDATE users SET name = ?"));
+/// assert!(!is_read_only("SELECT 1; DROP TABLE users"));
+/// ```
+pub fn is_read_only(sql: &str) -> bool {
+    let tokens = tokenize(sql);
+    let mut statements = tokens.split(|token| token.is_punct(sql, ';')).peekable();
+    let mut any = false;
+    while let Some(statement) = statements.next() {
+        if statement.is_empty() && (any || statements.peek().is_some()) {
+            continue;
+        }
+        if !statement_is_read_only(sql, statement) {
+            return false;
+        }
+        any = true;
+    }
+    any
+}
+
+fn statement_is_read_only(sql: &str, tokens: &[Token]) -> bool {
+    let reads = match StatementKind::of_tokens(sql, tokens) {
+        StatementKind::Select => true,
+        StatementKind::Other => tokens.first().is_some_and(|first| {
+            READ_STATEMENTS
+                .iter()
+                .any(|keyword| first.is_keyword(sql, keyword))
+        }),
+        _ => false,
+    };
+    reads
+        && !tokens.iter().any(|token| {
+            WRITE_KEYWORDS
+                .iter()
+                .any(|keyword| token.is_keyword(sql, keyword))
+        })
+}
+
+fn check(sql: &str) -> Result<(), sqlx::Error> {
+    if is_read_only(sql) {
+        Ok(())
+    } else {
+        Err(sqlx::Error::InvalidArgument(format!(
+            "statement is not read-only: {sql}"
+        )))
+    }
+}
+
+/// An executor that rejects every statement [`is_read_only`] does not accept.
+///
+/// Rejected statements fail with `sqlx::Error::InvalidArgument`, surfaced by this
+/// crate as [`Error::Database`](crate::Error::Database).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{readonly::ReadOnly, PreparedQuery, PreparedQueryAs};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let reports = ReadOnly::new(&pool);
+///
+/// let names = PreparedQueryAs::<(String,), _>::new("SELECT name FROM users", |q, _| q)?
+///     .fetch_all(reports)
+///     .await?;
+///
+/// let wipe = PreparedQuery::new("DELETE FROM users", |q, _| q)?
+///     .execute(reports)
+///     .await;
+/// assert!(wipe.is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOnly<E>(E);
+
+impl<E> ReadOnly<E> {
+    /// Wraps `executor`.
+    pub fn new(executor: E) -> Self {
+        Self(executor)
+    }
+
+    /// Returns the wrapped executor.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<'c, E> Executor<'c> for ReadOnly<E>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    type Database = MySql;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxStream<'e, Result<Either<MySqlQueryResult, MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        match check(query.sql()) {
+            Ok(()) => self.0.fetch_many(query),
+            Err(e) => futures_util::stream::once(async { Err(e) }).boxed(),
+        }
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxFuture<'e, Result<Option<MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        match check(query.sql()) {
+            Ok(()) => self.0.fetch_optional(query),
+            Err(e) => futures_util::future::ready(Err(e)).boxed(),
+        }
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [MySqlTypeInfo],
+    ) -> BoxFuture<'e, Result<MySqlStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        match check(sql) {
+            Ok(()) => self.0.prepare_with(sql, parameters),
+            Err(e) => futures_util::future::ready(Err(e)).boxed(),
+        }
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<MySql>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.0.describe(sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_read_only() {
+        for sql in [
+            "SELECT * FROM users WHERE note = 'DELETE' -- UPDATE\n",
+            "with recent as (select id from orders) select * from recent;",
+            "SHOW TABLES",
+            "EXPLAIN SELECT * FROM users",
+            "SELECT `update` FROM t",
+            "SELECT /*+ MAX_EXECUTION_TIME(100) */ id FROM users /* FOR UPDATE */",
+            "SELECT /*!40001 SQL_NO_CACHE */ id FROM users",
+        ] {
+            assert!(is_read_only(sql), "{sql}");
+        }
+        for sql in [
+            "",
+            "INSERT INTO users (name) VALUES (?)",
+            "SELECT id INTO @id FROM users",
+            "SELECT * FROM users FOR UPDATE",
+            "WITH old AS (SELECT id FROM t) DELETE FROM t WHERE id IN (SELECT id FROM old)",
+            "SELECT 1; SET @x = 1",
+            "SELECT id FROM users /*! FOR UPDATE */",
+            "SELECT 1 /*! INTO OUTFILE '/tmp/x' */",
+            "SELECT 1 /*!50000 ; DROP TABLE users */",
+        ] {
+            assert!(!is_read_only(sql), "{sql}");
+        }
+    }
+}
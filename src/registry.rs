@@ -0,0 +1,1694 @@
+//! A registry of named SQL templates loaded from `.sql` files.
+//!
+//! Each file holds one query, or several in the style of yesql (see below). A query
+//! may start with a front-matter header of `--` comment lines in `key: value` form,
+//! which is parsed into a [`QueryMeta`]:
+//!
+//! ```sql
+//! -- name: find_active_users
+//! -- description: Active users of a tenant, newest first
+//! -- owner: accounts-team
+//! -- param: :tenant_id BIGINT
+//! -- param: :since DATETIME
+//! -- column: id BIGINT
+//! -- column: name VARCHAR
+//! -- timeout: 2s
+//! -- tag: criticality=high
+//! -- session: optimizer_switch=index_merge=off
+//! -- deprecated: find_users_seen_since
+//! SELECT id, name FROM users
+//! WHERE tenant_id = :tenant_id AND last_seen_at >= :since
+//! ORDER BY id DESC
+//! ```
+//!
+//! Every key is optional. The `timeout` is applied to every query prepared from the
+//! entry with [`PreparedQuery::timeout`]. The name defaults to the file name without its extension.
+//! When `param` lines are present they must list exactly the placeholders used by the
+//! template, so a template change that forgets its documentation fails at load time.
+//! `column` lines declare the result columns, which [`QueryRegistry::self_test`]
+//! compares with what the server reports.
+//!
+//! Related queries can share one file for review: every `-- name:` line after a query's
+//! SQL starts the next query, with the comment lines just above it as part of its front
+//! matter. [`QueryRepository`] is the same type under yesql's name.
+//!
+//! ```sql
+//! -- name: find_user
+//! SELECT id, name FROM users WHERE id = :id
+//!
+//! -- name: delete_user
+//! -- owner: accounts-team
+//! DELETE FROM users WHERE id = :id
+//! ```
+//!
+//! A `deprecated` key (optionally naming the replacement query) marks an entry as
+//! deprecated. Queries prepared from such an entry count each execution in
+//! [`RegistryEntry::deprecated_uses`] and report it through `tracing`, with a `uses`
+//! field holding the count: as a warning the first time, then at debug level. Remaining
+//! callers can thus be found before the query is removed.
+//!
+//! Shared predicates and column lists can live in their own file and be pulled in with
+//! an include directive on a line of its own:
+//!
+//! ```sql
+//! SELECT id, name FROM users
+//! WHERE tenant_id = :tenant_id
+//!   AND -- include: fragments/soft_delete_filter.sql
+//! ```
+//!
+//! The path is resolved relative to the including file, and included files may include
+//! others. The directive line is replaced by the fragment's text, so the fragment's
+//! placeholders join the parent's bind order where the directive stands. Include cycles
+//! are rejected at load time. Includes are only resolved for templates loaded from files.
+//!
+//! Values that must be literal in SQL, such as limits and schema names, can be written
+//! once as [`Constants`] and referenced as `{{MAX_RESULTS}}`. They are substituted when
+//! a template is registered; a template referencing a constant that is not in the
+//! registry's allow-list fails to load. Variables such as an environment's table prefix
+//! are referenced as `{env_prefix}` and substituted unquoted, so `{env_prefix}users`
+//! names the environment's `users` table.
+
+use crate::builder::{build_query, placeholder_order};
+use crate::lexer::{skip_line, skip_quoted};
+use crate::query::{check_names, Q};
+use crate::query_as::QA;
+use crate::session::SessionVars;
+use crate::tables::{referenced_tables, written_tables};
+use crate::tags::Tags;
+use crate::{PreparedQuery, PreparedQueryAs};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Column, Either, Executor, MySqlPool, TypeInfo};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A parameter declared in a template's front matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamMeta {
+    /// Placeholder name, including the leading `:`
+    pub name: String,
+    /// Declared SQL type (e.g. `BIGINT`), if given
+    pub sql_type: Option<String>,
+}
+
+/// A result column declared in a template's front matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMeta {
+    /// Column name, as returned by the query
+    pub name: String,
+    /// Declared SQL type (e.g. `BIGINT`), if given
+    pub sql_type: Option<String>,
+}
+
+/// Metadata parsed from a template's front-matter header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryMeta {
+    /// Name the query is registered under
+    pub name: String,
+    /// Human-readable description
+    pub description: Option<String>,
+    /// Team or person responsible for the query
+    pub owner: Option<String>,
+    /// Declared parameters, in declaration order
+    pub params: Vec<ParamMeta>,
+    /// Declared result columns, in declaration order
+    pub columns: Vec<ColumnMeta>,
+    /// Maximum time the query is expected to run
+    pub timeout: Option<Duration>,
+    /// Set if the query is deprecated
+    pub deprecation: Option<Deprecation>,
+    /// Tags attached to every query prepared from the entry
+    pub tags: Tags,
+    /// Session variables the query needs, applied with
+    /// [`with_session`](crate::session::with_session)
+    pub session: SessionVars,
+}
+
+/// Marks a registry entry as deprecated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    /// Name of the query callers should use instead, if any
+    pub replacement: Option<String>,
+}
+
+/// The value of a template constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstValue {
+    /// An integer, written as a numeric literal
+    Int(i64),
+    /// An identifier such as a schema or table name, written quoted in backticks
+    Ident(String),
+}
+
+impl ConstValue {
+    fn render(&self, name: &str) -> crate::Result<String> {
+        match self {
+            Self::Int(value) => Ok(value.to_string()),
+            Self::Ident(ident) => {
+                if ident.is_empty()
+                    || !ident
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "constant '{name}' is not a valid identifier: '{ident}'"
+                    )));
+                }
+                Ok(format!("`{ident}`"))
+            }
+        }
+    }
+}
+
+/// The allow-listed constants a [`QueryRegistry`] substitutes for `{{NAME}}` references,
+/// and variables it substitutes for `{name}` references.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::registry::{Constants, QueryRegistry};
+///
+/// let mut registry = QueryRegistry::with_constants(
+///     Constants::new().int("MAX_RESULTS", 100).ident("SCHEMA", "app"),
+/// );
+/// let entry = registry.add(
+///     "list_users",
+///     "SELECT id FROM {{SCHEMA}}.users WHERE tenant_id = :tenant_id LIMIT {{MAX_RESULTS}}",
+/// )?;
+/// assert_eq!(entry.sql(), "SELECT id FROM `app`.users WHERE tenant_id = ? LIMIT 100");
+///
+/// assert!(registry.add("broken", "SELECT id FROM users LIMIT {{MAX_ROWS}}").is_err());
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Constants {
+    values: BTreeMap<String, ConstValue>,
+    vars: BTreeMap<String, String>,
+}
+
+impl Constants {
+    /// Creates an empty set of constants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an integer constant.
+    pub fn int(mut self, name: &str, value: i64) -> Self {
+        self.values.insert(name.to_owned(), ConstValue::Int(value));
+        self
+    }
+
+    /// Adds an identifier constant. It must consist of ASCII letters, digits, `_` and
+    /// `$`, which is checked when a template using it is registered.
+    pub fn ident(mut self, name: &str, value: &str) -> Self {
+        self.values
+            .insert(name.to_owned(), ConstValue::Ident(value.to_owned()));
+        self
+    }
+
+    /// Adds a variable, substituted for `{name}` without quotes so it can form part of
+    /// an identifier, e.g. a per-environment table prefix in `{env_prefix}users`.
+    ///
+    /// It must consist of ASCII letters, digits, `_` and `$`, and may be empty, which is
+    /// checked when a template using it is registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::registry::{Constants, QueryRegistry};
+    ///
+    /// let constants = Constants::new().var("env_prefix", "blue_");
+    /// let mut registry = QueryRegistry::with_constants(constants);
+    /// let entry = registry.add("find_user", "SELECT id FROM {env_prefix}users WHERE id = :id")?;
+    /// assert_eq!(entry.sql(), "SELECT id FROM blue_users WHERE id = ?");
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.to_owned(), value.to_owned());
+        self
+    }
+
+    /// Adds a variable for each field of `config`, which must serialize to a map such as
+    /// a struct, with string or integer values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`](crate::Error::Json) if `config` fails to serialize, or
+    /// [`Error::InvalidMetadata`](crate::Error::InvalidMetadata) if it is not a map or a
+    /// field is neither a string nor an integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::registry::Constants;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct Deployment {
+    ///     env_prefix: &'static str,
+    ///     shard: u32,
+    /// }
+    ///
+    /// let constants = Constants::new().vars_from(&Deployment { env_prefix: "green_", shard: 3 })?;
+    /// assert_eq!(constants.get_var("shard"), Some("3"));
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn vars_from<S: serde::Serialize + ?Sized>(mut self, config: &S) -> crate::Result<Self> {
+        let serde_json::Value::Object(fields) = serde_json::to_value(config)? else {
+            return Err(crate::Error::InvalidMetadata(
+                "template variables must serialize to a map of names to values".to_owned(),
+            ));
+        };
+        for (name, value) in fields {
+            let value = match value {
+                serde_json::Value::String(value) => value,
+                serde_json::Value::Number(number) if number.is_i64() || number.is_u64() => {
+                    number.to_string()
+                }
+                other => {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "template variable '{name}' must be a string or an integer, not {other}"
+                    )))
+                }
+            };
+            self.vars.insert(name, value);
+        }
+        Ok(self)
+    }
+
+    /// Returns the value of the constant `name`.
+    pub fn get(&self, name: &str) -> Option<&ConstValue> {
+        self.values.get(name)
+    }
+
+    /// Returns the value of the variable `name`.
+    pub fn get_var(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    /// Replaces every `{{NAME}}` and `{name}` outside string literals and comments in
+    /// `source`.
+    fn substitute(&self, query: &str, source: &str) -> crate::Result<String> {
+        let bytes = source.as_bytes();
+        let mut out = String::with_capacity(source.len());
+        let mut copied = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i, quote),
+                b'#' => i = skip_line(bytes, i),
+                b'-' if bytes[i..].starts_with(b"--") => i = skip_line(bytes, i),
+                b'{' if bytes[i..].starts_with(b"{{") => {
+                    let Some(len) = source[i + 2..].find("}}") else {
+                        return Err(crate::Error::InvalidMetadata(format!(
+                            "query '{query}' has an unterminated constant reference"
+                        )));
+                    };
+                    let name = source[i + 2..i + 2 + len].trim();
+                    let value = self.get(name).ok_or_else(|| {
+                        crate::Error::InvalidMetadata(format!(
+                            "query '{query}' uses unknown constant '{name}'"
+                        ))
+                    })?;
+                    out.push_str(&source[copied..i]);
+                    out.push_str(&value.render(name)?);
+                    i += 2 + len + 2;
+                    copied = i;
+                }
+                b'{' if i == 0 || bytes[i - 1] != b'$' => {
+                    let mut end = i + 1;
+                    while end < bytes.len()
+                        && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_')
+                    {
+                        end += 1;
+                    }
+                    if end == i + 1 || bytes.get(end) != Some(&b'}') {
+                        i += 1;
+                        continue;
+                    }
+                    let name = &source[i + 1..end];
+                    let value = self.get_var(name).ok_or_else(|| {
+                        crate::Error::InvalidMetadata(format!(
+                            "query '{query}' uses unknown variable '{name}'"
+                        ))
+                    })?;
+                    if !value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                    {
+                        return Err(crate::Error::InvalidMetadata(format!(
+                            "variable '{name}' is not a valid identifier part: '{value}'"
+                        )));
+                    }
+                    out.push_str(&source[copied..i]);
+                    out.push_str(value);
+                    i = end + 1;
+                    copied = i;
+                }
+                _ => i += 1,
+            }
+        }
+        out.push_str(&source[copied..]);
+        Ok(out)
+    }
+}
+
+/// A template registered in a [`QueryRegistry`].
+#[derive(Debug, Clone)]
+pub struct RegistryEntry {
+    meta: QueryMeta,
+    template: String,
+    sql: String,
+    order: Vec<String>,
+    includes: Vec<PathBuf>,
+    tables: Vec<String>,
+    written_tables: Vec<String>,
+    deprecated_uses: Arc<AtomicU64>,
+}
+
+impl RegistryEntry {
+    /// Parses a template source with an optional front-matter header.
+    ///
+    /// `default_name` is used when the header has no `name` key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMetadata`](crate::Error::InvalidMetadata) if the header
+    /// is malformed or its declared parameters do not match the template, or an error
+    /// if the template cannot be parsed.
+    pub fn parse(default_name: &str, source: &str) -> crate::Result<Self> {
+        let (meta, template) = parse_front_matter(default_name, source)?;
+        let order = placeholder_order(template)?;
+
+        if !meta.params.is_empty() {
+            for key in &order {
+                if !meta.params.iter().any(|p| p.name == *key) {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "query '{}' uses undeclared parameter '{key}'",
+                        meta.name
+                    )));
+                }
+            }
+            for param in &meta.params {
+                if !order.contains(&param.name) {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "query '{}' declares unused parameter '{}'",
+                        meta.name, param.name
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            meta,
+            sql: build_query(template)?,
+            template: template.to_owned(),
+            order,
+            includes: Vec::new(),
+            tables: referenced_tables(template),
+            written_tables: written_tables(template),
+            deprecated_uses: Arc::default(),
+        })
+    }
+
+    /// Returns the name the entry is registered under.
+    pub fn name(&self) -> &str {
+        &self.meta.name
+    }
+
+    /// Returns the metadata parsed from the front matter.
+    pub fn meta(&self) -> &QueryMeta {
+        &self.meta
+    }
+
+    /// Returns the template with named placeholders, without the front matter.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the converted SQL with positional placeholders.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the placeholders in the order they are bound.
+    pub fn placeholders(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Checks that `provided`, the names the caller binds, are exactly the placeholders
+    /// of the query, with or without the leading `:`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PlaceholderMismatch`](crate::Error::PlaceholderMismatch) listing
+    /// the missing and extra names.
+    pub fn validate_bindings(&self, provided: &[&str]) -> crate::Result<()> {
+        check_names(&self.order, provided)
+    }
+
+    /// Returns the canonical paths of the files included by the template, directly or
+    /// through other includes, in the order they were first included.
+    pub fn includes(&self) -> &[PathBuf] {
+        &self.includes
+    }
+
+    /// Returns the tables the template references, as found by
+    /// [`referenced_tables`].
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+
+    /// Returns the tables the template modifies, as found by [`written_tables`].
+    pub fn written_tables(&self) -> &[String] {
+        &self.written_tables
+    }
+
+    /// Returns how many times queries prepared from this entry while it was deprecated
+    /// were executed.
+    ///
+    /// The count is shared by clones of the entry.
+    pub fn deprecated_uses(&self) -> u64 {
+        self.deprecated_uses.load(Ordering::Relaxed)
+    }
+
+    /// Creates a [`PreparedQuery`] for this entry, tagged with the entry's tags and
+    /// limited to the entry's `timeout`.
+    ///
+    /// If the entry is deprecated, every execution of the query is counted and reported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed.
+    pub fn prepare<F>(&self, binder: F) -> crate::Result<PreparedQuery<F>>
+    where
+        F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+    {
+        let query = PreparedQuery::new(self.template.as_str(), binder)?
+            .with_tags(&self.meta.tags)
+            .deprecation(self.deprecated_use());
+        Ok(match self.meta.timeout {
+            Some(timeout) => query.timeout(timeout),
+            None => query,
+        })
+    }
+
+    /// Creates a [`PreparedQueryAs`] for this entry, tagged with the entry's tags and
+    /// limited to the entry's `timeout`.
+    ///
+    /// If the entry is deprecated, every execution of the query is counted and reported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed.
+    pub fn prepare_as<R, F>(&self, binder: F) -> crate::Result<PreparedQueryAs<R, F>>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        let query = PreparedQueryAs::new(self.template.as_str(), binder)?
+            .with_tags(&self.meta.tags)
+            .deprecation(self.deprecated_use());
+        Ok(match self.meta.timeout {
+            Some(timeout) => query.timeout(timeout),
+            None => query,
+        })
+    }
+
+    /// Returns the marker counting the executions of queries prepared from the entry,
+    /// if it is deprecated.
+    fn deprecated_use(&self) -> Option<DeprecatedUse> {
+        let deprecation = self.meta.deprecation.as_ref()?;
+        Some(DeprecatedUse {
+            query: self.meta.name.clone(),
+            replacement: deprecation.replacement.clone(),
+            uses: Arc::clone(&self.deprecated_uses),
+        })
+    }
+}
+
+/// Carried by a query prepared from a deprecated [`RegistryEntry`], so each execution
+/// is counted in the entry's [`deprecated_uses`](RegistryEntry::deprecated_uses).
+#[derive(Debug, Clone)]
+pub(crate) struct DeprecatedUse {
+    query: String,
+    replacement: Option<String>,
+    uses: Arc<AtomicU64>,
+}
+
+impl DeprecatedUse {
+    /// Counts one execution and reports it, as a warning the first time.
+    pub(crate) fn record(&self) {
+        let uses = self.uses.fetch_add(1, Ordering::Relaxed) + 1;
+        let replacement = self.replacement.as_deref().unwrap_or_default();
+        if uses == 1 {
+            tracing::warn!(query = %self.query, replacement, uses, "deprecated query used");
+        } else {
+            tracing::debug!(query = %self.query, replacement, uses, "deprecated query used");
+        }
+    }
+}
+
+/// Named SQL templates, usually loaded from a directory of `.sql` files.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{MySqlPool, FromRow};
+/// use sqlx_named_bind::registry::QueryRegistry;
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let registry = QueryRegistry::load_dir("sql/queries")?;
+///
+/// let entry = registry.get("find_active_users").expect("query is registered");
+/// println!("{:?} (owner: {:?})", entry.meta().description, entry.meta().owner);
+///
+/// let users = entry
+///     .prepare_as::<User, _>(|q, key| match key {
+///         ":tenant_id" => q.bind(7),
+///         ":since" => q.bind("2024-01-01"),
+///         _ => q,
+///     })?
+///     .fetch_all(&pool)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryRegistry {
+    entries: BTreeMap<String, RegistryEntry>,
+    constants: Constants,
+}
+
+/// The name yesql-style loaders use for a [`QueryRegistry`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx_named_bind::registry::QueryRepository;
+///
+/// let queries = QueryRepository::load_dir("sql")?;
+/// let find_user = queries.get("find_user").expect("query is defined in sql/users.sql");
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+pub type QueryRepository = QueryRegistry;
+
+impl QueryRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty registry that substitutes `constants` into the templates
+    /// registered with it.
+    ///
+    /// Templates referencing any other `{{NAME}}` or `{name}` fail to register.
+    pub fn with_constants(constants: Constants) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            constants,
+        }
+    }
+
+    /// Loads every `.sql` file directly inside `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a file cannot be read, or a template is
+    /// invalid or registered twice.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> crate::Result<Self> {
+        Self::load_dir_with_constants(dir, Constants::new())
+    }
+
+    /// Loads every `.sql` file directly inside `dir`, substituting `constants`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a file cannot be read, or a template is
+    /// invalid, registered twice or references an unknown constant or variable.
+    pub fn load_dir_with_constants<P: AsRef<Path>>(
+        dir: P,
+        constants: Constants,
+    ) -> crate::Result<Self> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "sql") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut registry = Self::with_constants(constants);
+        for path in paths {
+            registry.load_file(path)?;
+        }
+        Ok(registry)
+    }
+
+    /// Loads one `.sql` file, named after the file unless its front matter says otherwise.
+    ///
+    /// `-- include:` directives in the file are resolved first. A file holding several
+    /// queries, each starting at a `-- name:` line, registers all of them and returns
+    /// the first; each one records every file the whole file includes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or a template is invalid or
+    /// registered twice, and [`Error::InvalidMetadata`](crate::Error::InvalidMetadata)
+    /// if an included file is missing or includes form a cycle.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> crate::Result<&RegistryEntry> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        let mut includes = Vec::new();
+        let source = resolve_includes(path, &source, &mut Vec::new(), &mut includes)?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let source = self.constants.substitute(stem, &source)?;
+        let mut first = None;
+        for query in split_queries(&source) {
+            let mut entry = RegistryEntry::parse(stem, query)?;
+            entry.includes = includes.clone();
+            let name = self.insert(entry)?.name().to_owned();
+            first.get_or_insert(name);
+        }
+        let first = first.unwrap_or_default();
+        Ok(&self.entries[&first])
+    }
+
+    /// Registers a template given as a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template is invalid, registered twice or references an
+    /// unknown constant or variable.
+    pub fn add(&mut self, name: &str, source: &str) -> crate::Result<&RegistryEntry> {
+        let source = self.constants.substitute(name, source)?;
+        self.insert(RegistryEntry::parse(name, &source)?)
+    }
+
+    /// Marks the entry registered under `name` as deprecated.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMetadata`](crate::Error::InvalidMetadata) if no entry is
+    /// registered under `name`, or `replacement` is given but not registered.
+    pub fn deprecate(&mut self, name: &str, replacement: Option<&str>) -> crate::Result<()> {
+        if let Some(replacement) = replacement {
+            if !self.entries.contains_key(replacement) {
+                return Err(crate::Error::InvalidMetadata(format!(
+                    "replacement query '{replacement}' is not registered"
+                )));
+            }
+        }
+        let entry = self.entries.get_mut(name).ok_or_else(|| {
+            crate::Error::InvalidMetadata(format!("query '{name}' is not registered"))
+        })?;
+        entry.meta.deprecation = Some(Deprecation {
+            replacement: replacement.map(str::to_owned),
+        });
+        Ok(())
+    }
+
+    /// Returns the entry registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&RegistryEntry> {
+        self.entries.get(name)
+    }
+
+    /// Returns every entry, ordered by name.
+    pub fn entries(&self) -> impl Iterator<Item = &RegistryEntry> {
+        self.entries.values()
+    }
+
+    /// Returns the number of registered entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no entries are registered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Prepares every registered statement on the pool's minimum number of connections
+    /// (at least one).
+    ///
+    /// See [`prepare_all_on`](Self::prepare_all_on).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a connection cannot be acquired or a statement fails to
+    /// prepare.
+    pub async fn prepare_all(&self, pool: &MySqlPool) -> crate::Result<usize> {
+        let connections = pool.options().get_min_connections().max(1) as usize;
+        self.prepare_all_on(pool, connections).await
+    }
+
+    /// Prepares every registered statement on `connections` connections of `pool`.
+    ///
+    /// Prepared statements are cached per connection, so the first execution of each
+    /// query on these connections skips the prepare round trip. Call this at startup to
+    /// pay that cost before serving traffic. All `connections` are held at once so that
+    /// each one is distinct; keep the number at or below the pool's maximum.
+    ///
+    /// Returns the number of statements prepared.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a connection cannot be acquired or a statement fails to
+    /// prepare, e.g. because it references a missing table.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::registry::QueryRegistry;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let registry = QueryRegistry::load_dir("queries")?;
+    /// let prepared = registry.prepare_all_on(&pool, 4).await?;
+    /// println!("warmed up {prepared} statements");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn prepare_all_on(
+        &self,
+        pool: &MySqlPool,
+        connections: usize,
+    ) -> crate::Result<usize> {
+        let mut held = Vec::with_capacity(connections);
+        for _ in 0..connections {
+            held.push(pool.acquire().await?);
+        }
+        let prepared = held.iter_mut().map(|conn| async move {
+            for entry in self.entries.values() {
+                conn.prepare(entry.sql()).await?;
+            }
+            Ok::<_, crate::Error>(self.entries.len())
+        });
+        Ok(futures_util::future::try_join_all(prepared)
+            .await?
+            .into_iter()
+            .sum())
+    }
+
+    /// Describes every registered statement on one connection of `pool` and checks the
+    /// result against its entry, so templates broken by a schema change fail a deploy
+    /// at startup instead of a user request.
+    ///
+    /// Each statement must be accepted by the server and take one parameter per
+    /// placeholder. If the entry declares `column` lines, the statement must return
+    /// exactly those columns, and a column with a declared type must have a compatible
+    /// one: integer, decimal, floating-point, text, binary and date-time types are each
+    /// compatible with the others of their kind. Statements are described, not run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a connection cannot be acquired. Problems with individual
+    /// queries are collected in the report; use [`SelfTestReport::ensure_ok`] to turn
+    /// them into an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::registry::QueryRegistry;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let registry = QueryRegistry::load_dir("queries")?;
+    /// registry.self_test(&pool).await?.ensure_ok()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn self_test(&self, pool: &MySqlPool) -> crate::Result<SelfTestReport> {
+        let mut conn = pool.acquire().await?;
+        let mut report = SelfTestReport {
+            checked: self.entries.len(),
+            failures: Vec::new(),
+        };
+        for entry in self.entries.values() {
+            let problems = match (&mut *conn).describe(entry.sql()).await {
+                Ok(described) => {
+                    let parameters = described.parameters().map(|params| match params {
+                        Either::Left(types) => types.len(),
+                        Either::Right(count) => count,
+                    });
+                    let columns: Vec<(&str, &str)> = described
+                        .columns()
+                        .iter()
+                        .map(|column| (column.name(), column.type_info().name()))
+                        .collect();
+                    describe_problems(entry, parameters, &columns)
+                }
+                Err(e) => vec![format!("cannot be described: {e}")],
+            };
+            report
+                .failures
+                .extend(problems.into_iter().map(|problem| SelfTestFailure {
+                    query: entry.name().to_owned(),
+                    problem,
+                }));
+        }
+        Ok(report)
+    }
+
+    /// Returns which included files and tables each registered query depends on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::registry::QueryRegistry;
+    ///
+    /// let mut registry = QueryRegistry::new();
+    /// registry.add("find_user", "SELECT id, name FROM users WHERE id = :id")?;
+    /// registry.add("user_orders", "SELECT o.id FROM orders o JOIN users u ON u.id = o.user_id")?;
+    ///
+    /// let graph = registry.dependency_graph();
+    /// assert_eq!(graph.dependents_of_table("users"), vec!["find_user", "user_orders"]);
+    /// assert_eq!(graph.dependencies("user_orders").unwrap().tables, vec!["orders", "users"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph {
+            queries: self
+                .entries
+                .iter()
+                .map(|(name, entry)| {
+                    let dependencies = Dependencies {
+                        fragments: entry.includes.clone(),
+                        tables: entry.tables.clone(),
+                    };
+                    (name.clone(), dependencies)
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns, for every table referenced by a registered query, the queries using it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::registry::QueryRegistry;
+    ///
+    /// let mut registry = QueryRegistry::new();
+    /// registry.add("find_user", "SELECT id, name FROM users WHERE id = :id")?;
+    /// registry.add("rename_user", "UPDATE users SET name = :name WHERE id = :id")?;
+    ///
+    /// let inventory = registry.table_inventory();
+    /// assert_eq!(inventory["users"].queries, vec!["find_user", "rename_user"]);
+    /// assert_eq!(inventory["users"].writers, vec!["rename_user"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn table_inventory(&self) -> BTreeMap<String, TableUsage> {
+        let mut inventory: BTreeMap<String, TableUsage> = BTreeMap::new();
+        for (name, entry) in &self.entries {
+            for table in &entry.tables {
+                let usage = inventory.entry(table.clone()).or_default();
+                usage.queries.push(name.clone());
+                if entry.written_tables.contains(table) {
+                    usage.writers.push(name.clone());
+                }
+            }
+        }
+        inventory
+    }
+
+    fn insert(&mut self, entry: RegistryEntry) -> crate::Result<&RegistryEntry> {
+        match self.entries.entry(entry.name().to_owned()) {
+            std::collections::btree_map::Entry::Occupied(existing) => {
+                Err(crate::Error::InvalidMetadata(format!(
+                    "query '{}' is registered twice",
+                    existing.key()
+                )))
+            }
+            std::collections::btree_map::Entry::Vacant(slot) => Ok(slot.insert(entry)),
+        }
+    }
+}
+
+/// The registered queries that use one table, ordered by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableUsage {
+    /// Every query referencing the table
+    pub queries: Vec<String>,
+    /// The queries that modify the table
+    pub writers: Vec<String>,
+}
+
+/// The fragments and tables one query depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    /// Canonical paths of the included files (see [`RegistryEntry::includes`])
+    pub fragments: Vec<PathBuf>,
+    /// Referenced tables (see [`RegistryEntry::tables`])
+    pub tables: Vec<String>,
+}
+
+/// The dependencies of every query in a registry, for answering "what breaks if this
+/// table or fragment changes" before a migration.
+///
+/// Created by [`QueryRegistry::dependency_graph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    queries: BTreeMap<String, Dependencies>,
+}
+
+impl DependencyGraph {
+    /// Returns the dependencies of the query registered under `name`.
+    pub fn dependencies(&self, name: &str) -> Option<&Dependencies> {
+        self.queries.get(name)
+    }
+
+    /// Returns every query with its dependencies, ordered by name.
+    pub fn queries(&self) -> impl Iterator<Item = (&str, &Dependencies)> {
+        self.queries
+            .iter()
+            .map(|(name, deps)| (name.as_str(), deps))
+    }
+
+    /// Returns the names of the queries that include `fragment`, directly or through
+    /// other includes.
+    ///
+    /// `fragment` is canonicalized when it exists, so relative paths work.
+    pub fn dependents_of_fragment<P: AsRef<Path>>(&self, fragment: P) -> Vec<&str> {
+        let fragment = fragment.as_ref();
+        let fragment = fragment
+            .canonicalize()
+            .unwrap_or_else(|_| fragment.to_owned());
+        self.dependents(|deps| deps.fragments.contains(&fragment))
+    }
+
+    /// Returns the names of the queries that reference `table`.
+    ///
+    /// `table` is compared exactly, so a query referencing `shop.orders` is not a
+    /// dependent of `orders`.
+    pub fn dependents_of_table(&self, table: &str) -> Vec<&str> {
+        self.dependents(|deps| deps.tables.iter().any(|t| t == table))
+    }
+
+    fn dependents(&self, depends: impl Fn(&Dependencies) -> bool) -> Vec<&str> {
+        self.queries
+            .iter()
+            .filter(|(_, deps)| depends(deps))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// The outcome of [`QueryRegistry::self_test`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// Number of queries checked
+    pub checked: usize,
+    /// Every problem found, ordered by query name
+    pub failures: Vec<SelfTestFailure>,
+}
+
+/// A problem [`QueryRegistry::self_test`] found with one query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestFailure {
+    /// Name of the query
+    pub query: String,
+    /// What is wrong with it
+    pub problem: String,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Fails with [`Error::SelfTestFailed`](crate::Error::SelfTestFailed), listing every
+    /// problem, if any were found.
+    pub fn ensure_ok(&self) -> crate::Result<()> {
+        if self.is_ok() {
+            return Ok(());
+        }
+        Err(crate::Error::SelfTestFailed(self.to_string()))
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "{} queries ok", self.checked);
+        }
+        for (i, failure) in self.failures.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "query '{}': {}", failure.query, failure.problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the parameter count and `(name, type)` result columns the server reported
+/// for `entry` with its placeholders and declared columns.
+fn describe_problems(
+    entry: &RegistryEntry,
+    parameters: Option<usize>,
+    columns: &[(&str, &str)],
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(parameters) = parameters {
+        if parameters != entry.order.len() {
+            problems.push(format!(
+                "server expects {parameters} parameters but the template has {} placeholders",
+                entry.order.len()
+            ));
+        }
+    }
+    let declared = &entry.meta.columns;
+    if declared.is_empty() {
+        return problems;
+    }
+    for column in declared {
+        let Some((_, actual)) = columns.iter().find(|(name, _)| *name == column.name) else {
+            problems.push(format!("declared column '{}' is not returned", column.name));
+            continue;
+        };
+        if let Some(sql_type) = &column.sql_type {
+            if type_family(sql_type) != type_family(actual) {
+                problems.push(format!(
+                    "column '{}' is declared {sql_type} but returned as {actual}",
+                    column.name
+                ));
+            }
+        }
+    }
+    for (name, _) in columns {
+        if !declared.iter().any(|column| column.name == *name) {
+            problems.push(format!("returned column '{name}' is not declared"));
+        }
+    }
+    problems
+}
+
+/// Groups MySQL type names whose values decode into the same kinds of Rust values.
+fn type_family(sql_type: &str) -> String {
+    let base = sql_type
+        .split(['(', ' '])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let family = match base.as_str() {
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "INTEGER" | "BIGINT" | "BOOL"
+        | "BOOLEAN" => "integer",
+        "DECIMAL" | "DEC" | "NUMERIC" => "decimal",
+        "FLOAT" | "DOUBLE" | "REAL" => "float",
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
+            "text"
+        }
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => "binary",
+        "DATETIME" | "TIMESTAMP" => "datetime",
+        _ => return base,
+    };
+    family.to_owned()
+}
+
+/// Replaces every `-- include: path` line of `source`, read from `path`, with the
+/// included file's text. `stack` holds the canonical paths of the files being
+/// resolved, to detect cycles; the canonical path of every included file is added to
+/// `includes`.
+fn resolve_includes(
+    path: &Path,
+    source: &str,
+    stack: &mut Vec<PathBuf>,
+    includes: &mut Vec<PathBuf>,
+) -> crate::Result<String> {
+    let canonical = path.canonicalize()?;
+    if let Some(start) = stack.iter().position(|p| *p == canonical) {
+        let cycle: Vec<String> = stack[start..]
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(crate::Error::InvalidMetadata(format!(
+            "include cycle: {}",
+            cycle.join(" -> ")
+        )));
+    }
+    stack.push(canonical);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut resolved = String::with_capacity(source.len());
+    for line in source.split_inclusive('\n') {
+        let Some((prefix, target)) = include_directive(line) else {
+            resolved.push_str(line);
+            continue;
+        };
+        let included_path = dir.join(target);
+        let included = std::fs::read_to_string(&included_path).map_err(|e| {
+            crate::Error::InvalidMetadata(format!(
+                "cannot include '{}' from '{}': {e}",
+                included_path.display(),
+                path.display()
+            ))
+        })?;
+        if let Ok(canonical) = included_path.canonicalize() {
+            if !includes.contains(&canonical) {
+                includes.push(canonical);
+            }
+        }
+        resolved.push_str(prefix);
+        resolved.push_str(resolve_includes(&included_path, &included, stack, includes)?.trim_end());
+        if line.ends_with('\n') {
+            resolved.push('\n');
+        }
+    }
+
+    stack.pop();
+    Ok(resolved)
+}
+
+/// If `line` ends in an `-- include: path` comment, returns the text before the
+/// comment and the path.
+fn include_directive(line: &str) -> Option<(&str, &str)> {
+    let start = line.find("--")?;
+    if line[..start].contains(['\'', '"', '`']) {
+        return None;
+    }
+    let (key, target) = line[start + 2..].split_once(':')?;
+    let target = target.trim();
+    (key.trim() == "include" && !target.is_empty()).then(|| (&line[..start], target))
+}
+
+/// Splits a file into its queries: a `-- name:` line starts a new query once the
+/// current one has a name or SQL, taking the comment and blank lines right above it
+/// along as front matter.
+fn split_queries(source: &str) -> Vec<&str> {
+    let mut starts = vec![0];
+    let (mut named, mut has_sql) = (false, false);
+    let mut comments_from = None;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let key = trimmed
+            .strip_prefix("--")
+            .and_then(|comment| comment.split_once(':'))
+            .map(|(key, _)| key.trim());
+        if key == Some("name") {
+            if named || has_sql {
+                starts.push(comments_from.unwrap_or(offset));
+                has_sql = false;
+            }
+            named = true;
+            comments_from = None;
+        } else if trimmed.is_empty() || trimmed.starts_with("--") {
+            comments_from.get_or_insert(offset);
+        } else {
+            has_sql = true;
+            comments_from = None;
+        }
+        offset += line.len();
+    }
+    starts.push(source.len());
+    starts.windows(2).map(|w| &source[w[0]..w[1]]).collect()
+}
+
+/// Splits the leading `-- key: value` comment lines off `source`.
+fn parse_front_matter<'a>(
+    default_name: &str,
+    source: &'a str,
+) -> crate::Result<(QueryMeta, &'a str)> {
+    let mut meta = QueryMeta {
+        name: default_name.to_owned(),
+        ..QueryMeta::default()
+    };
+
+    let mut rest = source;
+    loop {
+        let (line, remainder) = rest.split_once('\n').unwrap_or((rest, ""));
+        let trimmed = line.trim();
+        let Some(comment) = trimmed.strip_prefix("--") else {
+            if trimmed.is_empty() && !remainder.is_empty() {
+                rest = remainder;
+                continue;
+            }
+            break;
+        };
+        rest = remainder;
+
+        let Some((key, value)) = comment.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "name" => meta.name = value.to_owned(),
+            "description" => meta.description = Some(value.to_owned()),
+            "owner" => meta.owner = Some(value.to_owned()),
+            "param" => meta.params.push(parse_param(&meta.name, value)?),
+            "column" => meta.columns.push(parse_column(&meta.name, value)?),
+            "timeout" => meta.timeout = Some(parse_duration(&meta.name, value)?),
+            "tag" => {
+                let Some((tag, tag_value)) = value.split_once('=') else {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "query '{}' has invalid tag '{value}', expected key=value",
+                        meta.name
+                    )));
+                };
+                meta.tags.insert(tag.trim(), tag_value.trim());
+            }
+            "session" => {
+                let Some((name, value)) = value.split_once('=') else {
+                    return Err(crate::Error::InvalidMetadata(format!(
+                        "query '{}' has invalid session variable '{value}', expected \
+                         name=value",
+                        meta.name
+                    )));
+                };
+                let value = value.trim();
+                let session = std::mem::take(&mut meta.session);
+                meta.session = match value.parse::<i64>() {
+                    Ok(number) => session.set(name.trim(), number),
+                    Err(_) => session.set(name.trim(), value),
+                }
+                .map_err(|_| {
+                    crate::Error::InvalidMetadata(format!(
+                        "query '{}' has invalid session variable name '{}'",
+                        meta.name,
+                        name.trim()
+                    ))
+                })?;
+            }
+            "deprecated" => {
+                meta.deprecation = Some(Deprecation {
+                    replacement: (!value.is_empty()).then(|| value.to_owned()),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    if meta.name.is_empty() {
+        return Err(crate::Error::InvalidMetadata(
+            "query name must not be empty".to_owned(),
+        ));
+    }
+    Ok((meta, rest))
+}
+
+fn parse_param(query: &str, value: &str) -> crate::Result<ParamMeta> {
+    let mut parts = value.split_whitespace();
+    let name = parts.next().unwrap_or_default();
+    if placeholder_order(name)? != [name] {
+        return Err(crate::Error::InvalidMetadata(format!(
+            "query '{query}' declares invalid parameter '{value}'"
+        )));
+    }
+    let sql_type = parts.collect::<Vec<_>>().join(" ");
+    Ok(ParamMeta {
+        name: name.to_owned(),
+        sql_type: (!sql_type.is_empty()).then_some(sql_type),
+    })
+}
+
+fn parse_column(query: &str, value: &str) -> crate::Result<ColumnMeta> {
+    let mut parts = value.split_whitespace();
+    let Some(name) = parts.next() else {
+        return Err(crate::Error::InvalidMetadata(format!(
+            "query '{query}' declares a column without a name"
+        )));
+    };
+    let sql_type = parts.collect::<Vec<_>>().join(" ");
+    Ok(ColumnMeta {
+        name: name.trim_matches('`').to_owned(),
+        sql_type: (!sql_type.is_empty()).then_some(sql_type),
+    })
+}
+
+/// Parses durations such as `500ms`, `2s` or `5m`.
+fn parse_duration(query: &str, value: &str) -> crate::Result<Duration> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid_timeout(query, value))?;
+    match unit.trim() {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" | "" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        _ => Err(invalid_timeout(query, value)),
+    }
+}
+
+fn invalid_timeout(query: &str, value: &str) -> crate::Error {
+    crate::Error::InvalidMetadata(format!("query '{query}' has invalid timeout '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "-- name: find_user\n\
+                          -- description: Looks up a user by email\n\
+                          -- owner: accounts\n\
+                          -- param: :email VARCHAR(255)\n\
+                          -- param: :tenant_id\n\
+                          -- timeout: 1500ms\n\
+                          -- tag: team = accounts\n\
+                          -- session: sql_mode = ANSI_QUOTES\n\
+                          -- session: max_execution_time = 2000\n\
+                          SELECT id FROM users WHERE email = :email AND tenant_id = :tenant_id\n";
+
+    #[test]
+    fn test_parse_front_matter() {
+        let entry = RegistryEntry::parse("ignored", SOURCE).unwrap();
+        let meta = entry.meta();
+        assert_eq!(meta.name, "find_user");
+        assert_eq!(
+            meta.description.as_deref(),
+            Some("Looks up a user by email")
+        );
+        assert_eq!(meta.owner.as_deref(), Some("accounts"));
+        assert_eq!(
+            meta.params,
+            vec![
+                ParamMeta {
+                    name: ":email".to_owned(),
+                    sql_type: Some("VARCHAR(255)".to_owned()),
+                },
+                ParamMeta {
+                    name: ":tenant_id".to_owned(),
+                    sql_type: None,
+                },
+            ]
+        );
+        assert_eq!(meta.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(meta.tags.to_string(), "team=accounts");
+        assert_eq!(
+            meta.session,
+            SessionVars::new()
+                .set("sql_mode", "ANSI_QUOTES")
+                .unwrap()
+                .set("max_execution_time", 2000)
+                .unwrap()
+        );
+        assert_eq!(
+            entry.template(),
+            "SELECT id FROM users WHERE email = :email AND tenant_id = :tenant_id\n"
+        );
+        assert_eq!(entry.placeholders(), [":email", ":tenant_id"]);
+    }
+
+    #[test]
+    fn test_params_must_match_template() {
+        let undeclared = "-- param: :email\nSELECT id FROM users WHERE email = :email AND x = :x";
+        assert!(matches!(
+            RegistryEntry::parse("q", undeclared),
+            Err(crate::Error::InvalidMetadata(_))
+        ));
+        let unused = "-- param: :email\n-- param: :x\nSELECT id FROM users WHERE email = :email";
+        assert!(RegistryEntry::parse("q", unused).is_err());
+        assert!(RegistryEntry::parse("q", "-- timeout: soon\nSELECT 1").is_err());
+        assert!(RegistryEntry::parse("q", "-- tag: accounts\nSELECT 1").is_err());
+        assert!(RegistryEntry::parse("q", "-- session: sql mode=x\nSELECT 1").is_err());
+    }
+
+    #[test]
+    fn test_registry_defaults_and_duplicates() {
+        let mut registry = QueryRegistry::new();
+        let entry = registry
+            .add("count_users", "SELECT COUNT(*) FROM users")
+            .unwrap();
+        assert_eq!(entry.name(), "count_users");
+        assert_eq!(entry.meta().timeout, None);
+        assert!(registry.add("count_users", "SELECT 1").is_err());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_deprecation() {
+        let mut registry = QueryRegistry::new();
+        registry
+            .add("old", "-- deprecated: new\nSELECT id FROM users")
+            .unwrap();
+        registry.add("new", "SELECT id FROM users").unwrap();
+        registry.add("legacy", "SELECT 1").unwrap();
+        registry.deprecate("legacy", None).unwrap();
+        assert!(registry.deprecate("legacy", Some("missing")).is_err());
+        assert!(registry.deprecate("missing", None).is_err());
+
+        let old = registry.get("old").unwrap();
+        assert_eq!(
+            old.meta().deprecation,
+            Some(Deprecation {
+                replacement: Some("new".to_owned())
+            })
+        );
+        assert_eq!(
+            registry.get("legacy").unwrap().meta().deprecation,
+            Some(Deprecation { replacement: None })
+        );
+
+        fn binder<'q>(q: Q<'q>, _: &str) -> Q<'q> {
+            q
+        }
+
+        assert!(old.prepare(binder).unwrap().into_sqlx_query().is_ok());
+        let mut query = old.clone().prepare(binder).unwrap();
+        assert_eq!(old.deprecated_uses(), 1);
+        assert!(query.into_sqlx_query().is_ok());
+        assert_eq!(old.deprecated_uses(), 2);
+
+        let new = registry.get("new").unwrap();
+        assert!(new.prepare(binder).unwrap().into_sqlx_query().is_ok());
+        assert_eq!(new.deprecated_uses(), 0);
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("sqlx-named-bind-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("find_user.sql"), SOURCE).unwrap();
+        std::fs::write(dir.join("list_users.sql"), "SELECT id FROM users").unwrap();
+        std::fs::write(dir.join("README.md"), "not a query").unwrap();
+
+        let registry = QueryRegistry::load_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = registry.entries().map(RegistryEntry::name).collect();
+        assert_eq!(names, vec!["find_user", "list_users"]);
+        assert_eq!(
+            registry.get("list_users").unwrap().sql(),
+            "SELECT id FROM users"
+        );
+    }
+
+    #[test]
+    fn test_load_file_with_several_queries() {
+        let dir =
+            std::env::temp_dir().join(format!("sqlx-named-bind-yesql-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("users.sql"),
+            "-- Queries of the accounts team\n\
+             -- name: find_user\n\
+             SELECT id, name FROM users WHERE id = :id\n\
+             \n\
+             -- Soft-deletes a user\n\
+             -- name: delete_user\n\
+             -- owner: accounts-team\n\
+             UPDATE users SET deleted_at = NOW() WHERE id = :id\n",
+        )
+        .unwrap();
+
+        let mut repository = QueryRepository::new();
+        let first = repository
+            .load_file(dir.join("users.sql"))
+            .unwrap()
+            .name()
+            .to_owned();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, "find_user");
+        let names: Vec<_> = repository.entries().map(RegistryEntry::name).collect();
+        assert_eq!(names, ["delete_user", "find_user"]);
+        let find_user = repository.get("find_user").unwrap();
+        assert_eq!(find_user.sql(), "SELECT id, name FROM users WHERE id = ?\n");
+        let delete_user = repository.get("delete_user").unwrap();
+        assert_eq!(delete_user.meta().owner.as_deref(), Some("accounts-team"));
+        assert_eq!(delete_user.placeholders(), [":id"]);
+
+        assert_eq!(split_queries(SOURCE), [SOURCE]);
+    }
+
+    #[test]
+    fn test_load_file_resolves_includes() {
+        let dir =
+            std::env::temp_dir().join(format!("sqlx-named-bind-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("fragments")).unwrap();
+        std::fs::write(
+            dir.join("fragments/soft_delete_filter.sql"),
+            "-- include: visible.sql\nAND deleted_at IS NULL\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("fragments/visible.sql"), "visible_to <= :viewer\n").unwrap();
+        std::fs::write(
+            dir.join("find_user.sql"),
+            "SELECT id FROM users\nWHERE id = :id\n  AND -- include: fragments/soft_delete_filter.sql\nLIMIT :limit\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("fragments/a.sql"), "-- include: b.sql\n").unwrap();
+        std::fs::write(dir.join("fragments/b.sql"), "-- include: a.sql\n").unwrap();
+        std::fs::write(
+            dir.join("cyclic.sql"),
+            "SELECT 1 -- include: fragments/a.sql\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("missing.sql"), "SELECT 1\n-- include: nope.sql\n").unwrap();
+
+        let mut registry = QueryRegistry::new();
+        let entry = registry
+            .load_file(dir.join("find_user.sql"))
+            .unwrap()
+            .clone();
+        let cyclic = registry.load_file(dir.join("cyclic.sql")).map(|_| ());
+        let missing = registry.load_file(dir.join("missing.sql")).map(|_| ());
+        let graph = registry.dependency_graph();
+        let dependents = graph.dependents_of_fragment(dir.join("fragments/visible.sql"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(dependents, ["find_user"]);
+        assert_eq!(graph.dependents_of_table("users"), ["find_user"]);
+
+        assert_eq!(
+            entry.template(),
+            "SELECT id FROM users\nWHERE id = :id\n  AND visible_to <= :viewer\nAND deleted_at IS NULL\nLIMIT :limit\n"
+        );
+        assert_eq!(entry.placeholders(), [":id", ":viewer", ":limit"]);
+        assert_eq!(entry.includes().len(), 2);
+        assert!(entry.includes()[0].ends_with("fragments/soft_delete_filter.sql"));
+        assert!(entry.includes()[1].ends_with("fragments/visible.sql"));
+        assert_eq!(entry.tables(), ["users"]);
+        match cyclic {
+            Err(crate::Error::InvalidMetadata(message)) => {
+                assert!(message.starts_with("include cycle: "), "{message}")
+            }
+            other => panic!("expected a cycle error, got {other:?}"),
+        }
+        assert!(matches!(missing, Err(crate::Error::InvalidMetadata(_))));
+    }
+
+    #[test]
+    fn test_constants() {
+        let constants = Constants::new()
+            .int("MAX_RESULTS", 50)
+            .ident("SCHEMA", "app")
+            .ident("BAD", "app; DROP TABLE users");
+        let mut registry = QueryRegistry::with_constants(constants);
+        let entry = registry
+            .add(
+                "q",
+                "SELECT '{{SCHEMA}}' FROM {{ SCHEMA }}.t -- {{MISSING}}\nLIMIT {{MAX_RESULTS}}",
+            )
+            .unwrap();
+        assert_eq!(
+            entry.template(),
+            "SELECT '{{SCHEMA}}' FROM `app`.t -- {{MISSING}}\nLIMIT 50"
+        );
+
+        for template in ["SELECT {{MISSING}}", "SELECT {{BAD}}", "SELECT {{SCHEMA"] {
+            assert!(matches!(
+                registry.add("bad", template),
+                Err(crate::Error::InvalidMetadata(_))
+            ));
+        }
+        assert!(QueryRegistry::new()
+            .add("q", "SELECT {{MAX_RESULTS}}")
+            .is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let constants = Constants::new()
+            .var("env_prefix", "blue_")
+            .var("empty", "")
+            .var("bad", "x; DROP TABLE users");
+        let mut registry = QueryRegistry::with_constants(constants);
+        let entry = registry
+            .add(
+                "q",
+                "SELECT '{env_prefix}', ${id} FROM {env_prefix}users{empty} -- {missing}\n\
+                 WHERE id = :id",
+            )
+            .unwrap();
+        assert_eq!(
+            entry.template(),
+            "SELECT '{env_prefix}', ${id} FROM blue_users -- {missing}\nWHERE id = :id"
+        );
+        assert_eq!(
+            registry
+                .add("odbc", "SELECT {d '2024-01-01'}")
+                .unwrap()
+                .template(),
+            "SELECT {d '2024-01-01'}"
+        );
+
+        for template in ["SELECT * FROM {missing}", "SELECT * FROM {bad}"] {
+            assert!(matches!(
+                registry.add("broken", template),
+                Err(crate::Error::InvalidMetadata(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_self_test_problems() {
+        let entry = RegistryEntry::parse(
+            "find_user",
+            "-- column: id BIGINT\n-- column: `name` VARCHAR(100)\n-- column: email\n\
+             SELECT id, name, email FROM users WHERE id = :id",
+        )
+        .unwrap();
+        assert_eq!(entry.meta().columns[1].name, "name");
+
+        let columns = [
+            ("id", "BIGINT UNSIGNED"),
+            ("name", "TEXT"),
+            ("email", "VARBINARY"),
+        ];
+        assert!(describe_problems(&entry, Some(1), &columns).is_empty());
+        assert_eq!(
+            describe_problems(&entry, Some(2), &[("id", "DATETIME"), ("age", "INT")]),
+            [
+                "server expects 2 parameters but the template has 1 placeholders",
+                "column 'id' is declared BIGINT but returned as DATETIME",
+                "declared column 'name' is not returned",
+                "declared column 'email' is not returned",
+                "returned column 'age' is not declared",
+            ]
+        );
+
+        let report = SelfTestReport {
+            checked: 2,
+            failures: vec![SelfTestFailure {
+                query: "find_user".to_owned(),
+                problem: "cannot be described: no such table".to_owned(),
+            }],
+        };
+        assert!(SelfTestReport::default().ensure_ok().is_ok());
+        assert!(matches!(
+            report.ensure_ok(),
+            Err(crate::Error::SelfTestFailed(message))
+                if message == "query 'find_user': cannot be described: no such table"
+        ));
+    }
+}
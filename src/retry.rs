@@ -0,0 +1,330 @@
+//! Retrying transient failures within a shared budget and overall deadline.
+//!
+//! Retries stack: a statement that is retried inside a transaction that is itself
+//! retried can multiply attempts and latency. To keep this bounded, every retry loop
+//! draws from a [`RetryBudget`] that is created once per request and passed down to
+//! every layer. The budget caps the total number of retries and, optionally, the total
+//! time spent, no matter how many nested [`retry`] calls share it.
+
+use crate::deadline;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// MySQL error numbers that are safe to retry: deadlock and lock wait timeout.
+const TRANSIENT_ERROR_NUMBERS: &[u16] = &[1213, 1205];
+
+/// Retry allowance shared by every retry loop working on the same request.
+///
+/// Cloning a budget is cheap and the clones share the same allowance, so it can be
+/// handed to nested retry loops (statement retry inside transaction retry) and to
+/// concurrent tasks alike.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    inner: Arc<BudgetInner>,
+}
+
+#[derive(Debug)]
+struct BudgetInner {
+    retries_left: AtomicU32,
+    deadline: Option<Instant>,
+}
+
+impl RetryBudget {
+    /// Creates a budget allowing at most `max_retries` retries in total, with no deadline.
+    pub fn new(max_retries: u32) -> Self {
+        Self::with_deadline_opt(max_retries, None)
+    }
+
+    /// Creates a budget allowing at most `max_retries` retries in total, all of which
+    /// (and the attempts themselves) must complete by `deadline`.
+    pub fn with_deadline(max_retries: u32, deadline: Instant) -> Self {
+        Self::with_deadline_opt(max_retries, Some(deadline))
+    }
+
+    /// Creates a budget whose deadline is `timeout` from now.
+    pub fn with_timeout(max_retries: u32, timeout: Duration) -> Self {
+        Self::with_deadline(max_retries, Instant::now() + timeout)
+    }
+
+    fn with_deadline_opt(max_retries: u32, deadline: Option<Instant>) -> Self {
+        Self {
+            inner: Arc::new(BudgetInner {
+                retries_left: AtomicU32::new(max_retries),
+                deadline,
+            }),
+        }
+    }
+
+    /// Returns the number of retries still available.
+    pub fn retries_left(&self) -> u32 {
+        self.inner.retries_left.load(Ordering::Acquire)
+    }
+
+    /// Returns the overall deadline, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.inner.deadline
+    }
+
+    /// Returns the time left until the deadline, or `None` if there is no deadline.
+    ///
+    /// Returns `Some(Duration::ZERO)` once the deadline has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.inner
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns `true` if the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Takes one retry from the budget, returning `false` if none are left.
+    pub fn try_acquire(&self) -> bool {
+        self.inner
+            .retries_left
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+/// Per-loop retry settings: how many attempts this layer makes and how long it backs off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts by this loop, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry number `retry` (starting at 1).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Returns `true` if `error` is transient and the operation can safely be retried.
+///
+/// Deadlocks, lock wait timeouts, I/O errors and pool acquire timeouts are transient.
+pub fn is_transient(error: &crate::Error) -> bool {
+    match error {
+        crate::Error::Database(sqlx::Error::Database(db)) => {
+            error_number(db.as_ref()).is_some_and(|n| TRANSIENT_ERROR_NUMBERS.contains(&n))
+        }
+        crate::Error::Database(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut) => true,
+        _ => false,
+    }
+}
+
+fn error_number(db: &dyn sqlx::error::DatabaseError) -> Option<u16> {
+    if let Some(e) = db.try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>() {
+        return Some(e.number());
+    }
+    #[cfg(any(test, feature = "test-utils"))]
+    if let Some(e) = db.try_downcast_ref::<crate::testing::InjectedDatabaseError>() {
+        return Some(e.number());
+    }
+    None
+}
+
+/// Runs `operation`, retrying transient failures according to `policy` and `budget`.
+///
+/// A failed attempt is retried only if the error [`is_transient`], this loop has
+/// attempts left under `policy`, a retry can be taken from the shared `budget`, and
+/// the backoff delay ends before the budget's deadline. Otherwise the error of the
+/// last attempt is returned. If the budget has a deadline, every attempt runs in a
+/// [`deadline::scope`](crate::deadline::scope) for it, so the statements it executes
+/// are limited to the time left and cancelled when it is reached.
+///
+/// `operation` must be safe to run more than once; for statements inside a
+/// transaction, retry the whole transaction instead.
+///
+/// # Errors
+///
+/// Returns the error of the last attempt, or [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded)
+/// if the deadline passed before or during an attempt.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::retry::{retry, RetryBudget, RetryPolicy};
+/// use sqlx_named_bind::PreparedQuery;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// // One budget per incoming request, shared by every retry loop below it
+/// let budget = RetryBudget::with_timeout(5, Duration::from_secs(2));
+///
+/// let result = retry(RetryPolicy::default(), &budget, || async {
+///     PreparedQuery::new(
+///         "UPDATE stock SET quantity = quantity - 1 WHERE sku = :sku",
+///         |q, key| match key {
+///             ":sku" => q.bind("A-100"),
+///             _ => q,
+///         },
+///     )?
+///     .execute(&pool)
+///     .await
+/// })
+/// .await?;
+/// println!("Updated {} rows", result.rows_matched());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn retry<T, Fut, Op>(
+    policy: RetryPolicy,
+    budget: &RetryBudget,
+    mut operation: Op,
+) -> crate::Result<T>
+where
+    Op: FnMut() -> Fut,
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = match budget.deadline() {
+            Some(_) if budget.is_expired() => return Err(crate::Error::DeadlineExceeded),
+            Some(deadline) => deadline::scope(deadline, operation()).await,
+            None => operation().await,
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        let delay = policy.delay(attempt);
+        let fits_deadline = budget.remaining().is_none_or(|remaining| delay < remaining);
+        if !is_transient(&error)
+            || attempt >= policy.max_attempts
+            || !fits_deadline
+            || !budget.try_acquire()
+        {
+            return Err(error);
+        }
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn transient() -> crate::Error {
+        crate::Error::Database(sqlx::Error::PoolTimedOut)
+    }
+
+    #[test]
+    fn test_budget_is_shared_between_clones() {
+        let budget = RetryBudget::new(2);
+        let nested = budget.clone();
+        assert!(budget.try_acquire());
+        assert!(nested.try_acquire());
+        assert!(!budget.try_acquire());
+        assert_eq!(nested.retries_left(), 0);
+    }
+
+    #[test]
+    fn test_policy_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(3), Duration::from_millis(40));
+        assert_eq!(policy.delay(4), Duration::from_millis(50));
+        assert_eq!(policy.delay(100), Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_nested_retries_share_budget() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let budget = RetryBudget::new(3);
+        let calls = Cell::new(0);
+
+        let result: crate::Result<()> = retry(policy, &budget, || async {
+            retry(policy, &budget, || async {
+                calls.set(calls.get() + 1);
+                Err(transient())
+            })
+            .await
+        })
+        .await;
+
+        assert!(result.is_err());
+        // One initial attempt plus the three retries the budget allows
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_at_deadline() {
+        let policy = RetryPolicy {
+            max_attempts: u32::MAX,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(20),
+        };
+        let budget = RetryBudget::with_timeout(u32::MAX, Duration::from_millis(100));
+        let started = Instant::now();
+
+        let result: crate::Result<()> = retry(policy, &budget, || async { Err(transient()) }).await;
+
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_attempts_run_within_budget_deadline() {
+        let budget = RetryBudget::with_timeout(0, Duration::from_secs(5));
+        let seen = retry(RetryPolicy::default(), &budget, || async {
+            Ok(deadline::current())
+        })
+        .await;
+        assert_eq!(seen.unwrap(), budget.deadline());
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_retry_permanent_errors() {
+        let budget = RetryBudget::new(10);
+        let calls = Cell::new(0);
+
+        let result: crate::Result<()> = retry(RetryPolicy::default(), &budget, || async {
+            calls.set(calls.get() + 1);
+            Err(crate::Error::InvalidIdentifier("x".into()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+        assert_eq!(budget.retries_left(), 10);
+    }
+}
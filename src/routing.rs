@@ -0,0 +1,388 @@
+//! Routing reads across weighted MySQL replicas, away from replicas that are down or
+//! lagging.
+//!
+//! A [`ReplicaRouter`] holds the primary pool and any number of replica pools, each
+//! with a weight. [`reader`](ReplicaRouter::reader) spreads reads over the routable
+//! replicas in proportion to their weights and falls back to the primary when none is
+//! routable, so reads keep working during a replica incident. Replicas start out
+//! routable; [`check_health`](ReplicaRouter::check_health) pings each one with
+//! [`health::ping`](crate::health::ping), reads its replication lag when a maximum is
+//! set, and excludes it until a later check succeeds.
+//! [`reader_for`](ReplicaRouter::reader_for) routes on a query's
+//! [`Tags`] instead, sending only reads tagged `criticality=low` to the replicas.
+//!
+//! When a request must see its own writes, record them with a [`ReadYourWrites`] and
+//! read through [`consistent_reader`](ReplicaRouter::consistent_reader), which waits
+//! until the chosen replica has applied them.
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use sqlx::MySqlPool;
+//! use sqlx_named_bind::{routing::ReplicaRouter, PreparedQuery};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let router = Arc::new(
+//!     ReplicaRouter::new(MySqlPool::connect("mysql://primary/app").await?)
+//!         .replica("replica-a", MySqlPool::connect("mysql://replica-a/app").await?, 3)
+//!         .replica("replica-b", MySqlPool::connect("mysql://replica-b/app").await?, 1)
+//!         .max_lag(Duration::from_secs(5)),
+//! );
+//! let monitor = Arc::clone(&router);
+//! tokio::spawn(async move { monitor.monitor(Duration::from_secs(10)).await });
+//!
+//! PreparedQuery::new("SELECT 1", |q, _| q)?
+//!     .fetch_rows(router.reader())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::consistency::ReadYourWrites;
+use crate::health::{self, HealthStatus};
+use crate::shutdown;
+use crate::tags::Tags;
+use smallvec::SmallVec;
+use sqlx::mysql::MySqlRow;
+use sqlx::pool::PoolConnection;
+use sqlx::{Executor, MySql, MySqlPool, Row};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// A primary pool with weighted replica pools for reads.
+#[derive(Debug)]
+pub struct ReplicaRouter {
+    primary: MySqlPool,
+    replicas: Vec<Replica>,
+    max_lag: Option<Duration>,
+    check_timeout: Duration,
+    next: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Replica {
+    name: String,
+    pool: MySqlPool,
+    weight: u32,
+    routable: AtomicBool,
+}
+
+/// The outcome of checking one replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaStatus {
+    /// Name the replica was added under
+    pub name: String,
+    /// The result of pinging the replica
+    pub health: HealthStatus,
+    /// Replication lag the replica reported; `None` if no maximum lag is set, the
+    /// replica is down, or replication is not running
+    pub lag: Option<Duration>,
+    /// Whether reads are routed to the replica until the next check
+    pub routable: bool,
+}
+
+impl ReplicaRouter {
+    /// Creates a router sending every read to `primary` until replicas are added.
+    ///
+    /// Health checks time out after one second unless
+    /// [`check_timeout`](Self::check_timeout) is set.
+    pub fn new(primary: MySqlPool) -> Self {
+        Self {
+            primary,
+            replicas: Vec::new(),
+            max_lag: None,
+            check_timeout: Duration::from_secs(1),
+            next: AtomicU64::new(0),
+        }
+    }
+
+    /// Adds a replica receiving reads in proportion to `weight`.
+    ///
+    /// A replica with weight `0` is checked but receives no reads, e.g. while it is
+    /// drained for maintenance.
+    pub fn replica(mut self, name: &str, pool: MySqlPool, weight: u32) -> Self {
+        self.replicas.push(Replica {
+            name: name.to_owned(),
+            pool,
+            weight,
+            routable: AtomicBool::new(true),
+        });
+        self
+    }
+
+    /// Excludes replicas whose replication lag exceeds `max_lag`, or whose replication
+    /// is not running, at each health check.
+    pub fn max_lag(mut self, max_lag: Duration) -> Self {
+        self.max_lag = Some(max_lag);
+        self
+    }
+
+    /// Sets how long each replica may take to answer a health check.
+    pub fn check_timeout(mut self, timeout: Duration) -> Self {
+        self.check_timeout = timeout;
+        self
+    }
+
+    /// Returns the primary pool, for writes and reads that must see them.
+    pub fn primary(&self) -> &MySqlPool {
+        &self.primary
+    }
+
+    /// Returns the pool to send the next read to: a routable replica chosen by weighted
+    /// round robin, or the primary if no replica is routable.
+    pub fn reader(&self) -> &MySqlPool {
+        let weights: SmallVec<[u64; 8]> = self
+            .replicas
+            .iter()
+            .map(|replica| match replica.routable.load(Ordering::Relaxed) {
+                true => u64::from(replica.weight),
+                false => 0,
+            })
+            .collect();
+        match pick(&weights, self.next.fetch_add(1, Ordering::Relaxed)) {
+            Some(i) => &self.replicas[i].pool,
+            None => &self.primary,
+        }
+    }
+
+    /// Returns the pool to send a read with `tags` to.
+    ///
+    /// Reads tagged `criticality=low` go to [`reader`](Self::reader); every other read
+    /// goes to the primary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx_named_bind::{routing::ReplicaRouter, PreparedQuery};
+    ///
+    /// # async fn example(router: &ReplicaRouter) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut report = PreparedQuery::new("SELECT COUNT(*) FROM orders", |q, _| q)?
+    ///     .tag("criticality", "low");
+    /// let pool = router.reader_for(report.tags());
+    /// report.fetch_rows(pool).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reader_for(&self, tags: &Tags) -> &MySqlPool {
+        match tags.get("criticality") {
+            Some("low") => self.reader(),
+            _ => &self.primary,
+        }
+    }
+
+    /// Returns a connection for a read that must see the writes recorded in `session`.
+    ///
+    /// The connection comes from [`reader`](Self::reader). On a replica, it is handed out
+    /// once [`ReadYourWrites::wait`] has seen the replica apply the recorded GTID set; if
+    /// the replica does not catch up in time, a primary connection is returned instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if acquiring a connection or the wait query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use sqlx_named_bind::{consistency::ReadYourWrites, routing::ReplicaRouter};
+    /// use sqlx_named_bind::{PreparedQuery, PreparedQueryAs};
+    ///
+    /// # async fn example(router: &ReplicaRouter) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut session = ReadYourWrites::new(Duration::from_secs(1));
+    /// let mut update = PreparedQuery::new("UPDATE users SET active = 1 WHERE id = 42", |q, _| q)?;
+    /// session.execute(&mut update, router.primary()).await?;
+    ///
+    /// let mut conn = router.consistent_reader(&session).await?;
+    /// let mut select =
+    ///     PreparedQueryAs::<(bool,), _>::new("SELECT active FROM users WHERE id = 42", |q, _| q)?;
+    /// let (active,) = select.fetch_one(&mut *conn).await?;
+    /// assert!(active);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn consistent_reader(
+        &self,
+        session: &ReadYourWrites,
+    ) -> crate::Result<PoolConnection<MySql>> {
+        let pool = self.reader();
+        if std::ptr::eq(pool, &self.primary) {
+            return Ok(self.primary.acquire().await?);
+        }
+        let mut conn = pool.acquire().await?;
+        match session.wait(&mut *conn).await {
+            Ok(()) => Ok(conn),
+            Err(crate::Error::GtidWaitTimeout(gtid_set)) => {
+                tracing::warn!(%gtid_set, "replica behind, reading from the primary");
+                Ok(self.primary.acquire().await?)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the names of the replicas reads are currently routed to.
+    pub fn routable_replicas(&self) -> Vec<&str> {
+        self.replicas
+            .iter()
+            .filter(|replica| replica.routable.load(Ordering::Relaxed))
+            .map(|replica| replica.name.as_str())
+            .collect()
+    }
+
+    /// Checks every replica once, in order, and updates which ones receive reads.
+    ///
+    /// A replica is routable if it answers a ping within the check timeout and, when a
+    /// [`max_lag`](Self::max_lag) is set, reports a replication lag no greater than it.
+    /// Changes are logged through `tracing`.
+    pub async fn check_health(&self) -> Vec<ReplicaStatus> {
+        let mut statuses = Vec::with_capacity(self.replicas.len());
+        for replica in &self.replicas {
+            let health = health::ping(&replica.pool, self.check_timeout).await;
+            let lag = match (self.max_lag, health.is_healthy()) {
+                (Some(_), true) => replication_lag(&replica.pool, self.check_timeout).await,
+                _ => None,
+            };
+            let routable = is_routable(&health, lag, self.max_lag);
+            if replica.routable.swap(routable, Ordering::Relaxed) != routable {
+                match routable {
+                    true => tracing::info!(replica = %replica.name, "replica routable again"),
+                    false => tracing::warn!(
+                        replica = %replica.name,
+                        health = ?health,
+                        lag = ?lag,
+                        "replica excluded from reads"
+                    ),
+                }
+            }
+            statuses.push(ReplicaStatus {
+                name: replica.name.clone(),
+                health,
+                lag,
+                routable,
+            });
+        }
+        statuses
+    }
+
+    /// Runs [`check_health`](Self::check_health) every `interval` until
+    /// [`shutdown`](crate::shutdown) begins.
+    ///
+    /// Spawn it on a task next to the service.
+    pub async fn monitor(&self, interval: Duration) {
+        while !shutdown::is_shutting_down() {
+            self.check_health().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Returns the index of the weight `ticket` falls on, counting each index as many
+/// times as its weight, or `None` if every weight is zero.
+fn pick(weights: &[u64], ticket: u64) -> Option<usize> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut ticket = ticket % total;
+    for (i, &weight) in weights.iter().enumerate() {
+        if ticket < weight {
+            return Some(i);
+        }
+        ticket -= weight;
+    }
+    None
+}
+
+fn is_routable(health: &HealthStatus, lag: Option<Duration>, max_lag: Option<Duration>) -> bool {
+    health.is_healthy()
+        && match max_lag {
+            Some(max_lag) => lag.is_some_and(|lag| lag <= max_lag),
+            None => true,
+        }
+}
+
+/// Reads the replica's lag from `SHOW REPLICA STATUS`, or `SHOW SLAVE STATUS` on
+/// servers before MySQL 8.0.22. Returns `None` if replication is not running or the
+/// status cannot be read in time.
+async fn replication_lag(pool: &MySqlPool, timeout: Duration) -> Option<Duration> {
+    let status = async {
+        // Sent with sqlx directly, so an installed allow-list does not reject them.
+        match pool.fetch_optional("SHOW REPLICA STATUS").await {
+            Ok(row) => row,
+            Err(_) => pool.fetch_optional("SHOW SLAVE STATUS").await.ok()?,
+        }
+    };
+    let row = tokio::time::timeout(timeout, status).await.ok()??;
+    seconds_behind(&row).map(Duration::from_secs)
+}
+
+fn seconds_behind(row: &MySqlRow) -> Option<u64> {
+    let column = ["Seconds_Behind_Source", "Seconds_Behind_Master"]
+        .into_iter()
+        .find(|column| row.try_get_raw(*column).is_ok())?;
+    if let Ok(seconds) = row.try_get::<Option<u64>, _>(column) {
+        return seconds;
+    }
+    if let Ok(seconds) = row.try_get::<Option<i64>, _>(column) {
+        return seconds.and_then(|seconds| u64::try_from(seconds).ok());
+    }
+    row.try_get::<Option<String>, _>(column).ok()??.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_pick() {
+        let picks: Vec<_> = (0..8).map(|ticket| pick(&[3, 0, 1], ticket)).collect();
+        assert_eq!(picks, [0, 0, 0, 2, 0, 0, 0, 2].map(Some).to_vec(),);
+        assert_eq!(pick(&[0, 0], 5), None);
+        assert_eq!(pick(&[], 0), None);
+    }
+
+    #[test]
+    fn test_routable() {
+        let healthy = HealthStatus::Healthy {
+            latency: Duration::from_millis(1),
+        };
+        let down = HealthStatus::TimedOut {
+            timeout: Duration::from_secs(1),
+        };
+        let max = Some(Duration::from_secs(5));
+        assert!(is_routable(&healthy, None, None));
+        assert!(is_routable(&healthy, Some(Duration::from_secs(5)), max));
+        assert!(!is_routable(&healthy, Some(Duration::from_secs(6)), max));
+        assert!(!is_routable(&healthy, None, max));
+        assert!(!is_routable(&down, None, None));
+    }
+
+    #[tokio::test]
+    async fn test_reader_falls_back_to_primary() {
+        let pool = || MySqlPool::connect_lazy("mysql://localhost/test").unwrap();
+        let router = ReplicaRouter::new(pool())
+            .replica("a", pool(), 1)
+            .replica("b", pool(), 1);
+        assert_eq!(router.routable_replicas(), ["a", "b"]);
+
+        router.replicas[0].routable.store(false, Ordering::Relaxed);
+        router.replicas[1].routable.store(false, Ordering::Relaxed);
+        assert!(std::ptr::eq(router.reader(), router.primary()));
+        router.replicas[1].routable.store(true, Ordering::Relaxed);
+        assert!(std::ptr::eq(router.reader(), &router.replicas[1].pool));
+        assert_eq!(router.routable_replicas(), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn test_reader_for_routes_on_criticality() {
+        let pool = || MySqlPool::connect_lazy("mysql://localhost/test").unwrap();
+        let router = ReplicaRouter::new(pool()).replica("a", pool(), 1);
+        let replica = &router.replicas[0].pool;
+
+        let mut tags = Tags::new();
+        assert!(std::ptr::eq(router.reader_for(&tags), router.primary()));
+        tags.insert("criticality", "high");
+        assert!(std::ptr::eq(router.reader_for(&tags), router.primary()));
+        tags.insert("criticality", "low");
+        assert!(std::ptr::eq(router.reader_for(&tags), replica));
+    }
+}
@@ -0,0 +1,262 @@
+//! Templates made of several `;`-separated statements sharing named parameters.
+//!
+//! A [`PreparedScript`] splits its template into statements and executes them one
+//! after another on one connection, with every statement's placeholders bound by the
+//! same binder, so `:tenant_id` means the same value throughout a setup script.
+//! Semicolons in string literals, comments and `BEGIN ... END` blocks do not split, so
+//! a `CREATE PROCEDURE` with a compound body stays one statement. Each statement goes
+//! through the same pipeline as a [`PreparedQuery`](crate::PreparedQuery).
+//!
+//! The statements are always sent one at a time: MySQL only accepts several
+//! statements in one request over the text protocol, which cannot carry bound
+//! parameters.
+
+use crate::builder::placeholder_order;
+use crate::lexer::tokenize;
+use crate::query::Q;
+use crate::{ExecuteResult, PreparedQuery};
+use sqlx::MySqlConnection;
+
+/// Keywords that, after `END`, close a block that was not opened with `BEGIN` or `CASE`.
+const END_SUFFIXES: &[&str] = &["IF", "LOOP", "WHILE", "REPEAT"];
+
+/// Splits `template` into its statements, without the separating `;`.
+///
+/// Semicolons inside string literals, comments and `BEGIN ... END` or `CASE ... END`
+/// blocks do not split. Statements with nothing but whitespace and comments are
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::script::split_statements;
+///
+/// let statements = split_statements(
+///     "INSERT INTO t VALUES (';');\n\
+///      CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END;\n",
+/// );
+/// assert_eq!(
+///     statements,
+///     ["INSERT INTO t VALUES (';')", "CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END"]
+/// );
+/// ```
+pub fn split_statements(template: &str) -> Vec<&str> {
+    let tokens = tokenize(template);
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut first = true;
+    let mut depth = 0_usize;
+    let mut tokens_in_statement = 0;
+    let mut iter = tokens.iter().peekable();
+    while let Some(token) = iter.next() {
+        if token.is_punct(template, ';') && depth == 0 {
+            if tokens_in_statement > 0 {
+                statements.push(template[start..token.start].trim());
+            }
+            start = token.end;
+            first = true;
+            tokens_in_statement = 0;
+            continue;
+        }
+        tokens_in_statement += 1;
+        // A leading `BEGIN` starts a transaction, not a block.
+        if (token.is_keyword(template, "BEGIN") && !first) || token.is_keyword(template, "CASE") {
+            depth += 1;
+        } else if token.is_keyword(template, "END") {
+            let closes_other = iter.peek().is_some_and(|next| {
+                END_SUFFIXES
+                    .iter()
+                    .any(|suffix| next.is_keyword(template, suffix))
+            });
+            if !closes_other {
+                depth = depth.saturating_sub(1);
+                // `END CASE` closes the `CASE` itself.
+                if iter
+                    .peek()
+                    .is_some_and(|next| next.is_keyword(template, "CASE"))
+                {
+                    iter.next();
+                }
+            }
+        }
+        first = false;
+    }
+    if tokens_in_statement > 0 {
+        statements.push(template[start..].trim());
+    }
+    statements
+}
+
+/// A template of several statements executed in order with one binder.
+///
+/// # Type Parameters
+///
+/// * `F` - Binder function type, as for [`PreparedQuery`]
+pub struct PreparedScript<F> {
+    statements: Vec<String>,
+    binder: F,
+}
+
+impl<F> PreparedScript<F>
+where
+    F: for<'q> FnMut(Q<'q>, &str) -> Q<'q>,
+{
+    /// Creates a script from a template of `;`-separated statements and a binder.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Statements with named placeholders, separated by `;`
+    /// * `binder` - Binds values to the placeholders of every statement by name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a statement cannot be parsed or is rejected by an installed
+    /// [`allowlist`](crate::allowlist) or [`tenancy`](crate::tenancy) policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sqlx_named_bind::script::PreparedScript;
+    ///
+    /// let script = PreparedScript::new(
+    ///     "DELETE FROM carts WHERE tenant_id = :tenant_id;
+    ///      INSERT INTO carts (tenant_id, name) VALUES (:tenant_id, 'default');",
+    ///     |q, key| match key {
+    ///         ":tenant_id" => q.bind(7_i64),
+    ///         _ => q,
+    ///     },
+    /// )?;
+    /// assert_eq!(script.len(), 2);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: AsRef<str>,
+    {
+        let statements: Vec<String> = split_statements(template.as_ref())
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        for statement in &statements {
+            PreparedQuery::new(statement.as_str(), |q, _| q)?;
+        }
+        Ok(Self { statements, binder })
+    }
+
+    /// Returns the statements, in execution order.
+    pub fn statements(&self) -> &[String] {
+        &self.statements
+    }
+
+    /// Returns the number of statements.
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Returns `true` if the template has no statements.
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Returns the placeholders of every statement, in binding order.
+    pub fn placeholders(&self) -> crate::Result<Vec<String>> {
+        let mut placeholders = Vec::new();
+        for statement in &self.statements {
+            placeholders.extend(placeholder_order(statement)?);
+        }
+        Ok(placeholders)
+    }
+
+    /// Executes the statements in order on `conn` and returns the result of each.
+    ///
+    /// Execution stops at the first failing statement; the statements before it stay
+    /// applied. Pass a transaction (`&mut *tx`) to undo them, keeping in mind that
+    /// MySQL commits implicitly around DDL statements.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first failing statement.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sqlx::MySqlPool;
+    /// use sqlx_named_bind::script::PreparedScript;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+    /// let mut tx = pool.begin().await?;
+    /// let results = PreparedScript::new(
+    ///     "UPDATE accounts SET balance = balance - :amount WHERE id = :from;
+    ///      UPDATE accounts SET balance = balance + :amount WHERE id = :to;",
+    ///     |q, key| match key {
+    ///         ":amount" => q.bind(100_i64),
+    ///         ":from" => q.bind(1_i64),
+    ///         ":to" => q.bind(2_i64),
+    ///         _ => q,
+    ///     },
+    /// )?
+    /// .execute(&mut *tx)
+    /// .await?;
+    /// assert!(results.iter().all(|result| result.rows_matched() == 1));
+    /// tx.commit().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute(
+        &mut self,
+        conn: &mut MySqlConnection,
+    ) -> crate::Result<Vec<ExecuteResult>> {
+        let mut results = Vec::with_capacity(self.statements.len());
+        for statement in &self.statements {
+            // Checked against the allow-list in `new`.
+            let result = PreparedQuery::new_unlisted(statement.as_str(), &mut self.binder)?
+                .execute(&mut *conn)
+                .await?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("BEGIN; UPDATE t SET a = ':x;'; -- done;\nCOMMIT"),
+            ["BEGIN", "UPDATE t SET a = ':x;'", "-- done;\nCOMMIT"]
+        );
+        assert_eq!(
+            split_statements(
+                "CREATE TRIGGER tr BEFORE INSERT ON t FOR EACH ROW BEGIN \
+                 IF NEW.a < 0 THEN SET NEW.a = 0; END IF; \
+                 SET NEW.b = CASE WHEN NEW.a > 9 THEN 1 ELSE 0 END; END;\n\
+                 SELECT 1;;  /* trailing */"
+            ),
+            [
+                "CREATE TRIGGER tr BEFORE INSERT ON t FOR EACH ROW BEGIN \
+                 IF NEW.a < 0 THEN SET NEW.a = 0; END IF; \
+                 SET NEW.b = CASE WHEN NEW.a > 9 THEN 1 ELSE 0 END; END",
+                "SELECT 1",
+            ]
+        );
+        assert!(split_statements(" ; -- nothing\n").is_empty());
+    }
+
+    #[test]
+    fn test_script_placeholders() {
+        let script = PreparedScript::new(
+            "DELETE FROM t WHERE a = :a; INSERT INTO t (a, b) VALUES (:a, :b)",
+            |q, _| q,
+        )
+        .unwrap();
+        assert_eq!(script.len(), 2);
+        assert_eq!(script.placeholders().unwrap(), [":a", ":a", ":b"]);
+        assert!(PreparedScript::new("-- nothing to run", |q, _| q)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+//! Session variables applied around a few statements on one connection.
+//!
+//! A handful of statements need special session behavior, such as a relaxed
+//! `sql_mode`, a tweaked `optimizer_switch` or a longer `max_execution_time`.
+//! [`SessionVars`] declares such settings, in code or with `-- session: name=value`
+//! lines in the front matter of a [`registry`](crate::registry) file, and
+//! [`with_session`] applies them on one connection, runs the queries and restores the
+//! previous values, whether the queries succeed or not.
+//!
+//! The previous values are saved in user variables on the connection, so they are
+//! restored with their original types. If they cannot be restored, because the call is
+//! cancelled or the restore fails, the connection is closed instead of going back to
+//! the pool with the variables still set.
+
+use crate::hint::validate_identifier;
+use futures_util::future::BoxFuture;
+use sqlx::pool::PoolConnection;
+use sqlx::{MySql, MySqlConnection};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes the user variables of nested or concurrent [`with_session`] calls.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Closes the connection when dropped before the variables are restored.
+struct Unrestored<'a> {
+    conn: &'a mut PoolConnection<MySql>,
+    restored: bool,
+}
+
+impl Drop for Unrestored<'_> {
+    fn drop(&mut self) {
+        if !self.restored {
+            self.conn.close_on_drop();
+        }
+    }
+}
+
+/// The value of a session variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionValue {
+    /// An integer, e.g. for `max_execution_time`
+    Int(i64),
+    /// A string, e.g. for `sql_mode`
+    Text(String),
+}
+
+impl From<i64> for SessionValue {
+    fn from(value: i64) -> Self {
+        SessionValue::Int(value)
+    }
+}
+
+impl From<&str> for SessionValue {
+    fn from(value: &str) -> Self {
+        SessionValue::Text(value.to_owned())
+    }
+}
+
+impl From<String> for SessionValue {
+    fn from(value: String) -> Self {
+        SessionValue::Text(value)
+    }
+}
+
+/// Session variables to set around a group of statements.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::session::{SessionValue, SessionVars};
+///
+/// let vars = SessionVars::new()
+///     .set("sql_mode", "ANSI_QUOTES")?
+///     .set("max_execution_time", 30_000)?;
+/// assert_eq!(vars.get("max_execution_time"), Some(&SessionValue::Int(30_000)));
+///
+/// assert!(SessionVars::new().set("sql_mode = ''; DROP TABLE users", "").is_err());
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionVars {
+    vars: Vec<(String, SessionValue)>,
+}
+
+impl SessionVars {
+    /// Creates an empty set of session variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the session variable `name` to `value`, replacing any value set before.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if `name` is
+    /// not a plain identifier.
+    pub fn set(mut self, name: &str, value: impl Into<SessionValue>) -> crate::Result<Self> {
+        validate_identifier(name)?;
+        let value = value.into();
+        match self
+            .vars
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.vars.push((name.to_owned(), value)),
+        }
+        Ok(self)
+    }
+
+    /// Returns the value set for `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&SessionValue> {
+        self.vars
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the variables in the order they were first set.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SessionValue)> {
+        self.vars.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Returns the number of variables.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Returns `true` if no variables are set.
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Returns the `SET` statement saving the current values in user variables tagged
+    /// `id` and applying the new ones, bound as parameters in order.
+    fn apply_sql(&self, id: u64) -> String {
+        let saves = self
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| format!("{} = @@SESSION.{name}", saved(id, i)));
+        let sets = self
+            .vars
+            .iter()
+            .map(|(name, _)| format!("SESSION {name} = ?"));
+        format!("SET {}", saves.chain(sets).collect::<Vec<_>>().join(", "))
+    }
+
+    /// Returns the `SET` statement restoring the values saved by
+    /// [`apply_sql`](Self::apply_sql) and clearing the user variables.
+    fn restore_sql(&self, id: u64) -> String {
+        let restores = self
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| format!("SESSION {name} = {}", saved(id, i)));
+        let clears = (0..self.vars.len()).map(|i| format!("{} = NULL", saved(id, i)));
+        format!(
+            "SET {}",
+            restores.chain(clears).collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+fn saved(id: u64, index: usize) -> String {
+    format!("@sqlx_named_bind_session_{id}_{index}")
+}
+
+/// Sets `vars` on `conn`, runs `f` with it and restores the previous values.
+///
+/// The values are restored whether `f` succeeds or not. If the call is cancelled or the
+/// restore fails, `conn` is closed when it is dropped instead of going back to the pool.
+/// With no variables, `f` simply runs on `conn`.
+///
+/// # Arguments
+///
+/// * `conn` - Connection acquired from the pool to run on
+/// * `vars` - The session variables to set
+/// * `f` - Runs the queries; return the future boxed with `Box::pin(async move { ... })`
+///
+/// # Errors
+///
+/// Returns the error returned by `f`, or an error if setting or restoring the
+/// variables fails. In the latter case `conn` is closed when it is dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{registry::QueryRegistry, session::with_session};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let mut registry = QueryRegistry::new();
+/// let entry = registry.add(
+///     "legacy_report",
+///     "-- session: sql_mode=ALLOW_INVALID_DATES\n\
+///      SELECT COUNT(*) FROM orders WHERE shipped_at > '2024-02-30'",
+/// )?;
+///
+/// let mut report = entry.prepare_as::<(i64,), _>(|q, _| q)?;
+///
+/// let mut conn = pool.acquire().await?;
+/// let (count,) = with_session(&mut conn, &entry.meta().session, |conn| {
+///     Box::pin(async move { report.fetch_one(conn).await })
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_session<T, F>(
+    conn: &mut PoolConnection<MySql>,
+    vars: &SessionVars,
+    f: F,
+) -> crate::Result<T>
+where
+    F: for<'c> FnOnce(&'c mut MySqlConnection) -> BoxFuture<'c, crate::Result<T>>,
+{
+    if vars.is_empty() {
+        return f(conn).await;
+    }
+
+    // Sent with sqlx directly, so an installed allow-list or tenant policy does not
+    // reject the bookkeeping statements.
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let apply = vars.apply_sql(id);
+    let mut query = sqlx::query(&apply);
+    for (_, value) in &vars.vars {
+        query = match value {
+            SessionValue::Int(value) => query.bind(*value),
+            SessionValue::Text(value) => query.bind(value.as_str()),
+        };
+    }
+    let mut guard = Unrestored {
+        conn,
+        restored: false,
+    };
+    query.execute(&mut **guard.conn).await?;
+    let result = f(guard.conn).await;
+    sqlx::query(&vars.restore_sql(id))
+        .execute(&mut **guard.conn)
+        .await?;
+    guard.restored = true;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_sql() {
+        let vars = SessionVars::new()
+            .set("sql_mode", "ANSI_QUOTES")
+            .unwrap()
+            .set("max_execution_time", 500)
+            .unwrap()
+            .set("SQL_MODE", "it's")
+            .unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(
+            vars.apply_sql(7),
+            "SET @sqlx_named_bind_session_7_0 = @@SESSION.sql_mode, \
+             @sqlx_named_bind_session_7_1 = @@SESSION.max_execution_time, \
+             SESSION sql_mode = ?, SESSION max_execution_time = ?"
+        );
+        assert_eq!(
+            vars.restore_sql(7),
+            "SET SESSION sql_mode = @sqlx_named_bind_session_7_0, \
+             SESSION max_execution_time = @sqlx_named_bind_session_7_1, \
+             @sqlx_named_bind_session_7_0 = NULL, @sqlx_named_bind_session_7_1 = NULL"
+        );
+        assert!(SessionVars::new().set("sql-mode", 1).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Graceful shutdown: stop starting queries, drain the ones in flight, close the pool.
+//!
+//! `execute` on [`PreparedQuery`](crate::PreparedQuery) and `fetch_all`, `fetch_one` and
+//! `fetch_optional` on [`PreparedQueryAs`](crate::PreparedQueryAs) register with a
+//! process-wide coordinator while they run. Once [`shutdown`] (or [`begin`]) is called,
+//! new executions fail with [`Error::ShuttingDown`](crate::Error::ShuttingDown) while
+//! running ones are allowed to finish, so a deployment does not cut writes off halfway.
+//!
+//! A transaction or pinned connection registers once for its whole duration, through
+//! [`with_transaction`](crate::connection::with_transaction),
+//! [`with_connection`](crate::connection::with_connection), the
+//! [`batch`](crate::batch) helpers or [`scope`], and the statements it runs are let
+//! through until it ends, so draining never stops a transaction between two statements.
+
+use sqlx::MySqlPool;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+static GATE: Gate = Gate::new();
+
+tokio::task_local! {
+    /// Set while a [`scope`] keeps the gate open for the task.
+    static SCOPED: ();
+}
+
+/// Counts in-flight executions and refuses new ones once closed.
+struct Gate {
+    closing: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Keeps an execution registered until dropped.
+pub(crate) struct InFlight<'g>(&'g Gate);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl Gate {
+    const fn new() -> Self {
+        Self {
+            closing: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::const_new(),
+        }
+    }
+
+    fn enter(&self) -> crate::Result<InFlight<'_>> {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = InFlight(self);
+        if self.closing.load(Ordering::Acquire) && SCOPED.try_with(|_| ()).is_err() {
+            return Err(crate::Error::ShuttingDown);
+        }
+        Ok(guard)
+    }
+
+    async fn scope<T, Fut>(&self, future: Fut) -> crate::Result<T>
+    where
+        Fut: Future<Output = crate::Result<T>>,
+    {
+        let in_flight = self.enter()?;
+        within(in_flight, future).await
+    }
+
+    async fn drain(&self, deadline: Duration) -> crate::Result<()> {
+        self.closing.store(true, Ordering::Release);
+        let drained = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::Acquire) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(deadline, drained)
+            .await
+            .map_err(|_| crate::Error::DeadlineExceeded)
+    }
+}
+
+/// Registers an execution, or fails if shutdown has begun.
+pub(crate) fn enter() -> crate::Result<InFlight<'static>> {
+    GATE.enter()
+}
+
+/// Runs `future` while `in_flight` keeps it registered, letting every execution inside
+/// it through once shutdown has begun.
+pub(crate) async fn within<F: Future>(in_flight: InFlight<'_>, future: F) -> F::Output {
+    let output = SCOPED.scope((), future).await;
+    drop(in_flight);
+    output
+}
+
+/// Runs `future` as one execution: it is refused once shutdown has begun, and draining
+/// waits for all of it, while the executions inside it are let through.
+///
+/// Wrap a transaction opened outside of
+/// [`with_transaction`](crate::connection::with_transaction) in it, so shutdown does
+/// not stop it between two statements. Tasks spawned by `future` are not covered.
+///
+/// # Errors
+///
+/// Returns [`Error::ShuttingDown`](crate::Error::ShuttingDown) if shutdown has begun,
+/// or the error of `future`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::{shutdown, PreparedQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// shutdown::scope(async {
+///     let mut tx = pool.begin().await?;
+///     PreparedQuery::new("UPDATE accounts SET balance = 0 WHERE id = :id", |q, _| q.bind(1))?
+///         .execute(&mut *tx)
+///         .await?;
+///     tx.commit().await?;
+///     Ok(())
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn scope<T, Fut>(future: Fut) -> crate::Result<T>
+where
+    Fut: Future<Output = crate::Result<T>>,
+{
+    GATE.scope(future).await
+}
+
+/// Stops accepting new executions without waiting for running ones.
+pub fn begin() {
+    GATE.closing.store(true, Ordering::Release);
+}
+
+/// Returns `true` once shutdown has begun.
+pub fn is_shutting_down() -> bool {
+    GATE.closing.load(Ordering::Acquire)
+}
+
+/// Returns the number of executions currently running.
+pub fn in_flight() -> usize {
+    GATE.in_flight.load(Ordering::Acquire)
+}
+
+/// Stops accepting new executions and waits up to `deadline` for running ones.
+///
+/// # Errors
+///
+/// Returns [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded) if executions
+/// are still running when the deadline passes.
+pub async fn drain(deadline: Duration) -> crate::Result<()> {
+    GATE.drain(deadline).await
+}
+
+/// Stops accepting new executions, waits up to `deadline` for running ones, then closes
+/// `pool`.
+///
+/// If the deadline passes first, the pool is still marked closed so it hands out no
+/// more connections, but connections held by the remaining executions are not waited
+/// for.
+///
+/// # Errors
+///
+/// Returns [`Error::DeadlineExceeded`](crate::Error::DeadlineExceeded) if executions
+/// were still running when the deadline passed.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::shutdown;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// // On SIGTERM:
+/// if let Err(e) = shutdown::shutdown(&pool, Duration::from_secs(20)).await {
+///     eprintln!("{} queries abandoned: {e}", shutdown::in_flight());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn shutdown(pool: &MySqlPool, deadline: Duration) -> crate::Result<()> {
+    match drain(deadline).await {
+        Ok(()) => {
+            pool.close().await;
+            Ok(())
+        }
+        Err(e) => {
+            // Marks the pool closed right away; awaiting it would wait for the
+            // connections of the executions still running.
+            drop(pool.close());
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight() {
+        static GATE: Gate = Gate::new();
+
+        let first = GATE.enter().unwrap();
+        let second = GATE.enter().unwrap();
+        assert!(matches!(
+            GATE.drain(Duration::from_millis(10)).await,
+            Err(crate::Error::DeadlineExceeded)
+        ));
+        assert!(matches!(GATE.enter(), Err(crate::Error::ShuttingDown)));
+        assert_eq!(GATE.in_flight.load(Ordering::Acquire), 2);
+
+        drop(first);
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(second);
+        });
+        GATE.drain(Duration::from_secs(5)).await.unwrap();
+        release.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scope_keeps_gate_open() {
+        static GATE: Gate = Gate::new();
+
+        let (started, mut start) = tokio::sync::mpsc::channel(1);
+        let (finish, finished) = tokio::sync::oneshot::channel::<()>();
+        let transaction = GATE.scope(async move {
+            let _first = GATE.enter()?;
+            started.send(()).await.unwrap();
+            finished.await.unwrap();
+            let _second = GATE.enter()?;
+            Ok(())
+        });
+        let transaction = tokio::spawn(transaction);
+        start.recv().await.unwrap();
+
+        let drain = tokio::spawn(GATE.drain(Duration::from_secs(5)));
+        tokio::task::yield_now().await;
+        assert!(matches!(GATE.enter(), Err(crate::Error::ShuttingDown)));
+        assert!(matches!(
+            GATE.scope(async { Ok(()) }).await,
+            Err(crate::Error::ShuttingDown)
+        ));
+        assert!(!drain.is_finished());
+
+        finish.send(()).unwrap();
+        transaction.await.unwrap().unwrap();
+        drain.await.unwrap().unwrap();
+        assert_eq!(GATE.in_flight.load(Ordering::Acquire), 0);
+    }
+}
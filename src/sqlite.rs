@@ -0,0 +1,269 @@
+//! SQLite support, behind the `sqlite` feature.
+//!
+//! [`SqlitePreparedQuery`] and [`SqlitePreparedQueryAs`] mirror
+//! [`PreparedQuery`](crate::PreparedQuery) and [`PreparedQueryAs`](crate::PreparedQueryAs)
+//! for `Sqlite` executors such as `SqlitePool` and `&mut SqliteConnection`. Although
+//! SQLite understands `:name` itself, placeholders are converted to `?` exactly as for
+//! MySQL, so the binder is called once per occurrence in the same order on every
+//! backend.
+
+use crate::builder::{build_query, placeholder_order};
+use sqlx::query::{Query, QueryAs};
+use sqlx::sqlite::{SqliteArguments, SqliteQueryResult, SqliteRow};
+use sqlx::{Executor, Sqlite};
+
+/// Type alias for SQLx Query with SQLite arguments
+pub type SqliteQ<'q> = Query<'q, Sqlite, SqliteArguments<'q>>;
+
+/// Type alias for SQLx QueryAs with SQLite arguments
+pub type SqliteQA<'q, R> = QueryAs<'q, Sqlite, R, SqliteArguments<'q>>;
+
+/// A prepared SQLite query with named placeholders.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::SqlitePool;
+/// use sqlx_named_bind::sqlite::SqlitePreparedQuery;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = SqlitePool::connect("sqlite://app.db").await?;
+/// let mut query = SqlitePreparedQuery::new(
+///     "UPDATE users SET name = :name WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42_i64),
+///         ":name" => q.bind("Jane"),
+///         _ => q,
+///     },
+/// )?;
+///
+/// let result = query.execute(&pool).await?;
+/// println!("Updated {} rows", result.rows_affected());
+/// # Ok(())
+/// # }
+/// ```
+pub struct SqlitePreparedQuery<F> {
+    sql: String,
+    order: Vec<String>,
+    binder: F,
+}
+
+impl<F> SqlitePreparedQuery<F>
+where
+    F: for<'q> FnMut(SqliteQ<'q>, &str) -> SqliteQ<'q>,
+{
+    /// Creates a new `SqlitePreparedQuery` from an SQL template and binder function.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `binder` - Function that binds values to placeholders based on their names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed.
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order(&template)?;
+        let sql = build_query(&template)?;
+        Ok(Self { sql, order, binder })
+    }
+
+    /// Executes the query and returns the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Any SQLx executor for `Sqlite` (pool, transaction, etc.)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn execute<'e, E>(&mut self, executor: E) -> crate::Result<SqliteQueryResult>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        let mut q = sqlx::query::<Sqlite>(&self.sql);
+        for key in &self.order {
+            q = (self.binder)(q, key);
+        }
+        Ok(q.execute(executor).await?)
+    }
+}
+
+/// A prepared SQLite query with named placeholders that returns typed rows.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{FromRow, SqlitePool};
+/// use sqlx_named_bind::sqlite::SqlitePreparedQueryAs;
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = SqlitePool::connect("sqlite://app.db").await?;
+/// let mut query = SqlitePreparedQueryAs::<User, _>::new(
+///     "SELECT id, name FROM users WHERE id = :id",
+///     |q, key| match key {
+///         ":id" => q.bind(42_i64),
+///         _ => q,
+///     },
+/// )?;
+///
+/// let user = query.fetch_one(&pool).await?;
+/// println!("User: {} ({})", user.name, user.id);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SqlitePreparedQueryAs<R, F>
+where
+    for<'row> R: sqlx::FromRow<'row, SqliteRow> + Send + Unpin,
+    F: for<'q> FnMut(SqliteQA<'q, R>, &str) -> SqliteQA<'q, R>,
+{
+    sql: String,
+    order: Vec<String>,
+    binder: F,
+    _pd: std::marker::PhantomData<R>,
+}
+
+impl<R, F> SqlitePreparedQueryAs<R, F>
+where
+    for<'row> R: sqlx::FromRow<'row, SqliteRow> + Send + Unpin,
+    F: for<'q> FnMut(SqliteQA<'q, R>, &str) -> SqliteQA<'q, R>,
+{
+    /// Creates a new `SqlitePreparedQueryAs` from an SQL template and binder function.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - SQL query template with named placeholders (e.g., `:user_id`)
+    /// * `binder` - Function that binds values to placeholders based on their names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SQL template cannot be parsed.
+    pub fn new<T>(template: T, binder: F) -> crate::Result<Self>
+    where
+        T: Into<String>,
+    {
+        let template = template.into();
+        let order = placeholder_order(&template)?;
+        let sql = build_query(&template)?;
+        Ok(Self {
+            sql,
+            order,
+            binder,
+            _pd: std::marker::PhantomData,
+        })
+    }
+
+    fn query(&mut self) -> SqliteQA<'_, R> {
+        let mut q = sqlx::query_as(&self.sql);
+        for key in &self.order {
+            q = (self.binder)(q, key);
+        }
+        q
+    }
+
+    /// Fetches all rows matching the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails or rows cannot be decoded.
+    pub async fn fetch_all<'e, E>(&mut self, executor: E) -> crate::Result<Vec<R>>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        Ok(self.query().fetch_all(executor).await?)
+    }
+
+    /// Fetches exactly one row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails, no rows are found, or the row
+    /// cannot be decoded.
+    pub async fn fetch_one<'e, E>(&mut self, executor: E) -> crate::Result<R>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        Ok(self.query().fetch_one(executor).await?)
+    }
+
+    /// Fetches at most one row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails or the row cannot be decoded.
+    pub async fn fetch_optional<'e, E>(&mut self, executor: E) -> crate::Result<Option<R>>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        Ok(self.query().fetch_optional(executor).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{Connection, FromRow, SqliteConnection};
+
+    #[derive(Debug, FromRow, PartialEq)]
+    struct Note {
+        id: i64,
+        body: String,
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_round_trip() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        SqlitePreparedQuery::new(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+            |q, _| q,
+        )
+        .unwrap()
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        for (id, body) in [(1_i64, "a"), (2, "b")] {
+            SqlitePreparedQuery::new(
+                "INSERT INTO notes (id, body) VALUES (:id, :body)",
+                |q, key| match key {
+                    ":id" => q.bind(id),
+                    ":body" => q.bind(body),
+                    _ => q,
+                },
+            )
+            .unwrap()
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+
+        let notes = SqlitePreparedQueryAs::<Note, _>::new(
+            "SELECT id, body FROM notes WHERE id >= :min OR id = :min ORDER BY id",
+            |q, key| match key {
+                ":min" => q.bind(2_i64),
+                _ => q,
+            },
+        )
+        .unwrap()
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            notes,
+            vec![Note {
+                id: 2,
+                body: "b".to_owned()
+            }]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Best-effort extraction of the tables a template references.
+//!
+//! Tables are found with the crate's tokenizer rather than a SQL parser: a table is a
+//! name following `FROM`, `JOIN`, `UPDATE` or `INTO`, plus the further names of a
+//! comma-separated `FROM` or `UPDATE` list. Names defined by a `WITH` clause are not tables, and
+//! `FROM` inside function calls such as `EXTRACT(YEAR FROM created_at)` is ignored.
+//! Tables referenced only through views, stored procedures or dynamic SQL are not found.
+
+use crate::hint::NON_ALIAS_KEYWORDS;
+use crate::lexer::{tokenize, Token, TokenKind};
+use std::collections::BTreeSet;
+
+/// Returns the tables referenced by `template`, sorted and without duplicates.
+///
+/// Schema-qualified references are returned as `schema.table`; backtick quoting is
+/// removed.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::tables::referenced_tables;
+///
+/// let tables = referenced_tables(
+///     "WITH recent AS (SELECT user_id FROM orders WHERE created_at >= :since) \
+///      SELECT u.name FROM users u, recent r JOIN audit.logins l ON l.user_id = r.user_id \
+///      WHERE u.id = r.user_id AND EXTRACT(YEAR FROM u.created_at) = :year",
+/// );
+/// assert_eq!(tables, vec!["audit.logins", "orders", "users"]);
+/// ```
+pub fn referenced_tables(template: &str) -> Vec<String> {
+    let tables: BTreeSet<String> = scan(template).into_iter().map(|(name, _)| name).collect();
+    tables.into_iter().collect()
+}
+
+/// Returns the tables `template` modifies, sorted and without duplicates.
+///
+/// These are the targets of `INSERT`/`REPLACE ... INTO`, the tables listed between
+/// `UPDATE` and `SET`, and the tables of a `DELETE` statement's `FROM` clause. Tables
+/// only read, e.g. by the `SELECT` of an `INSERT ... SELECT`, are not included.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::tables::written_tables;
+///
+/// let tables = written_tables(
+///     "INSERT INTO archive (id) SELECT id FROM orders WHERE placed_at < :cutoff",
+/// );
+/// assert_eq!(tables, vec!["archive"]);
+/// ```
+pub fn written_tables(template: &str) -> Vec<String> {
+    let tables: BTreeSet<String> = scan(template)
+        .into_iter()
+        .filter(|(_, written)| *written)
+        .map(|(name, _)| name)
+        .collect();
+    tables.into_iter().collect()
+}
+
+/// Returns every table reference in `template` and whether the statement writes to it.
+fn scan(template: &str) -> Vec<(String, bool)> {
+    let tokens = tokenize(template);
+    let ctes = cte_names(template, &tokens);
+    let mut tables = Vec::new();
+    // For each open parenthesis, whether it starts a subquery rather than a call or list.
+    let mut statement_scopes = vec![true];
+    let mut deleting = false;
+    let mut writing = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if token.is_punct(template, '(') {
+            let subquery = tokens.get(i + 1).is_some_and(|next| {
+                next.is_keyword(template, "SELECT") || next.is_keyword(template, "WITH")
+            });
+            statement_scopes.push(subquery);
+        } else if token.is_punct(template, ')') {
+            if statement_scopes.len() > 1 {
+                statement_scopes.pop();
+            }
+        } else if statement_scopes.last() == Some(&true) {
+            let top_level = statement_scopes.len() == 1;
+            if top_level && token.is_keyword(template, "DELETE") {
+                deleting = true;
+            }
+            if [
+                "SET", "WHERE", "SELECT", "VALUES", "VALUE", "USING", "ORDER", "LIMIT",
+            ]
+            .into_iter()
+            .any(|keyword| token.is_keyword(template, keyword))
+            {
+                writing = false;
+            }
+            // `FOR UPDATE` and `ON DUPLICATE KEY UPDATE` do not name a table.
+            let locking_update = token.is_keyword(template, "UPDATE")
+                && i > 0
+                && (tokens[i - 1].is_keyword(template, "FOR")
+                    || tokens[i - 1].is_keyword(template, "KEY"));
+            let keyword = ["FROM", "JOIN", "UPDATE", "INTO"]
+                .into_iter()
+                .find(|keyword| token.is_keyword(template, keyword))
+                .filter(|_| !locking_update);
+            if let Some(keyword) = keyword {
+                if top_level {
+                    match keyword {
+                        "INTO" | "UPDATE" => writing = true,
+                        "FROM" => writing = deleting,
+                        _ => {}
+                    }
+                }
+                let mut next = i + 1;
+                while let Some((name, end)) = table_reference(template, &tokens, next) {
+                    if !ctes.contains(&name) {
+                        tables.push((name, top_level && writing));
+                    }
+                    next = end;
+                    let more = matches!(keyword, "FROM" | "UPDATE")
+                        && tokens.get(next).is_some_and(|t| t.is_punct(template, ','));
+                    if !more {
+                        break;
+                    }
+                    next += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    tables
+}
+
+/// Returns the names defined by `name AS (` members of `WITH` clauses.
+fn cte_names(sql: &str, tokens: &[Token]) -> BTreeSet<String> {
+    tokens
+        .windows(3)
+        .filter(|w| {
+            w[0].kind == TokenKind::Word && w[1].is_keyword(sql, "AS") && w[2].is_punct(sql, '(')
+        })
+        .map(|w| w[0].ident(sql).to_owned())
+        .collect()
+}
+
+/// If a table name (optionally schema-qualified and aliased) starts at token `start`,
+/// returns it and the index of the token after the reference.
+fn table_reference(sql: &str, tokens: &[Token], start: usize) -> Option<(String, usize)> {
+    let first = tokens.get(start)?;
+    if first.kind != TokenKind::Word || is_keyword(sql, first) {
+        return None;
+    }
+    let mut name = first.ident(sql).to_owned();
+    let mut i = start + 1;
+    if tokens.get(i).is_some_and(|t| t.is_punct(sql, '.')) {
+        let table = tokens.get(i + 1).filter(|t| t.kind == TokenKind::Word)?;
+        name = format!("{name}.{}", table.ident(sql));
+        i += 2;
+    }
+    match tokens.get(i) {
+        Some(t) if t.is_keyword(sql, "AS") => i += 2,
+        Some(t) if t.kind == TokenKind::Word && !is_keyword(sql, t) => i += 1,
+        _ => {}
+    }
+    Some((name, i))
+}
+
+/// Returns `true` for unquoted words that end or modify a table reference.
+fn is_keyword(sql: &str, token: &Token) -> bool {
+    !token.quoted
+        && NON_ALIAS_KEYWORDS
+            .iter()
+            .chain(&["DUAL", "OUTFILE", "DUMPFILE", "LATERAL"])
+            .any(|keyword| keyword.eq_ignore_ascii_case(&sql[token.start..token.end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referenced_tables() {
+        assert_eq!(
+            referenced_tables(
+                "INSERT INTO `events` (user_id, note) \
+                 SELECT id, 'FROM fake' FROM users WHERE id IN (SELECT user_id FROM bans) \
+                 -- JOIN commented\n"
+            ),
+            vec!["bans", "events", "users"]
+        );
+        assert_eq!(
+            referenced_tables("UPDATE accounts a JOIN owners o ON o.id = a.owner_id SET a.x = :x"),
+            vec!["accounts", "owners"]
+        );
+        assert_eq!(
+            referenced_tables("SELECT TRIM(LEADING 'x' FROM name) FROM DUAL"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            referenced_tables("DELETE FROM sessions WHERE expires_at < :now"),
+            vec!["sessions"]
+        );
+        assert_eq!(
+            referenced_tables(
+                "INSERT INTO counters (id, n) VALUES (:id, 1) ON DUPLICATE KEY UPDATE n = n + 1"
+            ),
+            vec!["counters"]
+        );
+        assert_eq!(
+            referenced_tables("SELECT * FROM jobs WHERE id = :id FOR UPDATE NOWAIT"),
+            vec!["jobs"]
+        );
+    }
+
+    #[test]
+    fn test_written_tables() {
+        assert_eq!(
+            written_tables("UPDATE accounts a JOIN owners o ON o.id = a.owner_id SET a.x = :x"),
+            vec!["accounts", "owners"]
+        );
+        assert_eq!(
+            written_tables(
+                "DELETE FROM sessions WHERE user_id IN (SELECT id FROM users WHERE banned)"
+            ),
+            vec!["sessions"]
+        );
+        assert_eq!(
+            written_tables("REPLACE INTO cache (k, v) VALUES (:k, (SELECT v FROM src))"),
+            vec!["cache"]
+        );
+        assert!(written_tables("SELECT * FROM jobs FOR UPDATE").is_empty());
+        assert!(written_tables("SELECT id INTO @last FROM jobs").is_empty());
+    }
+}
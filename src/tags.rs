@@ -0,0 +1,87 @@
+//! Key/value tags attached to a query, such as its team, feature or criticality.
+//!
+//! Tags are set with [`PreparedQuery::tag`](crate::PreparedQuery::tag) and
+//! [`PreparedQueryAs::tag`](crate::PreparedQueryAs::tag), or with `-- tag: key=value`
+//! lines in a registry template's front matter. Executions run inside a
+//! `named_query` tracing span whose `tags` field lists them, so a tracing layer can turn
+//! them into metrics labels, and each [`query_log`](crate::query_log) entry carries
+//! them. [`ReplicaRouter::reader_for`](crate::routing::ReplicaRouter::reader_for)
+//! routes `criticality=low` reads to replicas, and other routing or policy code can read
+//! them back with `tags()`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A set of key/value tags, ordered by key.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::tags::Tags;
+///
+/// let mut tags = Tags::new();
+/// tags.insert("team", "accounts").insert("criticality", "low");
+/// assert_eq!(tags.get("criticality"), Some("low"));
+/// assert_eq!(tags.to_string(), "criticality=low,team=accounts");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    tags: BTreeMap<String, String>,
+}
+
+impl Tags {
+    /// Creates an empty set of tags.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the tag `key` to `value`, replacing any previous value.
+    pub fn insert(&mut self, key: &str, value: &str) -> &mut Self {
+        self.tags.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// Returns the value of the tag `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.tags.get(key).map(String::as_str)
+    }
+
+    /// Returns every tag as `(key, value)`, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the number of tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns `true` if there are no tags.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Adds every tag of `other`, overriding tags with the same key.
+    pub fn extend(&mut self, other: &Tags) {
+        self.tags
+            .extend(other.tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    /// Returns the tracing span an execution with these tags runs in.
+    pub(crate) fn span(&self) -> tracing::Span {
+        tracing::info_span!("named_query", tags = %self)
+    }
+}
+
+impl fmt::Display for Tags {
+    /// Formats the tags as `key=value` pairs separated by commas.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+//! An opt-in process-wide cache of parsed templates.
+//!
+//! Every [`PreparedQuery`](crate::PreparedQuery) and
+//! [`PreparedQueryAs`](crate::PreparedQueryAs) parses its template into positional SQL
+//! and a placeholder order when it is created. Code that builds the same queries on
+//! every request can [`enable`] a least-recently-used cache keyed by the template
+//! string, so repeated construction is a hash lookup instead of a parse, and queries
+//! built from the same template share one copy of its SQL and placeholder order.
+//!
+//! Allow-list and tenant checks still run on every construction, so policies installed
+//! after a template was cached apply to it as well.
+//!
+//! ```
+//! use sqlx_named_bind::{template_cache, PreparedQuery};
+//!
+//! template_cache::enable(1_000);
+//! for id in 0..3_i64 {
+//!     let query = PreparedQuery::new(
+//!         "DELETE FROM sessions WHERE user_id = :id",
+//!         move |q, _| q.bind(id),
+//!     )?;
+//! }
+//! # template_cache::disable();
+//! # Ok::<(), sqlx_named_bind::Error>(())
+//! ```
+
+use crate::builder::{rewrite_template, PlaceholderStyle};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+static CACHE: Mutex<Option<Lru>> = Mutex::new(None);
+
+/// Counters of the installed cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Constructions served from the cache
+    pub hits: u64,
+    /// Constructions that parsed their template
+    pub misses: u64,
+    /// Templates currently cached
+    pub len: usize,
+    /// Most templates the cache holds
+    pub capacity: usize,
+}
+
+struct Slot {
+    style: PlaceholderStyle,
+    sql: Arc<str>,
+    order: Arc<[String]>,
+    used: u64,
+}
+
+struct Lru {
+    entries: HashMap<Arc<str>, Slot>,
+    /// Templates by the tick they were last used at, least recent first.
+    recency: BTreeMap<u64, Arc<str>>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats {
+                capacity,
+                ..CacheStats::default()
+            },
+        }
+    }
+
+    fn get(&mut self, template: &str, style: PlaceholderStyle) -> Option<Parsed> {
+        self.tick += 1;
+        let (key, slot) = self.entries.get_key_value(template)?;
+        if slot.style != style {
+            return None;
+        }
+        let key = key.clone();
+        let slot = self.entries.get_mut(template)?;
+        self.recency.remove(&slot.used);
+        slot.used = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        Some(Parsed {
+            template: key,
+            sql: slot.sql.clone(),
+            order: slot.order.clone(),
+        })
+    }
+
+    fn insert(&mut self, style: PlaceholderStyle, parsed: Parsed) {
+        self.tick += 1;
+        if let Some(old) = self.entries.remove(&parsed.template) {
+            self.recency.remove(&old.used);
+        }
+        while self.entries.len() >= self.stats.capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&evicted);
+        }
+        self.recency.insert(self.tick, parsed.template.clone());
+        self.entries.insert(
+            parsed.template,
+            Slot {
+                style,
+                sql: parsed.sql,
+                order: parsed.order,
+                used: self.tick,
+            },
+        );
+    }
+}
+
+/// Installs an empty cache holding up to `capacity` templates, replacing any cache
+/// installed before.
+///
+/// A `capacity` of `0` is treated as `1`.
+pub fn enable(capacity: usize) {
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Lru::new(capacity.max(1)));
+}
+
+/// Removes the cache; templates are parsed on every construction again.
+pub fn disable() {
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Empties the cache and resets its counters, keeping it installed.
+pub fn clear() {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(lru) = cache.as_mut() {
+        *lru = Lru::new(lru.stats.capacity);
+    }
+}
+
+/// Returns the counters of the installed cache, or `None` if none is installed.
+pub fn stats() -> Option<CacheStats> {
+    let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.as_ref().map(|lru| CacheStats {
+        len: lru.entries.len(),
+        ..lru.stats
+    })
+}
+
+/// A template with its positional SQL and placeholder order, shared between queries
+/// built from it.
+#[derive(Clone)]
+pub(crate) struct Parsed {
+    pub(crate) template: Arc<str>,
+    pub(crate) sql: Arc<str>,
+    pub(crate) order: Arc<[String]>,
+}
+
+/// Returns `template` with its positional SQL and placeholder order, from the cache if
+/// one is installed.
+pub(crate) fn parse(template: &str, style: PlaceholderStyle) -> crate::Result<Parsed> {
+    {
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(lru) = cache.as_mut() else {
+            drop(cache);
+            return Ok(parse_shared(template, style));
+        };
+        if let Some(parsed) = lru.get(template, style) {
+            lru.stats.hits += 1;
+            return Ok(parsed);
+        }
+        lru.stats.misses += 1;
+    }
+
+    let parsed = parse_shared(template, style);
+    if let Some(lru) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        lru.insert(style, parsed.clone());
+    }
+    Ok(parsed)
+}
+
+fn parse_shared(template: &str, style: PlaceholderStyle) -> Parsed {
+    let (sql, order) = rewrite_template(template, style);
+    Parsed {
+        template: template.into(),
+        sql: sql.into(),
+        order: order.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut lru = Lru::new(2);
+        let colon = PlaceholderStyle::Colon;
+        for template in ["SELECT :a", "SELECT :b"] {
+            assert!(lru.get(template, colon).is_none());
+            lru.insert(colon, parse_shared(template, colon));
+        }
+        let parsed = lru.get("SELECT :a", colon).unwrap();
+        assert_eq!(
+            (&*parsed.template, &*parsed.sql, &*parsed.order),
+            ("SELECT :a", "SELECT ?", &[":a".to_owned()][..])
+        );
+        assert!(lru.get("SELECT :a", PlaceholderStyle::At).is_none());
+
+        // `:b` is the least recently used, so it makes room for `:c`.
+        lru.insert(colon, parse_shared("SELECT :c", colon));
+        assert!(lru.get("SELECT :b", colon).is_none());
+        assert!(lru.get("SELECT :a", colon).is_some());
+        assert!(lru.get("SELECT :c", colon).is_some());
+        assert_eq!(lru.entries.len(), lru.recency.len());
+    }
+
+    #[test]
+    fn test_hits_share_parsed_template() {
+        let mut lru = Lru::new(1);
+        let colon = PlaceholderStyle::Colon;
+        lru.insert(colon, parse_shared("SELECT :a", colon));
+        let first = lru.get("SELECT :a", colon).unwrap();
+        let parsed = lru.get("SELECT :a", colon).unwrap();
+        assert!(Arc::ptr_eq(&first.template, &parsed.template));
+        assert!(Arc::ptr_eq(&first.sql, &parsed.sql));
+        assert!(Arc::ptr_eq(&first.order, &parsed.order));
+    }
+}
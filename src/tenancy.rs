@@ -0,0 +1,416 @@
+//! Guard against queries on multi-tenant tables that forget the tenant filter.
+//!
+//! A [`TenantPolicy`] installed with [`install`] names the tables whose rows belong to
+//! a tenant. From then on, creating a [`PreparedQuery`](crate::PreparedQuery) or
+//! [`PreparedQueryAs`](crate::PreparedQueryAs) whose template references one of them
+//! without a `:tenant_id` placeholder fails with
+//! [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter). Queries from a
+//! [`QueryRegistry`] are checked when they are prepared; call
+//! [`TenantPolicy::check_registry`] to verify every template right after loading. The
+//! templates of [`execute_batch_chunked`](crate::batch::execute_batch_chunked),
+//! [`bulk_insert`](crate::batch::bulk_insert) and
+//! [`import_ndjson`](crate::json::import_ndjson) are checked the same way, and a
+//! [`bulk_update`](crate::batch::bulk_update) of a tenant table, which has no tenant
+//! filter, is rejected.
+//!
+//! With [`TenantPolicy::append_filter`], a `SELECT`, `UPDATE` or `DELETE` on a single
+//! tenant table gets `tenant_id = :tenant_id` added to its `WHERE` clause instead, so
+//! the binder must bind `:tenant_id`; statements the filter cannot safely be added to
+//! (joins, unions, inserts, bulk updates) are still rejected.
+//!
+//! The guard only looks for the placeholder: a template that binds `:tenant_id` to the
+//! wrong column, or only in a subquery, passes.
+
+use crate::hint::validate_identifier;
+use crate::kind::StatementKind;
+use crate::lexer::{tokenize, Token};
+use crate::query::placeholder_key;
+use crate::registry::QueryRegistry;
+use crate::tables::referenced_tables;
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+static POLICY: RwLock<Option<Arc<TenantPolicy>>> = RwLock::new(None);
+
+/// Keywords that end a `WHERE` clause at the top level of a statement.
+const CLAUSE_ENDS: &[&str] = &["GROUP", "HAVING", "WINDOW", "ORDER", "LIMIT", "FOR", "LOCK"];
+
+/// The multi-tenant tables and how their queries are filtered.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::tenancy::{self, TenantPolicy};
+/// use sqlx_named_bind::PreparedQuery;
+///
+/// tenancy::install(TenantPolicy::new(["orders", "invoices"]))?;
+///
+/// let leaky = PreparedQuery::new("DELETE FROM orders WHERE id = :id", |q, _| q);
+/// assert!(matches!(leaky, Err(sqlx_named_bind::Error::MissingTenantFilter { .. })));
+///
+/// let scoped = PreparedQuery::new(
+///     "DELETE FROM orders WHERE id = :id AND tenant_id = :tenant_id",
+///     |q, _| q,
+/// );
+/// assert!(scoped.is_ok());
+/// # tenancy::uninstall();
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TenantPolicy {
+    tables: BTreeSet<String>,
+    placeholder: String,
+    column: String,
+    append: bool,
+}
+
+impl TenantPolicy {
+    /// Creates a policy for `tables`, requiring a `:tenant_id` placeholder.
+    ///
+    /// Table names are matched case-insensitively, with or without a schema.
+    pub fn new<I, S>(tables: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            tables: tables
+                .into_iter()
+                .map(|table| table.as_ref().to_ascii_lowercase())
+                .collect(),
+            placeholder: ":tenant_id".to_owned(),
+            column: "tenant_id".to_owned(),
+            append: false,
+        }
+    }
+
+    /// Requires the placeholder `name` instead of `:tenant_id`.
+    pub fn placeholder(mut self, name: &str) -> Self {
+        self.placeholder = placeholder_key(name);
+        self
+    }
+
+    /// Compares the tenant placeholder with `column` when appending the filter, instead
+    /// of `tenant_id`.
+    ///
+    /// `column` is pasted into the SQL, so [`install`] rejects it unless it is a plain
+    /// identifier.
+    pub fn column(mut self, column: &str) -> Self {
+        self.column = column.to_owned();
+        self
+    }
+
+    /// Appends the tenant filter to queries that lack it, where possible, instead of
+    /// rejecting them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlx_named_bind::tenancy::TenantPolicy;
+    ///
+    /// let policy = TenantPolicy::new(["orders"]).append_filter();
+    /// let mut sql = "SELECT * FROM orders WHERE id = ? OR ref = ? LIMIT 1".to_owned();
+    /// let mut order = vec![":id".to_owned(), ":ref".to_owned()];
+    /// policy.enforce(&mut sql, &mut order)?;
+    /// assert_eq!(
+    ///     sql,
+    ///     "SELECT * FROM orders WHERE tenant_id = ? AND (id = ? OR ref = ?) LIMIT 1"
+    /// );
+    /// assert_eq!(order, [":tenant_id", ":id", ":ref"]);
+    /// # Ok::<(), sqlx_named_bind::Error>(())
+    /// ```
+    pub fn append_filter(mut self) -> Self {
+        self.append = true;
+        self
+    }
+
+    /// Returns the tenant table `sql` references, if any.
+    fn tenant_table(&self, sql: &str) -> Option<String> {
+        referenced_tables(sql).into_iter().find(|table| {
+            let table = table.to_ascii_lowercase();
+            let unqualified = table.rsplit('.').next().unwrap_or_default();
+            self.tables.contains(&table) || self.tables.contains(unqualified)
+        })
+    }
+
+    /// Checks converted `sql`, whose placeholders are `order`, appending the tenant
+    /// filter if the policy does so.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) if
+    /// `sql` references a tenant table without the tenant placeholder and the filter
+    /// cannot be appended.
+    pub fn enforce(&self, sql: &mut String, order: &mut Vec<String>) -> crate::Result<()> {
+        if order.contains(&self.placeholder) {
+            return Ok(());
+        }
+        let Some(table) = self.tenant_table(sql) else {
+            return Ok(());
+        };
+        let appended = if self.append {
+            append_filter(sql, &self.column)
+        } else {
+            None
+        };
+        match appended {
+            Some((filtered, index)) => {
+                *sql = filtered;
+                order.insert(index, self.placeholder.clone());
+                Ok(())
+            }
+            None => Err(crate::Error::MissingTenantFilter {
+                table,
+                sql: sql.clone(),
+            }),
+        }
+    }
+
+    /// Fails unless converted `sql`, whose placeholders are `order`, has the tenant
+    /// placeholder or references no tenant table, without appending the filter.
+    fn require(&self, sql: &str, order: &[String]) -> crate::Result<()> {
+        if order.contains(&self.placeholder) {
+            return Ok(());
+        }
+        match self.tenant_table(sql) {
+            Some(table) => Err(crate::Error::MissingTenantFilter {
+                table,
+                sql: sql.to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks every template of `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingTenantFilter`](crate::Error::MissingTenantFilter) for
+    /// the first template the policy would reject.
+    pub fn check_registry(&self, registry: &QueryRegistry) -> crate::Result<()> {
+        for entry in registry.entries() {
+            let mut sql = entry.sql().to_owned();
+            let mut order = entry.placeholders().to_vec();
+            self.enforce(&mut sql, &mut order)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds `column = ?` to the `WHERE` clause of `sql`, returning the new SQL and the
+/// index of the added placeholder among the statement's placeholders.
+///
+/// Returns `None` unless `sql` is a single-table `SELECT`, `UPDATE` or `DELETE`.
+fn append_filter(sql: &str, column: &str) -> Option<(String, usize)> {
+    if referenced_tables(sql).len() != 1 {
+        return None;
+    }
+    if !matches!(
+        StatementKind::of(sql),
+        StatementKind::Select | StatementKind::Update | StatementKind::Delete
+    ) {
+        return None;
+    }
+    let tokens = tokenize(sql);
+
+    // Tokens outside parentheses, plus the outermost parentheses themselves.
+    let mut depth = 0_usize;
+    let mut top_level = Vec::new();
+    for token in &tokens {
+        if token.is_punct(sql, ')') {
+            depth = depth.saturating_sub(1);
+        }
+        if depth == 0 {
+            top_level.push(*token);
+        }
+        if token.is_punct(sql, '(') {
+            depth += 1;
+        }
+    }
+    if top_level.iter().any(|token| token.is_keyword(sql, "UNION")) {
+        return None;
+    }
+    let is_end = |token: &Token| {
+        token.is_punct(sql, ';')
+            || CLAUSE_ENDS
+                .iter()
+                .any(|keyword| token.is_keyword(sql, keyword))
+    };
+    let placeholders_before = |offset: usize| {
+        tokens
+            .iter()
+            .filter(|token| token.start < offset && token.is_punct(sql, '?'))
+            .count()
+    };
+
+    let where_at = top_level
+        .iter()
+        .position(|token| token.is_keyword(sql, "WHERE"));
+    let from = where_at.map_or(0, |i| i + 1);
+    let end = top_level[from..]
+        .iter()
+        .find(|token| is_end(token))
+        .map_or(sql.trim_end().len(), |token| {
+            sql[..token.start].trim_end().len()
+        });
+    Some(match where_at {
+        Some(i) => {
+            let condition_start = top_level.get(i + 1).map_or(end, |token| token.start);
+            let filtered = format!(
+                "{}{column} = ? AND ({}){}",
+                &sql[..condition_start],
+                &sql[condition_start..end],
+                &sql[end..]
+            );
+            (filtered, placeholders_before(condition_start))
+        }
+        None => {
+            let filtered = format!("{} WHERE {column} = ?{}", &sql[..end], &sql[end..]);
+            (filtered, placeholders_before(end))
+        }
+    })
+}
+
+/// Installs `policy` for every subsequent query created in the process, replacing any
+/// policy installed before.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIdentifier`](crate::Error::InvalidIdentifier) if the
+/// policy's [`column`](TenantPolicy::column) is not a plain identifier.
+pub fn install(policy: TenantPolicy) -> crate::Result<()> {
+    validate_identifier(&policy.column)?;
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(policy));
+    Ok(())
+}
+
+/// Removes the installed policy.
+pub fn uninstall() {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Enforces the installed policy on converted `sql` and its placeholders, copying them
+/// only if a policy is installed.
+pub(crate) fn enforce(sql: &mut Arc<str>, order: &mut Arc<[String]>) -> crate::Result<()> {
+    let Some(policy) = &*POLICY.read().unwrap_or_else(|e| e.into_inner()) else {
+        return Ok(());
+    };
+    let mut owned_sql = sql.to_string();
+    let mut owned_order = order.to_vec();
+    policy.enforce(&mut owned_sql, &mut owned_order)?;
+    *sql = owned_sql.into();
+    *order = owned_order.into();
+    Ok(())
+}
+
+/// Checks converted `sql`, whose placeholders are `order`, against the installed
+/// policy without appending the filter, for statements the crate builds itself that
+/// could not bind an added placeholder.
+pub(crate) fn check(sql: &str, order: &[String]) -> crate::Result<()> {
+    match &*POLICY.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(policy) => policy.require(sql, order),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enforce(policy: &TenantPolicy, sql: &str, order: &[&str]) -> crate::Result<String> {
+        let mut sql = sql.to_owned();
+        let mut order: Vec<String> = order.iter().map(|key| key.to_string()).collect();
+        policy.enforce(&mut sql, &mut order)?;
+        assert_eq!(sql.matches('?').count(), order.len());
+        Ok(sql)
+    }
+
+    #[test]
+    fn test_enforce_requires_placeholder() {
+        let policy = TenantPolicy::new(["Orders"]);
+        assert!(matches!(
+            enforce(&policy, "SELECT * FROM shop.orders WHERE id = ?", &[":id"]),
+            Err(crate::Error::MissingTenantFilter { table, .. }) if table == "shop.orders"
+        ));
+        enforce(
+            &policy,
+            "SELECT * FROM orders WHERE tenant_id = ?",
+            &[":tenant_id"],
+        )
+        .unwrap();
+        enforce(&policy, "SELECT * FROM users WHERE id = ?", &[":id"]).unwrap();
+
+        let policy = TenantPolicy::new(["orders"]).placeholder("org");
+        enforce(&policy, "DELETE FROM orders WHERE org_id = ?", &[":org"]).unwrap();
+    }
+
+    #[test]
+    fn test_require_never_appends() {
+        let policy = TenantPolicy::new(["orders"]).append_filter();
+        let update = "UPDATE orders SET status = CASE id WHEN ? THEN ? ELSE status END \
+                      WHERE id IN (?)";
+        assert!(matches!(
+            policy.require(update, &[]),
+            Err(crate::Error::MissingTenantFilter { table, .. }) if table == "orders"
+        ));
+        let insert = "INSERT INTO orders (tenant_id, item) VALUES ?";
+        let columns = [":tenant_id".to_owned(), ":item".to_owned()];
+        policy.require(insert, &columns).unwrap();
+        assert!(policy.require(insert, &columns[1..]).is_err());
+        policy.require("UPDATE users SET name = ?", &[]).unwrap();
+    }
+
+    #[test]
+    fn test_install_rejects_invalid_column() {
+        let policy = TenantPolicy::new(["orders"]).append_filter();
+        let injected = policy.column("1 OR 1 = 1 OR tenant_id");
+        assert!(matches!(
+            install(injected),
+            Err(crate::Error::InvalidIdentifier(column)) if column == "1 OR 1 = 1 OR tenant_id"
+        ));
+        assert!(POLICY.read().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_append_filter() {
+        let policy = TenantPolicy::new(["orders"]).append_filter();
+        assert_eq!(
+            enforce(&policy, "SELECT * FROM orders", &[]).unwrap(),
+            "SELECT * FROM orders WHERE tenant_id = ?"
+        );
+        assert_eq!(
+            enforce(
+                &policy,
+                "UPDATE orders SET state = ? WHERE id = ? OR (id = ?) ORDER BY id LIMIT ?",
+                &[":state", ":id", ":id", ":limit"],
+            )
+            .unwrap(),
+            "UPDATE orders SET state = ? WHERE tenant_id = ? AND (id = ? OR (id = ?)) \
+             ORDER BY id LIMIT ?"
+        );
+        assert_eq!(
+            enforce(&policy, "SELECT COUNT(*) FROM orders GROUP BY state;", &[]).unwrap(),
+            "SELECT COUNT(*) FROM orders WHERE tenant_id = ? GROUP BY state;"
+        );
+        assert_eq!(
+            enforce(
+                &policy,
+                "DELETE FROM orders WHERE (id = ?) OR id = ?",
+                &[":a", ":b"]
+            )
+            .unwrap(),
+            "DELETE FROM orders WHERE tenant_id = ? AND ((id = ?) OR id = ?)"
+        );
+        for sql in [
+            "INSERT INTO orders (id) VALUES (?)",
+            "SELECT * FROM orders o JOIN users u ON u.id = o.user_id WHERE o.id = ?",
+            "SELECT id FROM orders WHERE id = ? UNION SELECT id FROM orders WHERE id = ?",
+        ] {
+            let order = vec![":id"; sql.matches('?').count()];
+            assert!(matches!(
+                enforce(&policy, sql, &order),
+                Err(crate::Error::MissingTenantFilter { .. })
+            ));
+        }
+    }
+}
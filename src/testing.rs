@@ -0,0 +1,637 @@
+//! Test utilities for exercising error handling around this crate (feature `test-utils`).
+//!
+//! A [`FaultInjector`] holds rules that make statements fail with a chosen [`Fault`].
+//! Wrapping an executor with [`FaultInjector::wrap`] yields a [`FaultyExecutor`] that
+//! checks every statement's [fingerprint](crate::fingerprint) against the rules before
+//! passing it on, so retry and fallback logic can be tested deterministically without
+//! breaking a real database.
+//!
+//! A [`LatencyInjector`] works the same way but delays matching statements by a fixed
+//! or random [`Latency`], for staging tests of timeout and concurrency behavior.
+//!
+//! A [`Recorder`] captures statements and their result rows during a run against a
+//! real database; a [`ReplayExecutor`] serves them back in hermetic tests.
+//!
+//! A [`SlowLogReplayer`] re-executes captured slow queries ([`SlowQuery`], e.g. from
+//! the [`query_log`](crate::query_log)) through the registry queries they came from,
+//! against a staging database, to reproduce production performance problems.
+
+use crate::fingerprint::fingerprint;
+use futures_util::future::{self, BoxFuture};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use sqlx::error::{DatabaseError, ErrorKind};
+use sqlx::mysql::{MySqlQueryResult, MySqlRow, MySqlStatement, MySqlTypeInfo};
+use sqlx::{Describe, Either, Execute, Executor, MySql};
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod replay;
+mod slowlog;
+
+pub use replay::{Recorder, RecordingExecutor, ReplayExecutor};
+pub use slowlog::{CapturedParam, ReplayedQuery, SlowLogReplayer, SlowQuery};
+
+/// A failure a [`FaultyExecutor`] can inject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// MySQL error 1213, `ER_LOCK_DEADLOCK`
+    Deadlock,
+    /// MySQL error 1205, `ER_LOCK_WAIT_TIMEOUT`
+    LockWaitTimeout,
+    /// The statement timed out on the network (an I/O `TimedOut` error)
+    Timeout,
+    /// The server closed the connection (an I/O `ConnectionReset` error)
+    ConnectionDropped,
+    /// No connection could be checked out of the pool in time
+    PoolTimedOut,
+}
+
+impl Fault {
+    fn to_error(self) -> sqlx::Error {
+        match self {
+            Fault::Deadlock => InjectedDatabaseError::error(
+                1213,
+                "40001",
+                "Deadlock found when trying to get lock; try restarting transaction",
+            ),
+            Fault::LockWaitTimeout => InjectedDatabaseError::error(
+                1205,
+                "HY000",
+                "Lock wait timeout exceeded; try restarting transaction",
+            ),
+            Fault::Timeout => {
+                sqlx::Error::Io(io::Error::new(io::ErrorKind::TimedOut, "injected timeout"))
+            }
+            Fault::ConnectionDropped => sqlx::Error::Io(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "injected connection drop",
+            )),
+            Fault::PoolTimedOut => sqlx::Error::PoolTimedOut,
+        }
+    }
+}
+
+/// A MySQL server error produced by a [`FaultyExecutor`].
+///
+/// [`retry::is_transient`](crate::retry::is_transient) classifies it by its error
+/// number like a real `MySqlDatabaseError`.
+#[derive(Debug)]
+pub struct InjectedDatabaseError {
+    number: u16,
+    sqlstate: &'static str,
+    message: &'static str,
+}
+
+impl InjectedDatabaseError {
+    fn error(number: u16, sqlstate: &'static str, message: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(Self {
+            number,
+            sqlstate,
+            message,
+        }))
+    }
+
+    /// Returns the MySQL error number.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+}
+
+impl fmt::Display for InjectedDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.number, self.sqlstate, self.message)
+    }
+}
+
+impl std::error::Error for InjectedDatabaseError {}
+
+impl DatabaseError for InjectedDatabaseError {
+    fn message(&self) -> &str {
+        self.message
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.sqlstate))
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+#[derive(Debug)]
+struct FaultRule {
+    pattern: String,
+    fault: Fault,
+    remaining: Option<usize>,
+}
+
+/// Rules deciding which statements a [`FaultyExecutor`] fails.
+///
+/// A rule matches a statement when the statement's fingerprint contains the
+/// fingerprint of the rule's pattern, so a pattern can be a whole template or just a
+/// distinctive part such as `UPDATE accounts`. Rules are checked in the order they
+/// were added; the first matching rule with failures left is applied.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::retry::{retry, RetryBudget, RetryPolicy};
+/// use sqlx_named_bind::testing::{Fault, FaultInjector};
+/// use sqlx_named_bind::PreparedQuery;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let faults = FaultInjector::new();
+/// faults.fail_times("UPDATE accounts", Fault::Deadlock, 2);
+/// let executor = faults.wrap(&pool);
+///
+/// // The first two attempts hit the injected deadlock; the third reaches the database.
+/// let budget = RetryBudget::new(10);
+/// retry(RetryPolicy::default(), &budget, || async {
+///     PreparedQuery::new("UPDATE accounts SET balance = 0 WHERE id = :id", |q, key| {
+///         match key {
+///             ":id" => q.bind(1),
+///             _ => q,
+///         }
+///     })?
+///     .execute(executor)
+///     .await
+/// })
+/// .await?;
+/// assert_eq!(faults.injected(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    rules: Mutex<Vec<FaultRule>>,
+    injected: Mutex<usize>,
+}
+
+impl FaultInjector {
+    /// Creates an injector with no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails every statement matching `pattern` with `fault`.
+    pub fn fail(&self, pattern: &str, fault: Fault) -> &Self {
+        self.add_rule(pattern, fault, None)
+    }
+
+    /// Fails the next `times` statements matching `pattern` with `fault`.
+    pub fn fail_times(&self, pattern: &str, fault: Fault, times: usize) -> &Self {
+        self.add_rule(pattern, fault, Some(times))
+    }
+
+    /// Removes every rule.
+    pub fn clear(&self) {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Returns how many failures have been injected so far.
+    pub fn injected(&self) -> usize {
+        *self.injected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wraps `executor` so statements run through it are checked against the rules.
+    pub fn wrap<E>(&self, executor: E) -> FaultyExecutor<'_, E> {
+        FaultyExecutor {
+            inner: executor,
+            faults: self,
+        }
+    }
+
+    fn add_rule(&self, pattern: &str, fault: Fault, remaining: Option<usize>) -> &Self {
+        self.rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(FaultRule {
+                pattern: fingerprint(pattern),
+                fault,
+                remaining,
+            });
+        self
+    }
+
+    /// Returns the error to inject for `sql`, consuming one failure of the matched rule.
+    fn check(&self, sql: &str) -> Option<sqlx::Error> {
+        let statement = fingerprint(sql);
+        let mut rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        let rule = rules
+            .iter_mut()
+            .filter(|rule| rule.remaining != Some(0))
+            .find(|rule| statement.contains(&rule.pattern))?;
+        if let Some(remaining) = &mut rule.remaining {
+            *remaining -= 1;
+        }
+        *self.injected.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        Some(rule.fault.to_error())
+    }
+}
+
+/// An executor that fails statements according to a [`FaultInjector`] and passes the
+/// rest on to the wrapped executor.
+///
+/// Created by [`FaultInjector::wrap`]. It is `Copy` when the wrapped executor is, so a
+/// wrapped `&MySqlPool` can be used wherever the pool reference was.
+#[derive(Debug, Clone, Copy)]
+pub struct FaultyExecutor<'f, E> {
+    inner: E,
+    faults: &'f FaultInjector,
+}
+
+impl<'c, E> Executor<'c> for FaultyExecutor<'_, E>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    type Database = MySql;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxStream<'e, Result<Either<MySqlQueryResult, MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        match self.faults.check(query.sql()) {
+            Some(error) => Box::pin(stream::once(future::ready(Err(error)))),
+            None => self.inner.fetch_many(query),
+        }
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxFuture<'e, Result<Option<MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        match self.faults.check(query.sql()) {
+            Some(error) => Box::pin(future::ready(Err(error))),
+            None => self.inner.fetch_optional(query),
+        }
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [MySqlTypeInfo],
+    ) -> BoxFuture<'e, Result<MySqlStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        match self.faults.check(sql) {
+            Some(error) => Box::pin(future::ready(Err(error))),
+            None => self.inner.prepare_with(sql, parameters),
+        }
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<MySql>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        match self.faults.check(sql) {
+            Some(error) => Box::pin(future::ready(Err(error))),
+            None => self.inner.describe(sql),
+        }
+    }
+}
+
+/// A delay added by a [`LatencyExecutor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Latency {
+    /// Always the same delay
+    Fixed(Duration),
+    /// A delay drawn uniformly between `min` and `max`
+    Uniform {
+        /// Shortest delay
+        min: Duration,
+        /// Longest delay
+        max: Duration,
+    },
+    /// A delay drawn from an exponential distribution with the given mean, capped at
+    /// `max`, which resembles the long tail of real query latencies
+    Exponential {
+        /// Mean delay
+        mean: Duration,
+        /// Longest delay
+        max: Duration,
+    },
+}
+
+impl Latency {
+    /// Draws a delay, using `unit` (uniform in `[0, 1)`) as the source of randomness.
+    fn sample(&self, unit: f64) -> Duration {
+        match *self {
+            Latency::Fixed(delay) => delay,
+            Latency::Uniform { min, max } => min + max.saturating_sub(min).mul_f64(unit),
+            Latency::Exponential { mean, max } => mean.mul_f64(-(1.0 - unit).ln()).min(max),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LatencyRule {
+    pattern: String,
+    latency: Latency,
+}
+
+/// Rules deciding how long a [`LatencyExecutor`] delays each statement.
+///
+/// Rules match by fingerprint like [`FaultInjector`] rules; the first matching rule is
+/// applied, and statements no rule matches run without delay. Random delays come from
+/// a seeded generator, so a run can be reproduced with [`with_seed`](Self::with_seed).
+/// The delay is added before the statement is sent, using the Tokio timer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::testing::{Latency, LatencyInjector};
+/// use sqlx_named_bind::PreparedQueryAs;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let latency = LatencyInjector::new();
+/// latency.delay(
+///     "SELECT * FROM reports",
+///     Latency::Uniform {
+///         min: Duration::from_millis(200),
+///         max: Duration::from_secs(2),
+///     },
+/// );
+///
+/// let reports = PreparedQueryAs::<(i64,), _>::new("SELECT * FROM reports WHERE id = :id", |q, key| {
+///     match key {
+///         ":id" => q.bind(1),
+///         _ => q,
+///     }
+/// })?
+/// .fetch_all(latency.wrap(&pool))
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LatencyInjector {
+    rules: Mutex<Vec<LatencyRule>>,
+    state: Mutex<u64>,
+}
+
+impl Default for LatencyInjector {
+    fn default() -> Self {
+        Self::with_seed(0x5EED)
+    }
+}
+
+impl LatencyInjector {
+    /// Creates an injector with no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an injector with no rules whose random delays are derived from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rules: Mutex::default(),
+            state: Mutex::new(seed),
+        }
+    }
+
+    /// Delays every statement matching `pattern` by `latency`.
+    pub fn delay(&self, pattern: &str, latency: Latency) -> &Self {
+        self.rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(LatencyRule {
+                pattern: fingerprint(pattern),
+                latency,
+            });
+        self
+    }
+
+    /// Removes every rule.
+    pub fn clear(&self) {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Wraps `executor` so statements run through it are delayed according to the rules.
+    pub fn wrap<E>(&self, executor: E) -> LatencyExecutor<'_, E> {
+        LatencyExecutor {
+            inner: executor,
+            latency: self,
+        }
+    }
+
+    /// Returns the delay for `sql`.
+    fn delay_for(&self, sql: &str) -> Duration {
+        let statement = fingerprint(sql);
+        let rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        match rules.iter().find(|rule| statement.contains(&rule.pattern)) {
+            Some(rule) => rule.latency.sample(self.next_unit()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Returns the next number of a SplitMix64 sequence, scaled to `[0, 1)`.
+    fn next_unit(&self) -> f64 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// An executor that delays statements according to a [`LatencyInjector`] before
+/// passing them on to the wrapped executor.
+///
+/// Created by [`LatencyInjector::wrap`]. It is `Copy` when the wrapped executor is.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyExecutor<'l, E> {
+    inner: E,
+    latency: &'l LatencyInjector,
+}
+
+impl<'c, E> Executor<'c> for LatencyExecutor<'_, E>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    type Database = MySql;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxStream<'e, Result<Either<MySqlQueryResult, MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        let delay = self.latency.delay_for(query.sql());
+        let rows = self.inner.fetch_many(query);
+        Box::pin(
+            stream::once(async move {
+                tokio::time::sleep(delay).await;
+                rows
+            })
+            .flatten(),
+        )
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxFuture<'e, Result<Option<MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        let delay = self.latency.delay_for(query.sql());
+        let row = self.inner.fetch_optional(query);
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            row.await
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [MySqlTypeInfo],
+    ) -> BoxFuture<'e, Result<MySqlStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.inner.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<MySql>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.inner.describe(sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::is_transient;
+    use crate::PreparedQuery;
+
+    #[test]
+    fn test_rules_match_by_fingerprint() {
+        let faults = FaultInjector::new();
+        faults
+            .fail_times("UPDATE accounts", Fault::Deadlock, 1)
+            .fail("select * from audit_log", Fault::Timeout);
+
+        assert!(faults.check("SELECT 1").is_none());
+        assert!(faults
+            .check("update  accounts SET balance = ? WHERE id = ?")
+            .is_some());
+        assert!(faults.check("UPDATE accounts SET balance = ?").is_none());
+        assert!(faults
+            .check("SELECT * FROM audit_log WHERE id = 1")
+            .is_some());
+        assert!(faults
+            .check("SELECT * FROM audit_log WHERE id = 2")
+            .is_some());
+        assert_eq!(faults.injected(), 3);
+
+        faults.clear();
+        assert!(faults.check("SELECT * FROM audit_log").is_none());
+    }
+
+    #[test]
+    fn test_latency_rules() {
+        let latency = LatencyInjector::with_seed(7);
+        let (min, max) = (Duration::from_millis(10), Duration::from_millis(20));
+        latency
+            .delay("UPDATE accounts", Latency::Fixed(Duration::from_millis(5)))
+            .delay("SELECT * FROM reports", Latency::Uniform { min, max });
+
+        assert_eq!(latency.delay_for("SELECT 1"), Duration::ZERO);
+        assert_eq!(
+            latency.delay_for("UPDATE accounts SET balance = ?"),
+            Duration::from_millis(5)
+        );
+        let delays: Vec<_> = (0..100)
+            .map(|_| latency.delay_for("SELECT * FROM reports WHERE id = ?"))
+            .collect();
+        assert!(delays.iter().all(|d| (min..=max).contains(d)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+
+        let replay = LatencyInjector::with_seed(7);
+        replay.delay("SELECT * FROM reports", Latency::Uniform { min, max });
+        replay.delay_for("SELECT * FROM reports");
+        assert_eq!(replay.delay_for("SELECT * FROM reports"), delays[0]);
+    }
+
+    #[test]
+    fn test_exponential_latency_is_capped() {
+        let latency = Latency::Exponential {
+            mean: Duration::from_millis(50),
+            max: Duration::from_millis(400),
+        };
+        assert_eq!(latency.sample(0.0), Duration::ZERO);
+        assert_eq!(latency.sample(0.999_999), Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_injected_faults_are_transient() {
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://localhost/test").unwrap();
+        let faults = FaultInjector::new();
+        let executor = faults.wrap(&pool);
+
+        for fault in [
+            Fault::Deadlock,
+            Fault::LockWaitTimeout,
+            Fault::Timeout,
+            Fault::ConnectionDropped,
+            Fault::PoolTimedOut,
+        ] {
+            faults.clear();
+            faults.fail("DELETE FROM sessions", fault);
+            let error =
+                PreparedQuery::new("DELETE FROM sessions WHERE id = :id", |q, key| match key {
+                    ":id" => q.bind(1),
+                    _ => q,
+                })
+                .unwrap()
+                .execute(executor)
+                .await
+                .unwrap_err();
+            assert!(is_transient(&error), "{fault:?} should be transient");
+        }
+    }
+}
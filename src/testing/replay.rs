@@ -0,0 +1,426 @@
+//! Recording query results to a file and replaying them without a database.
+
+use crate::json::row_to_json;
+use crate::query_as::QA;
+use crate::PreparedQueryAs;
+use futures_util::future::{self, BoxFuture};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::mysql::{MySqlArguments, MySqlQueryResult, MySqlRow, MySqlStatement, MySqlTypeInfo};
+use sqlx::{Describe, Either, Execute, Executor, MySql};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+
+/// Returns a digest identifying the bound values and their types.
+///
+/// It is derived from the encoded arguments, so it changes whenever a value or its
+/// SQL type does. Recordings should be regenerated after upgrading SQLx.
+fn binds_digest(arguments: &MySqlArguments) -> String {
+    Sha256::digest(format!("{arguments:?}"))
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RecordedQuery {
+    sql: String,
+    binds: String,
+    params: usize,
+    rows: Vec<Value>,
+}
+
+/// Collects the statements and result rows seen by a [`RecordingExecutor`].
+///
+/// Save the recording with [`save`](Self::save) at the end of a "record" run against a
+/// real database, then load it with [`ReplayExecutor::load`] in tests.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::{MySqlPool, FromRow};
+/// use sqlx_named_bind::testing::Recorder;
+/// use sqlx_named_bind::PreparedQueryAs;
+///
+/// #[derive(FromRow, serde::Deserialize)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// # let pool = MySqlPool::connect("mysql://localhost/test").await?;
+/// let recorder = Recorder::new();
+/// let users = PreparedQueryAs::<User, _>::new("SELECT id, name FROM users WHERE id = :id", |q, key| {
+///     match key {
+///         ":id" => q.bind(42),
+///         _ => q,
+///     }
+/// })?
+/// .fetch_all(recorder.wrap(&pool))
+/// .await?;
+///
+/// recorder.save(std::fs::File::create("tests/recordings/users.ndjson")?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Recorder {
+    queries: Mutex<Vec<RecordedQuery>>,
+}
+
+impl Recorder {
+    /// Creates an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps `executor` so statements run through it and their result rows are recorded.
+    pub fn wrap<E>(&self, executor: E) -> RecordingExecutor<'_, E> {
+        RecordingExecutor {
+            inner: executor,
+            recorder: self,
+        }
+    }
+
+    /// Returns the number of statements recorded so far.
+    pub fn len(&self) -> usize {
+        self.queries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Returns `true` if nothing has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the recording to `writer` as newline-delimited JSON, one statement per line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn save<W: Write>(&self, mut writer: W) -> crate::Result<()> {
+        let queries = self.queries.lock().unwrap_or_else(|e| e.into_inner());
+        for query in queries.iter() {
+            let line = json!({
+                "sql": query.sql,
+                "binds": query.binds,
+                "params": query.params,
+                "rows": query.rows,
+            });
+            serde_json::to_writer(&mut writer, &line)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Starts recording a statement and returns its index.
+    fn start(&self, sql: &str, arguments: &MySqlArguments) -> usize {
+        use sqlx::Arguments;
+
+        let mut queries = self.queries.lock().unwrap_or_else(|e| e.into_inner());
+        queries.push(RecordedQuery {
+            sql: sql.to_owned(),
+            binds: binds_digest(arguments),
+            params: arguments.len(),
+            rows: Vec::new(),
+        });
+        queries.len() - 1
+    }
+
+    fn push_row(&self, index: usize, row: &MySqlRow) -> Result<(), sqlx::Error> {
+        let value = row_to_json(row).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        self.queries.lock().unwrap_or_else(|e| e.into_inner())[index]
+            .rows
+            .push(value);
+        Ok(())
+    }
+}
+
+/// An executor that records statements and their result rows into a [`Recorder`].
+///
+/// Created by [`Recorder::wrap`]. It is `Copy` when the wrapped executor is.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordingExecutor<'r, E> {
+    inner: E,
+    recorder: &'r Recorder,
+}
+
+impl<'c, 'r: 'c, E> Executor<'c> for RecordingExecutor<'r, E>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    type Database = MySql;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        mut query: Q,
+    ) -> BoxStream<'e, Result<Either<MySqlQueryResult, MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        let sql = query.sql();
+        let persistent = query.persistent();
+        let arguments = match query.take_arguments() {
+            Ok(arguments) => arguments.unwrap_or_default(),
+            Err(e) => return Box::pin(stream::once(future::ready(Err(sqlx::Error::Encode(e))))),
+        };
+
+        let recorder = self.recorder;
+        let index = recorder.start(sql, &arguments);
+        let results = self
+            .inner
+            .fetch_many(sqlx::query_with(sql, arguments).persistent(persistent));
+        Box::pin(results.map(move |result| {
+            if let Ok(Either::Right(row)) = &result {
+                recorder.push_row(index, row)?;
+            }
+            result
+        }))
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        mut query: Q,
+    ) -> BoxFuture<'e, Result<Option<MySqlRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, MySql>,
+    {
+        let sql = query.sql();
+        let persistent = query.persistent();
+        let arguments = match query.take_arguments() {
+            Ok(arguments) => arguments.unwrap_or_default(),
+            Err(e) => return Box::pin(future::ready(Err(sqlx::Error::Encode(e)))),
+        };
+
+        let recorder = self.recorder;
+        let index = recorder.start(sql, &arguments);
+        let row = self
+            .inner
+            .fetch_optional(sqlx::query_with(sql, arguments).persistent(persistent));
+        Box::pin(async move {
+            let row = row.await?;
+            if let Some(row) = &row {
+                recorder.push_row(index, row)?;
+            }
+            Ok(row)
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [MySqlTypeInfo],
+    ) -> BoxFuture<'e, Result<MySqlStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.inner.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<MySql>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.inner.describe(sql)
+    }
+}
+
+/// Serves the rows of a recording made with a [`Recorder`], without a database.
+///
+/// Rows are looked up by the query's converted SQL and a digest of its bound values,
+/// so a query replays only if it was recorded with the same parameters. They are
+/// stored as JSON (see [`row_to_json`]), so the row type must implement
+/// `serde::Deserialize` in addition to `FromRow`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use sqlx::FromRow;
+/// use sqlx_named_bind::testing::ReplayExecutor;
+/// use sqlx_named_bind::PreparedQueryAs;
+///
+/// #[derive(FromRow, serde::Deserialize)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let file = std::io::BufReader::new(std::fs::File::open("tests/recordings/users.ndjson")?);
+/// let replay = ReplayExecutor::load(file)?;
+///
+/// let mut query = PreparedQueryAs::<User, _>::new("SELECT id, name FROM users WHERE id = :id", |q, key| {
+///     match key {
+///         ":id" => q.bind(42),
+///         _ => q,
+///     }
+/// })?;
+/// let users = replay.fetch_all(&mut query)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ReplayExecutor {
+    results: HashMap<(String, String), Vec<Value>>,
+}
+
+impl ReplayExecutor {
+    /// Loads a recording written by [`Recorder::save`].
+    ///
+    /// When the same statement was recorded more than once, the last result is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or a line is not a recorded statement.
+    pub fn load<B: BufRead>(reader: B) -> crate::Result<Self> {
+        let mut results = HashMap::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut record: Value = serde_json::from_str(&line)?;
+            let field = |name: &str| record[name].as_str().map(str::to_owned);
+            let (Some(sql), Some(binds)) = (field("sql"), field("binds")) else {
+                return Err(crate::Error::MissingField("sql/binds".to_owned()));
+            };
+            let rows = match record["rows"].take() {
+                Value::Array(rows) => rows,
+                _ => return Err(crate::Error::MissingField("rows".to_owned())),
+            };
+            results.insert((sql, binds), rows);
+        }
+        Ok(Self { results })
+    }
+
+    /// Returns every recorded row for `query`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotRecorded`](crate::Error::NotRecorded) if the query was not
+    /// recorded with these parameters, or an error if a row cannot be deserialized.
+    pub fn fetch_all<R, F>(&self, query: &mut PreparedQueryAs<R, F>) -> crate::Result<Vec<R>>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + DeserializeOwned,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        self.rows(query)?
+            .iter()
+            .map(|row| Ok(serde_json::from_value(row.clone())?))
+            .collect()
+    }
+
+    /// Returns the first recorded row for `query`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotRecorded`](crate::Error::NotRecorded) if the query was not
+    /// recorded with these parameters, a database `RowNotFound` error if it returned
+    /// no rows, or an error if the row cannot be deserialized.
+    pub fn fetch_one<R, F>(&self, query: &mut PreparedQueryAs<R, F>) -> crate::Result<R>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + DeserializeOwned,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        self.fetch_optional(query)?
+            .ok_or(crate::Error::Database(sqlx::Error::RowNotFound))
+    }
+
+    /// Returns the first recorded row for `query`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotRecorded`](crate::Error::NotRecorded) if the query was not
+    /// recorded with these parameters, or an error if the row cannot be deserialized.
+    pub fn fetch_optional<R, F>(
+        &self,
+        query: &mut PreparedQueryAs<R, F>,
+    ) -> crate::Result<Option<R>>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin + DeserializeOwned,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        match self.rows(query)?.first() {
+            Some(row) => Ok(Some(serde_json::from_value(row.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn rows<R, F>(&self, query: &mut PreparedQueryAs<R, F>) -> crate::Result<&[Value]>
+    where
+        for<'row> R: sqlx::FromRow<'row, MySqlRow> + Send + Unpin,
+        F: for<'q> FnMut(QA<'q, R>, &str) -> QA<'q, R>,
+    {
+        let bound = query.bound_arguments()?;
+        let key = (query.sql().to_owned(), binds_digest(&bound.arguments));
+        self.results
+            .get(&key)
+            .map(Vec::as_slice)
+            .ok_or_else(|| crate::Error::NotRecorded(key.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, sqlx::FromRow, serde::Deserialize)]
+    struct User {
+        id: i64,
+        name: String,
+    }
+
+    type Binder = for<'q> fn(QA<'q, User>, &str) -> QA<'q, User>;
+
+    type BoxedBinder = Box<dyn for<'q> FnMut(QA<'q, User>, &str) -> QA<'q, User>>;
+
+    fn find_user(id: i64) -> PreparedQueryAs<User, BoxedBinder> {
+        let binder: BoxedBinder = Box::new(move |q, key| match key {
+            ":id" => q.bind(id),
+            _ => q,
+        });
+        PreparedQueryAs::new("SELECT id, name FROM users WHERE id = :id", binder).unwrap()
+    }
+
+    #[test]
+    fn test_replay_matches_sql_and_binds() {
+        let mut query = find_user(1);
+        let bound = query.bound_arguments().unwrap();
+        let recording = format!(
+            "{}\n",
+            json!({
+                "sql": query.sql(),
+                "binds": binds_digest(&bound.arguments),
+                "params": 1,
+                "rows": [{"id": 1, "name": "Jane"}],
+            })
+        );
+        let replay = ReplayExecutor::load(recording.as_bytes()).unwrap();
+
+        assert_eq!(
+            replay.fetch_all(&mut query).unwrap(),
+            vec![User {
+                id: 1,
+                name: "Jane".to_owned()
+            }]
+        );
+        assert_eq!(replay.fetch_one(&mut query).unwrap().name, "Jane");
+        assert!(matches!(
+            replay.fetch_all(&mut find_user(2)),
+            Err(crate::Error::NotRecorded(_))
+        ));
+
+        let mut other =
+            PreparedQueryAs::<User, Binder>::new("SELECT id, name FROM users", |q, _| q).unwrap();
+        assert!(replay.fetch_optional(&mut other).is_err());
+    }
+}
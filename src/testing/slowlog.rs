@@ -0,0 +1,288 @@
+//! Re-executing captured slow queries against a staging database.
+
+use crate::conditional::NamedBinds;
+use crate::fingerprint::fingerprint;
+use crate::query_log::LoggedQuery;
+use crate::registry::{QueryRegistry, RegistryEntry};
+use serde_json::Value;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+/// A parameter of a captured execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedParam {
+    /// Declared or observed SQL type (e.g. `BIGINT`), if known
+    pub sql_type: Option<String>,
+    /// The recorded value, if it was captured
+    pub value: Option<Value>,
+}
+
+/// One execution captured in production, in the style of a slow-query log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowQuery {
+    /// Fingerprint of the executed SQL (see [`fingerprint`])
+    pub fingerprint: String,
+    /// Parameters in the order of the query's distinct placeholders; may be empty
+    pub params: Vec<CapturedParam>,
+    /// How long the execution took in production
+    pub duration: Duration,
+}
+
+impl SlowQuery {
+    /// Parses captured executions from JSON Lines, one object per line:
+    ///
+    /// ```json
+    /// {"fingerprint": "select * from users where id = ?", "duration_ms": 1200,
+    ///  "params": [{"type": "BIGINT", "value": 42}]}
+    /// ```
+    ///
+    /// `params` and each parameter's `type` and `value` are optional. Blank lines are
+    /// skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be read, or
+    /// [`Error::InvalidMetadata`](crate::Error::InvalidMetadata) if a line is not such
+    /// an object.
+    pub fn load<B: BufRead>(reader: B) -> crate::Result<Vec<Self>> {
+        let mut captured = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                crate::Error::InvalidMetadata(format!("capture line {}: {reason}", number + 1))
+            };
+            let object: Value = serde_json::from_str(&line)?;
+            let fingerprint = object["fingerprint"]
+                .as_str()
+                .ok_or_else(|| invalid("missing \"fingerprint\""))?;
+            let duration_ms = object["duration_ms"]
+                .as_f64()
+                .ok_or_else(|| invalid("missing \"duration_ms\""))?;
+            let params = match &object["params"] {
+                Value::Null => Vec::new(),
+                Value::Array(params) => params
+                    .iter()
+                    .map(|param| CapturedParam {
+                        sql_type: param["type"].as_str().map(str::to_owned),
+                        value: param.get("value").cloned(),
+                    })
+                    .collect(),
+                _ => return Err(invalid("\"params\" is not an array")),
+            };
+            captured.push(SlowQuery {
+                fingerprint: fingerprint.to_owned(),
+                params,
+                duration: Duration::from_secs_f64(duration_ms.max(0.0) / 1000.0),
+            });
+        }
+        Ok(captured)
+    }
+}
+
+impl From<&LoggedQuery> for SlowQuery {
+    /// Captures a [`query_log`](crate::query_log) entry. The log keeps only a hash of
+    /// the parameters, so they are synthesized on replay.
+    fn from(logged: &LoggedQuery) -> Self {
+        SlowQuery {
+            fingerprint: logged.fingerprint.clone(),
+            params: Vec::new(),
+            duration: logged.duration,
+        }
+    }
+}
+
+/// The outcome of replaying one [`SlowQuery`].
+#[derive(Debug)]
+pub struct ReplayedQuery {
+    /// Fingerprint of the captured execution
+    pub fingerprint: String,
+    /// Name of the registry query it matched, or `None` if none did
+    pub name: Option<String>,
+    /// How long the execution took in production
+    pub captured: Duration,
+    /// How long the replay took, or the error it failed with; `None` if not replayed
+    pub replayed: Option<crate::Result<Duration>>,
+}
+
+/// Re-executes captured slow queries through the registry queries they came from.
+///
+/// Captured executions are matched to registry entries by fingerprint. Each is run
+/// with its recorded parameter values where present, and with synthetic values derived
+/// from the captured type, the entry's declared `param` type, or a placeholder string
+/// otherwise. Replays run sequentially so their timings do not disturb each other.
+///
+/// Replayed writes change the target database; point it at staging only.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io::BufReader;
+/// use sqlx::MySqlPool;
+/// use sqlx_named_bind::registry::QueryRegistry;
+/// use sqlx_named_bind::testing::{SlowLogReplayer, SlowQuery};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let staging = MySqlPool::connect("mysql://staging/app").await?;
+/// let registry = QueryRegistry::load_dir("queries")?;
+/// let captured = SlowQuery::load(BufReader::new(std::fs::File::open("slow.jsonl")?))?;
+///
+/// for replay in SlowLogReplayer::new(&registry).replay(&staging, &captured).await {
+///     if let Some(Ok(took)) = replay.replayed {
+///         println!("{:?}: {:?} in production, {took:?} on staging", replay.name, replay.captured);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SlowLogReplayer<'r> {
+    by_fingerprint: HashMap<String, &'r RegistryEntry>,
+}
+
+impl<'r> SlowLogReplayer<'r> {
+    /// Indexes the entries of `registry` by fingerprint.
+    pub fn new(registry: &'r QueryRegistry) -> Self {
+        let by_fingerprint = registry
+            .entries()
+            .map(|entry| (fingerprint(entry.sql()), entry))
+            .collect();
+        Self { by_fingerprint }
+    }
+
+    /// Returns the registry entry a captured fingerprint belongs to, if any.
+    pub fn entry_for(&self, fingerprint: &str) -> Option<&'r RegistryEntry> {
+        self.by_fingerprint.get(fingerprint).copied()
+    }
+
+    /// Replays every captured execution on `pool`, in order.
+    pub async fn replay(&self, pool: &MySqlPool, captured: &[SlowQuery]) -> Vec<ReplayedQuery> {
+        let mut replayed = Vec::with_capacity(captured.len());
+        for query in captured {
+            let entry = self.entry_for(&query.fingerprint);
+            let outcome = match entry {
+                Some(entry) => Some(replay_one(pool, entry, &query.params).await),
+                None => None,
+            };
+            replayed.push(ReplayedQuery {
+                fingerprint: query.fingerprint.clone(),
+                name: entry.map(|entry| entry.name().to_owned()),
+                captured: query.duration,
+                replayed: outcome,
+            });
+        }
+        replayed
+    }
+}
+
+async fn replay_one(
+    pool: &MySqlPool,
+    entry: &RegistryEntry,
+    params: &[CapturedParam],
+) -> crate::Result<Duration> {
+    let binds = replay_binds(entry, params);
+    let mut query = entry.prepare(binds.binder())?;
+    let started = Instant::now();
+    query.fetch_rows(pool).await?;
+    Ok(started.elapsed())
+}
+
+/// Binds each distinct placeholder of `entry` to its captured or synthetic value.
+fn replay_binds(entry: &RegistryEntry, params: &[CapturedParam]) -> NamedBinds {
+    let mut distinct: Vec<&String> = Vec::new();
+    for placeholder in entry.placeholders() {
+        if !distinct.contains(&placeholder) {
+            distinct.push(placeholder);
+        }
+    }
+
+    let mut binds = NamedBinds::new();
+    for (i, placeholder) in distinct.into_iter().enumerate() {
+        let captured = params.get(i);
+        let declared = entry
+            .meta()
+            .params
+            .iter()
+            .find(|param| &param.name == placeholder)
+            .and_then(|param| param.sql_type.as_deref());
+        let sql_type = captured
+            .and_then(|param| param.sql_type.as_deref())
+            .or(declared);
+        match captured.and_then(|param| param.value.as_ref()) {
+            Some(value) => bind_json(&mut binds, placeholder, value),
+            None => bind_synthetic(&mut binds, placeholder, sql_type),
+        }
+    }
+    binds
+}
+
+fn bind_json(binds: &mut NamedBinds, name: &str, value: &Value) {
+    match value {
+        Value::Null => binds.set(name, None::<String>),
+        Value::Bool(b) => binds.set(name, *b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => binds.set(name, i),
+            (None, Some(u)) => binds.set(name, u),
+            _ => binds.set(name, n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => binds.set(name, s.clone()),
+        other => binds.set(name, other.to_string()),
+    };
+}
+
+/// Binds a plausible value of `sql_type`, ignoring any length or precision.
+fn bind_synthetic(binds: &mut NamedBinds, name: &str, sql_type: Option<&str>) {
+    let base = sql_type
+        .and_then(|ty| ty.split(['(', ' ']).next())
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    match base.as_str() {
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "INTEGER" | "BIGINT" => {
+            binds.set(name, 1_i64)
+        }
+        "DECIMAL" | "NUMERIC" | "FLOAT" | "DOUBLE" | "REAL" => binds.set(name, 1.0_f64),
+        "BOOL" | "BOOLEAN" => binds.set(name, true),
+        "DATE" => binds.set(name, "2000-01-01"),
+        "DATETIME" | "TIMESTAMP" => binds.set(name, "2000-01-01 00:00:00"),
+        "TIME" => binds.set(name, "00:00:00"),
+        _ => binds.set(name, "x"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_match_captures() {
+        let input = "{\"fingerprint\": \"select id from users where team = ? and id > ?\", \
+                     \"duration_ms\": 1500, \"params\": [{\"value\": \"search\"}]}\n\
+                     \n\
+                     {\"fingerprint\": \"select 1\", \"duration_ms\": 2.5}\n";
+        let captured = SlowQuery::load(input.as_bytes()).unwrap();
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].duration, Duration::from_millis(1500));
+        assert_eq!(captured[0].params[0].value, Some(Value::from("search")));
+        assert!(captured[1].params.is_empty());
+        assert!(SlowQuery::load("{\"duration_ms\": 1}".as_bytes()).is_err());
+
+        let mut registry = QueryRegistry::new();
+        registry
+            .add(
+                "team_users",
+                "-- param: :team\n-- param: :after BIGINT\n\
+                 SELECT id FROM users WHERE team = :team AND id > :after",
+            )
+            .unwrap();
+        let replayer = SlowLogReplayer::new(&registry);
+        let entry = replayer.entry_for(&captured[0].fingerprint).unwrap();
+        assert_eq!(entry.name(), "team_users");
+        assert!(replayer.entry_for("select 1").is_none());
+
+        let binds = replay_binds(entry, &captured[0].params);
+        assert_eq!(binds.names().collect::<Vec<_>>(), [":after", ":team"]);
+    }
+}
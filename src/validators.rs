@@ -0,0 +1,458 @@
+//! Validators run on bound values before a query executes.
+//!
+//! [`PreparedQuery::validate`](crate::PreparedQuery::validate) and
+//! [`PreparedQueryAs::validate`](crate::PreparedQueryAs::validate) attach a
+//! [`Validator`] to a placeholder, so input checks such as a range for `:limit` or a
+//! pattern for `:slug` live next to the query that consumes the value. After the
+//! binder runs, each validated value is read back as a [`ParamValue`]; a value that
+//! fails its check stops the execution with
+//! [`Error::InvalidParam`](crate::Error::InvalidParam) before anything is sent to the
+//! server.
+//!
+//! `MySqlArguments` keeps its encoded values private, so they are decoded from its
+//! `Debug` output. This only happens for queries with validators attached.
+
+use crate::cipher;
+use crate::registry::DeprecatedUse;
+#[cfg(feature = "regex")]
+use regex::Regex;
+use sqlx::mysql::MySqlArguments;
+use std::fmt;
+use std::sync::Arc;
+
+/// A bound value, as decoded for a [`Validator`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    /// `NULL`, e.g. a bound `None`
+    Null,
+    /// A signed integer
+    Int(i64),
+    /// An unsigned integer or boolean
+    UInt(u64),
+    /// A floating point number
+    Float(f64),
+    /// A string, decimal or JSON document
+    Text(String),
+    /// A binary string
+    Bytes(Vec<u8>),
+    /// A date or time, in its encoded form
+    Temporal(Vec<u8>),
+}
+
+impl ParamValue {
+    /// Returns the value as an `i128` if it is an integer.
+    fn as_integer(&self) -> Option<i128> {
+        match *self {
+            ParamValue::Int(i) => Some(i.into()),
+            ParamValue::UInt(u) => Some(u.into()),
+            _ => None,
+        }
+    }
+}
+
+type Check = Arc<dyn Fn(&ParamValue) -> Result<(), String> + Send + Sync>;
+
+/// A check of one bound value.
+///
+/// # Examples
+///
+/// ```
+/// use sqlx_named_bind::validators::{ParamValue, Validator};
+/// use sqlx_named_bind::PreparedQueryAs;
+///
+/// let query = PreparedQueryAs::<(i64,), _>::new(
+///     "SELECT id FROM posts WHERE slug = :slug LIMIT :limit",
+///     |q, key| match key {
+///         ":slug" => q.bind("hello-world"),
+///         ":limit" => q.bind(500_i64),
+///         _ => q,
+///     },
+/// )?
+/// .validate("slug", Validator::non_empty())?
+/// .validate("limit", Validator::range(1, 100))?
+/// .validate("limit", Validator::new(|value| match value {
+///     ParamValue::Int(n) if n % 10 != 0 => Err("must be a multiple of 10".to_owned()),
+///     _ => Ok(()),
+/// }))?;
+/// # Ok::<(), sqlx_named_bind::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Validator {
+    description: String,
+    check: Check,
+}
+
+impl Validator {
+    /// Creates a validator from a function returning why a value is rejected.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&ParamValue) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self {
+            description: "custom".to_owned(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// Accepts integers from `min` to `max`, inclusive.
+    pub fn range(min: i64, max: i64) -> Self {
+        Self {
+            description: format!("range({min}..={max})"),
+            check: Arc::new(move |value| match value.as_integer() {
+                Some(n) if (i128::from(min)..=i128::from(max)).contains(&n) => Ok(()),
+                Some(n) => Err(format!("{n} is outside {min}..={max}")),
+                None => Err(format!("expected an integer, got {value:?}")),
+            }),
+        }
+    }
+
+    /// Accepts strings matching `regex`.
+    ///
+    /// Requires the `regex` feature. The caller compiles the pattern, so an invalid one
+    /// is reported where it is written rather than when the validator is attached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use sqlx_named_bind::validators::Validator;
+    ///
+    /// let slug = Validator::matches(Regex::new("^[a-z0-9-]+$").unwrap());
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn matches(regex: Regex) -> Self {
+        Self {
+            description: format!("matches({regex})"),
+            check: Arc::new(move |value| match value {
+                ParamValue::Text(text) if regex.is_match(text) => Ok(()),
+                ParamValue::Text(text) => Err(format!("'{text}' does not match {regex}")),
+                _ => Err(format!("expected a string, got {value:?}")),
+            }),
+        }
+    }
+
+    /// Rejects `NULL`, empty strings and empty binary strings.
+    pub fn non_empty() -> Self {
+        Self {
+            description: "non_empty".to_owned(),
+            check: Arc::new(|value| match value {
+                ParamValue::Null => Err("must not be NULL".to_owned()),
+                ParamValue::Text(text) if text.is_empty() => Err("must not be empty".to_owned()),
+                ParamValue::Bytes(bytes) if bytes.is_empty() => Err("must not be empty".to_owned()),
+                _ => Ok(()),
+            }),
+        }
+    }
+
+    /// Rejects `NULL`.
+    pub fn not_null() -> Self {
+        Self {
+            description: "not_null".to_owned(),
+            check: Arc::new(|value| match value {
+                ParamValue::Null => Err("must not be NULL".to_owned()),
+                _ => Ok(()),
+            }),
+        }
+    }
+
+    /// Returns why `value` is rejected, if it is.
+    pub fn check(&self, value: &ParamValue) -> Result<(), String> {
+        (self.check)(value)
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Validator").field(&self.description).finish()
+    }
+}
+
+/// Checks applied while binding a query's placeholders.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BindChecks {
+    /// Whether occurrences of a repeated placeholder must be bound to equal values
+    pub(crate) repeats: bool,
+    /// Validators by placeholder, including the leading `:`
+    pub(crate) validators: Vec<(String, Validator)>,
+    /// Placeholders whose values are encrypted with the installed
+    /// [`FieldCipher`](crate::cipher::FieldCipher) before they are bound
+    pub(crate) encrypted: Vec<String>,
+    /// Placeholders carrying large payloads, excluded from checks that copy values
+    pub(crate) long_data: Vec<String>,
+    /// Set for queries prepared from a deprecated registry entry, counting each binding
+    pub(crate) deprecation: Option<DeprecatedUse>,
+}
+
+impl BindChecks {
+    /// Returns `true` if any validator is attached.
+    pub(crate) fn validates(&self) -> bool {
+        !self.validators.is_empty()
+    }
+
+    /// Returns `true` if the values of `key` are encrypted before they are bound.
+    pub(crate) fn encrypts(&self, key: &str) -> bool {
+        self.encrypted.iter().any(|name| name == key)
+    }
+
+    /// Returns `true` if `key` is marked as a large payload.
+    pub(crate) fn is_long_data(&self, key: &str) -> bool {
+        self.long_data.iter().any(|name| name == key)
+    }
+
+    /// Validates the plaintext values `plain` bound for `key`, then appends them to
+    /// `arguments` encrypted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidParam`](crate::Error::InvalidParam) for a value a
+    /// validator rejects, or [`Error::Cipher`](crate::Error::Cipher) if a value cannot
+    /// be encrypted.
+    pub(crate) fn encrypt(
+        &self,
+        key: &str,
+        plain: &MySqlArguments,
+        arguments: &mut MySqlArguments,
+    ) -> crate::Result<()> {
+        let values = decode_arguments(plain);
+        self.check_values(key, &values)?;
+        cipher::encrypt_values(key, values, arguments)
+    }
+
+    /// Runs the validators on `arguments`, where the values of `order[i]` start at
+    /// argument index `starts[i]`.
+    ///
+    /// Every value bound for a list placeholder is checked. Encrypted placeholders are
+    /// skipped; their plaintext was checked by [`encrypt`](Self::encrypt).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidParam`](crate::Error::InvalidParam) for the first value
+    /// a validator rejects.
+    pub(crate) fn validate(
+        &self,
+        order: &[String],
+        starts: &[usize],
+        arguments: &MySqlArguments,
+    ) -> crate::Result<()> {
+        if !self.validates() {
+            return Ok(());
+        }
+        let values = decode_arguments(arguments);
+        for (i, (key, &start)) in order.iter().zip(starts).enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(values.len());
+            let Some(bound) = values.get(start..end) else {
+                continue;
+            };
+            if !self.encrypts(key) {
+                self.check_values(key, bound)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the validators of `key` on each of `values`.
+    fn check_values(&self, key: &str, values: &[ParamValue]) -> crate::Result<()> {
+        for (_, validator) in self.validators.iter().filter(|(name, _)| name == key) {
+            for value in values {
+                validator
+                    .check(value)
+                    .map_err(|reason| crate::Error::InvalidParam {
+                        name: key.to_owned(),
+                        reason,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes every value in `arguments` from its `Debug` output.
+fn decode_arguments(arguments: &MySqlArguments) -> Vec<ParamValue> {
+    let debug = format!("{arguments:?}");
+    let list = |field: &str| {
+        debug
+            .split_once(field)
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .map(|(list, _)| list)
+            .unwrap_or_default()
+    };
+    let bytes: Vec<u8> = list("values: [")
+        .split(", ")
+        .filter_map(|b| b.parse().ok())
+        .collect();
+    let nulls: Vec<u8> = list("null_bitmap: NullBitMap { bytes: [")
+        .split(", ")
+        .filter_map(|b| b.parse().ok())
+        .collect();
+
+    let mut values = Vec::new();
+    let mut offset = 0;
+    for (index, info) in list("types: [")
+        .split("MySqlTypeInfo { type: ")
+        .skip(1)
+        .enumerate()
+    {
+        let null = nulls
+            .get(index / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0);
+        if null {
+            values.push(ParamValue::Null);
+            continue;
+        }
+        let type_name = info.split(',').next().unwrap_or_default();
+        let unsigned = info.contains("UNSIGNED");
+        let binary = info.contains("BINARY");
+        let Some((value, len)) = decode_value(
+            &bytes[offset.min(bytes.len())..],
+            type_name,
+            unsigned,
+            binary,
+        ) else {
+            break;
+        };
+        values.push(value);
+        offset += len;
+    }
+    values
+}
+
+/// Decodes one value of the binary protocol, returning it and its encoded length.
+fn decode_value(
+    bytes: &[u8],
+    type_name: &str,
+    unsigned: bool,
+    binary: bool,
+) -> Option<(ParamValue, usize)> {
+    let fixed = |len: usize| bytes.get(..len).map(|b| (b, len));
+    let int = |len: usize| {
+        fixed(len).map(|(b, len)| {
+            let mut buf = [0; 8];
+            buf[..len].copy_from_slice(b);
+            let raw = u64::from_le_bytes(buf);
+            let value = if unsigned {
+                ParamValue::UInt(raw)
+            } else {
+                let shift = 64 - 8 * len as u32;
+                ParamValue::Int(((raw << shift) as i64) >> shift)
+            };
+            (value, len)
+        })
+    };
+    match type_name {
+        "Tiny" => int(1),
+        "Short" | "Year" => int(2),
+        "Long" | "Int24" => int(4),
+        "LongLong" => int(8),
+        "Float" => fixed(4).map(|(b, len)| {
+            let value = f32::from_le_bytes(b.try_into().ok()?);
+            Some((ParamValue::Float(value.into()), len))
+        })?,
+        "Double" => fixed(8).map(|(b, len)| {
+            let value = f64::from_le_bytes(b.try_into().ok()?);
+            Some((ParamValue::Float(value), len))
+        })?,
+        "Date" | "Time" | "Datetime" | "Timestamp" => {
+            let len = usize::from(*bytes.first()?);
+            let value = bytes.get(1..=len)?.to_vec();
+            Some((ParamValue::Temporal(value), 1 + len))
+        }
+        _ => {
+            let (len, header) = match *bytes.first()? {
+                0xfc => (
+                    u64::from(u16::from_le_bytes(bytes.get(1..3)?.try_into().ok()?)),
+                    3,
+                ),
+                0xfd => {
+                    let mut buf = [0; 4];
+                    buf[..3].copy_from_slice(bytes.get(1..4)?);
+                    (u64::from(u32::from_le_bytes(buf)), 4)
+                }
+                0xfe => (u64::from_le_bytes(bytes.get(1..9)?.try_into().ok()?), 9),
+                len => (u64::from(len), 1),
+            };
+            let len = usize::try_from(len).ok()?;
+            let data = bytes.get(header..header + len)?.to_vec();
+            let value = match String::from_utf8(data) {
+                Ok(text) if !binary => ParamValue::Text(text),
+                Ok(text) => ParamValue::Bytes(text.into_bytes()),
+                Err(e) => ParamValue::Bytes(e.into_bytes()),
+            };
+            Some((value, header + len))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Arguments;
+
+    #[test]
+    fn test_decode_arguments() {
+        let mut arguments = MySqlArguments::default();
+        arguments.add(-5_i64).unwrap();
+        arguments.add("héllo").unwrap();
+        arguments.add(None::<i32>).unwrap();
+        arguments.add(1.5_f64).unwrap();
+        arguments.add(true).unwrap();
+        arguments.add(7_u16).unwrap();
+        arguments.add(-3_i8).unwrap();
+        arguments.add(vec![1_u8, 2]).unwrap();
+        arguments.add("x".repeat(300)).unwrap();
+        arguments.add(2.5_f32).unwrap();
+        assert_eq!(
+            decode_arguments(&arguments),
+            [
+                ParamValue::Int(-5),
+                ParamValue::Text("héllo".to_owned()),
+                ParamValue::Null,
+                ParamValue::Float(1.5),
+                ParamValue::UInt(1),
+                ParamValue::UInt(7),
+                ParamValue::Int(-3),
+                ParamValue::Bytes(vec![1, 2]),
+                ParamValue::Text("x".repeat(300)),
+                ParamValue::Float(2.5),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_validators_report_first_rejection() {
+        let checks = BindChecks {
+            repeats: true,
+            encrypted: Vec::new(),
+            long_data: Vec::new(),
+            deprecation: None,
+            validators: vec![
+                (":limit".to_owned(), Validator::range(1, 100)),
+                (":slug".to_owned(), Validator::matches(Regex::new("^[a-z-]+$").unwrap())),
+                (":slug".to_owned(), Validator::non_empty()),
+            ],
+        };
+        let order: Vec<String> = [":slug", ":limit"].map(String::from).to_vec();
+        let mut arguments = MySqlArguments::default();
+        arguments.add("a-b").unwrap();
+        arguments.add(100_u32).unwrap();
+        checks.validate(&order, &[0, 1], &arguments).unwrap();
+
+        let mut arguments = MySqlArguments::default();
+        arguments.add("a-b").unwrap();
+        arguments.add(0_i64).unwrap();
+        assert!(matches!(
+            checks.validate(&order, &[0, 1], &arguments),
+            Err(crate::Error::InvalidParam { name, reason })
+                if name == ":limit" && reason == "0 is outside 1..=100"
+        ));
+
+        let mut arguments = MySqlArguments::default();
+        arguments.add("a-b").unwrap();
+        arguments.add("").unwrap();
+        arguments.add(5_i64).unwrap();
+        assert!(matches!(
+            checks.validate(&order, &[0, 2], &arguments),
+            Err(crate::Error::InvalidParam { name, .. }) if name == ":slug"
+        ));
+    }
+}
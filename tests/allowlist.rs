@@ -0,0 +1,75 @@
+//! The allow-list is process-wide, so these tests run in their own binary instead of
+//! next to the unit tests, which create queries for arbitrary SQL.
+
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use sqlx_named_bind::allowlist::{self, AllowList, Mode};
+use sqlx_named_bind::batch::execute_batch_chunked;
+use sqlx_named_bind::json::import_ndjson;
+use sqlx_named_bind::lock::NamedLock;
+use sqlx_named_bind::query::Q;
+use sqlx_named_bind::{Error, PreparedQuery};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Serializes the tests, which each install their own list.
+static INSTALLED: Mutex<()> = Mutex::const_new(());
+
+/// A pool whose connections fail, since nothing listens on port 1, so getting past the
+/// allow-list ends at the connection.
+fn unreachable_pool() -> MySqlPool {
+    MySqlPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(100))
+        .connect_lazy("mysql://localhost:1/test")
+        .unwrap()
+}
+
+fn bind<'q>(q: Q<'q>, _: &str, id: &i64) -> Q<'q> {
+    q.bind(*id)
+}
+
+#[tokio::test]
+async fn test_named_lock_is_not_checked() {
+    let _installed = INSTALLED.lock().await;
+    allowlist::install(AllowList::new(Mode::Reject));
+    assert!(matches!(
+        PreparedQuery::new("SELECT GET_LOCK(:name, :timeout)", |q, _| q),
+        Err(Error::NotAllowed(_))
+    ));
+
+    let pool = unreachable_pool();
+    let locked = NamedLock::acquire(&pool, "nightly-report", Duration::from_secs(1)).await;
+    assert!(matches!(locked, Err(Error::Database(_))));
+    allowlist::uninstall();
+}
+
+#[tokio::test]
+async fn test_batch_helpers_are_checked() {
+    let _installed = INSTALLED.lock().await;
+    let list = AllowList::new(Mode::Reject)
+        .allow("UPDATE users SET active = 0 WHERE id = :id")
+        .unwrap();
+    allowlist::install(list);
+    let pool = unreachable_pool();
+
+    let adhoc = execute_batch_chunked(&pool, "DELETE FROM users WHERE id = :id", [1], 10, bind);
+    assert!(matches!(adhoc.await, Err(Error::NotAllowed(_))));
+    let listed = execute_batch_chunked(
+        &pool,
+        "UPDATE users SET active = 0 WHERE id = :id",
+        [1],
+        10,
+        bind,
+    );
+    assert!(matches!(listed.await, Err(Error::BatchFailed { .. })));
+
+    let lines: &[u8] = b"{\"id\": 1}\n";
+    let import = import_ndjson(
+        &pool,
+        lines,
+        "DELETE FROM users WHERE id = :id",
+        [("id", "id")],
+        10,
+    );
+    assert!(matches!(import.await, Err(Error::NotAllowed(_))));
+    allowlist::uninstall();
+}